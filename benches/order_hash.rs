@@ -0,0 +1,47 @@
+// benches/order_hash.rs
+// EIP-712 order digest: hand-rolled encoder vs ethers TypedData
+
+use arb_bot::polymarket_clob::{OrderStruct, order_digest, order_digest_typed_data};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+fn sample_order() -> OrderStruct {
+    OrderStruct {
+        salt: 479249096354,
+        maker: "0x70997970C51812dc3A010C7d01b50e0d17dc79C8".into(),
+        signer: "0x2c7536E3605D9C16a7a3D7b1898e529396a65c23".into(),
+        taker: "0x0000000000000000000000000000000000000000".into(),
+        token_id: "71321045679252212594626385532706912750332728571942532289631379312455583992563".into(),
+        maker_amount: "4500000".into(),
+        taker_amount: "10000000".into(),
+        expiration: "0".into(),
+        nonce: "0".into(),
+        fee_rate_bps: "0".into(),
+        side: 0,
+        signature_type: 1,
+    }
+}
+
+fn bench_order_digest(c: &mut Criterion) {
+    let order = sample_order();
+
+    // Both paths must agree before timing them
+    for neg_risk in [false, true] {
+        assert_eq!(
+            order_digest(137, neg_risk, &order).unwrap(),
+            order_digest_typed_data(137, neg_risk, &order).unwrap(),
+            "encoder digest differs from TypedData (neg_risk={})", neg_risk
+        );
+    }
+
+    let mut group = c.benchmark_group("order_digest");
+    group.bench_function("keccak_encoder", |b| {
+        b.iter(|| order_digest(black_box(137), black_box(false), black_box(&order)).unwrap())
+    });
+    group.bench_function("typed_data", |b| {
+        b.iter(|| order_digest_typed_data(black_box(137), black_box(false), black_box(&order)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_order_digest);
+criterion_main!(benches);
@@ -0,0 +1,312 @@
+// src/backtest.rs
+// Backtesting - replay recorded data over a parameter grid and summarize each run
+//
+// Each combination is a full deterministic replay (see replay.rs) with its own threshold,
+// paper fill model and breaker limits. Frames are loaded once and shared; combinations
+// run in parallel on plain threads, each with its own paused runtime.
+
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tracing::{info, warn};
+
+use crate::recorder::{MarketSnapshot, RecordedFrame, load_markets, parse_time, read_range};
+use crate::replay::{ReplayConfig, ReplayReport, replay};
+use crate::types::PriceCents;
+
+/// Backtest configuration from environment
+#[derive(Debug, Clone)]
+pub struct BacktestConfig {
+    /// Recording, market list, date range and the defaults for every grid axis
+    pub base: ReplayConfig,
+    pub grid: SweepGrid,
+    /// Worker threads (one replay each at a time)
+    pub threads: usize,
+}
+
+impl BacktestConfig {
+    pub fn from_env() -> Result<Self> {
+        let mut base = ReplayConfig::from_env();
+        // Backtests always execute; detection-only runs are what replay is for
+        base.dry_run = false;
+
+        if let Ok(from) = std::env::var("BACKTEST_FROM") {
+            base.from_ns = parse_time(&from, false)?;
+        }
+        if let Ok(to) = std::env::var("BACKTEST_TO") {
+            base.to_ns = parse_time(&to, true)?;
+        }
+
+        let grid = SweepGrid {
+            thresholds_cents: env_list("BACKTEST_THRESHOLDS", base.threshold_cents)?,
+            latencies_ms: env_list("BACKTEST_LATENCIES_MS", base.paper.latency_ms)?,
+            latency_jitters_ms: env_list("BACKTEST_JITTERS_MS", base.paper.latency_jitter_ms)?,
+            queue_shares: env_list("BACKTEST_QUEUE_SHARES", base.paper.queue_share)?,
+            max_positions_per_market: env_list("BACKTEST_MAX_POSITIONS", base.breaker.max_position_per_market)?,
+            max_total_positions: env_list("BACKTEST_MAX_TOTAL_POSITIONS", base.breaker.max_total_position)?,
+            max_daily_losses: env_list("BACKTEST_MAX_DAILY_LOSSES", base.breaker.max_daily_loss)?,
+        };
+
+        Ok(Self {
+            threads: std::env::var("BACKTEST_THREADS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or_else(|| std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1))
+                .max(1),
+            base,
+            grid,
+        })
+    }
+}
+
+/// Comma-separated values for one grid axis; unset means the single default
+fn env_list<T: std::str::FromStr>(name: &str, default: T) -> Result<Vec<T>> {
+    let Ok(raw) = std::env::var(name) else { return Ok(vec![default]) };
+    let values = raw.split(',')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(|v| v.parse().map_err(|_| anyhow::anyhow!("{name}: invalid value {v:?}")))
+        .collect::<Result<Vec<T>>>()?;
+    if values.is_empty() {
+        bail!("{name} is set but empty");
+    }
+    Ok(values)
+}
+
+/// Values to sweep on each axis; the grid is their cartesian product
+#[derive(Debug, Clone)]
+pub struct SweepGrid {
+    pub thresholds_cents: Vec<PriceCents>,
+    pub latencies_ms: Vec<u64>,
+    pub latency_jitters_ms: Vec<u64>,
+    pub queue_shares: Vec<f64>,
+    pub max_positions_per_market: Vec<i64>,
+    pub max_total_positions: Vec<i64>,
+    pub max_daily_losses: Vec<f64>,
+}
+
+impl SweepGrid {
+    /// A grid holding only the base config's values
+    pub fn single(base: &ReplayConfig) -> Self {
+        Self {
+            thresholds_cents: vec![base.threshold_cents],
+            latencies_ms: vec![base.paper.latency_ms],
+            latency_jitters_ms: vec![base.paper.latency_jitter_ms],
+            queue_shares: vec![base.paper.queue_share],
+            max_positions_per_market: vec![base.breaker.max_position_per_market],
+            max_total_positions: vec![base.breaker.max_total_position],
+            max_daily_losses: vec![base.breaker.max_daily_loss],
+        }
+    }
+
+    pub fn combinations(&self) -> Vec<BacktestParams> {
+        let mut out = Vec::new();
+        for &threshold_cents in &self.thresholds_cents {
+            for &latency_ms in &self.latencies_ms {
+                for &latency_jitter_ms in &self.latency_jitters_ms {
+                    for &queue_share in &self.queue_shares {
+                        for &max_position_per_market in &self.max_positions_per_market {
+                            for &max_total_position in &self.max_total_positions {
+                                for &max_daily_loss in &self.max_daily_losses {
+                                    out.push(BacktestParams {
+                                        threshold_cents,
+                                        latency_ms,
+                                        latency_jitter_ms,
+                                        queue_share,
+                                        max_position_per_market,
+                                        max_total_position,
+                                        max_daily_loss,
+                                    });
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// One point of the grid
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BacktestParams {
+    pub threshold_cents: PriceCents,
+    pub latency_ms: u64,
+    pub latency_jitter_ms: u64,
+    pub queue_share: f64,
+    pub max_position_per_market: i64,
+    pub max_total_position: i64,
+    pub max_daily_loss: f64,
+}
+
+impl BacktestParams {
+    /// Replay config for this point: the base with threshold, fill model and limits overridden
+    pub fn apply(&self, base: &ReplayConfig) -> ReplayConfig {
+        let mut config = base.clone();
+        config.threshold_cents = self.threshold_cents;
+        config.paper.latency_ms = self.latency_ms;
+        config.paper.latency_jitter_ms = self.latency_jitter_ms;
+        config.paper.queue_share = self.queue_share;
+        config.breaker.max_position_per_market = self.max_position_per_market;
+        config.breaker.max_total_position = self.max_total_position;
+        config.breaker.max_daily_loss = self.max_daily_loss;
+        config
+    }
+}
+
+impl std::fmt::Display for BacktestParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "thr={}¢ lat={}±{}ms share={:.2} pos={}/{} loss=${:.0}",
+               self.threshold_cents, self.latency_ms, self.latency_jitter_ms, self.queue_share,
+               self.max_position_per_market, self.max_total_position, self.max_daily_loss)
+    }
+}
+
+/// Arb and P&L statistics for one market (or all markets combined)
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MarketStats {
+    pub pair_id: String,
+    /// Arb episodes
+    pub arbs: usize,
+    pub total_duration_ms: f64,
+    pub avg_duration_ms: f64,
+    pub max_duration_ms: f64,
+    /// Sum over episodes of the largest two-sided size (contracts)
+    pub capturable_contracts: f64,
+    /// Sum over episodes of the largest available profit (dollars)
+    pub capturable_profit: f64,
+    /// Requests sent to execution
+    pub detections: usize,
+    /// Requests that executed
+    pub executed: usize,
+    pub matched_contracts: f64,
+    pub unmatched_contracts: f64,
+    /// Simulated P&L (matched payout minus net cost; unmatched valued at zero)
+    pub pnl: f64,
+    /// Losses from selling off mismatched legs
+    pub mismatch_loss: f64,
+}
+
+impl MarketStats {
+    fn new(pair_id: &str) -> Self {
+        Self { pair_id: pair_id.to_string(), ..Default::default() }
+    }
+
+    fn add(&mut self, other: &MarketStats) {
+        self.arbs += other.arbs;
+        self.total_duration_ms += other.total_duration_ms;
+        self.max_duration_ms = self.max_duration_ms.max(other.max_duration_ms);
+        self.capturable_contracts += other.capturable_contracts;
+        self.capturable_profit += other.capturable_profit;
+        self.detections += other.detections;
+        self.executed += other.executed;
+        self.matched_contracts += other.matched_contracts;
+        self.unmatched_contracts += other.unmatched_contracts;
+        self.pnl += other.pnl;
+        self.mismatch_loss += other.mismatch_loss;
+        self.finish();
+    }
+
+    fn finish(&mut self) {
+        self.avg_duration_ms = if self.arbs > 0 { self.total_duration_ms / self.arbs as f64 } else { 0.0 };
+    }
+}
+
+/// Per-market stats (sorted by pair_id) and the aggregate over all markets
+pub fn summarize(report: &ReplayReport) -> (Vec<MarketStats>, MarketStats) {
+    let mut markets: BTreeMap<&str, MarketStats> = BTreeMap::new();
+
+    for episode in &report.episodes {
+        let stats = markets.entry(&episode.pair_id).or_insert_with(|| MarketStats::new(&episode.pair_id));
+        let duration = episode.duration_ms();
+        stats.arbs += 1;
+        stats.total_duration_ms += duration;
+        stats.max_duration_ms = stats.max_duration_ms.max(duration);
+        stats.capturable_contracts += episode.max_contracts;
+        stats.capturable_profit += episode.max_capturable;
+    }
+    for decision in &report.decisions {
+        let stats = markets.entry(&decision.pair_id).or_insert_with(|| MarketStats::new(&decision.pair_id));
+        stats.detections += 1;
+        stats.executed += decision.executed as usize;
+    }
+    for outcome in &report.markets {
+        let stats = markets.entry(&outcome.pair_id).or_insert_with(|| MarketStats::new(&outcome.pair_id));
+        stats.matched_contracts += outcome.matched_contracts;
+        stats.unmatched_contracts += outcome.unmatched_contracts;
+        stats.pnl += outcome.pnl;
+        stats.mismatch_loss += outcome.mismatch_loss;
+    }
+
+    let mut total = MarketStats::new("ALL");
+    let markets: Vec<MarketStats> = markets.into_values()
+        .map(|mut stats| {
+            stats.finish();
+            total.add(&stats);
+            stats
+        })
+        .collect();
+    (markets, total)
+}
+
+/// Result of one grid point
+#[derive(Debug, Clone, Serialize)]
+pub struct RunStats {
+    pub params: BacktestParams,
+    pub frames: usize,
+    pub digest: u64,
+    pub total: MarketStats,
+    pub markets: Vec<MarketStats>,
+}
+
+/// Load the configured range once and sweep the grid over it
+pub fn run_backtest(config: &BacktestConfig) -> Result<Vec<RunStats>> {
+    let markets = load_markets(&config.base.markets_file)
+        .with_context(|| format!("loading market list {}", config.base.markets_file))?;
+    let frames = read_range(&config.base.dir, config.base.from_ns, config.base.to_ns)?;
+    info!("[BACKTEST] {} frames, {} markets from {}", frames.len(), markets.len(), config.base.dir);
+    sweep(config, &frames, &markets)
+}
+
+/// Run every grid point over the same frames, `config.threads` at a time.
+/// Results come back in grid order regardless of which thread finished first.
+pub fn sweep(config: &BacktestConfig, frames: &[RecordedFrame], markets: &[MarketSnapshot]) -> Result<Vec<RunStats>> {
+    let combos = config.grid.combinations();
+    info!("[BACKTEST] Sweeping {} combinations on {} threads", combos.len(), config.threads);
+
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<Result<RunStats>>>> = Mutex::new((0..combos.len()).map(|_| None).collect());
+
+    std::thread::scope(|scope| {
+        for _ in 0..config.threads.min(combos.len()) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(params) = combos.get(i) else { break };
+                let result = replay(&params.apply(&config.base), frames, markets).map(|report| {
+                    let (per_market, total) = summarize(&report);
+                    info!("[BACKTEST] {} | {} arbs, {} executed, P&L ${:.2}, mismatch ${:.2}",
+                          params, total.arbs, total.executed, total.pnl, total.mismatch_loss);
+                    RunStats {
+                        params: params.clone(),
+                        frames: report.frames,
+                        digest: report.digest(),
+                        total,
+                        markets: per_market,
+                    }
+                });
+                if let Err(e) = &result {
+                    warn!("[BACKTEST] {} failed: {}", params, e);
+                }
+                results.lock().unwrap()[i] = Some(result);
+            });
+        }
+    });
+
+    results.into_inner().unwrap()
+        .into_iter()
+        .map(|r| r.expect("every combination is run"))
+        .collect()
+}
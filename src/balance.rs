@@ -0,0 +1,450 @@
+// src/balance.rs
+// Collateral balance and allowance checks (CLOB endpoint + on-chain reads)
+
+use anyhow::{Result, anyhow};
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{Notify, RwLock};
+use tracing::{error, info, warn};
+
+use crate::polymarket_clob::{SharedAsyncClient, get_exchange_address};
+use crate::exchange::ExchangeClient;
+
+/// USDC.e collateral token on Polygon
+pub const POLYGON_USDC: &str = "0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174";
+
+/// ERC-20 balanceOf(address)
+const BALANCE_OF_SELECTOR: &str = "70a08231";
+/// ERC-20 allowance(address,address)
+const ALLOWANCE_SELECTOR: &str = "dd62ed3e";
+
+/// Balance check configuration from environment
+#[derive(Debug, Clone)]
+pub struct BalanceConfig {
+    /// Polygon JSON-RPC endpoint for on-chain reads (CLOB endpoint only if unset)
+    pub rpc_url: Option<String>,
+    /// Periodic refresh interval (seconds)
+    pub refresh_secs: u64,
+    /// USDC kept aside and never committed to trades
+    pub reserve_usdc: f64,
+    /// Whether balance checks gate execution
+    pub enabled: bool,
+}
+
+impl BalanceConfig {
+    pub fn from_env() -> Self {
+        Self {
+            rpc_url: std::env::var("POLYGON_RPC_URL").ok().filter(|v| !v.is_empty()),
+
+            refresh_secs: std::env::var("BALANCE_REFRESH_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
+
+            reserve_usdc: std::env::var("BALANCE_RESERVE_USDC")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0.0),
+
+            enabled: std::env::var("BALANCE_CHECK_ENABLED")
+                .map(|v| v == "1" || v == "true")
+                .unwrap_or(true),
+        }
+    }
+}
+
+/// Why collateral checks are blocking a trade
+#[derive(Debug, Clone, PartialEq)]
+pub enum BalanceBlock {
+    /// No successful balance read yet
+    Unknown,
+    /// Exchange contract has no USDC allowance
+    AllowanceMissing { spender: String },
+    /// Not enough free collateral for a single contract
+    InsufficientCollateral { available_usdc: f64, required_usdc: f64 },
+}
+
+impl BalanceBlock {
+    /// Short label for ExecutionResult
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BalanceBlock::Unknown => "Balance unknown",
+            BalanceBlock::AllowanceMissing { .. } => "Allowance missing",
+            BalanceBlock::InsufficientCollateral { .. } => "Insufficient collateral",
+        }
+    }
+}
+
+impl std::fmt::Display for BalanceBlock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BalanceBlock::Unknown => write!(f, "Collateral balance not yet known"),
+            BalanceBlock::AllowanceMissing { spender } => {
+                write!(f, "USDC allowance missing for exchange {} - approve it before trading", spender)
+            }
+            BalanceBlock::InsufficientCollateral { available_usdc, required_usdc } => {
+                write!(f, "Insufficient collateral: ${:.2} available, ${:.2} required", available_usdc, required_usdc)
+            }
+        }
+    }
+}
+
+/// Collateral reading from one source (micro-USDC)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CollateralSnapshot {
+    pub balance: u64,
+    /// Allowance for the standard CTF exchange
+    pub allowance: u64,
+    /// Allowance for the neg-risk CTF exchange
+    pub neg_risk_allowance: u64,
+}
+
+impl CollateralSnapshot {
+    /// Conservative combination of two sources
+    pub fn min(self, other: CollateralSnapshot) -> CollateralSnapshot {
+        CollateralSnapshot {
+            balance: self.balance.min(other.balance),
+            allowance: self.allowance.min(other.allowance),
+            neg_risk_allowance: self.neg_risk_allowance.min(other.neg_risk_allowance),
+        }
+    }
+}
+
+/// Minimal JSON-RPC reader for ERC-20 balance/allowance
+pub struct ChainReader {
+    rpc_url: String,
+    http: reqwest::Client,
+}
+
+impl ChainReader {
+    pub fn new(rpc_url: &str) -> Self {
+        Self {
+            rpc_url: rpc_url.to_string(),
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .expect("Failed to build HTTP client"),
+        }
+    }
+
+    /// ERC-20 balanceOf(owner)
+    pub async fn erc20_balance(&self, token: &str, owner: &str) -> Result<u64> {
+        let data = format!("0x{}{}", BALANCE_OF_SELECTOR, abi_address(owner)?);
+        self.eth_call_u64(token, &data).await
+    }
+
+    /// ERC-20 allowance(owner, spender)
+    pub async fn erc20_allowance(&self, token: &str, owner: &str, spender: &str) -> Result<u64> {
+        let data = format!("0x{}{}{}", ALLOWANCE_SELECTOR, abi_address(owner)?, abi_address(spender)?);
+        self.eth_call_u64(token, &data).await
+    }
+
+    async fn eth_call_u64(&self, to: &str, data: &str) -> Result<u64> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_call",
+            "params": [{"to": to, "data": data}, "latest"],
+        });
+
+        let resp: serde_json::Value = self.http
+            .post(&self.rpc_url)
+            .json(&body)
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        if let Some(err) = resp.get("error") {
+            return Err(anyhow!("eth_call failed: {}", err));
+        }
+        let result = resp["result"].as_str().ok_or_else(|| anyhow!("eth_call: missing result"))?;
+        parse_uint256_saturating(result)
+    }
+}
+
+/// ABI-encode an address as a 32-byte word (hex, no 0x)
+pub fn abi_address(addr: &str) -> Result<String> {
+    let hex = addr.trim_start_matches("0x");
+    if hex.len() != 40 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(anyhow!("invalid address: {}", addr));
+    }
+    Ok(format!("{:0>64}", hex.to_ascii_lowercase()))
+}
+
+/// Parse a hex uint256, saturating at u64::MAX (max approvals)
+pub fn parse_uint256_saturating(hex: &str) -> Result<u64> {
+    let digits = hex.trim_start_matches("0x").trim_start_matches('0');
+    if digits.is_empty() {
+        return Ok(0);
+    }
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(anyhow!("invalid uint256: {}", hex));
+    }
+    if digits.len() > 16 {
+        return Ok(u64::MAX);
+    }
+    Ok(u64::from_str_radix(digits, 16)?)
+}
+
+/// Collateral taken out of `available` locally (micro-USDC)
+#[derive(Debug, Default)]
+struct Debits {
+    /// Total debited by `record_spend` and settled reservations
+    spent: u64,
+    /// Held by executions still in progress
+    reserved: u64,
+}
+
+/// Tracks free collateral and allowances; caps trade size and blocks trading
+pub struct BalanceMonitor {
+    config: BalanceConfig,
+    chain: Option<ChainReader>,
+    /// Free collateral after reserve and local spends (micro-USDC)
+    available: AtomicU64,
+    allowance: AtomicU64,
+    neg_risk_allowance: AtomicU64,
+    /// Set after the first successful refresh
+    known: AtomicBool,
+    /// Chain of the last refresh (selects exchange addresses)
+    chain_id: AtomicU64,
+    /// Last block reason logged (to log only on change)
+    last_block: RwLock<Option<BalanceBlock>>,
+    /// Local spends and reservations. The lock also orders debits against
+    /// snapshot application so neither overwrites the other.
+    debits: Mutex<Debits>,
+    refresh_notify: Notify,
+}
+
+impl BalanceMonitor {
+    pub fn new(config: BalanceConfig) -> Self {
+        info!("[BALANCE] Balance monitor initialized:");
+        info!("[BALANCE]   Enabled: {}", config.enabled);
+        info!("[BALANCE]   On-chain RPC: {}", if config.rpc_url.is_some() { "configured" } else { "none (CLOB only)" });
+        info!("[BALANCE]   Refresh: {}s | Reserve: ${:.2}", config.refresh_secs, config.reserve_usdc);
+
+        Self {
+            chain: config.rpc_url.as_deref().map(ChainReader::new),
+            config,
+            available: AtomicU64::new(0),
+            allowance: AtomicU64::new(0),
+            neg_risk_allowance: AtomicU64::new(0),
+            known: AtomicBool::new(false),
+            chain_id: AtomicU64::new(137),
+            last_block: RwLock::new(None),
+            debits: Mutex::new(Debits::default()),
+            refresh_notify: Notify::new(),
+        }
+    }
+
+    fn reserve_micro(&self) -> u64 {
+        (self.config.reserve_usdc.max(0.0) * 1_000_000.0) as u64
+    }
+
+    /// Apply a collateral reading taken now
+    #[allow(dead_code)]
+    pub fn update(&self, snapshot: CollateralSnapshot) {
+        self.update_since(snapshot, self.spend_mark());
+    }
+
+    /// Running total of local debits; take it before requesting a snapshot
+    pub fn spend_mark(&self) -> u64 {
+        self.debits.lock().unwrap().spent
+    }
+
+    /// Apply a collateral reading requested at `mark`. Spends recorded after that may
+    /// not be reflected in it yet, so they stay debited, as do open reservations.
+    pub fn update_since(&self, snapshot: CollateralSnapshot, mark: u64) {
+        let debits = self.debits.lock().unwrap();
+        let in_flight = debits.spent.saturating_sub(mark) + debits.reserved;
+        let available = snapshot.balance.saturating_sub(self.reserve_micro()).saturating_sub(in_flight);
+        self.available.store(available, Ordering::Release);
+        self.allowance.store(snapshot.allowance, Ordering::Release);
+        self.neg_risk_allowance.store(snapshot.neg_risk_allowance, Ordering::Release);
+        self.known.store(true, Ordering::Release);
+    }
+
+    /// Free collateral in dollars
+    pub fn available_usdc(&self) -> f64 {
+        self.available.load(Ordering::Acquire) as f64 / 1_000_000.0
+    }
+
+    /// Max contracts affordable at `cost_per_contract_cents` (both legs + fees),
+    /// or why trading is blocked
+    pub fn max_contracts(&self, cost_per_contract_cents: i64, neg_risk: bool) -> Result<i64, BalanceBlock> {
+        if !self.config.enabled {
+            return Ok(i64::MAX);
+        }
+        if !self.known.load(Ordering::Acquire) {
+            return Err(BalanceBlock::Unknown);
+        }
+
+        let allowance = if neg_risk {
+            self.neg_risk_allowance.load(Ordering::Acquire)
+        } else {
+            self.allowance.load(Ordering::Acquire)
+        };
+        if allowance == 0 {
+            return Err(BalanceBlock::AllowanceMissing {
+                spender: self.spender(neg_risk),
+            });
+        }
+
+        let usable = self.available.load(Ordering::Acquire).min(allowance);
+        let cost_micro = (cost_per_contract_cents.max(1) as u64) * 10_000;
+        let contracts = (usable / cost_micro) as i64;
+        if contracts < 1 {
+            return Err(BalanceBlock::InsufficientCollateral {
+                available_usdc: usable as f64 / 1_000_000.0,
+                required_usdc: cost_micro as f64 / 1_000_000.0,
+            });
+        }
+        Ok(contracts)
+    }
+
+    /// Cap `max_contracts` by free collateral and hold the cost of the capped size
+    /// until the reservation is settled or dropped, so executions running at the
+    /// same time can't each commit the same collateral
+    pub fn reserve(
+        self: &Arc<Self>,
+        cost_per_contract_cents: i64,
+        max_contracts: i64,
+        neg_risk: bool,
+    ) -> Result<Reservation, BalanceBlock> {
+        let mut debits = self.debits.lock().unwrap();
+        let contracts = self.max_contracts(cost_per_contract_cents, neg_risk)?.min(max_contracts);
+        let micro = if self.config.enabled {
+            contracts.max(0) as u64 * cost_per_contract_cents.max(1) as u64 * 10_000
+        } else {
+            0
+        };
+        debits.reserved += micro;
+        let _ = self.available.fetch_update(Ordering::AcqRel, Ordering::Acquire, |v| Some(v.saturating_sub(micro)));
+        Ok(Reservation { monitor: self.clone(), contracts, micro })
+    }
+
+    /// Return collateral held by a reservation
+    fn release(&self, micro: u64) {
+        let mut debits = self.debits.lock().unwrap();
+        debits.reserved = debits.reserved.saturating_sub(micro);
+        let _ = self.available.fetch_update(Ordering::AcqRel, Ordering::Acquire, |v| Some(v + micro));
+    }
+
+    fn spender(&self, neg_risk: bool) -> String {
+        get_exchange_address(self.chain_id.load(Ordering::Relaxed), neg_risk).unwrap_or_default()
+    }
+
+    /// Debit collateral spent on a fill until the next refresh confirms it
+    pub fn record_spend(&self, cents: i64) {
+        if cents <= 0 {
+            return;
+        }
+        let micro = cents as u64 * 10_000;
+        let mut debits = self.debits.lock().unwrap();
+        debits.spent += micro;
+        let _ = self.available.fetch_update(Ordering::AcqRel, Ordering::Acquire, |v| Some(v.saturating_sub(micro)));
+    }
+
+    /// Ask the refresh loop to re-read balances now (e.g. after fills)
+    pub fn request_refresh(&self) {
+        self.refresh_notify.notify_one();
+    }
+
+    /// Read balances from the CLOB and (if configured) on-chain, then update
+    pub async fn refresh(&self, client: &SharedAsyncClient) -> Result<CollateralSnapshot> {
+        let chain_id = client.chain_id();
+        self.chain_id.store(chain_id, Ordering::Relaxed);
+        let exchange = get_exchange_address(chain_id, false)?;
+        let neg_risk_exchange = get_exchange_address(chain_id, true)?;
+        let mark = self.spend_mark();
+
+        let mut snapshot = client.collateral().await?;
+
+        if let Some(chain) = &self.chain {
+            let funder = client.funder();
+            let on_chain = async {
+                let (balance, allowance, neg_risk_allowance) = tokio::try_join!(
+                    chain.erc20_balance(POLYGON_USDC, funder),
+                    chain.erc20_allowance(POLYGON_USDC, funder, &exchange),
+                    chain.erc20_allowance(POLYGON_USDC, funder, &neg_risk_exchange),
+                )?;
+                Ok::<_, anyhow::Error>(CollateralSnapshot { balance, allowance, neg_risk_allowance })
+            }.await;
+
+            match on_chain {
+                Ok(chain_snapshot) => snapshot = snapshot.min(chain_snapshot),
+                Err(e) => warn!("[BALANCE] On-chain read failed (using CLOB only): {}", e),
+            }
+        }
+
+        self.update_since(snapshot, mark);
+        self.log_block_change().await;
+        Ok(snapshot)
+    }
+
+    /// Log when the blocking reason appears, changes or clears
+    async fn log_block_change(&self) {
+        let block = self.max_contracts(1, false)
+            .and_then(|_| self.max_contracts(1, true))
+            .err();
+
+        let mut last = self.last_block.write().await;
+        if *last != block {
+            match &block {
+                Some(b) => error!("[BALANCE] 🚫 Trading blocked: {}", b),
+                None => info!("[BALANCE] ✅ Collateral OK: ${:.2} available", self.available_usdc()),
+            }
+            *last = block;
+        }
+    }
+}
+
+/// Collateral held for one execution; released when dropped unless settled
+pub struct Reservation {
+    monitor: Arc<BalanceMonitor>,
+    contracts: i64,
+    micro: u64,
+}
+
+impl Reservation {
+    /// Contracts the reserved collateral pays for
+    pub fn contracts(&self) -> i64 {
+        self.contracts
+    }
+
+    /// Cost held (cents)
+    pub fn cost_cents(&self) -> i64 {
+        (self.micro / 10_000) as i64
+    }
+
+    /// Turn the reservation into a spend of `cents`. The spend is debited before
+    /// the hold is released, so the collateral is never free in between.
+    pub fn settle(self, cents: i64) {
+        self.monitor.record_spend(cents);
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        if self.micro > 0 {
+            self.monitor.release(self.micro);
+        }
+    }
+}
+
+/// Refresh balances periodically and whenever a refresh is requested
+pub async fn balance_refresh_loop(monitor: Arc<BalanceMonitor>, client: Arc<SharedAsyncClient>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(monitor.config.refresh_secs.max(1)));
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = monitor.refresh_notify.notified() => {}
+        }
+
+        if let Err(e) = monitor.refresh(&client).await {
+            warn!("[BALANCE] Refresh failed: {}", e);
+        }
+    }
+}
@@ -0,0 +1,194 @@
+// src/behaviour.rs
+// Behavioural circuit breakers - sliding windows over order, fill and latency events
+
+use std::collections::VecDeque;
+
+use crate::circuit_breaker::TripReason;
+
+/// Orders-per-minute is always measured over the last minute
+const RATE_WINDOW_MS: u64 = 60_000;
+
+/// Sliding-window breaker thresholds
+#[derive(Debug, Clone)]
+pub struct BehaviourConfig {
+    /// Window for the ratio and latency breakers (seconds)
+    pub window_secs: u64,
+
+    /// Samples a window needs before a ratio or percentile can trip
+    pub min_samples: usize,
+
+    /// Maximum share of orders the exchange rejected (0.0-1.0)
+    pub max_reject_ratio: f64,
+
+    /// Maximum share of executions whose legs filled different sizes (0.0-1.0)
+    pub max_mismatch_ratio: f64,
+
+    /// Detect-to-ack latency percentile checked against `max_latency_ms` (0.0-1.0)
+    pub latency_percentile: f64,
+
+    /// Maximum detect-to-ack latency at `latency_percentile` (milliseconds)
+    pub max_latency_ms: f64,
+
+    /// Maximum orders placed in any minute
+    pub max_orders_per_min: usize,
+}
+
+impl BehaviourConfig {
+    pub fn from_env() -> Self {
+        Self {
+            window_secs: std::env::var("CB_WINDOW_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(300),
+
+            min_samples: std::env::var("CB_WINDOW_MIN_SAMPLES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10),
+
+            max_reject_ratio: std::env::var("CB_MAX_REJECT_RATIO")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0.5),
+
+            max_mismatch_ratio: std::env::var("CB_MAX_MISMATCH_RATIO")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0.3),
+
+            latency_percentile: std::env::var("CB_LATENCY_PERCENTILE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0.95),
+
+            max_latency_ms: std::env::var("CB_MAX_LATENCY_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(2000.0),
+
+            max_orders_per_min: std::env::var("CB_MAX_ORDERS_PER_MIN")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(120),
+        }
+    }
+}
+
+/// Something the execution path observed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BehaviourEvent {
+    /// One order leg sent; `rejected` when the exchange refused it for a non-benign reason
+    Order { rejected: bool },
+    /// One execution that filled at least one leg
+    Execution { mismatched: bool },
+    /// Detect-to-ack latency of one execution
+    Latency { ms: f64 },
+}
+
+/// Sliding windows of recent events, fed with explicit timestamps so the same
+/// stream always gives the same trips (live clock, replay clock or a test)
+#[derive(Debug)]
+pub struct BehaviourMonitor {
+    config: BehaviourConfig,
+    orders: VecDeque<(u64, bool)>,
+    executions: VecDeque<(u64, bool)>,
+    latencies: VecDeque<(u64, f64)>,
+}
+
+impl BehaviourMonitor {
+    pub fn new(config: BehaviourConfig) -> Self {
+        Self {
+            config,
+            orders: VecDeque::new(),
+            executions: VecDeque::new(),
+            latencies: VecDeque::new(),
+        }
+    }
+
+    /// Add an event at `at_ms` and return the first breaker it trips, if any
+    pub fn record(&mut self, at_ms: u64, event: BehaviourEvent) -> Option<TripReason> {
+        let window_ms = self.config.window_secs * 1000;
+        match event {
+            BehaviourEvent::Order { rejected } => {
+                self.orders.push_back((at_ms, rejected));
+                prune(&mut self.orders, at_ms, window_ms.max(RATE_WINDOW_MS));
+                self.check_order_rate(at_ms).or_else(|| self.check_reject_ratio(at_ms))
+            }
+            BehaviourEvent::Execution { mismatched } => {
+                self.executions.push_back((at_ms, mismatched));
+                prune(&mut self.executions, at_ms, window_ms);
+                self.check_mismatch_ratio()
+            }
+            BehaviourEvent::Latency { ms } => {
+                self.latencies.push_back((at_ms, ms));
+                prune(&mut self.latencies, at_ms, window_ms);
+                self.check_latency()
+            }
+        }
+    }
+
+    /// Forget everything (after a reset, so old events don't re-trip)
+    pub fn clear(&mut self) {
+        self.orders.clear();
+        self.executions.clear();
+        self.latencies.clear();
+    }
+
+    fn check_order_rate(&self, at_ms: u64) -> Option<TripReason> {
+        let orders = self.orders.iter().filter(|(t, _)| in_window(*t, at_ms, RATE_WINDOW_MS)).count();
+        (orders > self.config.max_orders_per_min).then_some(TripReason::OrderRate {
+            orders,
+            limit: self.config.max_orders_per_min,
+        })
+    }
+
+    fn check_reject_ratio(&self, at_ms: u64) -> Option<TripReason> {
+        let window_ms = self.config.window_secs * 1000;
+        let window = self.orders.iter()
+            .filter(|(t, _)| in_window(*t, at_ms, window_ms))
+            .map(|(_, rejected)| *rejected);
+        let (samples, ratio) = ratio(window)?;
+        (samples >= self.config.min_samples && ratio > self.config.max_reject_ratio)
+            .then_some(TripReason::RejectRatio { ratio, limit: self.config.max_reject_ratio, samples })
+    }
+
+    fn check_mismatch_ratio(&self) -> Option<TripReason> {
+        let (samples, ratio) = ratio(self.executions.iter().map(|(_, mismatched)| *mismatched))?;
+        (samples >= self.config.min_samples && ratio > self.config.max_mismatch_ratio)
+            .then_some(TripReason::MismatchRatio { ratio, limit: self.config.max_mismatch_ratio, samples })
+    }
+
+    fn check_latency(&self) -> Option<TripReason> {
+        if self.latencies.len() < self.config.min_samples.max(1) {
+            return None;
+        }
+        let mut sorted: Vec<f64> = self.latencies.iter().map(|(_, ms)| *ms).collect();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let percentile = self.config.latency_percentile.clamp(0.0, 1.0);
+        let rank = ((percentile * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len());
+        let latency_ms = sorted[rank - 1];
+        (latency_ms > self.config.max_latency_ms).then_some(TripReason::LatencySpike {
+            percentile,
+            latency_ms,
+            limit_ms: self.config.max_latency_ms,
+        })
+    }
+}
+
+/// Event at `t_ms` happened less than `window_ms` before `now_ms`
+fn in_window(t_ms: u64, now_ms: u64, window_ms: u64) -> bool {
+    now_ms.saturating_sub(t_ms) < window_ms
+}
+
+/// Drop events that fell out of the window
+fn prune<T>(events: &mut VecDeque<(u64, T)>, now_ms: u64, window_ms: u64) {
+    while events.front().is_some_and(|(t, _)| !in_window(*t, now_ms, window_ms)) {
+        events.pop_front();
+    }
+}
+
+/// (samples, share of true) of a stream of flags
+fn ratio(flags: impl Iterator<Item = bool>) -> Option<(usize, f64)> {
+    let (samples, hits) = flags.fold((0usize, 0usize), |(n, k), flag| (n + 1, k + flag as usize));
+    (samples > 0).then_some((samples, hits as f64 / samples as f64))
+}
@@ -0,0 +1,54 @@
+// src/bin/backtest.rs
+//! Backtest detection and execution over recorded data with a parameter sweep
+//!
+//!   REPLAY_DIR=recordings BACKTEST_FROM=2025-01-10 BACKTEST_TO=2025-01-12 \
+//!   BACKTEST_THRESHOLDS=97,98,99 BACKTEST_LATENCIES_MS=20,50,100 cargo run --release --features replay --bin backtest
+//!
+//! Other axes: BACKTEST_JITTERS_MS, BACKTEST_QUEUE_SHARES, BACKTEST_MAX_POSITIONS,
+//! BACKTEST_MAX_TOTAL_POSITIONS, BACKTEST_MAX_DAILY_LOSSES (comma-separated; unset
+//! axes use the PAPER_* / CB_* values). BACKTEST_OUTPUT writes the full results as JSON.
+
+use anyhow::Result;
+use tracing::info;
+
+use arb_bot::backtest::{BacktestConfig, run_backtest};
+
+fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::from_default_env()
+                .add_directive("arb_bot=info".parse().unwrap()),
+        )
+        .init();
+
+    dotenvy::dotenv().ok();
+    let config = BacktestConfig::from_env()?;
+    let mut runs = run_backtest(&config)?;
+
+    if let Ok(path) = std::env::var("BACKTEST_OUTPUT") {
+        std::fs::write(&path, serde_json::to_vec_pretty(&runs)?)?;
+        info!("[BACKTEST] Wrote {} runs to {}", runs.len(), path);
+    }
+
+    runs.sort_by(|a, b| b.total.pnl.total_cmp(&a.total.pnl));
+    println!("{:<52} {:>6} {:>9} {:>10} {:>8} {:>10} {:>10} {:>10}",
+             "params", "arbs", "avg ms", "capturable", "executed", "matched", "P&L", "mismatch");
+    for run in &runs {
+        let t = &run.total;
+        println!("{:<52} {:>6} {:>9.1} {:>10.2} {:>8} {:>10.0} {:>10.2} {:>10.2}",
+                 run.params.to_string(), t.arbs, t.avg_duration_ms, t.capturable_profit,
+                 t.executed, t.matched_contracts, t.pnl, t.mismatch_loss);
+    }
+
+    if let Some(best) = runs.first() {
+        println!("\nBest: {}", best.params);
+        println!("{:<40} {:>6} {:>9} {:>10} {:>10} {:>8} {:>10} {:>10}",
+                 "market", "arbs", "avg ms", "contracts", "capturable", "executed", "P&L", "mismatch");
+        for m in &best.markets {
+            println!("{:<40} {:>6} {:>9.1} {:>10.0} {:>10.2} {:>8} {:>10.2} {:>10.2}",
+                     m.pair_id, m.arbs, m.avg_duration_ms, m.capturable_contracts, m.capturable_profit,
+                     m.executed, m.pnl, m.mismatch_loss);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,32 @@
+// src/bin/clob_emulator.rs
+//! Local Polymarket CLOB emulator
+//!
+//! Serves the REST endpoints the bot uses, the Gamma /markets lookup and the
+//! ws/market feed from a scripted book. Point the bot at it with:
+//!   POLY_CLOB_HOST=http://127.0.0.1:8080
+//!   GAMMA_API_BASE=http://127.0.0.1:8080
+//!   POLY_WS_URL=ws://127.0.0.1:8080/ws/market
+
+use anyhow::Result;
+use tracing::info;
+
+use arb_bot::emulator::{Emulator, EmulatorConfig};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::from_default_env()
+                .add_directive("arb_bot=info".parse().unwrap()),
+        )
+        .init();
+
+    dotenvy::dotenv().ok();
+    let config = EmulatorConfig::from_env()?;
+    let addr = Emulator::spawn(config).await?;
+
+    info!("[EMU] Bot env: POLY_CLOB_HOST=http://{addr} GAMMA_API_BASE=http://{addr} POLY_WS_URL=ws://{addr}/ws/market");
+    tokio::signal::ctrl_c().await?;
+    info!("[EMU] Shutting down");
+    Ok(())
+}
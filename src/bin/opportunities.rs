@@ -0,0 +1,52 @@
+// src/bin/opportunities.rs
+//! Query the opportunity episode log and print daily summaries
+//!
+//!   OPPORTUNITY_LOG=opportunities.jsonl OPP_FROM=2025-01-10 OPP_MARKET=nba cargo run --bin opportunities
+//!
+//! Filters: OPP_FROM / OPP_TO (YYYY-MM-DD, RFC 3339 or Unix ns), OPP_MARKET (pair id or
+//! description substring), OPP_MIN_EDGE_CENTS, OPP_MIN_DURATION_MS, OPP_OUTCOME
+//! (not_attempted, missed, partial, captured). OPP_LIST=1 also prints matching episodes
+//! as JSON lines.
+
+use anyhow::Result;
+use std::io::Write;
+
+use arb_bot::recorder::parse_time;
+use arb_bot::opportunity::{EpisodeQuery, OpportunityConfig, daily_summaries, query};
+
+fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::from_default_env()
+                .add_directive("arb_bot=info".parse().unwrap()),
+        )
+        .init();
+
+    dotenvy::dotenv().ok();
+    let config = OpportunityConfig::from_env();
+    let env = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+
+    let q = EpisodeQuery {
+        from_ns: env("OPP_FROM").map(|v| parse_time(&v, false)).transpose()?,
+        to_ns: env("OPP_TO").map(|v| parse_time(&v, true)).transpose()?,
+        market: env("OPP_MARKET"),
+        min_edge_cents: env("OPP_MIN_EDGE_CENTS").and_then(|v| v.parse().ok()),
+        min_duration_ms: env("OPP_MIN_DURATION_MS").and_then(|v| v.parse().ok()),
+        outcome: env("OPP_OUTCOME").map(|v| v.parse()).transpose()?,
+    };
+    let episodes = query(&config.log_file, &q)?;
+
+    if env("OPP_LIST").is_some_and(|v| v == "1" || v == "true") {
+        let mut out = std::io::stdout().lock();
+        for episode in &episodes {
+            serde_json::to_writer(&mut out, episode)?;
+            out.write_all(b"\n")?;
+        }
+    }
+
+    println!("{} episodes in {}", episodes.len(), config.log_file);
+    for day in daily_summaries(&episodes) {
+        println!("{}", day);
+    }
+    Ok(())
+}
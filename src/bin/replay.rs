@@ -0,0 +1,51 @@
+// src/bin/replay.rs
+//! Replay a WS recording through detection and paper execution
+//!
+//!   REPLAY_DIR=recordings REPLAY_FROM_NS=... REPLAY_TO_NS=... cargo run --features replay --bin replay
+//!
+//! REPLAY_OUTPUT writes one JSON decision per line; REPLAY_VERIFY=1 runs the
+//! replay twice and fails if the decisions differ.
+
+use anyhow::{Result, bail};
+use std::io::Write;
+use tracing::info;
+
+use arb_bot::replay::{ReplayConfig, replay_recording};
+
+fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::from_default_env()
+                .add_directive("arb_bot=info".parse().unwrap()),
+        )
+        .init();
+
+    dotenvy::dotenv().ok();
+    let config = ReplayConfig::from_env();
+    info!("[REPLAY] {} | threshold {}¢ | {}", config.dir, config.threshold_cents,
+          if config.dry_run { "detection only" } else { "paper execution" });
+
+    let report = replay_recording(&config)?;
+
+    let verify = std::env::var("REPLAY_VERIFY").map(|v| v == "1" || v == "true").unwrap_or(false);
+    if verify {
+        let rerun = replay_recording(&config)?;
+        if rerun.decisions != report.decisions {
+            bail!("replay is not deterministic: digest {:016x} vs {:016x}", report.digest(), rerun.digest());
+        }
+        info!("[REPLAY] Verified: second run produced identical decisions");
+    }
+
+    if let Ok(path) = std::env::var("REPLAY_OUTPUT") {
+        let mut out = std::io::BufWriter::new(std::fs::File::create(&path)?);
+        for decision in &report.decisions {
+            serde_json::to_writer(&mut out, decision)?;
+            out.write_all(b"\n")?;
+        }
+        out.flush()?;
+        info!("[REPLAY] Wrote {} decisions to {}", report.decisions.len(), path);
+    }
+
+    info!("[REPLAY] {}", report);
+    Ok(())
+}
@@ -1,11 +1,36 @@
 // src/circuit_breaker.rs
 // Safety circuit breakers - halt trading on various conditions
 
+use anyhow::Result;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
-use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{Mutex, RwLock};
 use tracing::{error, warn, info};
 
+use crate::behaviour::{BehaviourConfig, BehaviourEvent, BehaviourMonitor};
+use crate::config::{get_league_config, get_league_configs};
+use crate::position_tracker::FillRecord;
+use crate::polymarket::GammaClient;
+use crate::types::MarketPair;
+
+/// How often dirty breaker state is flushed to disk
+const PERSIST_INTERVAL_MS: u64 = 1000;
+
+/// How often the supervisor checks cooldowns and the trading day
+const SUPERVISOR_INTERVAL_MS: u64 = 1000;
+
+/// How often held markets are checked for resolution
+const RESOLUTION_INTERVAL_SECS: u64 = 300;
+
+/// Holdings below this many contracts on every leg count as flat
+const FLAT_CONTRACTS: f64 = 1e-6;
+
 /// Circuit breaker configuration from environment
 #[derive(Debug, Clone)]
 pub struct CircuitBreakerConfig {
@@ -15,20 +40,72 @@ pub struct CircuitBreakerConfig {
     /// Maximum total position across all markets (in contracts)
     pub max_total_position: i64,
     
+    /// Maximum unmatched (directional) exposure per market (in contracts)
+    pub max_unmatched_per_market: i64,
+
+    /// Maximum unmatched exposure summed across all markets (in contracts)
+    pub max_unmatched_total: i64,
+    
+    /// Maximum notional of a single trade (in dollars, both legs at request prices)
+    pub max_trade_notional: f64,
+
+    /// Maximum cost basis held in one market (in dollars)
+    pub max_market_cost: f64,
+
+    /// Maximum cost basis held across all markets (in dollars)
+    pub max_total_deployed: f64,
+
+    /// Maximum cost basis in markets that have not resolved yet (in dollars)
+    pub max_locked_capital: f64,
+
+    /// Maximum unresolved cost basis on one event, across its market types (in dollars)
+    pub max_event_cost: f64,
+
+    /// Maximum unresolved cost basis on games played the same date (in dollars)
+    pub max_date_cost: f64,
+
+    /// Maximum unresolved cost basis per league unless `league_cost_limits` has one (in dollars)
+    pub max_league_cost: f64,
+
+    /// Per-league overrides of `max_league_cost`, keyed by league code
+    pub league_cost_limits: HashMap<String, f64>,
+    
     /// Maximum daily loss (in dollars) before halting
     pub max_daily_loss: f64,
     
     /// Maximum number of consecutive errors before halting
     pub max_consecutive_errors: u32,
+
+    /// Sliding-window thresholds: reject and mismatch ratios, latency, order rate
+    pub behaviour: BehaviourConfig,
     
     /// Cooldown period after a trip (seconds)
     pub cooldown_secs: u64,
+
+    /// Trip reason classes (see `TripReason::class`) reset automatically after the cooldown
+    pub auto_reset: Vec<String>,
+
+    /// IANA timezone whose local midnight starts a new trading day (follows DST)
+    pub rollover_tz: Tz,
     
     /// Whether circuit breakers are enabled
     pub enabled: bool,
+
+    /// State snapshot restored at startup (empty = in memory only)
+    pub state_file: String,
 }
 
 impl CircuitBreakerConfig {
+    /// Exposure limit for a league (dollars)
+    pub fn league_limit(&self, league: &str) -> f64 {
+        self.league_cost_limits.get(league).copied().unwrap_or(self.max_league_cost)
+    }
+
+    /// Whether a halt for this reason resets itself once the cooldown elapses
+    pub fn is_recoverable(&self, reason: &TripReason) -> bool {
+        !reason.is_hard() && self.auto_reset.iter().any(|c| c == reason.class())
+    }
+
     pub fn from_env() -> Self {
         Self {
             max_position_per_market: std::env::var("CB_MAX_POSITION_PER_MARKET")
@@ -40,6 +117,56 @@ impl CircuitBreakerConfig {
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(100000),
+
+            max_unmatched_per_market: std::env::var("CB_MAX_UNMATCHED_PER_MARKET")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(100),
+
+            max_unmatched_total: std::env::var("CB_MAX_UNMATCHED_TOTAL")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(500),
+
+            max_trade_notional: std::env::var("CB_MAX_TRADE_NOTIONAL")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1000.0),
+
+            max_market_cost: std::env::var("CB_MAX_MARKET_COST")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5000.0),
+
+            max_total_deployed: std::env::var("CB_MAX_TOTAL_DEPLOYED")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(25000.0),
+
+            max_locked_capital: std::env::var("CB_MAX_LOCKED_CAPITAL")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(20000.0),
+
+            max_event_cost: std::env::var("CB_MAX_EVENT_COST")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(7500.0),
+
+            max_date_cost: std::env::var("CB_MAX_DATE_COST")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(15000.0),
+
+            max_league_cost: std::env::var("CB_MAX_LEAGUE_COST")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(15000.0),
+
+            league_cost_limits: get_league_configs()
+                .iter()
+                .filter_map(|c| c.max_cost.map(|limit| (c.league_code.to_string(), limit)))
+                .collect(),
             
             max_daily_loss: std::env::var("CB_MAX_DAILY_LOSS")
                 .ok()
@@ -50,29 +177,105 @@ impl CircuitBreakerConfig {
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(5),
+
+            behaviour: BehaviourConfig::from_env(),
             
             cooldown_secs: std::env::var("CB_COOLDOWN_SECS")
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(300), // 5 minutes default
+
+            auto_reset: std::env::var("CB_AUTO_RESET")
+                .unwrap_or_else(|_| "consecutive_errors".to_string())
+                .split(',')
+                .map(|c| c.trim().to_string())
+                .filter(|c| !c.is_empty())
+                .collect(),
+
+            rollover_tz: std::env::var("CB_ROLLOVER_TZ")
+                .ok()
+                .and_then(|v| {
+                    let tz = parse_rollover_tz(&v);
+                    if tz.is_none() {
+                        warn!("[CB] CB_ROLLOVER_TZ={} is not an IANA timezone; using UTC", v);
+                    }
+                    tz
+                })
+                .unwrap_or(Tz::UTC),
             
             enabled: std::env::var("CB_ENABLED")
                 .map(|v| v == "1" || v == "true")
                 .unwrap_or(true), // Enabled by default for safety
+
+            state_file: std::env::var("CB_STATE_FILE")
+                .unwrap_or_else(|_| "circuit_breaker.json".to_string()),
         }
     }
 }
 
 /// Reason why circuit breaker was tripped
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TripReason {
     MaxPositionPerMarket { market: String, position: i64, limit: i64 },
     MaxTotalPosition { position: i64, limit: i64 },
+    MaxUnmatchedPerMarket { market: String, unmatched: i64, limit: i64 },
+    MaxUnmatchedTotal { unmatched: i64, limit: i64 },
+    MaxTradeNotional { market: String, notional: f64, limit: f64 },
+    MaxMarketCost { market: String, cost: f64, limit: f64 },
+    MaxTotalDeployed { deployed: f64, limit: f64 },
+    MaxLockedCapital { locked: f64, limit: f64 },
+    MaxEventExposure { event: String, cost: f64, limit: f64 },
+    MaxLeagueExposure { league: String, cost: f64, limit: f64 },
+    MaxDateExposure { date: String, cost: f64, limit: f64 },
+    /// Market unregistered, or registered without the event or date correlated limits need
+    UngroupedMarket { market: String },
     MaxDailyLoss { loss: f64, limit: f64 },
     ConsecutiveErrors { count: u32, limit: u32 },
+    RejectRatio { ratio: f64, limit: f64, samples: usize },
+    MismatchRatio { ratio: f64, limit: f64, samples: usize },
+    LatencySpike { percentile: f64, latency_ms: f64, limit_ms: f64 },
+    OrderRate { orders: usize, limit: usize },
+    /// Exchange rejected an order with an error that affects every market
+    ExchangeError { kind: String, message: String },
+    /// Market disabled after a market-specific exchange error (does not halt trading)
+    MarketDisabled { market: String, reason: String },
     ManualHalt,
 }
 
+impl TripReason {
+    /// Stable name of the reason's class, as listed in `CB_AUTO_RESET`
+    pub fn class(&self) -> &'static str {
+        match self {
+            TripReason::MaxPositionPerMarket { .. } => "max_position_per_market",
+            TripReason::MaxTotalPosition { .. } => "max_total_position",
+            TripReason::MaxUnmatchedPerMarket { .. } => "max_unmatched_per_market",
+            TripReason::MaxUnmatchedTotal { .. } => "max_unmatched_total",
+            TripReason::MaxTradeNotional { .. } => "max_trade_notional",
+            TripReason::MaxMarketCost { .. } => "max_market_cost",
+            TripReason::MaxTotalDeployed { .. } => "max_total_deployed",
+            TripReason::MaxLockedCapital { .. } => "max_locked_capital",
+            TripReason::MaxEventExposure { .. } => "max_event_exposure",
+            TripReason::MaxLeagueExposure { .. } => "max_league_exposure",
+            TripReason::MaxDateExposure { .. } => "max_date_exposure",
+            TripReason::UngroupedMarket { .. } => "ungrouped_market",
+            TripReason::MaxDailyLoss { .. } => "max_daily_loss",
+            TripReason::ConsecutiveErrors { .. } => "consecutive_errors",
+            TripReason::RejectRatio { .. } => "reject_ratio",
+            TripReason::MismatchRatio { .. } => "mismatch_ratio",
+            TripReason::LatencySpike { .. } => "latency_spike",
+            TripReason::OrderRate { .. } => "order_rate",
+            TripReason::ExchangeError { .. } => "exchange_error",
+            TripReason::MarketDisabled { .. } => "market_disabled",
+            TripReason::ManualHalt => "manual_halt",
+        }
+    }
+
+    /// Hard halts only clear on a manual reset, whatever `auto_reset` says
+    pub fn is_hard(&self) -> bool {
+        matches!(self, TripReason::MaxDailyLoss { .. } | TripReason::ManualHalt)
+    }
+}
+
 impl std::fmt::Display for TripReason {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -82,12 +285,60 @@ impl std::fmt::Display for TripReason {
             TripReason::MaxTotalPosition { position, limit } => {
                 write!(f, "Max total position: {} contracts (limit: {})", position, limit)
             }
+            TripReason::MaxUnmatchedPerMarket { market, unmatched, limit } => {
+                write!(f, "Max unmatched per market: {} has {} unhedged contracts (limit: {})", market, unmatched, limit)
+            }
+            TripReason::MaxUnmatchedTotal { unmatched, limit } => {
+                write!(f, "Max unmatched total: {} unhedged contracts (limit: {})", unmatched, limit)
+            }
+            TripReason::MaxTradeNotional { market, notional, limit } => {
+                write!(f, "Max trade notional: ${:.2} on {} (limit: ${:.2})", notional, market, limit)
+            }
+            TripReason::MaxMarketCost { market, cost, limit } => {
+                write!(f, "Max market cost: {} would hold ${:.2} (limit: ${:.2})", market, cost, limit)
+            }
+            TripReason::MaxTotalDeployed { deployed, limit } => {
+                write!(f, "Max total deployed: ${:.2} (limit: ${:.2})", deployed, limit)
+            }
+            TripReason::MaxLockedCapital { locked, limit } => {
+                write!(f, "Max locked capital: ${:.2} in unresolved markets (limit: ${:.2})", locked, limit)
+            }
+            TripReason::MaxEventExposure { event, cost, limit } => {
+                write!(f, "Max event exposure: {} would hold ${:.2} (limit: ${:.2})", event, cost, limit)
+            }
+            TripReason::MaxLeagueExposure { league, cost, limit } => {
+                write!(f, "Max league exposure: {} would hold ${:.2} (limit: ${:.2})", league, cost, limit)
+            }
+            TripReason::MaxDateExposure { date, cost, limit } => {
+                write!(f, "Max date exposure: games on {} would hold ${:.2} (limit: ${:.2})", date, cost, limit)
+            }
+            TripReason::UngroupedMarket { market } => {
+                write!(f, "Ungrouped market: {} has no event or game date to group it by", market)
+            }
             TripReason::MaxDailyLoss { loss, limit } => {
                 write!(f, "Max daily loss: ${:.2} (limit: ${:.2})", loss, limit)
             }
             TripReason::ConsecutiveErrors { count, limit } => {
                 write!(f, "Consecutive errors: {} (limit: {})", count, limit)
             }
+            TripReason::RejectRatio { ratio, limit, samples } => {
+                write!(f, "Reject ratio: {:.0}% of {} orders (limit: {:.0}%)", ratio * 100.0, samples, limit * 100.0)
+            }
+            TripReason::MismatchRatio { ratio, limit, samples } => {
+                write!(f, "Mismatch ratio: {:.0}% of {} executions (limit: {:.0}%)", ratio * 100.0, samples, limit * 100.0)
+            }
+            TripReason::LatencySpike { percentile, latency_ms, limit_ms } => {
+                write!(f, "Latency spike: p{:.0} {:.0}ms (limit: {:.0}ms)", percentile * 100.0, latency_ms, limit_ms)
+            }
+            TripReason::OrderRate { orders, limit } => {
+                write!(f, "Order rate: {} orders/min (limit: {})", orders, limit)
+            }
+            TripReason::ExchangeError { kind, message } => {
+                write!(f, "Exchange error: {} ({})", kind, message)
+            }
+            TripReason::MarketDisabled { market, reason } => {
+                write!(f, "Market disabled: {} ({})", market, reason)
+            }
             TripReason::ManualHalt => {
                 write!(f, "Manual halt triggered")
             }
@@ -96,26 +347,103 @@ impl std::fmt::Display for TripReason {
 }
 
 /// Position tracking for a single market
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct MarketPosition {
-    pub kalshi_yes: i64,
-    pub kalshi_no: i64,
-    pub poly_yes: i64,
-    pub poly_no: i64,
+    /// Exact filled contracts per leg; rounded only where they meet the integer limits
+    pub kalshi_yes: f64,
+    pub kalshi_no: f64,
+    pub poly_yes: f64,
+    pub poly_no: f64,
+    /// Dollars paid for the current holdings, fees included (sells reduce it by their proceeds)
+    #[serde(default)]
+    pub cost_basis: f64,
+    /// Market resolved; its capital is no longer locked, only awaiting redemption
+    #[serde(default)]
+    pub resolved: bool,
 }
 
 #[allow(dead_code)]
 impl MarketPosition {
     pub fn net_position(&self) -> i64 {
         // Net exposure: positive = long YES, negative = long NO
-        (self.kalshi_yes + self.poly_yes) - (self.kalshi_no + self.poly_no)
+        ((self.kalshi_yes + self.poly_yes) - (self.kalshi_no + self.poly_no)).round() as i64
     }
     
     pub fn total_contracts(&self) -> i64 {
-        self.kalshi_yes + self.kalshi_no + self.poly_yes + self.poly_no
+        (self.kalshi_yes + self.kalshi_no + self.poly_yes + self.poly_no).round() as i64
+    }
+
+    /// Contracts held on one side without the opposite side to hedge them
+    pub fn unmatched(&self) -> i64 {
+        self.net_position().abs()
+    }
+
+    /// Apply a fill (sells carry negative contracts)
+    pub fn apply(&mut self, fill: &FillRecord) {
+        let leg = match (fill.platform.as_str(), fill.side.as_str()) {
+            ("kalshi", "yes") => &mut self.kalshi_yes,
+            ("kalshi", "no") => &mut self.kalshi_no,
+            ("polymarket", "yes") => &mut self.poly_yes,
+            ("polymarket", "no") => &mut self.poly_no,
+            _ => {
+                warn!("[CB] Ignoring fill with unknown leg {}/{}", fill.platform, fill.side);
+                return;
+            }
+        };
+        *leg += fill.contracts;
+        self.cost_basis += fill.contracts * fill.price + fill.fees;
+    }
+
+    /// No contracts left on any leg (float residue from partial sells counts as none)
+    pub fn is_flat(&self) -> bool {
+        [self.kalshi_yes, self.kalshi_no, self.poly_yes, self.poly_no]
+            .iter()
+            .all(|c| c.abs() < FLAT_CONTRACTS)
+    }
+}
+
+/// Correlation groups a market belongs to, from discovery metadata
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketGroup {
+    /// Gamma event ID, or the slug's event prefix when discovery had no event metadata
+    pub event: String,
+    /// League code (normalized through `LeagueConfig`)
+    pub league: String,
+    pub game_date: NaiveDate,
+}
+
+impl MarketGroup {
+    /// Falls back to the slug's event prefix and date when discovery found no Gamma
+    /// event or start date; None when neither gives a game date.
+    pub fn of(pair: &MarketPair) -> Option<Self> {
+        Some(Self {
+            event: pair.event_id.as_deref().unwrap_or_else(|| pair.event_slug()).to_string(),
+            league: get_league_config(&pair.league)
+                .map(|c| c.league_code.to_string())
+                .unwrap_or_else(|| pair.league.to_string()),
+            game_date: pair.game_date.or_else(|| pair.slug_game_date())?,
+        })
     }
 }
 
+/// Breaker state as persisted to `state_file`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BreakerState {
+    pub halted: bool,
+    pub trip_reason: Option<TripReason>,
+    /// Operator halt, kept apart from breaker trips
+    #[serde(default)]
+    pub manual_halt: bool,
+    /// When the breaker tripped (Unix milliseconds)
+    pub tripped_at_ms: Option<u64>,
+    pub consecutive_errors: u32,
+    pub daily_pnl_cents: i64,
+    /// Trading day the daily P&L belongs to (YYYY-MM-DD, in `rollover_tz`)
+    pub trading_date: String,
+    pub positions: HashMap<String, MarketPosition>,
+    pub disabled_markets: HashMap<String, String>,
+}
+
 /// Circuit breaker state
 pub struct CircuitBreaker {
     config: CircuitBreakerConfig,
@@ -128,6 +456,10 @@ pub struct CircuitBreaker {
     
     /// Reason for trip
     trip_reason: RwLock<Option<TripReason>>,
+
+    /// Operator halt (kill switch): set and cleared only by the operator, and enforced
+    /// even with the breaker disabled
+    manual_halt: AtomicBool,
     
     /// Consecutive error count
     consecutive_errors: AtomicI64,
@@ -137,6 +469,24 @@ pub struct CircuitBreaker {
     
     /// Positions per market
     positions: RwLock<std::collections::HashMap<String, MarketPosition>>,
+
+    /// Markets disabled by market-specific exchange errors (market -> reason)
+    disabled_markets: RwLock<std::collections::HashMap<String, String>>,
+
+    /// Sliding windows for the behavioural breakers
+    behaviour: std::sync::Mutex<BehaviourMonitor>,
+
+    /// Event / league / date of each known market (market -> group; None = no event metadata)
+    groups: HashMap<String, Option<MarketGroup>>,
+
+    /// Trading day of daily_pnl_cents (days since the Common Era, in `rollover_tz`)
+    trading_day: AtomicI64,
+
+    /// State changed since the last save
+    dirty: AtomicBool,
+
+    /// Serializes saves so an older snapshot never overwrites a newer one
+    save_lock: Mutex<()>,
 }
 
 impl CircuitBreaker {
@@ -145,40 +495,221 @@ impl CircuitBreaker {
         info!("[CB]   Enabled: {}", config.enabled);
         info!("[CB]   Max position per market: {} contracts", config.max_position_per_market);
         info!("[CB]   Max total position: {} contracts", config.max_total_position);
+        info!("[CB]   Max unmatched: {} per market, {} total", config.max_unmatched_per_market, config.max_unmatched_total);
+        info!("[CB]   Max notional: ${:.2}/trade, ${:.2}/market, ${:.2} deployed, ${:.2} locked",
+              config.max_trade_notional, config.max_market_cost,
+              config.max_total_deployed, config.max_locked_capital);
+        info!("[CB]   Max correlated: ${:.2}/event, ${:.2}/date, ${:.2}/league ({} league overrides)",
+              config.max_event_cost, config.max_date_cost, config.max_league_cost,
+              config.league_cost_limits.len());
         info!("[CB]   Max daily loss: ${:.2}", config.max_daily_loss);
         info!("[CB]   Max consecutive errors: {}", config.max_consecutive_errors);
-        info!("[CB]   Cooldown: {}s", config.cooldown_secs);
+        info!("[CB]   Window {}s (min {} samples): reject ≤{:.0}%, mismatch ≤{:.0}%, p{:.0} latency ≤{:.0}ms, ≤{} orders/min",
+              config.behaviour.window_secs, config.behaviour.min_samples,
+              config.behaviour.max_reject_ratio * 100.0, config.behaviour.max_mismatch_ratio * 100.0,
+              config.behaviour.latency_percentile * 100.0, config.behaviour.max_latency_ms,
+              config.behaviour.max_orders_per_min);
+        info!("[CB]   Cooldown: {}s (auto-reset: {})", config.cooldown_secs,
+              if config.auto_reset.is_empty() { "none".to_string() } else { config.auto_reset.join(", ") });
+        info!("[CB]   Trading day starts at midnight {}", config.rollover_tz);
+        for class in ["max_daily_loss", "manual_halt"] {
+            if config.auto_reset.iter().any(|c| c == class) {
+                warn!("[CB] {} is a hard halt and never auto-resets", class);
+            }
+        }
         
+        let day = trading_day(config.rollover_tz);
+        let behaviour = BehaviourMonitor::new(config.behaviour.clone());
         Self {
             config,
             halted: AtomicBool::new(false),
             tripped_at: RwLock::new(None),
             trip_reason: RwLock::new(None),
+            manual_halt: AtomicBool::new(false),
             consecutive_errors: AtomicI64::new(0),
             daily_pnl_cents: AtomicI64::new(0),
             positions: RwLock::new(std::collections::HashMap::new()),
+            disabled_markets: RwLock::new(std::collections::HashMap::new()),
+            behaviour: std::sync::Mutex::new(behaviour),
+            groups: HashMap::new(),
+            trading_day: AtomicI64::new(day),
+            dirty: AtomicBool::new(false),
+            save_lock: Mutex::new(()),
+        }
+    }
+
+    /// Create and restore the state a previous run saved to `config.state_file`.
+    /// A new trading day clears the daily P&L; a halt stays in place with its cooldown
+    /// counted from the original trip.
+    pub fn load(config: CircuitBreakerConfig) -> Self {
+        let path = config.state_file.clone();
+        let mut breaker = Self::new(config);
+        if path.is_empty() {
+            return breaker;
+        }
+
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => match serde_json::from_str::<BreakerState>(&contents) {
+                Ok(state) => breaker.restore(state),
+                Err(e) => warn!("[CB] Ignoring unreadable state file {}: {}", path, e),
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                info!("[CB] No saved state at {}, starting fresh", path);
+            }
+            Err(e) => warn!("[CB] Cannot read state file {}: {}", path, e),
+        }
+        breaker
+    }
+
+    /// Register the markets' event, league and game date for correlated-exposure limits.
+    /// Markets without Gamma event metadata are grouped by their slug; one whose slug
+    /// carries no date either can't be grouped, so its trades are rejected - as are the
+    /// trades of markets never registered.
+    pub fn with_markets<'a>(mut self, pairs: impl IntoIterator<Item = &'a MarketPair>) -> Self {
+        for pair in pairs {
+            let group = MarketGroup::of(pair);
+            match &group {
+                None => error!("[CB] {} ({}) has no Gamma event or start date - rejecting its trades",
+                               pair.pair_id, pair.poly_slug),
+                Some(g) if pair.event_id.is_none() || pair.game_date.is_none() => {
+                    warn!("[CB] {} has no Gamma event metadata - grouping by slug ({} / {} / {})",
+                          pair.pair_id, g.event, g.league, g.game_date)
+                }
+                Some(_) => {}
+            }
+            self.groups.insert(pair.pair_id.to_string(), group);
+        }
+        self
+    }
+
+    fn restore(&mut self, state: BreakerState) {
+        let saved_day = NaiveDate::parse_from_str(&state.trading_date, "%Y-%m-%d")
+            .map(|d| d.num_days_from_ce() as i64)
+            .unwrap_or(i64::MIN);
+        if saved_day == trading_day(self.config.rollover_tz) {
+            *self.daily_pnl_cents.get_mut() = state.daily_pnl_cents;
+        } else {
+            info!("[CB] New trading day since {} - daily P&L reset (was ${:.2})",
+                  state.trading_date, state.daily_pnl_cents as f64 / 100.0);
+        }
+
+        *self.consecutive_errors.get_mut() = state.consecutive_errors as i64;
+        *self.positions.get_mut() = state.positions;
+        *self.disabled_markets.get_mut() = state.disabled_markets;
+
+        if state.manual_halt {
+            warn!("[CB] Restored MANUAL HALT - resume through the kill switch");
+            *self.manual_halt.get_mut() = true;
+        }
+
+        if state.halted {
+            let ago = state.tripped_at_ms
+                .map(|ms| Duration::from_millis(now_ms().saturating_sub(ms)))
+                .unwrap_or_default();
+            *self.halted.get_mut() = true;
+            *self.tripped_at.get_mut() = Some(Instant::now().checked_sub(ago).unwrap_or_else(Instant::now));
+            let reason = state.trip_reason.unwrap_or(TripReason::ManualHalt);
+            warn!("[CB] Restored HALTED state: {} (tripped {}s ago)", reason, ago.as_secs());
+            *self.trip_reason.get_mut() = Some(reason);
+        }
+
+        let positions = self.positions.get_mut();
+        info!("[CB] Restored state: {} markets, {} contracts, daily P&L ${:.2}, {} disabled markets",
+              positions.len(),
+              positions.values().map(|p| p.total_contracts()).sum::<i64>(),
+              *self.daily_pnl_cents.get_mut() as f64 / 100.0,
+              self.disabled_markets.get_mut().len());
+    }
+
+    /// Current state in its persisted form
+    pub async fn snapshot(&self) -> BreakerState {
+        let tripped_at_ms = self.tripped_at.read().await
+            .map(|t| now_ms().saturating_sub(t.elapsed().as_millis() as u64));
+        let trading_date = NaiveDate::from_num_days_from_ce_opt(self.trading_day.load(Ordering::SeqCst) as i32)
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .unwrap_or_default();
+
+        BreakerState {
+            halted: self.halted.load(Ordering::SeqCst),
+            trip_reason: self.trip_reason.read().await.clone(),
+            manual_halt: self.manual_halt.load(Ordering::SeqCst),
+            tripped_at_ms,
+            consecutive_errors: self.consecutive_errors.load(Ordering::SeqCst) as u32,
+            daily_pnl_cents: self.daily_pnl_cents.load(Ordering::SeqCst),
+            trading_date,
+            positions: self.positions.read().await.clone(),
+            disabled_markets: self.disabled_markets.read().await.clone(),
+        }
+    }
+
+    /// Write the current state to `state_file` (no-op without one)
+    pub async fn save(&self) -> Result<()> {
+        if self.config.state_file.is_empty() {
+            return Ok(());
+        }
+        let _guard = self.save_lock.lock().await;
+        self.dirty.store(false, Ordering::SeqCst);
+        let json = serde_json::to_string_pretty(&self.snapshot().await)?;
+
+        // Write-then-rename so a crash mid-write leaves the previous snapshot intact
+        let path = Path::new(&self.config.state_file);
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, json).await?;
+        tokio::fs::rename(&tmp, path).await?;
+        Ok(())
+    }
+
+    fn mark_dirty(&self) {
+        self.dirty.store(true, Ordering::SeqCst);
+    }
+
+    /// Start a new trading day if the date changed (in `rollover_tz`): clears daily P&L
+    pub fn roll_trading_day(&self) -> bool {
+        let today = trading_day(self.config.rollover_tz);
+        let previous = self.trading_day.swap(today, Ordering::SeqCst);
+        if previous == today {
+            return false;
         }
+        let pnl = self.daily_pnl_cents.swap(0, Ordering::SeqCst);
+        info!("[CB] New trading day - daily P&L reset (was ${:.2})", pnl as f64 / 100.0);
+        self.mark_dirty();
+        true
     }
     
     /// Check if trading is allowed
     #[allow(dead_code)]
     pub fn is_trading_allowed(&self) -> bool {
+        if self.manual_halt.load(Ordering::SeqCst) {
+            return false;
+        }
         if !self.config.enabled {
             return true;
         }
         !self.halted.load(Ordering::SeqCst)
     }
     
-    /// Check if we can execute a trade for a specific market
-    pub async fn can_execute(&self, market_id: &str, contracts: i64) -> Result<(), TripReason> {
+    /// Check if we can execute a trade for a specific market at the request's prices (cents)
+    pub async fn can_execute(&self, market_id: &str, contracts: i64, yes_price: u16, no_price: u16) -> Result<(), TripReason> {
+        if self.manual_halt.load(Ordering::SeqCst) {
+            return Err(TripReason::ManualHalt);
+        }
         if !self.config.enabled {
             return Ok(());
         }
+
+        self.roll_trading_day();
         
         if self.halted.load(Ordering::SeqCst) {
             let reason = self.trip_reason.read().await;
             return Err(reason.clone().unwrap_or(TripReason::ManualHalt));
         }
+
+        if let Some(reason) = self.disabled_markets.read().await.get(market_id) {
+            return Err(TripReason::MarketDisabled {
+                market: market_id.to_string(),
+                reason: reason.clone(),
+            });
+        }
         
         // Check position limits
         let positions = self.positions.read().await;
@@ -203,38 +734,186 @@ impl CircuitBreaker {
                 limit: self.config.max_total_position,
             });
         }
+
+        // Unhedged exposure limits: no new trades until the excess is closed
+        if let Some(pos) = positions.get(market_id) {
+            let unmatched = pos.unmatched();
+            if unmatched > self.config.max_unmatched_per_market {
+                return Err(TripReason::MaxUnmatchedPerMarket {
+                    market: market_id.to_string(),
+                    unmatched,
+                    limit: self.config.max_unmatched_per_market,
+                });
+            }
+        }
+
+        let unmatched_total: i64 = positions.values().map(|p| p.unmatched()).sum();
+        if unmatched_total > self.config.max_unmatched_total {
+            return Err(TripReason::MaxUnmatchedTotal {
+                unmatched: unmatched_total,
+                limit: self.config.max_unmatched_total,
+            });
+        }
         
-        // Daily loss limit
+        // Dollar-notional limits at the request's prices
+        let notional = contracts as f64 * (yes_price as f64 + no_price as f64) / 100.0;
+        if notional > self.config.max_trade_notional {
+            return Err(TripReason::MaxTradeNotional {
+                market: market_id.to_string(),
+                notional,
+                limit: self.config.max_trade_notional,
+            });
+        }
+
+        let market_cost = positions.get(market_id).map_or(0.0, |p| p.cost_basis) + notional;
+        if market_cost > self.config.max_market_cost {
+            return Err(TripReason::MaxMarketCost {
+                market: market_id.to_string(),
+                cost: market_cost,
+                limit: self.config.max_market_cost,
+            });
+        }
+
+        let deployed = positions.values().map(|p| p.cost_basis).sum::<f64>() + notional;
+        if deployed > self.config.max_total_deployed {
+            return Err(TripReason::MaxTotalDeployed {
+                deployed,
+                limit: self.config.max_total_deployed,
+            });
+        }
+
+        let locked = locked_capital(&positions) + notional;
+        if locked > self.config.max_locked_capital {
+            return Err(TripReason::MaxLockedCapital {
+                locked,
+                limit: self.config.max_locked_capital,
+            });
+        }
+        
+        if let Some(reason) = self.check_correlated(&positions, market_id, notional) {
+            return Err(reason);
+        }
+        
+        // Daily loss limit: a hard halt that outlives the day's rollover
         let daily_loss = -self.daily_pnl_cents.load(Ordering::SeqCst) as f64 / 100.0;
         if daily_loss > self.config.max_daily_loss {
-            return Err(TripReason::MaxDailyLoss {
+            drop(positions);
+            let reason = TripReason::MaxDailyLoss {
                 loss: daily_loss,
                 limit: self.config.max_daily_loss,
-            });
+            };
+            self.trip(reason.clone()).await;
+            return Err(reason);
         }
         
         Ok(())
     }
     
-    /// Record a successful execution
-    pub async fn record_success(&self, market_id: &str, kalshi_contracts: i64, poly_contracts: i64, pnl: f64) {
+    /// Unresolved cost basis on the market's event, league and game date, plus this trade
+    fn check_correlated(
+        &self,
+        positions: &HashMap<String, MarketPosition>,
+        market_id: &str,
+        notional: f64,
+    ) -> Option<TripReason> {
+        let Some(Some(group)) = self.groups.get(market_id) else {
+            return Some(TripReason::UngroupedMarket { market: market_id.to_string() });
+        };
+        let exposure = |same: &dyn Fn(&MarketGroup) -> bool| -> f64 {
+            positions.iter()
+                .filter(|(id, p)| !p.resolved && self.groups.get(*id).is_some_and(|g| g.as_ref().is_some_and(same)))
+                .map(|(_, p)| p.cost_basis)
+                .sum::<f64>() + notional
+        };
+
+        let cost = exposure(&|g| g.event == group.event);
+        if cost > self.config.max_event_cost {
+            return Some(TripReason::MaxEventExposure {
+                event: group.event.clone(),
+                cost,
+                limit: self.config.max_event_cost,
+            });
+        }
+
+        let limit = self.config.league_limit(&group.league);
+        let cost = exposure(&|g| g.league == group.league);
+        if cost > limit {
+            return Some(TripReason::MaxLeagueExposure { league: group.league.clone(), cost, limit });
+        }
+
+        let cost = exposure(&|g| g.game_date == group.game_date);
+        if cost > self.config.max_date_cost {
+            return Some(TripReason::MaxDateExposure {
+                date: group.game_date.to_string(),
+                cost,
+                limit: self.config.max_date_cost,
+            });
+        }
+        None
+    }
+
+    /// Record a successful execution (holdings come from `record_fill`)
+    pub fn record_success(&self, pnl: f64) {
+        self.roll_trading_day();
+
         // Reset consecutive errors
         self.consecutive_errors.store(0, Ordering::SeqCst);
         
         // Update P&L
         let pnl_cents = (pnl * 100.0) as i64;
         self.daily_pnl_cents.fetch_add(pnl_cents, Ordering::SeqCst);
-        
-        // Update positions
+        self.mark_dirty();
+    }
+
+    /// Record a fill on one leg - the same stream the position tracker consumes
+    pub async fn record_fill(&self, fill: &FillRecord) {
         let mut positions = self.positions.write().await;
-        let pos = positions.entry(market_id.to_string()).or_default();
-        pos.kalshi_yes += kalshi_contracts;
-        pos.poly_no += poly_contracts;
+        let pos = positions.entry(fill.market_id.clone()).or_default();
+        pos.apply(fill);
+        if pos.is_flat() {
+            positions.remove(&fill.market_id);
+        }
+        self.mark_dirty();
+    }
+
+    /// Mark a market resolved: its holdings stop counting as locked capital but stay
+    /// deployed until redeemed (a redemption is a sell fill at $1)
+    pub async fn resolve_market(&self, market_id: &str) {
+        if let Some(pos) = self.positions.write().await.get_mut(market_id) {
+            info!("[CB] Market {} resolved - ${:.2} no longer locked", market_id, pos.cost_basis);
+            pos.resolved = true;
+            self.mark_dirty();
+        }
+    }
+
+    /// Ask Gamma whether held markets have resolved and release their locked capital
+    pub async fn check_resolutions(&self, gamma: &GammaClient) {
+        let pending: Vec<String> = self.positions.read().await.iter()
+            .filter(|(_, p)| !p.resolved)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for market_id in pending {
+            // Discovery ids are "poly-{slug}"; other ids (replay, tests) have no Gamma market
+            let Some(slug) = market_id.strip_prefix("poly-") else { continue };
+            match gamma.lookup_resolution(slug).await {
+                Ok(Some(_)) => self.resolve_market(&market_id).await,
+                Ok(None) => {}
+                Err(e) => warn!("[CB] Resolution lookup for {} failed: {}", market_id, e),
+            }
+        }
+    }
+
+    /// Holdings for a single market
+    #[allow(dead_code)]
+    pub async fn position(&self, market_id: &str) -> MarketPosition {
+        self.positions.read().await.get(market_id).cloned().unwrap_or_default()
     }
     
     /// Record an error
     pub async fn record_error(&self) {
         let errors = self.consecutive_errors.fetch_add(1, Ordering::SeqCst) + 1;
+        self.mark_dirty();
         
         if errors >= self.config.max_consecutive_errors as i64 {
             self.trip(TripReason::ConsecutiveErrors {
@@ -244,11 +923,49 @@ impl CircuitBreaker {
         }
     }
     
+    /// Feed an order, fill or latency observation (at `at_ms` on the engine's clock)
+    /// to the sliding-window breakers; trips when one crosses its threshold
+    pub async fn record_behaviour(&self, at_ms: u64, event: BehaviourEvent) {
+        let tripped = self.behaviour.lock().unwrap_or_else(|e| e.into_inner()).record(at_ms, event);
+        if let Some(reason) = tripped {
+            if !self.halted.load(Ordering::SeqCst) {
+                self.trip(reason).await;
+            }
+        }
+    }
+
+    /// Stop trading a single market (e.g. closed or resolved) without halting others
+    pub async fn disable_market(&self, market_id: &str, reason: &str) {
+        if !self.config.enabled {
+            return;
+        }
+
+        let mut disabled = self.disabled_markets.write().await;
+        if !disabled.contains_key(market_id) {
+            warn!("[CB] Market {} disabled: {}", market_id, reason);
+            disabled.insert(market_id.to_string(), reason.to_string());
+            self.mark_dirty();
+        }
+    }
+
+    /// Re-enable a previously disabled market
+    #[allow(dead_code)]
+    pub async fn enable_market(&self, market_id: &str) -> bool {
+        let removed = self.disabled_markets.write().await.remove(market_id).is_some();
+        if removed {
+            info!("[CB] Market {} re-enabled", market_id);
+            self.mark_dirty();
+        }
+        removed
+    }
+
     /// Record P&L update (for tracking without execution)
     #[allow(dead_code)]
     pub fn record_pnl(&self, pnl: f64) {
+        self.roll_trading_day();
         let pnl_cents = (pnl * 100.0) as i64;
         self.daily_pnl_cents.fetch_add(pnl_cents, Ordering::SeqCst);
+        self.mark_dirty();
     }
 
     /// Trip the circuit breaker
@@ -262,13 +979,32 @@ impl CircuitBreaker {
         self.halted.store(true, Ordering::SeqCst);
         *self.tripped_at.write().await = Some(Instant::now());
         *self.trip_reason.write().await = Some(reason);
+
+        // Persist right away: a crash loop must not come back up un-halted
+        if let Err(e) = self.save().await {
+            error!("[CB] Failed to save halted state: {}", e);
+        }
     }
     
-    /// Manually halt trading
-    #[allow(dead_code)]
-    pub async fn halt(&self) {
-        warn!("[CB] Manual halt triggered");
-        self.trip(TripReason::ManualHalt).await;
+    /// Manually halt trading; `by` names who asked (signal, kill-file, operator).
+    /// Leaves any breaker trip and its reason in place.
+    pub async fn halt(&self, by: &str) {
+        warn!("[CB] Manual halt triggered by {}", by);
+        self.manual_halt.store(true, Ordering::SeqCst);
+        if let Err(e) = self.save().await {
+            error!("[CB] Failed to save halted state: {}", e);
+        }
+    }
+
+    /// Clear a manual halt; a breaker trip stays in force. Returns whether one was set.
+    pub async fn clear_manual_halt(&self) -> bool {
+        if !self.manual_halt.swap(false, Ordering::SeqCst) {
+            return false;
+        }
+        if let Err(e) = self.save().await {
+            error!("[CB] Failed to save resumed state: {}", e);
+        }
+        true
     }
 
     /// Reset the circuit breaker (after cooldown or manual reset)
@@ -279,6 +1015,11 @@ impl CircuitBreaker {
         *self.tripped_at.write().await = None;
         *self.trip_reason.write().await = None;
         self.consecutive_errors.store(0, Ordering::SeqCst);
+        self.behaviour.lock().unwrap_or_else(|e| e.into_inner()).clear();
+
+        if let Err(e) = self.save().await {
+            error!("[CB] Failed to save reset state: {}", e);
+        }
     }
 
     /// Reset daily P&L (call at midnight)
@@ -286,19 +1027,26 @@ impl CircuitBreaker {
     pub fn reset_daily_pnl(&self) {
         info!("[CB] Daily P&L reset");
         self.daily_pnl_cents.store(0, Ordering::SeqCst);
+        self.mark_dirty();
     }
 
-    /// Check if cooldown has elapsed and auto-reset if so
-    #[allow(dead_code)]
+    /// Check if cooldown has elapsed and auto-reset if the trip reason is recoverable
     pub async fn check_cooldown(&self) -> bool {
         if !self.halted.load(Ordering::SeqCst) {
             return true;
         }
 
+        let recoverable = self.trip_reason.read().await.as_ref()
+            .is_some_and(|reason| self.config.is_recoverable(reason));
+        if !recoverable {
+            return false;
+        }
+
         let tripped_at = self.tripped_at.read().await;
         if let Some(tripped) = *tripped_at {
             if tripped.elapsed() > Duration::from_secs(self.config.cooldown_secs) {
                 drop(tripped_at); // Release read lock before reset
+                info!("[CB] Cooldown of {}s elapsed - auto-resetting", self.config.cooldown_secs);
                 self.reset().await;
                 return true;
             }
@@ -312,48 +1060,135 @@ impl CircuitBreaker {
     pub async fn status(&self) -> CircuitBreakerStatus {
         let positions = self.positions.read().await;
         let total_position: i64 = positions.values().map(|p| p.total_contracts()).sum();
+        let unmatched_position: i64 = positions.values().map(|p| p.unmatched()).sum();
+        let deployed = positions.values().map(|p| p.cost_basis).sum();
         
         CircuitBreakerStatus {
             enabled: self.config.enabled,
             halted: self.halted.load(Ordering::SeqCst),
             trip_reason: self.trip_reason.read().await.clone(),
+            manual_halt: self.manual_halt.load(Ordering::SeqCst),
             consecutive_errors: self.consecutive_errors.load(Ordering::SeqCst) as u32,
             daily_pnl: self.daily_pnl_cents.load(Ordering::SeqCst) as f64 / 100.0,
             total_position,
+            unmatched_position,
+            deployed,
+            locked: locked_capital(&positions),
             market_count: positions.len(),
         }
     }
 }
 
+/// Flush breaker state to disk whenever it changed
+pub async fn persist_loop(breaker: Arc<CircuitBreaker>) {
+    if breaker.config.state_file.is_empty() {
+        return;
+    }
+    let mut interval = tokio::time::interval(Duration::from_millis(PERSIST_INTERVAL_MS));
+    loop {
+        interval.tick().await;
+        if breaker.dirty.load(Ordering::SeqCst) {
+            if let Err(e) = breaker.save().await {
+                warn!("[CB] Failed to save state to {}: {}", breaker.config.state_file, e);
+            }
+        }
+    }
+}
+
+/// Cost basis held in markets that have not resolved
+fn locked_capital(positions: &HashMap<String, MarketPosition>) -> f64 {
+    positions.values().filter(|p| !p.resolved).map(|p| p.cost_basis).sum()
+}
+
+/// Release locked capital as held markets resolve
+pub async fn resolution_loop(breaker: Arc<CircuitBreaker>, gamma: GammaClient) {
+    if !breaker.config.enabled {
+        return;
+    }
+    let mut interval = tokio::time::interval(Duration::from_secs(RESOLUTION_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+        breaker.check_resolutions(&gamma).await;
+    }
+}
+
+/// Roll the trading day and auto-reset recoverable halts once their cooldown elapses
+pub async fn supervisor_loop(breaker: Arc<CircuitBreaker>) {
+    if !breaker.config.enabled {
+        return;
+    }
+    let mut interval = tokio::time::interval(Duration::from_millis(SUPERVISOR_INTERVAL_MS));
+    loop {
+        interval.tick().await;
+        breaker.roll_trading_day();
+        breaker.check_cooldown().await;
+    }
+}
+
+/// Parse an IANA timezone name such as "America/New_York" (or "UTC")
+pub fn parse_rollover_tz(s: &str) -> Option<Tz> {
+    s.trim().parse().ok()
+}
+
+/// Calendar date in `tz` at instant `at`
+pub fn trading_date(tz: Tz, at: DateTime<Utc>) -> NaiveDate {
+    at.with_timezone(&tz).date_naive()
+}
+
+/// Today's trading day in `tz` (days since the Common Era)
+fn trading_day(tz: Tz) -> i64 {
+    trading_date(tz, Utc::now()).num_days_from_ce() as i64
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub struct CircuitBreakerStatus {
     pub enabled: bool,
     pub halted: bool,
     pub trip_reason: Option<TripReason>,
+    /// Halted by the operator (independent of `enabled` and of breaker trips)
+    pub manual_halt: bool,
     pub consecutive_errors: u32,
     pub daily_pnl: f64,
     pub total_position: i64,
+    pub unmatched_position: i64,
+    /// Cost basis of all holdings (dollars)
+    pub deployed: f64,
+    /// Cost basis in unresolved markets (dollars)
+    pub locked: f64,
     pub market_count: usize,
 }
 
 impl std::fmt::Display for CircuitBreakerStatus {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if !self.enabled {
-            return write!(f, "Circuit Breaker: DISABLED");
+            write!(f, "Circuit Breaker: DISABLED")?;
+            if self.manual_halt {
+                write!(f, " | 🛑 MANUAL HALT")?;
+            }
+            return Ok(());
         }
         
-        if self.halted {
+        if self.halted || self.manual_halt {
             write!(f, "Circuit Breaker: 🛑 HALTED")?;
-            if let Some(reason) = &self.trip_reason {
-                write!(f, " ({})", reason)?;
+            let reasons: Vec<String> = self.manual_halt.then(|| TripReason::ManualHalt.to_string())
+                .into_iter()
+                .chain(self.trip_reason.as_ref().map(|r| r.to_string()))
+                .collect();
+            if !reasons.is_empty() {
+                write!(f, " ({})", reasons.join("; "))?;
             }
         } else {
             write!(f, "Circuit Breaker: ✅ OK")?;
         }
         
-        write!(f, " | P&L: ${:.2} | Pos: {} contracts across {} markets | Errors: {}",
-               self.daily_pnl, self.total_position, self.market_count, self.consecutive_errors)
+        write!(f, " | P&L: ${:.2} | Pos: {} contracts across {} markets ({} unmatched) | Deployed: ${:.2} (${:.2} locked) | Errors: {}",
+               self.daily_pnl, self.total_position, self.market_count, self.unmatched_position,
+               self.deployed, self.locked, self.consecutive_errors)
     }
 }
 
@@ -366,22 +1201,38 @@ mod tests {
         let config = CircuitBreakerConfig {
             max_position_per_market: 10,
             max_total_position: 50,
+            max_unmatched_per_market: 10,
+            max_unmatched_total: 50,
+            max_trade_notional: 1000.0,
+            max_market_cost: 1000.0,
+            max_total_deployed: 1000.0,
+            max_locked_capital: 1000.0,
+            max_event_cost: 1000.0,
+            max_date_cost: 1000.0,
+            max_league_cost: 1000.0,
+            league_cost_limits: HashMap::new(),
             max_daily_loss: 100.0,
             max_consecutive_errors: 3,
+            behaviour: BehaviourConfig::from_env(),
             cooldown_secs: 60,
+            auto_reset: vec!["consecutive_errors".to_string()],
+            rollover_tz: Tz::UTC,
             enabled: true,
+            state_file: String::new(),
         };
         
         let cb = CircuitBreaker::new(config);
         
         // Should allow initial trade
-        assert!(cb.can_execute("market1", 5).await.is_ok());
+        assert!(cb.can_execute("market1", 5, 45, 50).await.is_ok());
         
         // Record the trade
-        cb.record_success("market1", 5, 5, 0.0).await;
+        cb.record_fill(&FillRecord::new("market1", "Market 1", "polymarket", "yes", 5.0, 0.45, 0.0, "y1")).await;
+        cb.record_fill(&FillRecord::new("market1", "Market 1", "polymarket", "no", 5.0, 0.50, 0.0, "n1")).await;
+        cb.record_success(0.0);
         
         // Should reject trade exceeding per-market limit
-        let result = cb.can_execute("market1", 10).await;
+        let result = cb.can_execute("market1", 10, 45, 50).await;
         assert!(matches!(result, Err(TripReason::MaxPositionPerMarket { .. })));
     }
     
@@ -390,10 +1241,24 @@ mod tests {
         let config = CircuitBreakerConfig {
             max_position_per_market: 100,
             max_total_position: 500,
+            max_unmatched_per_market: 100,
+            max_unmatched_total: 500,
+            max_trade_notional: 1000.0,
+            max_market_cost: 1000.0,
+            max_total_deployed: 1000.0,
+            max_locked_capital: 1000.0,
+            max_event_cost: 1000.0,
+            max_date_cost: 1000.0,
+            max_league_cost: 1000.0,
+            league_cost_limits: HashMap::new(),
             max_daily_loss: 100.0,
             max_consecutive_errors: 3,
+            behaviour: BehaviourConfig::from_env(),
             cooldown_secs: 60,
+            auto_reset: vec!["consecutive_errors".to_string()],
+            rollover_tz: Tz::UTC,
             enabled: true,
+            state_file: String::new(),
         };
         
         let cb = CircuitBreaker::new(config);
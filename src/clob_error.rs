@@ -0,0 +1,260 @@
+// src/clob_error.rs
+// Typed CLOB errors and per-class retry / circuit breaker policy
+
+use std::time::Duration;
+
+/// Error class parsed from a CLOB response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ClobErrorKind {
+    /// Not enough USDC (or outcome tokens when selling)
+    InsufficientBalance,
+    /// Exchange contract not approved to spend collateral
+    InsufficientAllowance,
+    /// Price breaks the market's minimum tick size
+    InvalidTickSize,
+    /// Other order validation failures (min size, duplicate, expiration)
+    InvalidOrder,
+    /// Order signature rejected (wrong signer, funder or signature type)
+    InvalidSignature,
+    /// FAK/FOK order found nothing to match against
+    NoMatch,
+    /// Market closed, resolved or not accepting orders
+    MarketClosed,
+    /// Order lookup failed (404, unknown order ID); says nothing about the market
+    OrderQuery,
+    /// HTTP 429 / throttled by the exchange
+    RateLimited,
+    /// API key expired or rejected
+    AuthExpired,
+    /// Exchange-side 5xx
+    ServerError,
+    /// Request never got a response (connect/timeout)
+    Transport,
+    Unknown,
+}
+
+/// What a failed execution does to the circuit breaker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerAction {
+    /// Not a fault (e.g. liquidity gone before our FAK arrived)
+    Ignore,
+    /// Counts toward max_consecutive_errors
+    CountError,
+    /// Stop trading this market only
+    DisableMarket,
+    /// Halt all trading immediately
+    Halt,
+}
+
+/// Retry and circuit breaker policy for one error class
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorPolicy {
+    /// Retries for idempotent requests (order queries, lookups)
+    pub max_retries: u32,
+    /// Base backoff, multiplied by the attempt number
+    pub backoff: Duration,
+    pub breaker: BreakerAction,
+}
+
+impl ErrorPolicy {
+    const fn new(max_retries: u32, backoff_ms: u64, breaker: BreakerAction) -> Self {
+        Self { max_retries, backoff: Duration::from_millis(backoff_ms), breaker }
+    }
+
+    /// Retries allowed for this request type. FAK order posts are never retried.
+    #[inline]
+    pub fn retries_for(&self, is_post: bool) -> u32 {
+        if is_post { 0 } else { self.max_retries }
+    }
+}
+
+impl ClobErrorKind {
+    pub fn policy(self) -> ErrorPolicy {
+        use BreakerAction::*;
+        match self {
+            ClobErrorKind::InsufficientBalance => ErrorPolicy::new(0, 0, Halt),
+            ClobErrorKind::InsufficientAllowance => ErrorPolicy::new(0, 0, Halt),
+            ClobErrorKind::InvalidSignature => ErrorPolicy::new(0, 0, Halt),
+            ClobErrorKind::AuthExpired => ErrorPolicy::new(0, 0, Halt),
+            ClobErrorKind::MarketClosed => ErrorPolicy::new(0, 0, DisableMarket),
+            ClobErrorKind::InvalidTickSize => ErrorPolicy::new(0, 0, DisableMarket),
+            ClobErrorKind::OrderQuery => ErrorPolicy::new(2, 100, CountError),
+            ClobErrorKind::NoMatch => ErrorPolicy::new(0, 0, Ignore),
+            ClobErrorKind::InvalidOrder => ErrorPolicy::new(0, 0, CountError),
+            ClobErrorKind::RateLimited => ErrorPolicy::new(3, 250, Ignore),
+            ClobErrorKind::ServerError => ErrorPolicy::new(2, 100, CountError),
+            ClobErrorKind::Transport => ErrorPolicy::new(2, 100, CountError),
+            ClobErrorKind::Unknown => ErrorPolicy::new(0, 0, CountError),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClobErrorKind::InsufficientBalance => "Insufficient balance",
+            ClobErrorKind::InsufficientAllowance => "Insufficient allowance",
+            ClobErrorKind::InvalidTickSize => "Invalid tick size",
+            ClobErrorKind::InvalidOrder => "Invalid order",
+            ClobErrorKind::InvalidSignature => "Invalid signature",
+            ClobErrorKind::NoMatch => "No match",
+            ClobErrorKind::MarketClosed => "Market closed",
+            ClobErrorKind::OrderQuery => "Order query failed",
+            ClobErrorKind::RateLimited => "Rate limited",
+            ClobErrorKind::AuthExpired => "Auth expired",
+            ClobErrorKind::ServerError => "Server error",
+            ClobErrorKind::Transport => "Transport error",
+            ClobErrorKind::Unknown => "Unknown error",
+        }
+    }
+
+    /// Classify an order-post rejection (and HTTP status, when there is one)
+    pub fn classify(status: Option<u16>, message: &str) -> Self {
+        match status {
+            Some(429) => return ClobErrorKind::RateLimited,
+            Some(401) => return ClobErrorKind::AuthExpired,
+            _ => {}
+        }
+
+        let msg = message.to_ascii_lowercase();
+        if msg.contains("allowance") && !msg.contains("balance") {
+            ClobErrorKind::InsufficientAllowance
+        } else if msg.contains("not enough balance") || msg.contains("insufficient balance") {
+            ClobErrorKind::InsufficientBalance
+        } else if msg.contains("tick size") {
+            ClobErrorKind::InvalidTickSize
+        } else if msg.contains("no orders found to match") || msg.contains("couldn't be fully filled") {
+            ClobErrorKind::NoMatch
+        } else if msg.contains("market is closed")
+            || msg.contains("market closed")
+            || msg.contains("closed market")
+            || msg.contains("not accepting orders")
+            || msg.contains("does not exist")
+            || msg.contains("market not ready")
+        {
+            ClobErrorKind::MarketClosed
+        } else if msg.contains("too many requests") || msg.contains("rate limit") {
+            ClobErrorKind::RateLimited
+        } else if msg.contains("invalid signature") {
+            ClobErrorKind::InvalidSignature
+        } else if msg.contains("api key") || msg.contains("unauthorized") {
+            ClobErrorKind::AuthExpired
+        } else if msg.contains("invalid") || msg.contains("duplicated") || msg.contains("minimum") {
+            ClobErrorKind::InvalidOrder
+        } else if matches!(status, Some(s) if s >= 500) {
+            ClobErrorKind::ServerError
+        } else {
+            ClobErrorKind::Unknown
+        }
+    }
+
+    /// Classify a failed query or cancel. Only transport-level classes carry over:
+    /// "not found" / "does not exist" here is about the order, never the market.
+    pub fn classify_query(status: Option<u16>, message: &str) -> Self {
+        match status {
+            Some(429) => ClobErrorKind::RateLimited,
+            Some(401) => ClobErrorKind::AuthExpired,
+            Some(s) if s >= 500 => ClobErrorKind::ServerError,
+            _ => match Self::classify(status, message) {
+                kind @ (ClobErrorKind::RateLimited | ClobErrorKind::AuthExpired) => kind,
+                _ => ClobErrorKind::OrderQuery,
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for ClobErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Error returned by the CLOB (HTTP error or per-order rejection)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClobError {
+    pub kind: ClobErrorKind,
+    /// HTTP status, None for per-order rejections inside a 200 response
+    pub status: Option<u16>,
+    pub message: String,
+}
+
+impl ClobError {
+    /// Parse a non-2xx order-post response body ({"error": "..."} or plain text)
+    pub fn from_response(status: u16, body: &str) -> Self {
+        let message = error_message(body);
+        Self {
+            kind: ClobErrorKind::classify(Some(status), &message),
+            status: Some(status),
+            message,
+        }
+    }
+
+    /// Parse a non-2xx response to an order query, listing or cancel
+    pub fn from_query_response(status: u16, body: &str) -> Self {
+        let message = error_message(body);
+        Self {
+            kind: ClobErrorKind::classify_query(Some(status), &message),
+            status: Some(status),
+            message,
+        }
+    }
+
+    /// Per-order rejection (success=false with errorMsg)
+    pub fn rejected(error_msg: &str) -> Self {
+        Self {
+            kind: ClobErrorKind::classify(None, error_msg),
+            status: None,
+            message: error_msg.to_string(),
+        }
+    }
+
+    /// Class of any error from the CLOB client (transport failures included)
+    pub fn kind_of(err: &anyhow::Error) -> ClobErrorKind {
+        if let Some(e) = err.downcast_ref::<ClobError>() {
+            e.kind
+        } else if err.downcast_ref::<reqwest::Error>().is_some() {
+            ClobErrorKind::Transport
+        } else {
+            ClobErrorKind::Unknown
+        }
+    }
+
+    /// Typed view of any CLOB client error
+    pub fn from_anyhow(err: &anyhow::Error) -> Self {
+        match err.downcast_ref::<ClobError>() {
+            Some(e) => e.clone(),
+            None => Self {
+                kind: Self::kind_of(err),
+                status: None,
+                message: err.to_string(),
+            },
+        }
+    }
+
+    #[inline]
+    pub fn policy(&self) -> ErrorPolicy {
+        self.kind.policy()
+    }
+}
+
+/// Error text from a response body ({"error": "..."}, {"errorMsg": "..."} or plain text)
+fn error_message(body: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| {
+            v.get("error")
+                .or_else(|| v.get("errorMsg"))
+                .and_then(|m| m.as_str())
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| body.trim().to_string())
+}
+
+impl std::fmt::Display for ClobError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.status {
+            Some(status) => write!(f, "{} ({}): {}", self.kind, status, self.message),
+            None => write!(f, "{}: {}", self.kind, self.message),
+        }
+    }
+}
+
+impl std::error::Error for ClobError {}
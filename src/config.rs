@@ -1,12 +1,38 @@
 // src/config.rs
 // Configuration constants and league mappings
 
+/// Polymarket CLOB API host
+pub const POLY_CLOB_HOST: &str = "https://clob.polymarket.com";
+
 /// Polymarket WebSocket URL
 pub const POLYMARKET_WS_URL: &str = "wss://ws-subscriptions-clob.polymarket.com/ws/market";
 
 /// Gamma API base URL (Polymarket market data)
 pub const GAMMA_API_BASE: &str = "https://gamma-api.polymarket.com";
 
+/// CLOB host (POLY_CLOB_HOST overrides, e.g. to point at the local emulator)
+pub fn poly_clob_host() -> String {
+    env_or("POLY_CLOB_HOST", POLY_CLOB_HOST)
+}
+
+/// Market WebSocket URL (POLY_WS_URL overrides)
+pub fn polymarket_ws_url() -> String {
+    env_or("POLY_WS_URL", POLYMARKET_WS_URL)
+}
+
+/// Gamma API base URL (GAMMA_API_BASE overrides)
+pub fn gamma_api_base() -> String {
+    env_or("GAMMA_API_BASE", GAMMA_API_BASE)
+}
+
+fn env_or(key: &str, default: &str) -> String {
+    std::env::var(key)
+        .ok()
+        .map(|v| v.trim().trim_end_matches('/').to_string())
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| default.to_string())
+}
+
 /// Arb threshold: alert when total cost < this (e.g., 0.995 = 0.5% profit)
 pub const ARB_THRESHOLD: f64 = 0.995;
 
@@ -16,6 +42,31 @@ pub const POLY_PING_INTERVAL_SECS: u64 = 30;
 /// WebSocket reconnect delay (seconds)
 pub const WS_RECONNECT_DELAY_SECS: u64 = 5;
 
+/// Oldest quote (either side) detection and execution will act on (MAX_QUOTE_AGE_MS, 0 disables)
+pub fn max_quote_age_ms() -> u64 {
+    std::env::var("MAX_QUOTE_AGE_MS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(60_000)
+}
+
+/// Silence after which a token is resubscribed for a fresh snapshot (WS_QUIET_RESYNC_SECS, 0 disables).
+/// Keep it under MAX_QUOTE_AGE_MS so a quiet book is resnapshotted before it goes stale.
+pub fn ws_quiet_resync_secs() -> u64 {
+    std::env::var("WS_QUIET_RESYNC_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(30)
+}
+
+/// Longest a shutdown waits for in-flight orders and auto-closes (SHUTDOWN_TIMEOUT_SECS)
+pub fn shutdown_timeout_secs() -> u64 {
+    std::env::var("SHUTDOWN_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(30)
+}
+
 /// Which leagues to monitor (empty slice = all)
 pub const ENABLED_LEAGUES: &[&str] = &[];
 
@@ -30,11 +81,32 @@ pub fn price_logging_enabled() -> bool {
     })
 }
 
-/// League configuration for market discovery
+/// League configuration for market discovery and breaker limits
 #[derive(Debug, Clone)]
 pub struct LeagueConfig {
     pub league_code: &'static str,
     pub poly_prefix: &'static str,
+    /// Breaker limit on unresolved cost basis in this league (dollars); None = CB_MAX_LEAGUE_COST
+    pub max_cost: Option<f64>,
+}
+
+/// Check the league table at startup: unique codes and prefixes, positive cost limits
+pub fn validate_league_configs(configs: &[LeagueConfig]) -> anyhow::Result<()> {
+    for (i, config) in configs.iter().enumerate() {
+        for other in &configs[..i] {
+            for name in [config.league_code, config.poly_prefix] {
+                if name == other.league_code || name == other.poly_prefix {
+                    anyhow::bail!("leagues {} and {} both use \"{}\"", other.league_code, config.league_code, name);
+                }
+            }
+        }
+        if let Some(limit) = config.max_cost {
+            if !(limit.is_finite() && limit > 0.0) {
+                anyhow::bail!("league {}: max_cost must be a positive dollar amount, got {}", config.league_code, limit);
+            }
+        }
+    }
+    Ok(())
 }
 
 /// Get all supported leagues with their configurations
@@ -43,58 +115,72 @@ pub fn get_league_configs() -> Vec<LeagueConfig> {
         LeagueConfig {
             league_code: "epl",
             poly_prefix: "epl",
+            max_cost: None,
         },
         LeagueConfig {
             league_code: "bundesliga",
             poly_prefix: "bun",
+            max_cost: None,
         },
         LeagueConfig {
             league_code: "laliga",
             poly_prefix: "lal",
+            max_cost: None,
         },
         LeagueConfig {
             league_code: "seriea",
             poly_prefix: "sea",
+            max_cost: None,
         },
         LeagueConfig {
             league_code: "ligue1",
             poly_prefix: "fl1",
+            max_cost: None,
         },
         LeagueConfig {
             league_code: "ucl",
             poly_prefix: "ucl",
+            max_cost: None,
         },
         LeagueConfig {
             league_code: "uel",
             poly_prefix: "uel",
+            max_cost: None,
         },
         LeagueConfig {
             league_code: "eflc",
             poly_prefix: "elc",
+            max_cost: None,
         },
         LeagueConfig {
             league_code: "nba",
             poly_prefix: "nba",
+            max_cost: None,
         },
         LeagueConfig {
             league_code: "nfl",
             poly_prefix: "nfl",
+            max_cost: None,
         },
         LeagueConfig {
             league_code: "nhl",
             poly_prefix: "nhl",
+            max_cost: None,
         },
         LeagueConfig {
             league_code: "mlb",
             poly_prefix: "mlb",
+            max_cost: None,
         },
         LeagueConfig {
             league_code: "mls",
             poly_prefix: "mls",
+            max_cost: None,
         },
         LeagueConfig {
             league_code: "ncaaf",
             poly_prefix: "cfb",
+            max_cost: None,
         },
     ]
 }
@@ -9,6 +9,7 @@ use tracing::{info, warn};
 
 use crate::config::{LeagueConfig, get_league_configs, get_league_config};
 use crate::polymarket::GammaClient;
+use crate::rate_limit::RateLimiter;
 use crate::types::{MarketPair, MarketType, DiscoveryResult};
 
 /// Max concurrent Gamma API requests
@@ -56,6 +57,18 @@ impl DiscoveryCache {
     fn has_slug(&self, slug: &str) -> bool {
         self.known_poly_slugs.iter().any(|s| s == slug)
     }
+
+    /// Some pair was cached without Gamma event metadata (by an older version)
+    fn has_ungrouped(&self) -> bool {
+        self.pairs.iter().any(|p| p.event_id.is_none() || p.game_date.is_none())
+    }
+
+    /// Drop pairs cached without Gamma event metadata so they are looked up again
+    fn without_ungrouped(mut self) -> Self {
+        self.pairs.retain(|p| p.event_id.is_some() && p.game_date.is_some());
+        self.known_poly_slugs = self.pairs.iter().map(|p| p.poly_slug.to_string()).collect();
+        self
+    }
 }
 
 fn current_unix_secs() -> u64 {
@@ -71,12 +84,20 @@ pub struct DiscoveryClient {
 }
 
 impl DiscoveryClient {
+    #[allow(dead_code)]
     pub fn new() -> Self {
         Self {
             gamma: Arc::new(GammaClient::new()),
         }
     }
 
+    /// Discovery client whose Gamma requests share the process-wide rate limiter
+    pub fn with_rate_limiter(limiter: Arc<RateLimiter>) -> Self {
+        Self {
+            gamma: Arc::new(GammaClient::with_rate_limiter(limiter)),
+        }
+    }
+
     /// Load cache from disk (async)
     async fn load_cache() -> Option<DiscoveryCache> {
         let data = tokio::fs::read_to_string(DISCOVERY_CACHE_PATH).await.ok()?;
@@ -102,7 +123,7 @@ impl DiscoveryClient {
         let cached = Self::load_cache().await;
 
         match cached {
-            Some(cache) if !cache.is_expired() => {
+            Some(cache) if !cache.is_expired() && !cache.has_ungrouped() => {
                 // Cache is fresh - use it directly
                 let pair_count = cache.pairs.len();
                 info!("📂 Loaded {} pairs from cache (age: {}s)",
@@ -116,7 +137,7 @@ impl DiscoveryClient {
                 };
             }
             Some(cache) => {
-                // Cache is stale - do incremental discovery
+                // Cache is stale or lacks event metadata - do incremental discovery
                 info!("📂 Cache expired (age: {}s), doing incremental refresh...", cache.age_secs());
                 return self.discover_incremental(leagues, cache).await;
             }
@@ -191,6 +212,7 @@ impl DiscoveryClient {
 
     /// Incremental discovery - merge cached pairs with newly discovered ones
     async fn discover_incremental(&self, leagues: &[&str], cache: DiscoveryCache) -> DiscoveryResult {
+        let cache = cache.without_ungrouped();
         let configs: Vec<_> = if leagues.is_empty() {
             get_league_configs()
         } else {
@@ -271,7 +293,7 @@ impl DiscoveryClient {
                 }
                 
                 match self.gamma.lookup_market(slug).await {
-                    Ok(Some((yes_token, no_token, description))) => {
+                    Ok(Some(info)) => {
                         // Extract market info from slug
                         let parts: Vec<&str> = slug.split('-').collect();
                         let league = if parts.len() > 0 { parts[0] } else { &config.league_code };
@@ -280,12 +302,14 @@ impl DiscoveryClient {
                             pair_id: format!("poly-{}", slug).into(),
                             league: league.into(),
                             market_type: MarketType::Moneyline, // Default to moneyline
-                            description: description.into(),
+                            description: info.description.into(),
                             poly_slug: slug.into(),
-                            poly_yes_token: yes_token.into(),
-                            poly_no_token: no_token.into(),
+                            poly_yes_token: info.yes_token.into(),
+                            poly_no_token: info.no_token.into(),
                             line_value: None,
                             team_suffix: None,
+                            event_id: info.event_id.map(Into::into),
+                            game_date: info.start_date,
                         };
                         
                         result.pairs.push(pair);
@@ -332,6 +356,8 @@ impl DiscoveryClient {
                             poly_no_token: no_token.into(),
                             line_value: None,
                             team_suffix: None,
+                            event_id: None,
+                            game_date: None,
                         };
                         
                         result.pairs.push(pair);
@@ -0,0 +1,811 @@
+// src/emulator.rs
+// Local CLOB emulator (REST + market WebSocket) for end-to-end tests
+
+use anyhow::{Context, Result, anyhow};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE;
+use ethers::types::{Address, Signature};
+use ethers::utils::keccak256;
+use futures_util::{SinkExt, StreamExt};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, HeaderMap, Method, Request, Response, Server, StatusCode};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+use tokio_tungstenite::WebSocketStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::Role;
+use tracing::{debug, info, warn};
+
+use crate::paper::{BookSide, DepthBook};
+use crate::polymarket_clob::{
+    ApiCreds, OrderStruct, PolyOrderType, PreparedCreds, SignedOrder, clob_auth_digest, get_exchange_address,
+};
+use crate::types::{PriceCents, cents_to_price, price_to_cents};
+
+/// Max age of an L1/L2 auth timestamp (seconds)
+const AUTH_WINDOW_SECS: u64 = 300;
+/// Unlimited ERC-20 approval, as returned for approved exchanges
+const MAX_ALLOWANCE: &str = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+
+/// Emulator configuration from environment
+#[derive(Debug, Clone)]
+pub struct EmulatorConfig {
+    /// Listen address for REST and WS (EMULATOR_ADDR, port 0 picks a free port)
+    pub addr: SocketAddr,
+    /// Chain used to verify signatures (must match the bot's)
+    pub chain_id: u64,
+    /// Starting USDC balance of the account
+    pub balance_usdc: f64,
+    pub script: EmulatorScript,
+}
+
+impl EmulatorConfig {
+    pub fn from_env() -> Result<Self> {
+        let script = match std::env::var("EMULATOR_SCRIPT") {
+            Ok(path) => EmulatorScript::load(&path)?,
+            Err(_) => EmulatorScript::default(),
+        };
+
+        Ok(Self {
+            addr: std::env::var("EMULATOR_ADDR")
+                .unwrap_or_else(|_| "127.0.0.1:8080".to_string())
+                .parse()
+                .context("EMULATOR_ADDR must be host:port")?,
+
+            chain_id: std::env::var("EMULATOR_CHAIN_ID")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(137),
+
+            balance_usdc: std::env::var("EMULATOR_BALANCE_USDC")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10_000.0),
+
+            script,
+        })
+    }
+}
+
+/// Markets, starting books and timed book updates served by the emulator
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EmulatorScript {
+    #[serde(default)]
+    pub markets: Vec<ScriptMarket>,
+    #[serde(default)]
+    pub updates: Vec<ScriptUpdate>,
+    #[serde(default)]
+    pub faults: ScriptFaults,
+}
+
+impl EmulatorScript {
+    pub fn load(path: &str) -> Result<Self> {
+        let data = std::fs::read_to_string(path).with_context(|| format!("reading {}", path))?;
+        serde_json::from_str(&data).with_context(|| format!("parsing {}", path))
+    }
+}
+
+/// One binary market as listed by Gamma
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScriptMarket {
+    pub slug: String,
+    pub question: String,
+    pub yes_token: String,
+    pub no_token: String,
+    #[serde(default)]
+    pub neg_risk: bool,
+    #[serde(default)]
+    pub fee_rate_bps: u16,
+    #[serde(default)]
+    pub yes_book: ScriptBook,
+    #[serde(default)]
+    pub no_book: ScriptBook,
+    /// Resolved outcome (true = YES won); Gamma reports the market closed once set
+    #[serde(default)]
+    pub outcome: Option<bool>,
+    /// Gamma event (game) the market belongs to and its start, e.g. "2026-01-15T00:30:00Z"
+    #[serde(default)]
+    pub event_id: Option<String>,
+    #[serde(default)]
+    pub start_time: Option<String>,
+}
+
+/// Starting book: (price, size) strings, e.g. ("0.45", "100")
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ScriptBook {
+    #[serde(default)]
+    pub bids: Vec<(String, String)>,
+    #[serde(default)]
+    pub asks: Vec<(String, String)>,
+}
+
+/// Exchange misbehaviour to exercise the bot's error handling
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ScriptFaults {
+    /// GET /data/order/{id} answers 404 "order does not exist" even for known orders
+    #[serde(default)]
+    pub hide_orders: bool,
+    /// GET /data/trades answers 503
+    #[serde(default)]
+    pub trades_unavailable: bool,
+    /// POST /orders answers with at most this many entries (orders past it are still matched)
+    #[serde(default)]
+    pub batch_response_limit: Option<usize>,
+    /// POST /order answers rejections with 200 {"success": false, "errorMsg": ...}
+    /// like a batch entry, instead of 400
+    #[serde(default)]
+    pub single_reject_ok: bool,
+    /// POST /order and /orders match the orders, then close the connection unanswered
+    #[serde(default)]
+    pub drop_post_response: bool,
+}
+
+/// Book level change sent `at_ms` after the emulator starts
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScriptUpdate {
+    pub at_ms: u64,
+    pub asset_id: String,
+    /// "BUY" (bid) or "SELL" (ask)
+    pub side: String,
+    pub price: String,
+    /// New size at the level, "0" removes it
+    pub size: String,
+}
+
+/// Level change broadcast to WS subscribers
+#[derive(Debug, Clone)]
+struct LevelChange {
+    asset_id: String,
+    side: BookSide,
+    price: PriceCents,
+    size: f64,
+}
+
+impl LevelChange {
+    fn to_json(&self) -> Value {
+        json!({
+            "asset_id": self.asset_id,
+            "price": format!("{:.2}", cents_to_price(self.price)),
+            "size": self.size.to_string(),
+            "side": match self.side { BookSide::Bid => "BUY", BookSide::Ask => "SELL" },
+        })
+    }
+}
+
+/// Order as posted to /order and /orders
+#[derive(Debug, Deserialize)]
+struct PostEntry {
+    order: WireOrder,
+    owner: String,
+    #[serde(rename = "orderType")]
+    order_type: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WireOrder {
+    salt: u128,
+    maker: String,
+    signer: String,
+    taker: String,
+    token_id: String,
+    maker_amount: String,
+    taker_amount: String,
+    expiration: String,
+    nonce: String,
+    fee_rate_bps: String,
+    side: String,
+    signature_type: i32,
+    signature: String,
+}
+
+impl WireOrder {
+    fn into_signed(self) -> Result<SignedOrder> {
+        let side = match self.side.as_str() {
+            "BUY" => 0,
+            "SELL" => 1,
+            other => return Err(anyhow!("invalid side {}", other)),
+        };
+        Ok(SignedOrder {
+            order: OrderStruct {
+                salt: self.salt,
+                maker: self.maker,
+                signer: self.signer,
+                taker: self.taker,
+                token_id: self.token_id,
+                maker_amount: self.maker_amount,
+                taker_amount: self.taker_amount,
+                expiration: self.expiration,
+                nonce: self.nonce,
+                fee_rate_bps: self.fee_rate_bps,
+                side,
+                signature_type: self.signature_type,
+            },
+            signature: self.signature,
+        })
+    }
+}
+
+/// Account that derived API credentials
+#[derive(Debug, Clone)]
+struct Account {
+    address: Address,
+    creds: ApiCreds,
+}
+
+/// Emulated CLOB + Gamma + market WS
+pub struct Emulator {
+    config: EmulatorConfig,
+    depth: DepthBook,
+    /// token_id -> market
+    tokens: HashMap<String, ScriptMarket>,
+    /// api_key -> account
+    accounts: Mutex<HashMap<String, Account>>,
+    orders: Mutex<HashMap<String, Value>>,
+    /// Taker trades, oldest first
+    trades: Mutex<Vec<Value>>,
+    balance_micro: Mutex<u64>,
+    next_trade_id: AtomicU64,
+    changes: broadcast::Sender<LevelChange>,
+}
+
+impl Emulator {
+    pub fn new(config: EmulatorConfig) -> Self {
+        let depth = DepthBook::new();
+        let mut tokens = HashMap::new();
+        for market in &config.script.markets {
+            for (token, book) in [(&market.yes_token, &market.yes_book), (&market.no_token, &market.no_book)] {
+                depth.apply_snapshot(
+                    token,
+                    book.bids.iter().map(|(p, s)| (p.as_str(), s.as_str())),
+                    book.asks.iter().map(|(p, s)| (p.as_str(), s.as_str())),
+                );
+                tokens.insert(token.clone(), market.clone());
+            }
+        }
+        let (changes, _) = broadcast::channel(1024);
+
+        Self {
+            depth,
+            tokens,
+            accounts: Mutex::new(HashMap::new()),
+            orders: Mutex::new(HashMap::new()),
+            trades: Mutex::new(Vec::new()),
+            balance_micro: Mutex::new((config.balance_usdc * 1_000_000.0) as u64),
+            next_trade_id: AtomicU64::new(1),
+            changes,
+            config,
+        }
+    }
+
+    /// Start serving in the background. Returns the bound address.
+    pub async fn spawn(config: EmulatorConfig) -> Result<SocketAddr> {
+        let addr = config.addr;
+        let emulator = Arc::new(Self::new(config));
+
+        let svc_emulator = emulator.clone();
+        let make_svc = make_service_fn(move |_conn| {
+            let emulator = svc_emulator.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let emulator = emulator.clone();
+                    async move {
+                        // A service error makes hyper close the connection without a response
+                        let drop_response = emulator.config.script.faults.drop_post_response
+                            && req.method() == Method::POST
+                            && matches!(req.uri().path(), "/order" | "/orders");
+                        let resp = emulator.handle(req).await;
+                        if drop_response {
+                            return Err(std::io::Error::new(std::io::ErrorKind::ConnectionAborted, "response dropped"));
+                        }
+                        Ok(resp)
+                    }
+                }))
+            }
+        });
+
+        let server = Server::try_bind(&addr)?.serve(make_svc);
+        let local_addr = server.local_addr();
+        tokio::spawn(async move {
+            if let Err(e) = server.await {
+                warn!("[EMU] Server stopped: {}", e);
+            }
+        });
+        tokio::spawn(emulator.clone().play_script());
+
+        info!("[EMU] CLOB emulator on http://{} (ws://{}/ws/market) | {} markets | chain {}",
+              local_addr, local_addr, emulator.config.script.markets.len(), emulator.config.chain_id);
+        Ok(local_addr)
+    }
+
+    /// Apply scripted book updates at their offsets and push them to subscribers
+    async fn play_script(self: Arc<Self>) {
+        let start = tokio::time::Instant::now();
+        let mut updates = self.config.script.updates.clone();
+        updates.sort_by_key(|u| u.at_ms);
+
+        for update in updates {
+            tokio::time::sleep_until(start + Duration::from_millis(update.at_ms)).await;
+            let Some(side) = BookSide::parse(&update.side) else {
+                warn!("[EMU] Skipping update with side {}", update.side);
+                continue;
+            };
+            let change = LevelChange {
+                asset_id: update.asset_id,
+                side,
+                price: crate::types::parse_price(&update.price),
+                size: update.size.parse().unwrap_or(0.0),
+            };
+            self.depth.apply_change(&change.asset_id, side, change.price, change.size);
+            let _ = self.changes.send(change);
+        }
+    }
+
+    async fn handle(self: Arc<Self>, req: Request<Body>) -> Response<Body> {
+        let method = req.method().clone();
+        let path = req.uri().path().to_string();
+        let query = parse_query(req.uri().query().unwrap_or(""));
+        debug!("[EMU] {} {}", method, req.uri());
+
+        if method == Method::GET && path == "/ws/market" {
+            return self.upgrade_ws(req);
+        }
+
+        let headers = req.headers().clone();
+        let body = match hyper::body::to_bytes(req.into_body()).await {
+            Ok(b) => String::from_utf8_lossy(&b).into_owned(),
+            Err(e) => return error_response(StatusCode::BAD_REQUEST, &e.to_string()),
+        };
+
+        match (method.as_str(), path.as_str()) {
+            ("GET", "/auth/derive-api-key") => match self.verify_l1(&headers) {
+                Ok(address) => json_response(StatusCode::OK, self.derive_creds(address)),
+                Err(e) => error_response(StatusCode::UNAUTHORIZED, &e),
+            },
+            ("GET", "/neg-risk") => {
+                let neg_risk = self.market(&query).map(|m| m.neg_risk).unwrap_or(false);
+                json_response(StatusCode::OK, json!({ "neg_risk": neg_risk }))
+            }
+            ("GET", "/fee-rate") => {
+                let fee = self.market(&query).map(|m| m.fee_rate_bps).unwrap_or(0);
+                json_response(StatusCode::OK, json!({ "base_fee": fee }))
+            }
+            ("GET", "/markets") => json_response(StatusCode::OK, self.gamma_markets(&query)),
+            _ => {
+                let account = match self.verify_l2(method.as_str(), &path, &body, &headers) {
+                    Ok(account) => account,
+                    Err(e) => return error_response(StatusCode::UNAUTHORIZED, &e),
+                };
+                self.handle_l2(method.as_str(), &path, &query, &body, &account)
+            }
+        }
+    }
+
+    fn handle_l2(&self, method: &str, path: &str, query: &HashMap<String, String>, body: &str, account: &Account) -> Response<Body> {
+        let faults = &self.config.script.faults;
+        match (method, path) {
+            ("POST", "/order") => match serde_json::from_str::<PostEntry>(body) {
+                // Single-order rejections come back as 400 {"error": ...}
+                Ok(entry) if faults.single_reject_ok => json_response(StatusCode::OK, self.post_order(entry, account)),
+                Ok(entry) => match self.try_post_order(entry, account) {
+                    Ok(resp) => json_response(StatusCode::OK, resp),
+                    Err(msg) => error_response(StatusCode::BAD_REQUEST, &msg),
+                },
+                Err(e) => error_response(StatusCode::BAD_REQUEST, &format!("invalid order payload: {}", e)),
+            },
+            ("POST", "/orders") => match serde_json::from_str::<Vec<PostEntry>>(body) {
+                Ok(entries) => {
+                    let mut results: Vec<Value> = entries.into_iter().map(|e| self.post_order(e, account)).collect();
+                    if let Some(limit) = faults.batch_response_limit {
+                        results.truncate(limit);
+                    }
+                    json_response(StatusCode::OK, Value::Array(results))
+                }
+                Err(e) => error_response(StatusCode::BAD_REQUEST, &format!("invalid order payload: {}", e)),
+            },
+            ("GET", "/data/orders") => {
+                // Orders are FAK/FOK only, nothing ever rests
+                json_response(StatusCode::OK, json!({ "data": [], "next_cursor": "LTE=" }))
+            }
+            ("GET", "/data/trades") if faults.trades_unavailable => {
+                error_response(StatusCode::SERVICE_UNAVAILABLE, "trades unavailable")
+            }
+            ("GET", "/data/trades") => {
+                let trades: Vec<Value> = self.trades.lock().unwrap().iter()
+                    .filter(|t| query.get("asset_id").is_none_or(|a| t["asset_id"] == a.as_str()))
+                    .cloned()
+                    .collect();
+                json_response(StatusCode::OK, json!({ "data": trades, "next_cursor": "LTE=" }))
+            }
+            ("GET", "/balance-allowance") => {
+                let mut allowances = serde_json::Map::new();
+                for neg_risk in [false, true] {
+                    if let Ok(exchange) = get_exchange_address(self.config.chain_id, neg_risk) {
+                        allowances.insert(exchange, json!(MAX_ALLOWANCE));
+                    }
+                }
+                let balance = *self.balance_micro.lock().unwrap();
+                json_response(StatusCode::OK, json!({ "balance": balance.to_string(), "allowances": allowances }))
+            }
+            ("DELETE", "/order") | ("DELETE", "/orders") | ("DELETE", "/cancel-all") | ("DELETE", "/cancel-market-orders") => {
+                json_response(StatusCode::OK, json!({ "canceled": [], "not_canceled": {} }))
+            }
+            ("GET", p) if p.starts_with("/data/order/") => {
+                let id = &p["/data/order/".len()..];
+                match self.orders.lock().unwrap().get(id) {
+                    Some(order) if !faults.hide_orders => json_response(StatusCode::OK, order.clone()),
+                    _ => error_response(StatusCode::NOT_FOUND, "order does not exist"),
+                }
+            }
+            _ => error_response(StatusCode::NOT_FOUND, "not found"),
+        }
+    }
+
+    fn market(&self, query: &HashMap<String, String>) -> Option<&ScriptMarket> {
+        query.get("token_id").and_then(|t| self.tokens.get(t))
+    }
+
+    fn gamma_markets(&self, query: &HashMap<String, String>) -> Value {
+        let markets: Vec<Value> = self.config.script.markets.iter()
+            .filter(|m| query.get("slug").is_none_or(|slug| *slug == m.slug))
+            .map(|m| json!({
+                "slug": m.slug,
+                "question": m.question,
+                "clobTokenIds": serde_json::to_string(&[&m.yes_token, &m.no_token]).unwrap_or_default(),
+                "active": m.outcome.is_none(),
+                "closed": m.outcome.is_some(),
+                "outcomePrices": m.outcome.map(|yes| serde_json::to_string(&if yes { ["1", "0"] } else { ["0", "1"] }).unwrap_or_default()),
+                "negRisk": m.neg_risk,
+                "events": m.event_id.as_ref().map(|id| json!([{ "id": id, "startTime": m.start_time }])),
+            }))
+            .collect();
+        Value::Array(markets)
+    }
+
+    // === Authentication ===
+
+    /// L1: EIP-712 ClobAuth signature by POLY_ADDRESS
+    fn verify_l1(&self, headers: &HeaderMap) -> Result<Address, String> {
+        let address_str = header(headers, "POLY_ADDRESS")?;
+        let timestamp: u64 = header(headers, "POLY_TIMESTAMP")?.parse().map_err(|_| "bad POLY_TIMESTAMP")?;
+        let nonce: u64 = header(headers, "POLY_NONCE")?.parse().map_err(|_| "bad POLY_NONCE")?;
+        check_timestamp(timestamp)?;
+
+        let address: Address = address_str.parse().map_err(|_| "bad POLY_ADDRESS")?;
+        let signature: Signature = header(headers, "POLY_SIGNATURE")?.trim_start_matches("0x").parse()
+            .map_err(|_| "bad POLY_SIGNATURE")?;
+        let digest = clob_auth_digest(self.config.chain_id, address_str, timestamp, nonce)
+            .map_err(|e| e.to_string())?;
+
+        match signature.recover(digest) {
+            Ok(signer) if signer == address => Ok(address),
+            _ => Err("Invalid L1 Request headers".to_string()),
+        }
+    }
+
+    /// L2: HMAC over timestamp + method + path + body with the derived secret
+    fn verify_l2(&self, method: &str, path: &str, body: &str, headers: &HeaderMap) -> Result<Account, String> {
+        let api_key = header(headers, "POLY_API_KEY")?;
+        let account = self.accounts.lock().unwrap().get(api_key).cloned()
+            .ok_or("Unauthorized/Invalid api key")?;
+
+        if header(headers, "POLY_PASSPHRASE")? != account.creds.api_passphrase {
+            return Err("Unauthorized/Invalid api key".into());
+        }
+        let address: Address = header(headers, "POLY_ADDRESS")?.parse().map_err(|_| "bad POLY_ADDRESS")?;
+        if address != account.address {
+            return Err("Unauthorized/Invalid api key".into());
+        }
+        let timestamp = header(headers, "POLY_TIMESTAMP")?;
+        check_timestamp(timestamp.parse().map_err(|_| "bad POLY_TIMESTAMP")?)?;
+
+        let prepared = PreparedCreds::from_api_creds(&account.creds).map_err(|e| e.to_string())?;
+        let expected = prepared.sign_b64(format!("{}{}{}{}", timestamp, method, path, body).as_bytes());
+        if header(headers, "POLY_SIGNATURE")? != expected {
+            return Err("Unauthorized/Invalid L2 signature".into());
+        }
+        Ok(account)
+    }
+
+    /// Deterministic credentials per address (derive returns the same key every time)
+    fn derive_creds(&self, address: Address) -> Value {
+        let seed = |tag: &str| keccak256([address.as_bytes(), tag.as_bytes()].concat());
+        let key = seed("key");
+        let creds = ApiCreds {
+            api_key: format!(
+                "{}-{}-{}-{}-{}",
+                hex_str(&key[0..4]), hex_str(&key[4..6]), hex_str(&key[6..8]), hex_str(&key[8..10]), hex_str(&key[10..16])
+            ),
+            api_secret: URL_SAFE.encode(seed("secret")),
+            api_passphrase: hex_str(&seed("passphrase")[..16]),
+        };
+        let resp = json!({ "apiKey": creds.api_key, "secret": creds.api_secret, "passphrase": creds.api_passphrase });
+        self.accounts.lock().unwrap().insert(creds.api_key.clone(), Account { address, creds });
+        resp
+    }
+
+    // === Orders ===
+
+    /// Verify, match and record one posted order. Returns the per-order response.
+    fn post_order(&self, entry: PostEntry, account: &Account) -> Value {
+        match self.try_post_order(entry, account) {
+            Ok(resp) => resp,
+            Err(msg) => {
+                debug!("[EMU] Order rejected: {}", msg);
+                json!({ "success": false, "errorMsg": msg, "orderID": "" })
+            }
+        }
+    }
+
+    fn try_post_order(&self, entry: PostEntry, account: &Account) -> Result<Value, String> {
+        if entry.owner != account.creds.api_key {
+            return Err("owner does not match api key".into());
+        }
+        let order_type = match entry.order_type.as_str() {
+            "FAK" => PolyOrderType::FAK,
+            "FOK" => PolyOrderType::FOK,
+            other => return Err(format!("invalid order type {} (emulator supports FAK/FOK)", other)),
+        };
+        let signed = entry.order.into_signed().map_err(|e| format!("invalid order: {}", e))?;
+        let order = &signed.order;
+        let market = self.tokens.get(&order.token_id)
+            .ok_or_else(|| format!("market for token {} does not exist", order.token_id))?;
+
+        // Signature: EIP-712 over the exchange this token trades on, by the API key owner
+        let recovered = signed.recover_signer(self.config.chain_id, market.neg_risk)
+            .map_err(|_| "invalid signature".to_string())?;
+        let signer: Address = order.signer.parse().map_err(|_| "invalid signature".to_string())?;
+        if recovered != signer || signer != account.address {
+            return Err("invalid signature".into());
+        }
+        let maker: Address = order.maker.parse().map_err(|_| "invalid order maker".to_string())?;
+        if order.signature_type == 0 && maker != signer {
+            return Err("invalid signature: EOA orders must be made by the signer".into());
+        }
+        if order.fee_rate_bps != market.fee_rate_bps.to_string() {
+            return Err(format!("invalid fee rate ({}), current market fee rate is {}", order.fee_rate_bps, market.fee_rate_bps));
+        }
+
+        let maker_amount: f64 = order.maker_amount.parse().map_err(|_| "invalid makerAmount".to_string())?;
+        let taker_amount: f64 = order.taker_amount.parse().map_err(|_| "invalid takerAmount".to_string())?;
+        if maker_amount <= 0.0 || taker_amount <= 0.0 {
+            return Err("invalid order amounts".into());
+        }
+        let is_buy = order.side == 0;
+        // BUY: maker pays USDC for tokens; SELL: maker gives tokens for USDC
+        let (size, price) = if is_buy {
+            (taker_amount / 1e6, maker_amount / taker_amount)
+        } else {
+            (maker_amount / 1e6, taker_amount / maker_amount)
+        };
+        let limit = price_to_cents(price);
+
+        if is_buy && (maker_amount as u64) > *self.balance_micro.lock().unwrap() {
+            return Err("not enough balance / allowance".into());
+        }
+
+        let from = if is_buy { BookSide::Ask } else { BookSide::Bid };
+        let fills = self.depth.take(&order.token_id, from, limit, size, 1.0, order_type);
+        if fills.is_empty() {
+            return Err(match order_type {
+                PolyOrderType::FOK => "order couldn't be fully filled, FOK orders are fully filled or killed".into(),
+                _ => "no orders found to match with FAK order".into(),
+            });
+        }
+
+        let filled: f64 = fills.iter().map(|(_, q)| q).sum();
+        let notional: f64 = fills.iter().map(|&(p, q)| cents_to_price(p) * q).sum();
+        {
+            let mut balance = self.balance_micro.lock().unwrap();
+            let micro = (notional * 1_000_000.0) as u64;
+            *balance = if is_buy { balance.saturating_sub(micro) } else { *balance + micro };
+        }
+
+        // Push the levels we consumed to WS subscribers
+        let book = self.depth.book(&order.token_id).unwrap_or_default();
+        let levels = if is_buy { &book.asks } else { &book.bids };
+        for &(level_price, _) in &fills {
+            let size = levels.iter().find(|(p, _)| *p == level_price).map(|(_, s)| *s).unwrap_or(0.0);
+            let _ = self.changes.send(LevelChange { asset_id: order.token_id.clone(), side: from, price: level_price, size });
+        }
+
+        // Like the CLOB, the order ID is the order's EIP-712 hash
+        let order_id = signed.order_hash(self.config.chain_id, market.neg_risk).map_err(|e| e.to_string())?;
+        if self.orders.lock().unwrap().contains_key(&order_id) {
+            return Err("order is invalid. Duplicated.".into());
+        }
+        let side = if is_buy { "BUY" } else { "SELL" };
+        let outcome = if order.token_id == market.yes_token { "Yes" } else { "No" };
+        let status = if filled < size { "CANCELED" } else { "MATCHED" };
+        self.orders.lock().unwrap().insert(order_id.clone(), json!({
+            "id": order_id,
+            "status": status,
+            "market": market.slug,
+            "outcome": outcome,
+            "price": format!("{:.2}", cents_to_price(limit)),
+            "side": side,
+            "size_matched": filled.to_string(),
+            "original_size": size.to_string(),
+            "maker_address": order.maker,
+            "asset_id": order.token_id,
+            "associate_trades": [],
+            "created_at": unix_now(),
+            "expiration": order.expiration,
+            "type": entry.order_type,
+            "owner": account.creds.api_key,
+        }));
+
+        self.trades.lock().unwrap().push(json!({
+            "id": format!("trade-{}", self.next_trade_id.fetch_add(1, Ordering::Relaxed)),
+            "taker_order_id": order_id,
+            "market": market.slug,
+            "asset_id": order.token_id,
+            "side": side,
+            "size": filled.to_string(),
+            "price": (notional / filled).to_string(),
+            "fee_rate_bps": order.fee_rate_bps,
+            "status": "MATCHED",
+            "match_time": unix_now().to_string(),
+            "outcome": outcome,
+            "owner": account.creds.api_key,
+            "maker_address": order.maker,
+            "trader_side": "TAKER",
+            "maker_orders": [],
+        }));
+
+        info!("[EMU] {} {} {:.0}/{:.0} @{}¢ -> {}", side, order.token_id, filled, size, limit, order_id);
+
+        let (making, taking) = if is_buy { (notional, filled) } else { (filled, notional) };
+        Ok(json!({
+            "success": true,
+            "errorMsg": "",
+            "orderID": order_id,
+            "status": "matched",
+            "makingAmount": making.to_string(),
+            "takingAmount": taking.to_string(),
+        }))
+    }
+
+    // === Market WebSocket ===
+
+    fn upgrade_ws(self: Arc<Self>, mut req: Request<Body>) -> Response<Body> {
+        let Some(key) = req.headers().get("sec-websocket-key").cloned() else {
+            return error_response(StatusCode::BAD_REQUEST, "expected websocket upgrade");
+        };
+
+        tokio::spawn(async move {
+            match hyper::upgrade::on(&mut req).await {
+                Ok(upgraded) => {
+                    let ws = WebSocketStream::from_raw_socket(upgraded, Role::Server, None).await;
+                    if let Err(e) = self.serve_ws(ws).await {
+                        debug!("[EMU] WS closed: {}", e);
+                    }
+                }
+                Err(e) => warn!("[EMU] WS upgrade failed: {}", e),
+            }
+        });
+
+        Response::builder()
+            .status(StatusCode::SWITCHING_PROTOCOLS)
+            .header("Upgrade", "websocket")
+            .header("Connection", "Upgrade")
+            .header("Sec-WebSocket-Accept", derive_accept_key(key.as_bytes()))
+            .body(Body::empty())
+            .expect("valid response")
+    }
+
+    /// Send book snapshots for subscribed assets, then stream level changes.
+    /// Unsubscribing and subscribing again sends a fresh snapshot.
+    async fn serve_ws<S>(&self, ws: WebSocketStream<S>) -> Result<()>
+    where
+        S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+    {
+        let (mut write, mut read) = ws.split();
+        let mut changes = self.changes.subscribe();
+        let mut subscribed: HashSet<String> = HashSet::new();
+
+        loop {
+            tokio::select! {
+                msg = read.next() => {
+                    match msg {
+                        Some(Ok(Message::Text(text))) => {
+                            let Ok(cmd) = serde_json::from_str::<Value>(&text) else { continue };
+                            let Some(assets) = cmd["assets_ids"].as_array() else { continue };
+                            if cmd["operation"].as_str() == Some("unsubscribe") {
+                                for asset in assets.iter().filter_map(|a| a.as_str()) {
+                                    subscribed.remove(asset);
+                                }
+                                continue;
+                            }
+                            let new: Vec<String> = assets.iter()
+                                .filter_map(|a| a.as_str().map(str::to_string))
+                                .filter(|a| subscribed.insert(a.clone()))
+                                .collect();
+                            let snapshots: Vec<Value> = new.iter().map(|a| self.book_snapshot(a)).collect();
+                            write.send(Message::Text(Value::Array(snapshots).to_string())).await?;
+                        }
+                        Some(Ok(Message::Ping(data))) => write.send(Message::Pong(data)).await?,
+                        Some(Ok(Message::Close(_))) | None => return Ok(()),
+                        Some(Err(e)) => return Err(e.into()),
+                        _ => {}
+                    }
+                }
+                change = changes.recv() => {
+                    match change {
+                        Ok(change) if subscribed.contains(&change.asset_id) => {
+                            let msg = json!({ "event_type": "price_change", "price_changes": [change.to_json()] });
+                            write.send(Message::Text(msg.to_string())).await?;
+                        }
+                        Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                        Err(broadcast::error::RecvError::Closed) => return Ok(()),
+                    }
+                }
+            }
+        }
+    }
+
+    fn book_snapshot(&self, asset_id: &str) -> Value {
+        let book = self.depth.book(asset_id).unwrap_or_default();
+        let levels = |levels: &[(PriceCents, f64)]| -> Vec<Value> {
+            levels.iter()
+                .map(|&(p, s)| json!({ "price": format!("{:.2}", cents_to_price(p)), "size": s.to_string() }))
+                .collect()
+        };
+        json!({
+            "event_type": "book",
+            "asset_id": asset_id,
+            "market": self.tokens.get(asset_id).map(|m| m.slug.as_str()).unwrap_or_default(),
+            "bids": levels(&book.bids),
+            "asks": levels(&book.asks),
+            "timestamp": unix_now().to_string(),
+        })
+    }
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Result<&'a str, String> {
+    headers.get(name)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| format!("missing {} header", name))
+}
+
+fn check_timestamp(timestamp: u64) -> Result<(), String> {
+    if unix_now().abs_diff(timestamp) > AUTH_WINDOW_SECS {
+        return Err("auth timestamp expired".into());
+    }
+    Ok(())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn hex_str(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query.split('&')
+        .filter_map(|kv| kv.split_once('='))
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+fn json_response(status: StatusCode, body: Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .expect("valid response")
+}
+
+fn error_response(status: StatusCode, msg: &str) -> Response<Body> {
+    json_response(status, json!({ "error": msg }))
+}
@@ -0,0 +1,173 @@
+// src/exchange.rs
+// Exchange abstraction - lets ExecutionEngine run against any venue or a fake
+
+use anyhow::{Result, anyhow};
+use std::collections::HashMap;
+use std::future::Future;
+
+use crate::balance::CollateralSnapshot;
+use crate::polymarket_clob::{
+    CancelOrdersResponse, PolyFillAsync, PolymarketOrderResponse, SharedAsyncClient, get_exchange_address,
+};
+
+/// One order in a batch submission
+#[derive(Debug, Clone, Copy)]
+pub struct OrderLeg<'a> {
+    pub token_id: &'a str,
+    pub price: f64,
+    pub size: f64,
+    /// "BUY" or "SELL"
+    pub side: &'a str,
+}
+
+impl<'a> OrderLeg<'a> {
+    pub fn buy(token_id: &'a str, price: f64, size: f64) -> Self {
+        Self { token_id, price, size, side: "BUY" }
+    }
+
+    pub fn sell(token_id: &'a str, price: f64, size: f64) -> Self {
+        Self { token_id, price, size, side: "SELL" }
+    }
+}
+
+/// Fill of an immediate-or-cancel order
+#[derive(Debug, Clone)]
+pub struct Fill {
+    pub order_id: String,
+    pub filled_size: f64,
+    /// Notional paid or received (dollars, before fees)
+    pub fill_cost: f64,
+    /// Taker fees paid on this fill (dollars)
+    pub fees: f64,
+    /// Order was accepted but its fill could not be confirmed; sizes are zero
+    /// until the order is looked up again
+    pub fill_unknown: bool,
+}
+
+impl From<PolyFillAsync> for Fill {
+    fn from(fill: PolyFillAsync) -> Self {
+        Self {
+            order_id: fill.order_id,
+            filled_size: fill.filled_size,
+            fill_cost: fill.fill_cost,
+            fees: fill.fees,
+            fill_unknown: fill.fill_unknown,
+        }
+    }
+}
+
+/// Outcome of a cancel request
+#[derive(Debug, Clone, Default)]
+pub struct CancelResult {
+    /// Order IDs that were cancelled
+    pub canceled: Vec<String>,
+    /// Order ID -> reason for orders that could not be cancelled
+    pub not_canceled: HashMap<String, String>,
+}
+
+impl From<CancelOrdersResponse> for CancelResult {
+    fn from(resp: CancelOrdersResponse) -> Self {
+        Self { canceled: resp.canceled, not_canceled: resp.not_canceled }
+    }
+}
+
+/// Status of a previously placed order
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderStatus {
+    pub order_id: String,
+    pub status: String,
+    pub price: f64,
+    pub size_matched: f64,
+    pub original_size: f64,
+}
+
+impl From<PolymarketOrderResponse> for OrderStatus {
+    fn from(resp: PolymarketOrderResponse) -> Self {
+        Self {
+            price: resp.price.parse().unwrap_or(0.0),
+            size_matched: resp.size_matched.parse().unwrap_or(0.0),
+            original_size: resp.original_size.parse().unwrap_or(0.0),
+            order_id: resp.id,
+            status: resp.status,
+        }
+    }
+}
+
+/// Operations the execution engine needs from a venue
+pub trait ExchangeClient: Send + Sync + 'static {
+    /// Place immediate-or-cancel orders in one request. Returns one result per leg,
+    /// in input order; the outer error means the whole request failed.
+    fn place_orders(&self, legs: &[OrderLeg<'_>]) -> impl Future<Output = Result<Vec<Result<Fill>>>> + Send;
+
+    /// Place a single immediate-or-cancel order
+    fn place_order(&self, leg: OrderLeg<'_>) -> impl Future<Output = Result<Fill>> + Send {
+        async move {
+            self.place_orders(&[leg]).await?
+                .into_iter()
+                .next()
+                .unwrap_or_else(|| Err(anyhow!("missing order result")))
+        }
+    }
+
+    /// Look up an order by ID
+    fn query_order(&self, order_id: &str) -> impl Future<Output = Result<OrderStatus>> + Send;
+
+    /// Cancel orders by ID
+    #[allow(dead_code)]
+    fn cancel_orders(&self, order_ids: &[String]) -> impl Future<Output = Result<CancelResult>> + Send;
+
+    /// Cancel every open order on the account. Venues that never rest orders have nothing to cancel.
+    fn cancel_all(&self) -> impl Future<Output = Result<CancelResult>> + Send {
+        async { Ok(CancelResult::default()) }
+    }
+
+    /// Cached neg_risk flag for a token (no network I/O on the hot path)
+    fn neg_risk(&self, token_id: &str) -> Option<bool>;
+
+    /// Collateral balance and allowances as reported by the venue
+    fn collateral(&self) -> impl Future<Output = Result<CollateralSnapshot>> + Send;
+}
+
+impl ExchangeClient for SharedAsyncClient {
+    async fn place_orders(&self, legs: &[OrderLeg<'_>]) -> Result<Vec<Result<Fill>>> {
+        let results = self.execute_fak(legs).await?;
+        Ok(results.into_iter().map(|r| r.map(Fill::from)).collect())
+    }
+
+    async fn place_order(&self, leg: OrderLeg<'_>) -> Result<Fill> {
+        let fill = if leg.side.eq_ignore_ascii_case("SELL") {
+            self.sell_fak(leg.token_id, leg.price, leg.size).await?
+        } else {
+            self.buy_fak(leg.token_id, leg.price, leg.size).await?
+        };
+        Ok(fill.into())
+    }
+
+    async fn query_order(&self, order_id: &str) -> Result<OrderStatus> {
+        Ok(self.order(order_id).await?.into())
+    }
+
+    async fn cancel_orders(&self, order_ids: &[String]) -> Result<CancelResult> {
+        Ok(SharedAsyncClient::cancel_orders(self, order_ids).await?.into())
+    }
+
+    async fn cancel_all(&self) -> Result<CancelResult> {
+        Ok(SharedAsyncClient::cancel_all(self).await?.into())
+    }
+
+    fn neg_risk(&self, token_id: &str) -> Option<bool> {
+        self.cached_neg_risk(token_id)
+    }
+
+    async fn collateral(&self) -> Result<CollateralSnapshot> {
+        let resp = self.balance_allowance().await?;
+        let exchange = get_exchange_address(self.chain_id(), false)?;
+        let neg_risk_exchange = get_exchange_address(self.chain_id(), true)?;
+        Ok(CollateralSnapshot {
+            balance: resp.balance_micro(),
+            allowance: resp.allowance_micro(&exchange),
+            neg_risk_allowance: resp.allowance_micro(&neg_risk_exchange),
+        })
+    }
+}
@@ -3,37 +3,59 @@
 
 use anyhow::{Result, anyhow};
 use std::sync::Arc;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
-use tokio::sync::mpsc;
+use tokio::sync::{Notify, mpsc};
 use tracing::{info, warn, error};
 
-use crate::polymarket_clob::SharedAsyncClient;
+use crate::balance::BalanceMonitor;
+use crate::behaviour::BehaviourEvent;
+use crate::clob_error::{BreakerAction, ClobError};
+use crate::exchange::{ExchangeClient, Fill, OrderLeg, OrderStatus};
+use crate::opportunity::OpportunityTracker;
+use crate::polymarket_clob::{SharedAsyncClient, fee_for_fill};
 use crate::types::{
     MarketPair,
     FastExecutionRequest, GlobalState,
     cents_to_price,
 };
-use crate::circuit_breaker::CircuitBreaker;
+use crate::circuit_breaker::{CircuitBreaker, TripReason};
 use crate::position_tracker::{FillRecord, PositionChannel};
 
 // =============================================================================
 // EXECUTION ENGINE
 // =============================================================================
 
+/// Lookups of an accepted order whose fill was unknown before giving up
+const FILL_LOOKUP_ATTEMPTS: u32 = 3;
+/// Delay before the first lookup, multiplied by the attempt number
+const FILL_LOOKUP_DELAY: Duration = Duration::from_secs(1);
+
 /// Monotonic nanosecond clock for latency measurement
+#[derive(Debug, Clone, Copy)]
 pub struct NanoClock {
     start: Instant,
+    /// Simulated clock: reads tokio's (paused) virtual time instead of the wall clock
+    sim_start: Option<tokio::time::Instant>,
 }
 
 impl NanoClock {
     pub fn new() -> Self {
-        Self { start: Instant::now() }
+        Self { start: Instant::now(), sim_start: None }
+    }
+
+    /// Clock driven by tokio's virtual time (replay on a paused runtime)
+    #[allow(dead_code)]
+    pub fn simulated() -> Self {
+        Self { start: Instant::now(), sim_start: Some(tokio::time::Instant::now()) }
     }
 
     #[inline(always)]
     pub fn now_ns(&self) -> u64 {
-        self.start.elapsed().as_nanos() as u64
+        match self.sim_start {
+            None => self.start.elapsed().as_nanos() as u64,
+            Some(start) => start.elapsed().as_nanos() as u64,
+        }
     }
 }
 
@@ -43,21 +65,93 @@ impl Default for NanoClock {
     }
 }
 
-/// Execution engine
-pub struct ExecutionEngine {
-    poly_async: Arc<SharedAsyncClient>,
+/// Counts executions and auto-closes still running, so shutdown can refuse new
+/// work and wait for what was already sent to the exchange
+#[derive(Clone, Default)]
+pub struct ExecutionGate {
+    inner: Arc<GateInner>,
+}
+
+#[derive(Default)]
+struct GateInner {
+    closed: AtomicBool,
+    active: AtomicUsize,
+    idle: Notify,
+}
+
+/// One unit of running work; dropping it marks the work done
+pub struct GateGuard {
+    inner: Arc<GateInner>,
+}
+
+impl Drop for GateGuard {
+    fn drop(&mut self) {
+        if self.inner.active.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.inner.idle.notify_waiters();
+        }
+    }
+}
+
+impl ExecutionGate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start new work; None once the gate is closed
+    pub fn enter(&self) -> Option<GateGuard> {
+        // Count first so drain() can never miss work that passed the closed check
+        let guard = self.hold();
+        (!self.inner.closed.load(Ordering::SeqCst)).then_some(guard)
+    }
+
+    /// Continue work that was already admitted (e.g. an auto-close), even after close()
+    pub fn hold(&self) -> GateGuard {
+        self.inner.active.fetch_add(1, Ordering::SeqCst);
+        GateGuard { inner: self.inner.clone() }
+    }
+
+    /// Refuse new executions
+    pub fn close(&self) {
+        self.inner.closed.store(true, Ordering::SeqCst);
+    }
+
+    /// Work still running
+    pub fn active(&self) -> usize {
+        self.inner.active.load(Ordering::SeqCst)
+    }
+
+    /// Wait until no work is running; false if `timeout` elapsed first
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        tokio::time::timeout(timeout, async {
+            loop {
+                let idle = self.inner.idle.notified();
+                if self.active() == 0 {
+                    return;
+                }
+                idle.await;
+            }
+        }).await.is_ok()
+    }
+}
+
+/// Execution engine, generic over the venue it sends orders to
+pub struct ExecutionEngine<E: ExchangeClient = SharedAsyncClient> {
+    exchange: Arc<E>,
     state: Arc<GlobalState>,
     circuit_breaker: Arc<CircuitBreaker>,
     position_channel: PositionChannel,
+    balance: Option<Arc<BalanceMonitor>>,
+    opportunities: Option<Arc<OpportunityTracker>>,
     in_flight: Arc<[AtomicU64; 8]>,
+    gate: ExecutionGate,
     clock: NanoClock,
     pub dry_run: bool,
     test_mode: bool,
 }
 
-impl ExecutionEngine {
+impl<E: ExchangeClient> ExecutionEngine<E> {
     pub fn new(
-        poly_async: Arc<SharedAsyncClient>,
+        exchange: Arc<E>,
         state: Arc<GlobalState>,
         circuit_breaker: Arc<CircuitBreaker>,
         position_channel: PositionChannel,
@@ -68,22 +162,60 @@ impl ExecutionEngine {
             .unwrap_or(false);
 
         Self {
-            poly_async,
+            exchange,
             state,
             circuit_breaker,
             position_channel,
+            balance: None,
+            opportunities: None,
             in_flight: Arc::new(std::array::from_fn(|_| AtomicU64::new(0))),
+            gate: ExecutionGate::new(),
             clock: NanoClock::new(),
             dry_run,
             test_mode,
         }
     }
 
+    /// Cap trade size by free collateral and block trading when allowance is missing
+    pub fn with_balance_monitor(mut self, balance: Arc<BalanceMonitor>) -> Self {
+        self.balance = Some(balance);
+        self
+    }
+
+    /// Report orders and fills to the opportunity tracker
+    pub fn with_opportunities(mut self, opportunities: Arc<OpportunityTracker>) -> Self {
+        self.opportunities = Some(opportunities);
+        self
+    }
+
+    /// Track running work with this gate (shutdown closes and drains it)
+    pub fn with_gate(mut self, gate: ExecutionGate) -> Self {
+        self.gate = gate;
+        self
+    }
+
+    /// Measure latency with this clock (must be the one detection stamps requests with)
+    pub fn with_clock(mut self, clock: NanoClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
     /// Process an execution request
     #[inline]
     pub async fn process(&self, req: FastExecutionRequest) -> Result<ExecutionResult> {
         let market_id = req.market_id;
 
+        // Shutting down: nothing new goes to the exchange
+        let Some(_work) = self.gate.enter() else {
+            return Ok(ExecutionResult {
+                market_id,
+                success: false,
+                profit_cents: 0,
+                latency_ns: self.clock.now_ns() - req.detected_ns,
+                error: Some("Shutting down"),
+            });
+        };
+
         // Deduplication check (512 markets via 8x u64 bitmask)
         if market_id < 512 {
             let slot = (market_id / 64) as usize;
@@ -108,6 +240,19 @@ impl ExecutionEngine {
         let pair = market.pair.as_ref()
             .ok_or_else(|| anyhow!("No pair for market_id {}", market_id))?;
 
+        // Quote age check: never trade on a side the feed has stopped updating
+        if market.poly.is_stale(self.clock.now_ns(), self.state.max_quote_age_ns) {
+            warn!("[EXEC] Stale quote for {} - skipping", pair.description);
+            self.release_in_flight(market_id);
+            return Ok(ExecutionResult {
+                market_id,
+                success: false,
+                profit_cents: 0,
+                latency_ns: self.clock.now_ns() - req.detected_ns,
+                error: Some("Stale quote"),
+            });
+        }
+
         // Calculate profit
         let profit_cents = req.profit_cents();
         if profit_cents < 1 {
@@ -147,8 +292,36 @@ impl ExecutionEngine {
             });
         }
 
+        // Collateral check: cap by free USDC and hold it until the fill, block if allowance missing
+        let mut reservation = None;
+        if let Some(balance) = &self.balance {
+            let cost_per_contract = (req.yes_price + req.no_price) as i64 + req.estimated_fee_cents() as i64;
+            let neg_risk = self.exchange.neg_risk(&pair.poly_yes_token).unwrap_or(false);
+            match balance.reserve(cost_per_contract, max_contracts, neg_risk) {
+                Ok(held) => {
+                    if held.contracts() < max_contracts {
+                        info!("[EXEC] Capping contracts from {} to {} by collateral (${:.2} left free)",
+                              max_contracts, held.contracts(), balance.available_usdc());
+                        max_contracts = held.contracts();
+                    }
+                    reservation = Some(held);
+                }
+                Err(block) => {
+                    warn!("[EXEC] {}", block);
+                    self.release_in_flight(market_id);
+                    return Ok(ExecutionResult {
+                        market_id,
+                        success: false,
+                        profit_cents: 0,
+                        latency_ns: self.clock.now_ns() - req.detected_ns,
+                        error: Some(block.as_str()),
+                    });
+                }
+            }
+        }
+
         // Circuit breaker check
-        if let Err(_reason) = self.circuit_breaker.can_execute(&pair.pair_id, max_contracts).await {
+        if let Err(_reason) = self.circuit_breaker.can_execute(&pair.pair_id, max_contracts, req.yes_price, req.no_price).await {
             self.release_in_flight(market_id);
             return Ok(ExecutionResult {
                 market_id,
@@ -177,67 +350,150 @@ impl ExecutionEngine {
             return Ok(ExecutionResult {
                 market_id,
                 success: true,
-                profit_cents,
+                profit_cents: profit_cents as i64,
                 latency_ns: latency_to_exec,
                 error: Some("DRY_RUN"),
             });
         }
 
+        if let Some(opportunities) = &self.opportunities {
+            opportunities.on_order(market_id);
+        }
+
         // Execute both legs concurrently 
         let result = self.execute_both_legs_async(&req, pair, max_contracts).await;
+        self.record_behaviour(&req, &result).await;
 
         // Release in-flight after delay
         self.release_in_flight_delayed(market_id);
 
         match result {
-            // Note: For same-platform arbs (PolyOnly/KalshiOnly), these are YES/NO fills, not platform fills
-            Ok((yes_filled, no_filled, yes_cost, no_cost, yes_order_id, no_order_id)) => {
+            Ok((yes, no)) => {
+                let (yes_filled, no_filled) = (yes.filled, no.filled);
+                let (yes_cost, no_cost) = (yes.cost_cents, no.cost_cents);
                 let matched = yes_filled.min(no_filled);
                 let success = matched > 0;
-                let actual_profit = matched as i16 * 100 - (yes_cost + no_cost) as i16;
+                let fees_cents = yes.fee_cents + no.fee_cents;
+                let actual_profit = matched * 100 - (yes_cost + no_cost + fees_cents);
 
-                // === AUTO-CLOSE MISMATCHED EXPOSURE (non-blocking) ===
-                if yes_filled != no_filled && (yes_filled > 0 || no_filled > 0) {
-                    let excess = (yes_filled - no_filled).abs();
-                    warn!("[EXEC] ⚠️ Fill mismatch: P_yes={} P_no={} (excess={})",
-                        yes_filled, no_filled, excess);
+                if let Some(opportunities) = &self.opportunities {
+                    opportunities.on_result(market_id, yes_filled, no_filled, actual_profit);
+                }
 
-                    // Spawn auto-close in background (don't block hot path with 2s sleep)
-                    let poly_async = self.poly_async.clone();
+                // === SETTLE: look up unknown fills, auto-close mismatched exposure (non-blocking) ===
+                let unknown = yes.fill_unknown || no.fill_unknown;
+                if unknown || (yes_filled != no_filled && (yes_filled > 0 || no_filled > 0)) {
+                    if unknown {
+                        warn!("[EXEC] ⚠️ Fill unknown: P_yes={}{} P_no={}{} - looking up orders",
+                            yes_filled, if yes.fill_unknown { "?" } else { "" },
+                            no_filled, if no.fill_unknown { "?" } else { "" });
+                    } else {
+                        warn!("[EXEC] ⚠️ Fill mismatch: P_yes={} P_no={} (excess={})",
+                            yes_filled, no_filled, (yes_filled - no_filled).abs());
+                    }
+
+                    // Spawn in background (don't block hot path with lookups and the 2s sleep)
+                    let exchange = self.exchange.clone();
                     let yes_price = req.yes_price;
                     let no_price = req.no_price;
+                    let (yes_fee_bps, no_fee_bps) = (req.yes_fee_bps, req.no_fee_bps);
                     let poly_yes_token = pair.poly_yes_token.clone();
                     let poly_no_token = pair.poly_no_token.clone();
-                    let original_cost_per_contract = if yes_filled > no_filled {
-                        if yes_filled > 0 { yes_cost / yes_filled } else { 0 }
-                    } else {
-                        if no_filled > 0 { no_cost / no_filled } else { 0 }
-                    };
+                    let circuit_breaker = self.circuit_breaker.clone();
+                    let position_channel = self.position_channel.clone();
+                    let (pair_id, description) = (pair.pair_id.clone(), pair.description.clone());
+                    let (mut yes, mut no) = (yes.clone(), no.clone());
+                    let work = self.gate.hold();
 
                     tokio::spawn(async move {
-                        Self::auto_close_background(
-                            poly_async, yes_filled, no_filled,
+                        let _work = work;
+
+                        // Accepted legs with an unknown fill: look them up before sizing the close
+                        for (side, leg, fee_bps) in [("yes", &mut yes, yes_fee_bps), ("no", &mut no, no_fee_bps)] {
+                            if !leg.fill_unknown {
+                                continue;
+                            }
+                            let Some(status) = Self::lookup_fill(&*exchange, &leg.order_id).await else {
+                                // Leave the exposure to reconciliation rather than close against a guess
+                                error!("[EXEC] ❌ Fill of {} order {} still unknown - not auto-closing {}",
+                                    side, leg.order_id, description);
+                                circuit_breaker.record_error().await;
+                                return;
+                            };
+                            *leg = LegFill::resolved(&status, fee_bps);
+                            if leg.filled > 0 {
+                                let record = FillRecord::new(
+                                    &pair_id, &description, "polymarket", side,
+                                    leg.filled as f64, status.price, leg.fees, &leg.order_id,
+                                );
+                                circuit_breaker.record_fill(&record).await;
+                                position_channel.record_fill(record);
+                            }
+                            info!("[EXEC] Order {} ({}) filled {}x", leg.order_id, side, leg.filled);
+                        }
+
+                        let (yes_filled, no_filled) = (yes.filled, no.filled);
+                        let original_cost_per_contract = if yes_filled > no_filled {
+                            if yes_filled > 0 { yes.cost_cents / yes_filled } else { 0 }
+                        } else {
+                            if no_filled > 0 { no.cost_cents / no_filled } else { 0 }
+                        };
+
+                        let closed = Self::auto_close_background(
+                            exchange, yes_filled, no_filled,
                             yes_price, no_price, poly_yes_token, poly_no_token,
                             original_cost_per_contract
                         ).await;
+
+                        // The sell reduces the excess leg in both breaker and tracker
+                        if let Some((side, fill)) = closed {
+                            let record = FillRecord::new(
+                                &pair_id, &description, "polymarket", side,
+                                -fill.filled_size, fill.fill_cost / fill.filled_size,
+                                fill.fees, &fill.order_id,
+                            );
+                            circuit_breaker.record_fill(&record).await;
+                            position_channel.record_fill(record);
+                        }
                     });
                 }
 
+                // Rejected legs: apply each distinct error class's breaker policy once
+                match (&yes.error, &no.error) {
+                    (Some(y), Some(n)) if y.kind == n.kind => self.apply_error_policy(pair, y).await,
+                    (y, n) => {
+                        for err in [y, n].into_iter().flatten() {
+                            self.apply_error_policy(pair, err).await;
+                        }
+                    }
+                }
+
+                // Record the legs as actually filled; any excess is auto-closed above
+                for (side, leg) in [("yes", &yes), ("no", &no)] {
+                    if leg.filled > 0 {
+                        self.record_fill(FillRecord::new(
+                            &pair.pair_id, &pair.description, "polymarket", side,
+                            leg.filled as f64, leg.cost_cents as f64 / 100.0 / leg.filled as f64,
+                            leg.fees, &leg.order_id,
+                        )).await;
+                    }
+                }
+
                 if success {
-                    self.circuit_breaker.record_success(&pair.pair_id, matched, matched, actual_profit as f64 / 100.0).await;
+                    self.circuit_breaker.record_success(actual_profit as f64 / 100.0);
                 }
 
-                if matched > 0 {
-                    self.position_channel.record_fill(FillRecord::new(
-                        &pair.pair_id, &pair.description, "polymarket", "yes",
-                        matched as f64, yes_cost as f64 / 100.0 / yes_filled.max(1) as f64,
-                        0.0, &yes_order_id,
-                    ));
-                    self.position_channel.record_fill(FillRecord::new(
-                        &pair.pair_id, &pair.description, "polymarket", "no",
-                        matched as f64, no_cost as f64 / 100.0 / no_filled.max(1) as f64,
-                        0.0, &no_order_id,
-                    ));
+                // Debit collateral now (all of it while a fill is unknown); the refresh
+                // loop confirms the real balance
+                if let Some(reservation) = reservation {
+                    let known = yes_cost + no_cost + fees_cents;
+                    let spent = if unknown { known.max(reservation.cost_cents()) } else { known };
+                    reservation.settle(spent);
+                }
+                if yes_filled > 0 || no_filled > 0 || unknown {
+                    if let Some(balance) = &self.balance {
+                        balance.request_refresh();
+                    }
                 }
 
                 Ok(ExecutionResult {
@@ -245,40 +501,98 @@ impl ExecutionEngine {
                     success,
                     profit_cents: actual_profit,
                     latency_ns: self.clock.now_ns() - req.detected_ns,
-                    error: if success { None } else { Some("Partial/no fill") },
+                    error: if success {
+                        None
+                    } else if unknown {
+                        Some("Fill unknown")
+                    } else {
+                        Some("Partial/no fill")
+                    },
                 })
             }
-            Err(_e) => {
-                self.circuit_breaker.record_error().await;
+            Err(e) => {
+                let err = ClobError::from_anyhow(&e);
+                warn!("[EXEC] Execution failed: {}", err);
+                if let Some(opportunities) = &self.opportunities {
+                    opportunities.on_result(market_id, 0, 0, 0);
+                }
+                self.apply_error_policy(pair, &err).await;
                 Ok(ExecutionResult {
                     market_id,
                     success: false,
                     profit_cents: 0,
                     latency_ns: self.clock.now_ns() - req.detected_ns,
-                    error: Some("Execution failed"),
+                    error: Some(err.kind.as_str()),
                 })
             }
         }
     }
 
+    /// Feed the sliding-window breakers: each leg as an order, the fill balance and the latency
+    async fn record_behaviour(&self, req: &FastExecutionRequest, result: &Result<(LegFill, LegFill)>) {
+        let now_ns = self.clock.now_ns();
+        let at_ms = now_ns / 1_000_000;
+        let cb = &self.circuit_breaker;
+        match result {
+            Ok((yes, no)) => {
+                for leg in [yes, no] {
+                    let rejected = leg.error.as_ref().is_some_and(is_reject);
+                    cb.record_behaviour(at_ms, BehaviourEvent::Order { rejected }).await;
+                }
+                if yes.filled > 0 || no.filled > 0 {
+                    let mismatched = yes.filled != no.filled;
+                    cb.record_behaviour(at_ms, BehaviourEvent::Execution { mismatched }).await;
+                }
+            }
+            Err(e) => {
+                let rejected = is_reject(&ClobError::from_anyhow(e));
+                for _ in 0..2 {
+                    cb.record_behaviour(at_ms, BehaviourEvent::Order { rejected }).await;
+                }
+            }
+        }
+        let ms = now_ns.saturating_sub(req.detected_ns) as f64 / 1_000_000.0;
+        cb.record_behaviour(at_ms, BehaviourEvent::Latency { ms }).await;
+    }
+
+    /// Send a fill to the circuit breaker and the position tracker
+    async fn record_fill(&self, fill: FillRecord) {
+        self.circuit_breaker.record_fill(&fill).await;
+        self.position_channel.record_fill(fill);
+    }
+
+    /// Feed an exchange error into the circuit breaker according to its class policy
+    async fn apply_error_policy(&self, pair: &MarketPair, err: &ClobError) {
+        match err.policy().breaker {
+            BreakerAction::Ignore => {}
+            BreakerAction::CountError => self.circuit_breaker.record_error().await,
+            BreakerAction::DisableMarket => {
+                self.circuit_breaker.disable_market(&pair.pair_id, &err.to_string()).await;
+            }
+            BreakerAction::Halt => {
+                self.circuit_breaker.trip(TripReason::ExchangeError {
+                    kind: err.kind.to_string(),
+                    message: err.message.clone(),
+                }).await;
+            }
+        }
+    }
+
     async fn execute_both_legs_async(
         &self,
         req: &FastExecutionRequest,
         pair: &MarketPair,
         contracts: i64,
-    ) -> Result<(i64, i64, i64, i64, String, String)> {
-        // === SAME-PLATFORM: Poly YES + Poly NO ===
-        let yes_fut = self.poly_async.buy_fak(
-            &pair.poly_yes_token,
-            cents_to_price(req.yes_price),
-            contracts as f64,
-        );
-        let no_fut = self.poly_async.buy_fak(
-            &pair.poly_no_token,
-            cents_to_price(req.no_price),
-            contracts as f64,
-        );
-        let (yes_res, no_res) = tokio::join!(yes_fut, no_fut);
+    ) -> Result<(LegFill, LegFill)> {
+        // === SAME-PLATFORM: Poly YES + Poly NO in one batch request ===
+        let legs = [
+            OrderLeg::buy(&pair.poly_yes_token, cents_to_price(req.yes_price), contracts as f64),
+            OrderLeg::buy(&pair.poly_no_token, cents_to_price(req.no_price), contracts as f64),
+        ];
+        let results = self.exchange.place_orders(&legs).await?;
+        let mut results = results.into_iter();
+        let yes_res = results.next().unwrap_or_else(|| Err(anyhow!("missing YES result")));
+        let no_res = results.next().unwrap_or_else(|| Err(anyhow!("missing NO result")));
         self.extract_poly_only_results(yes_res, no_res)
     }
 
@@ -286,38 +600,44 @@ impl ExecutionEngine {
     /// Extract results from Poly-only execution (same-platform)
     fn extract_poly_only_results(
         &self,
-        yes_res: Result<crate::polymarket_clob::PolyFillAsync>,
-        no_res: Result<crate::polymarket_clob::PolyFillAsync>,
-    ) -> Result<(i64, i64, i64, i64, String, String)> {
-        let (yes_filled, yes_cost, yes_order_id) = match yes_res {
-            Ok(fill) => {
-                ((fill.filled_size as i64), (fill.fill_cost * 100.0) as i64, fill.order_id)
-            }
+        yes_res: Result<Fill>,
+        no_res: Result<Fill>,
+    ) -> Result<(LegFill, LegFill)> {
+        let yes = match yes_res {
+            Ok(fill) => LegFill::from(fill),
             Err(e) => {
                 warn!("[EXEC] Poly YES failed: {}", e);
-                (0, 0, String::new())
+                LegFill::failed(&e)
             }
         };
 
-        let (no_filled, no_cost, no_order_id) = match no_res {
-            Ok(fill) => {
-                ((fill.filled_size as i64), (fill.fill_cost * 100.0) as i64, fill.order_id)
-            }
+        let no = match no_res {
+            Ok(fill) => LegFill::from(fill),
             Err(e) => {
                 warn!("[EXEC] Poly NO failed: {}", e);
-                (0, 0, String::new())
+                LegFill::failed(&e)
             }
         };
 
-        // For same-platform, return YES as "kalshi" slot and NO as "poly" slot
-        // This keeps the existing result handling logic working
-        Ok((yes_filled, no_filled, yes_cost, no_cost, yes_order_id, no_order_id))
+        Ok((yes, no))
     }
 
 
-    /// Background auto-close for mismatched fills
+    /// Look up an accepted order whose fill was unknown, giving the exchange time to index it
+    async fn lookup_fill(exchange: &E, order_id: &str) -> Option<OrderStatus> {
+        for attempt in 1..=FILL_LOOKUP_ATTEMPTS {
+            tokio::time::sleep(FILL_LOOKUP_DELAY * attempt).await;
+            match exchange.query_order(order_id).await {
+                Ok(status) => return Some(status),
+                Err(e) => warn!("[EXEC] Fill lookup {} failed ({}/{}): {}", order_id, attempt, FILL_LOOKUP_ATTEMPTS, e),
+            }
+        }
+        None
+    }
+
+    /// Background auto-close for mismatched fills; returns the closing sell, if any filled
     async fn auto_close_background(
-        poly_async: Arc<SharedAsyncClient>,
+        exchange: Arc<E>,
         yes_filled: i64,
         no_filled: i64,
         yes_price: u16,
@@ -325,10 +645,10 @@ impl ExecutionEngine {
         poly_yes_token: Arc<str>,
         poly_no_token: Arc<str>,
         original_cost_per_contract: i64,
-    ) {
+    ) -> Option<(&'static str, Fill)> {
         let excess = (yes_filled - no_filled).abs();
         if excess == 0 {
-            return;
+            return None;
         }
 
         // Helper to log P&L after close
@@ -352,9 +672,15 @@ impl ExecutionEngine {
         info!("[EXEC] 🔄 Waiting 2s for Poly settlement before auto-close ({} {} contracts)", excess, side);
         tokio::time::sleep(Duration::from_secs(2)).await;
 
-        match poly_async.sell_fak(token, close_price, excess as f64).await {
-            Ok(fill) => log_close_pnl(fill.filled_size as i64, (fill.fill_cost * 100.0) as i64),
-            Err(e) => warn!("[EXEC] ⚠️ Failed to close Poly excess: {}", e),
+        match exchange.place_order(OrderLeg::sell(token, close_price, excess as f64)).await {
+            Ok(fill) => {
+                log_close_pnl(fill.filled_size as i64, (fill.fill_cost * 100.0) as i64);
+                (fill.filled_size > 0.0).then_some((side, fill))
+            }
+            Err(e) => {
+                warn!("[EXEC] ⚠️ Failed to close Poly excess: {}", e);
+                None
+            }
         }
     }
 
@@ -382,12 +708,70 @@ impl ExecutionEngine {
     }
 }
 
+/// An order the exchange refused; liquidity misses (FAK with nothing to match) don't count
+fn is_reject(err: &ClobError) -> bool {
+    err.policy().breaker != BreakerAction::Ignore
+}
+
+/// Fill outcome for a single leg (contracts and cents)
+#[derive(Debug, Clone, Default)]
+pub struct LegFill {
+    pub filled: i64,
+    pub cost_cents: i64,
+    pub fee_cents: i64,
+    /// Fees in dollars, as recorded in FillRecord
+    pub fees: f64,
+    pub order_id: String,
+    /// Why the leg was not placed, if it failed
+    pub error: Option<ClobError>,
+    /// Accepted but the fill is not known yet (filled is zero until looked up)
+    pub fill_unknown: bool,
+}
+
+impl LegFill {
+    pub fn failed(err: &anyhow::Error) -> Self {
+        Self {
+            error: Some(ClobError::from_anyhow(err)),
+            ..Default::default()
+        }
+    }
+
+    /// Leg as reported by an order lookup
+    fn resolved(status: &OrderStatus, fee_bps: u16) -> Self {
+        let filled = status.size_matched as i64;
+        let fees = fee_for_fill(status.price, filled as f64, fee_bps);
+        Self {
+            filled,
+            cost_cents: (filled as f64 * status.price * 100.0).round() as i64,
+            fee_cents: (fees * 100.0).ceil() as i64,
+            fees,
+            order_id: status.order_id.clone(),
+            error: None,
+            fill_unknown: false,
+        }
+    }
+}
+
+impl From<Fill> for LegFill {
+    fn from(fill: Fill) -> Self {
+        Self {
+            filled: fill.filled_size as i64,
+            cost_cents: (fill.fill_cost * 100.0) as i64,
+            fee_cents: (fill.fees * 100.0).ceil() as i64,
+            fees: fill.fees,
+            order_id: fill.order_id,
+            error: None,
+            fill_unknown: fill.fill_unknown,
+        }
+    }
+}
+
 /// Execution result
 #[derive(Debug, Clone, Copy)]
 pub struct ExecutionResult {
     pub market_id: u16,
     pub success: bool,
-    pub profit_cents: i16,
+    pub profit_cents: i64,
     pub latency_ns: u64,
     pub error: Option<&'static str>,
 }
@@ -398,9 +782,9 @@ pub fn create_execution_channel() -> (mpsc::Sender<FastExecutionRequest>, mpsc::
 }
 
 /// Execution loop
-pub async fn run_execution_loop(
+pub async fn run_execution_loop<E: ExchangeClient>(
     mut rx: mpsc::Receiver<FastExecutionRequest>,
-    engine: Arc<ExecutionEngine>,
+    engine: Arc<ExecutionEngine<E>>,
 ) {
     info!("[EXEC] Execution engine started (dry_run={})", engine.dry_run);
 
@@ -417,7 +801,7 @@ pub async fn run_execution_loop(
                     );
                 }
                 Ok(result) => {
-                    if result.error != Some("Already in-flight") {
+                    if !matches!(result.error, Some("Already in-flight" | "Shutting down")) {
                         warn!(
                             "[EXEC] ⚠️ market_id={}: {:?}",
                             result.market_id, result.error
@@ -0,0 +1,264 @@
+// src/kill_switch.rs
+// Operator kill switch - halt and resume trading by signal, kill-file or local control endpoint
+
+use anyhow::Result;
+use futures_util::future::BoxFuture;
+use hyper::server::conn::AddrStream;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use serde_json::{Value, json};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::circuit_breaker::CircuitBreaker;
+use crate::exchange::{CancelResult, ExchangeClient};
+use crate::rate_limit::RateLimiter;
+
+/// Kill switch configuration from environment
+#[derive(Debug, Clone)]
+pub struct KillSwitchConfig {
+    /// Halt while this file exists, resume when it is removed (empty = not watched)
+    pub kill_file: String,
+
+    /// How often the kill-file is checked (milliseconds)
+    pub poll_ms: u64,
+
+    /// Control endpoint bind address (keep it on loopback)
+    pub control_addr: String,
+
+    /// Bearer token for the control endpoint (empty = endpoint disabled)
+    pub control_token: String,
+
+    /// Cancel all open orders on every halt
+    pub cancel_on_halt: bool,
+}
+
+impl KillSwitchConfig {
+    pub fn from_env() -> Self {
+        Self {
+            kill_file: std::env::var("KILL_FILE").unwrap_or_default(),
+
+            poll_ms: std::env::var("KILL_FILE_POLL_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1000),
+
+            control_addr: std::env::var("CONTROL_ADDR")
+                .unwrap_or_else(|_| "127.0.0.1:9108".to_string()),
+
+            control_token: std::env::var("CONTROL_TOKEN").unwrap_or_default(),
+
+            cancel_on_halt: std::env::var("KILL_CANCEL_ORDERS")
+                .map(|v| v == "1" || v == "true")
+                .unwrap_or(false),
+        }
+    }
+}
+
+/// Object-safe view of the one venue call the kill switch needs
+trait CancelAll: Send + Sync {
+    fn cancel_all(&self) -> BoxFuture<'_, Result<CancelResult>>;
+}
+
+impl<E: ExchangeClient> CancelAll for E {
+    fn cancel_all(&self) -> BoxFuture<'_, Result<CancelResult>> {
+        Box::pin(ExchangeClient::cancel_all(self))
+    }
+}
+
+/// Halts and resumes trading through the circuit breaker on operator request
+pub struct KillSwitch {
+    config: KillSwitchConfig,
+    breaker: Arc<CircuitBreaker>,
+    exchange: Option<Arc<dyn CancelAll>>,
+    /// Limiter whose throttling counters /status reports
+    rate_limiter: Option<Arc<RateLimiter>>,
+    /// Kill-file existed at the last check
+    file_present: AtomicBool,
+}
+
+impl KillSwitch {
+    pub fn new(config: KillSwitchConfig, breaker: Arc<CircuitBreaker>) -> Self {
+        Self {
+            config,
+            breaker,
+            exchange: None,
+            rate_limiter: None,
+            file_present: AtomicBool::new(false),
+        }
+    }
+
+    /// Venue whose open orders a halt can cancel
+    pub fn with_exchange<E: ExchangeClient>(mut self, exchange: Arc<E>) -> Self {
+        self.exchange = Some(exchange);
+        self
+    }
+
+    /// Report this limiter's throttling counters on /status
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Halt trading, even with the breaker disabled.
+    /// Open orders are cancelled when `cancel` or KILL_CANCEL_ORDERS is set.
+    pub async fn halt(&self, by: &str, cancel: bool) {
+        self.breaker.halt(by).await;
+        if cancel || self.config.cancel_on_halt {
+            self.cancel_open_orders(by).await;
+        }
+    }
+
+    /// Clear the manual halt. A breaker trip (loss limit, errors, ...) stays in force.
+    pub async fn resume(&self, by: &str) {
+        if !self.breaker.clear_manual_halt().await {
+            info!("[KILL] Resume by {}: trading was not manually halted", by);
+            return;
+        }
+        let status = self.breaker.status().await;
+        match status.trip_reason.filter(|_| status.halted) {
+            Some(reason) => warn!("[KILL] Manual halt cleared by {} - breaker still halted: {}", by, reason),
+            None => warn!("[KILL] Trading resumed by {}", by),
+        }
+    }
+
+    async fn cancel_open_orders(&self, by: &str) {
+        let Some(exchange) = &self.exchange else {
+            warn!("[KILL] Cancel requested by {} but no venue is attached", by);
+            return;
+        };
+        match exchange.cancel_all().await {
+            Ok(resp) => warn!("[KILL] Cancelled {} open orders for {} ({} could not be cancelled)",
+                              resp.canceled.len(), by, resp.not_canceled.len()),
+            Err(e) => error!("[KILL] Cancel-all for {} failed: {}", by, e),
+        }
+    }
+
+    /// Act on kill-file changes since the last check: created halts, removed resumes
+    /// (clearing only the manual halt - a later loss limit stays in force)
+    pub async fn check_kill_file(&self) {
+        if self.config.kill_file.is_empty() {
+            return;
+        }
+        let present = Path::new(&self.config.kill_file).exists();
+        if self.file_present.swap(present, Ordering::SeqCst) == present {
+            return;
+        }
+        let by = format!("kill-file {}", self.config.kill_file);
+        if present {
+            self.halt(&by, false).await;
+        } else {
+            self.resume(&by).await;
+        }
+    }
+
+    /// Poll the kill-file forever
+    pub async fn watch_kill_file(self: Arc<Self>) {
+        if self.config.kill_file.is_empty() {
+            return;
+        }
+        info!("[KILL] Watching kill-file {}", self.config.kill_file);
+        let mut interval = tokio::time::interval(Duration::from_millis(self.config.poll_ms.max(10)));
+        loop {
+            interval.tick().await;
+            self.check_kill_file().await;
+        }
+    }
+
+    /// SIGUSR1 halts, SIGUSR2 resumes
+    pub async fn watch_signals(self: Arc<Self>) -> Result<()> {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let mut halt = signal(SignalKind::user_defined1())?;
+        let mut resume = signal(SignalKind::user_defined2())?;
+        info!("[KILL] SIGUSR1 halts trading, SIGUSR2 resumes");
+        loop {
+            tokio::select! {
+                _ = halt.recv() => self.halt("SIGUSR1", false).await,
+                _ = resume.recv() => self.resume("SIGUSR2").await,
+            }
+        }
+    }
+
+    /// Serve the control endpoint; returns the bound address.
+    ///
+    /// `POST /halt[?cancel=1]`, `POST /resume` and `GET /status`, each with
+    /// `Authorization: Bearer <CONTROL_TOKEN>`. `X-Operator` names who is asking.
+    /// Every answer carries the halt state and, with a limiter attached, its throttling counters.
+    pub async fn spawn_control(self: Arc<Self>, addr: SocketAddr) -> Result<SocketAddr> {
+        let make_svc = make_service_fn(move |conn: &AddrStream| {
+            let kill_switch = self.clone();
+            let peer = conn.remote_addr();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let kill_switch = kill_switch.clone();
+                    async move { Ok::<_, Infallible>(kill_switch.handle(req, peer).await) }
+                }))
+            }
+        });
+
+        let server = Server::try_bind(&addr)?.serve(make_svc);
+        let local_addr = server.local_addr();
+        tokio::spawn(async move {
+            if let Err(e) = server.await {
+                error!("[KILL] Control endpoint stopped: {}", e);
+            }
+        });
+        info!("[KILL] Control endpoint on http://{}", local_addr);
+        Ok(local_addr)
+    }
+
+    async fn handle(&self, req: Request<Body>, peer: SocketAddr) -> Response<Body> {
+        let authorized = req.headers().get("Authorization")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .is_some_and(|token| token_matches(token, &self.config.control_token));
+        if !authorized {
+            warn!("[KILL] Unauthorized {} {} from {}", req.method(), req.uri().path(), peer);
+            return respond(StatusCode::UNAUTHORIZED, json!({ "error": "unauthorized" }));
+        }
+
+        let operator = req.headers().get("X-Operator")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("unknown");
+        let by = format!("control endpoint ({} from {})", operator, peer);
+        let cancel = req.uri().query().unwrap_or("")
+            .split('&')
+            .any(|kv| kv == "cancel=1" || kv == "cancel=true");
+
+        match (req.method().as_str(), req.uri().path()) {
+            ("POST", "/halt") => self.halt(&by, cancel).await,
+            ("POST", "/resume") => self.resume(&by).await,
+            ("GET", "/status") => {}
+            _ => return respond(StatusCode::NOT_FOUND, json!({ "error": "not found" })),
+        }
+
+        let status = self.breaker.status().await;
+        respond(StatusCode::OK, json!({
+            "halted": status.halted || status.manual_halt,
+            "manual_halt": status.manual_halt,
+            "reason": status.trip_reason.filter(|_| status.halted).map(|r| r.to_string()),
+            "rate_limits": self.rate_limiter.as_ref().map(|l| l.metrics().to_json()),
+        }))
+    }
+}
+
+/// Compare tokens without exiting early on the first differing byte
+fn token_matches(given: &str, expected: &str) -> bool {
+    !expected.is_empty()
+        && given.len() == expected.len()
+        && given.bytes().zip(expected.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+fn respond(status: StatusCode, body: Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .expect("valid response")
+}
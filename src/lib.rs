@@ -1,11 +1,26 @@
 // src/lib.rs
 
+#[cfg(feature = "replay")]
+pub mod backtest;
+pub mod balance;
+pub mod behaviour;
 pub mod cache;
 pub mod circuit_breaker;
+pub mod clob_error;
 pub mod config;
 pub mod discovery;
+pub mod emulator;
+pub mod exchange;
 pub mod execution;
+pub mod kill_switch;
+pub mod opportunity;
+pub mod paper;
 pub mod polymarket;
 pub mod polymarket_clob;
 pub mod position_tracker;
+pub mod presign;
+pub mod rate_limit;
+pub mod recorder;
+#[cfg(feature = "replay")]
+pub mod replay;
 pub mod types;
\ No newline at end of file
@@ -3,14 +3,24 @@
 //! Strategy: BUY YES + BUY NO on Polymarket
 //! Arb exists when: YES_ask + NO_ask < $1.00
 
+mod balance;
+mod behaviour;
 mod cache;
 mod circuit_breaker;
+mod clob_error;
 mod config;
 mod discovery;
+mod exchange;
 mod execution;
+mod kill_switch;
+mod opportunity;
+mod paper;
 mod polymarket;
 mod polymarket_clob;
 mod position_tracker;
+mod presign;
+mod rate_limit;
+mod recorder;
 mod types;
 
 use anyhow::{Context, Result};
@@ -18,16 +28,25 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{error, info, warn};
 
+use balance::{BalanceConfig, BalanceMonitor, balance_refresh_loop};
 use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig};
-use config::{ARB_THRESHOLD, ENABLED_LEAGUES, WS_RECONNECT_DELAY_SECS};
+use config::{
+    ARB_THRESHOLD, ENABLED_LEAGUES, WS_RECONNECT_DELAY_SECS, get_league_configs, max_quote_age_ms, poly_clob_host,
+    shutdown_timeout_secs, validate_league_configs,
+};
 use discovery::DiscoveryClient;
-use execution::{ExecutionEngine, create_execution_channel, run_execution_loop};
-use polymarket_clob::{PolymarketAsyncClient, PreparedCreds, SharedAsyncClient};
+use execution::{ExecutionEngine, ExecutionGate, NanoClock, create_execution_channel, run_execution_loop};
+use kill_switch::{KillSwitch, KillSwitchConfig};
+use opportunity::{OpportunityConfig, OpportunityTracker};
+use paper::{DepthBook, PaperConfig, PaperExchange};
+use polymarket::GammaClient;
+use polymarket_clob::{PolymarketAsyncClient, PreparedCreds, SharedAsyncClient, SignatureType};
 use position_tracker::{PositionTracker, create_position_channel, position_writer_loop};
+use presign::{PresignConfig, PresignPool, presign_loop};
+use rate_limit::{RateLimitConfig, RateLimiter};
+use recorder::{RecorderConfig, WsRecorder};
 use types::{GlobalState, PriceCents};
 
-/// Polymarket CLOB API host
-const POLY_CLOB_HOST: &str = "https://clob.polymarket.com";
 /// Polygon chain ID
 const POLYGON_CHAIN_ID: u64 = 137;
 
@@ -45,10 +64,14 @@ async fn main() -> Result<()> {
     info!("   Threshold: <{:.1}¢ for {:.1}% profit",
           ARB_THRESHOLD * 100.0, (1.0 - ARB_THRESHOLD) * 100.0);
     info!("   Leagues: {:?}", ENABLED_LEAGUES);
+    validate_league_configs(&get_league_configs()).context("Invalid league table")?;
 
     // Check for dry run mode
     let dry_run = std::env::var("DRY_RUN").map(|v| v == "1" || v == "true").unwrap_or(true);
-    if dry_run {
+    let paper_config = PaperConfig::from_env();
+    if paper_config.enabled {
+        info!("   Mode: PAPER TRADING (simulated fills against the live book)");
+    } else if dry_run {
         info!("   Mode: DRY RUN (set DRY_RUN=0 to execute)");
     } else {
         warn!("   Mode: LIVE EXECUTION");
@@ -61,25 +84,66 @@ async fn main() -> Result<()> {
     let poly_funder = std::env::var("POLY_FUNDER")
         .context("POLY_FUNDER not set (your wallet address)")?;
 
-    // Create async Polymarket client and derive API credentials
-    info!("[POLYMARKET] Creating async client and deriving API credentials...");
-    let poly_async_client = PolymarketAsyncClient::new(
-        POLY_CLOB_HOST,
+    // Shared client-side rate limiter (CLOB + Gamma)
+    let rate_limiter = RateLimiter::shared(RateLimitConfig::from_env());
+
+    // Create async Polymarket client (public reads work without API credentials)
+    let clob_host = poly_clob_host();
+    info!("[POLYMARKET] Creating async client ({})...", clob_host);
+    let poly_client = Arc::new(PolymarketAsyncClient::new(
+        &clob_host,
         POLYGON_CHAIN_ID,
         &poly_private_key,
         &poly_funder,
-    )?;
-    let api_creds = poly_async_client.derive_api_key(0).await?;
-    let prepared_creds = PreparedCreds::from_api_creds(&api_creds)?;
-    let poly_async = Arc::new(SharedAsyncClient::new(poly_async_client, prepared_creds, POLYGON_CHAIN_ID));
-
-    // Load neg_risk cache from Python script output
-    match poly_async.load_cache(".clob_market_cache.json") {
-        Ok(count) => info!("[POLYMARKET] Loaded {} neg_risk entries from cache", count),
-        Err(e) => warn!("[POLYMARKET] Could not load neg_risk cache: {}", e),
-    }
+    )?
+    .with_rate_limiter(rate_limiter.clone())
+    .with_signature_type(SignatureType::from_env()?)
+    .context("Invalid POLY_SIGNATURE_TYPE / POLY_FUNDER combination")?);
+    info!("[POLYMARKET] Signature type: {}", poly_client.signature_type().as_str());
+
+    // Live venue: API credentials, stale order cleanup and collateral checks.
+    // Paper trading never touches the account, so none of it is set up.
+    let presign_pool = Arc::new(PresignPool::new(PresignConfig::from_env()));
+    let live = if paper_config.enabled {
+        info!("[POLYMARKET] Paper trading: skipping API credentials and open order reconciliation");
+        None
+    } else {
+        info!("[POLYMARKET] Deriving API credentials...");
+        let api_creds = poly_client.derive_api_key(0).await?;
+        let prepared_creds = PreparedCreds::from_api_creds(&api_creds)?;
+        let mut shared_client = SharedAsyncClient::new(poly_client.clone(), prepared_creds, POLYGON_CHAIN_ID);
+        if presign_pool.enabled() {
+            shared_client = shared_client.with_presign_pool(presign_pool.clone());
+        }
+        let poly_async = Arc::new(shared_client);
+
+        // Load neg_risk cache from Python script output
+        match poly_async.load_cache(".clob_market_cache.json") {
+            Ok(count) => info!("[POLYMARKET] Loaded {} neg_risk entries from cache", count),
+            Err(e) => warn!("[POLYMARKET] Could not load neg_risk cache: {}", e),
+        }
+
+        // Reconcile orders left resting by a previous run (cancel only when live)
+        match poly_async.reconcile_open_orders(!dry_run).await {
+            Ok(0) => info!("[POLYMARKET] No stale open orders"),
+            Ok(count) if dry_run => warn!("[POLYMARKET] {} stale open orders (DRY RUN - not cancelled)", count),
+            Ok(count) => info!("[POLYMARKET] Reconciled {} stale open orders", count),
+            Err(e) => warn!("[POLYMARKET] Open order reconciliation failed: {}", e),
+        }
 
-    info!("[POLYMARKET] Client ready for {}", &poly_funder[..10]);
+        info!("[POLYMARKET] Client ready for {}", &poly_funder[..10]);
+
+        // Collateral balance / allowance (gates live execution)
+        let balance_monitor = Arc::new(BalanceMonitor::new(BalanceConfig::from_env()));
+        match balance_monitor.refresh(&poly_async).await {
+            Ok(snapshot) => info!("[BALANCE] USDC balance ${:.2} (free ${:.2})",
+                                  snapshot.balance as f64 / 1_000_000.0, balance_monitor.available_usdc()),
+            Err(e) => warn!("[BALANCE] Initial balance read failed: {}", e),
+        }
+        tokio::spawn(balance_refresh_loop(balance_monitor.clone(), poly_async.clone()));
+
+        Some((poly_async, balance_monitor))
+    };
 
     // Run discovery (with caching support)
     let force_discovery = std::env::var("FORCE_DISCOVERY")
@@ -89,7 +153,7 @@ async fn main() -> Result<()> {
     info!("🔍 Discovering markets{}...",
           if force_discovery { " (forced refresh)" } else { "" });
 
-    let discovery = DiscoveryClient::new();
+    let discovery = DiscoveryClient::with_rate_limiter(rate_limiter.clone());
 
     let result = if force_discovery {
         discovery.discover_all_force(ENABLED_LEAGUES).await
@@ -125,31 +189,157 @@ async fn main() -> Result<()> {
         for pair in result.pairs {
             s.add_pair(pair);
         }
+        s.max_quote_age_ns = max_quote_age_ms() * 1_000_000;
         info!("📡 State: Tracking {} markets", s.market_count());
         s
     });
 
+    // Fetch taker fee rates so detection and signing use the exchange's rate
+    let fee_futures: Vec<_> = state.markets.iter()
+        .take(state.market_count())
+        .filter_map(|m| m.pair.as_ref().map(|p| (m, p)))
+        .map(|(market, pair)| {
+            let live_client = live.as_ref().map(|(poly_async, _)| poly_async.clone());
+            let poly_client = poly_client.clone();
+            async move {
+                // The live client caches rates for signing; paper only needs them for detection
+                let (yes_res, no_res) = match &live_client {
+                    Some(poly_async) => tokio::join!(
+                        poly_async.fee_rate_bps(&pair.poly_yes_token),
+                        poly_async.fee_rate_bps(&pair.poly_no_token),
+                    ),
+                    None => tokio::join!(
+                        poly_client.get_fee_rate_bps(&pair.poly_yes_token),
+                        poly_client.get_fee_rate_bps(&pair.poly_no_token),
+                    ),
+                };
+                match (yes_res, no_res) {
+                    (Ok(yes_bps), Ok(no_bps)) => {
+                        market.set_fee_rates(yes_bps, no_bps);
+                        (yes_bps > 0 || no_bps > 0) as usize
+                    }
+                    (Err(e), _) | (_, Err(e)) => {
+                        warn!("[POLYMARKET] Fee rate lookup failed for {}: {}", pair.description, e);
+                        0
+                    }
+                }
+            }
+        })
+        .collect();
+    let fee_markets: usize = futures_util::future::join_all(fee_futures).await.into_iter().sum();
+    info!("[POLYMARKET] Fee rates loaded ({} fee-bearing markets)", fee_markets);
+
     // Create execution infrastructure
     let (exec_tx, exec_rx) = create_execution_channel();
-    let circuit_breaker = Arc::new(CircuitBreaker::new(CircuitBreakerConfig::from_env()));
+    let mut cb_config = CircuitBreakerConfig::from_env();
+    if paper_config.enabled {
+        // Paper halts and exposure stay out of the live breaker's saved state
+        let path = std::path::Path::new(&cb_config.state_file);
+        if let Some(name) = path.file_name() {
+            cb_config.state_file = path.with_file_name(format!("paper_{}", name.to_string_lossy()))
+                .to_string_lossy()
+                .into_owned();
+        }
+    }
+    let circuit_breaker = Arc::new(CircuitBreaker::load(cb_config)
+        .with_markets(state.markets.iter().filter_map(|m| m.pair.as_deref())));
+    tokio::spawn(circuit_breaker::persist_loop(circuit_breaker.clone()));
+    tokio::spawn(circuit_breaker::supervisor_loop(circuit_breaker.clone()));
+    tokio::spawn(circuit_breaker::resolution_loop(
+        circuit_breaker.clone(),
+        GammaClient::with_rate_limiter(rate_limiter.clone()),
+    ));
 
     let position_tracker = Arc::new(RwLock::new(PositionTracker::new()));
-    let (position_channel, position_rx) = create_position_channel();
+    let (position_channel, mut position_rx) = create_position_channel();
 
-    tokio::spawn(position_writer_loop(position_rx, position_tracker));
+    // Both end once the engine and its auto-closes drop their senders (shutdown)
+    let position_handle = if paper_config.enabled {
+        // The paper exchange tracks its own fills; keep them out of the live positions file
+        tokio::spawn(async move { while position_rx.recv().await.is_some() {} })
+    } else {
+        tokio::spawn(position_writer_loop(position_rx, position_tracker.clone()))
+    };
 
     let threshold_cents: PriceCents = ((ARB_THRESHOLD * 100.0).round() as u16).max(1);
     info!("   Threshold: {} cents", threshold_cents);
 
-    let engine = Arc::new(ExecutionEngine::new(
-        poly_async,
-        state.clone(),
-        circuit_breaker.clone(),
-        position_channel,
-        dry_run,
-    ));
+    // One clock for quote stamps, detection and execution latency
+    let clock = NanoClock::new();
+
+    // Opportunity lifecycle: one logged episode per arb, from crossing until it's gone
+    let opportunity_config = OpportunityConfig::from_env();
+    let opportunities = opportunity_config.enabled.then(|| {
+        Arc::new(OpportunityTracker::new(clock, recorder::now_ns())
+            .with_log(&opportunity_config.log_file))
+    });
+
+    // Paper trading: full-depth book from the WS feeds the simulated exchange
+    let depth_book = paper_config.enabled.then(|| Arc::new(DepthBook::new()));
+    let paper_exchange = depth_book.as_ref().map(|depth| {
+        Arc::new(PaperExchange::new(paper_config.clone(), depth.clone(), state.clone()))
+    });
 
-    let exec_handle = tokio::spawn(run_execution_loop(exec_rx, engine));
+    // Operator kill switch: SIGUSR1/SIGUSR2, kill-file and the local control endpoint
+    let kill_config = KillSwitchConfig::from_env();
+    let kill_switch = KillSwitch::new(kill_config.clone(), circuit_breaker.clone())
+        .with_rate_limiter(rate_limiter.clone());
+    let kill_switch = Arc::new(match (&paper_exchange, &live) {
+        (Some(paper_exchange), _) => kill_switch.with_exchange(paper_exchange.clone()),
+        (None, Some((poly_async, _))) => kill_switch.with_exchange(poly_async.clone()),
+        (None, None) => kill_switch,
+    });
+    kill_switch.check_kill_file().await;
+    tokio::spawn(kill_switch.clone().watch_kill_file());
+    let signal_kill_switch = kill_switch.clone();
+    tokio::spawn(async move {
+        if let Err(e) = signal_kill_switch.watch_signals().await {
+            error!("[KILL] Signal handlers unavailable: {}", e);
+        }
+    });
+    if kill_config.control_token.is_empty() {
+        info!("[KILL] Control endpoint disabled (set CONTROL_TOKEN to enable)");
+    } else {
+        let addr = kill_config.control_addr.parse().context("Invalid CONTROL_ADDR")?;
+        kill_switch.clone().spawn_control(addr).await?;
+    }
+
+    // Running executions and auto-closes, drained on shutdown
+    let executions = ExecutionGate::new();
+
+    let exec_handle = if let Some(paper_exchange) = &paper_exchange {
+        let mut engine = ExecutionEngine::new(
+            paper_exchange.clone(),
+            state.clone(),
+            circuit_breaker.clone(),
+            position_channel,
+            false,
+        ).with_clock(clock).with_gate(executions.clone());
+        if let Some(opportunities) = &opportunities {
+            engine = engine.with_opportunities(opportunities.clone());
+        }
+        tokio::spawn(run_execution_loop(exec_rx, Arc::new(engine)))
+    } else if let Some((poly_async, balance_monitor)) = live {
+        let mut engine = ExecutionEngine::new(
+            poly_async.clone(),
+            state.clone(),
+            circuit_breaker.clone(),
+            position_channel,
+            dry_run,
+        ).with_clock(clock).with_gate(executions.clone());
+        if !dry_run {
+            engine = engine.with_balance_monitor(balance_monitor);
+        }
+        if let Some(opportunities) = &opportunities {
+            engine = engine.with_opportunities(opportunities.clone());
+        }
+        if presign_pool.enabled() {
+            tokio::spawn(presign_loop(presign_pool, state.clone(), poly_async));
+        }
+        tokio::spawn(run_execution_loop(exec_rx, Arc::new(engine)))
+    } else {
+        unreachable!("live venue is set up whenever paper trading is off")
+    };
 
     // === TEST MODE: Inject fake arb after delay ===
     // TEST_ARB=1 to enable, TEST_ARB_TYPE=poly_yes_kalshi_no|kalshi_yes_poly_no|poly_only|kalshi_only
@@ -181,12 +371,15 @@ async fn main() -> Result<()> {
                 if let Some(market) = test_state.get_by_id(market_id as u16) {
                     if let Some(pair) = &market.pair {
                         // SIZE: 1000 cents = 10 contracts (Poly $1 min requires ~3 contracts at 40¢)
+                        let (yes_fee_bps, no_fee_bps) = market.fee_rates();
                         let fake_req = FastExecutionRequest {
                             market_id: market_id as u16,
                             yes_price,
                             no_price,
                             yes_size: 1000,  // 1000¢ = 10 contracts
                             no_size: 1000,   // 1000¢ = 10 contracts
+                            yes_fee_bps,
+                            no_fee_bps,
                             arb_type,
                             detected_ns: 0,
                         };
@@ -206,13 +399,28 @@ async fn main() -> Result<()> {
         });
     }
 
+    // Optional raw WS recording for after-the-fact investigation
+    let recorder_config = RecorderConfig::from_env();
+    let ws_recorder = if recorder_config.enabled {
+        let recorder = WsRecorder::start(&recorder_config)?;
+        if let Err(e) = recorder::save_markets(&recorder_config.dir, &state) {
+            warn!("[REC] Could not save market list: {}", e);
+        }
+        Some(Arc::new(recorder))
+    } else {
+        None
+    };
+
     // Start Polymarket WebSocket
     let poly_state = state.clone();
     let poly_exec_tx = exec_tx.clone();
     let poly_threshold = threshold_cents;
+    let poly_depth = depth_book.clone();
+    let poly_recorder = ws_recorder.clone();
+    let poly_opportunities = opportunities.clone();
     let poly_handle = tokio::spawn(async move {
         loop {
-            if let Err(e) = polymarket::run_ws(poly_state.clone(), poly_exec_tx.clone(), poly_threshold).await {
+            if let Err(e) = polymarket::run_ws(poly_state.clone(), poly_exec_tx.clone(), poly_threshold, clock, poly_depth.clone(), poly_recorder.clone(), poly_opportunities.clone()).await {
                 error!("[POLYMARKET] Disconnected: {} - reconnecting...", e);
             }
             tokio::time::sleep(tokio::time::Duration::from_secs(WS_RECONNECT_DELAY_SECS)).await;
@@ -222,6 +430,10 @@ async fn main() -> Result<()> {
     // Heartbeat task with arb diagnostics
     let heartbeat_state = state.clone();
     let heartbeat_threshold = threshold_cents;
+    let heartbeat_limiter = rate_limiter.clone();
+    let heartbeat_paper = paper_exchange.clone();
+    let heartbeat_recorder = ws_recorder.clone();
+    let heartbeat_opportunities = opportunities.clone();
     let heartbeat_handle = tokio::spawn(async move {
         let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
         loop {
@@ -245,6 +457,23 @@ async fn main() -> Result<()> {
             info!("💓 Heartbeat | Markets: {} total, {} w/Poly | threshold={}¢",
                   market_count, with_poly, heartbeat_threshold);
 
+            let rate_metrics = heartbeat_limiter.metrics();
+            if rate_metrics.total_throttled() > 0 {
+                info!("   ⏳ {}", rate_metrics);
+            }
+
+            if let Some(paper) = &heartbeat_paper {
+                info!("   📝 {}", paper.stats());
+            }
+
+            if let Some(recorder) = &heartbeat_recorder {
+                info!("   🎞️ {}", recorder.stats());
+            }
+
+            if let Some(opportunities) = &heartbeat_opportunities {
+                info!("   🔭 {}", opportunities.stats());
+            }
+
             if let Some((cost, market_id, p_yes, p_no)) = best_arb {
                 let gap = cost as i16 - heartbeat_threshold as i16;
                 let desc = heartbeat_state.get_by_id(market_id)
@@ -264,8 +493,84 @@ async fn main() -> Result<()> {
         }
     });
 
-    // Run forever
-    let _ = tokio::join!(poly_handle, heartbeat_handle, exec_handle);
+    // Run until SIGTERM / Ctrl-C
+    shutdown_signal().await;
+    info!("🛑 Shutdown requested");
+
+    // 1. Stop detection (the WS keeps feeding books for orders still working)
+    state.stop_detection();
+
+    // 2. Refuse new executions; 3. wait for in-flight orders and auto-closes
+    executions.close();
+    let timeout_secs = shutdown_timeout_secs();
+    let drained = executions.drain(tokio::time::Duration::from_secs(timeout_secs)).await;
+    if drained {
+        info!("[SHUTDOWN] In-flight executions finished");
+    } else {
+        warn!("[SHUTDOWN] {} executions still running after {}s - not waiting", executions.active(), timeout_secs);
+    }
+    heartbeat_handle.abort();
+    exec_handle.abort();
+    let _ = exec_handle.await;
+
+    // 4. Flush state: the position writer saves its last batch once the engine is gone.
+    // Auto-closes still running hold position senders, so after a timeout the writer
+    // never finishes - save what it has applied instead of waiting on it.
+    if drained {
+        match tokio::time::timeout(tokio::time::Duration::from_secs(5), position_handle).await {
+            Ok(_) => info!("[SHUTDOWN] Positions flushed"),
+            Err(_) => warn!("[SHUTDOWN] Position writer did not finish - latest fills may be unsaved"),
+        }
+    } else if !paper_config.enabled {
+        match position_tracker.read().await.flush().await {
+            Ok(()) => info!("[SHUTDOWN] Positions flushed (fills still in flight are not included)"),
+            Err(e) => error!("[SHUTDOWN] Failed to save positions: {}", e),
+        }
+    }
+    if let Some(paper) = &paper_exchange {
+        match paper.tracker().read().await.flush().await {
+            Ok(()) => info!("[SHUTDOWN] Paper positions flushed"),
+            Err(e) => error!("[SHUTDOWN] Failed to save paper positions: {}", e),
+        }
+    }
+    if let Err(e) = circuit_breaker.save().await {
+        error!("[SHUTDOWN] Failed to save circuit breaker state: {}", e);
+    }
+    if let Some(recorder) = ws_recorder {
+        match tokio::task::spawn_blocking(move || recorder.flush()).await {
+            Ok(Ok(())) => info!("[SHUTDOWN] Recording flushed"),
+            Ok(Err(e)) => warn!("[SHUTDOWN] Recording flush failed: {}", e),
+            Err(e) => warn!("[SHUTDOWN] Recording flush failed: {}", e),
+        }
+    }
+    if let Some(opportunities) = &opportunities {
+        opportunities.stop();
+        opportunities.drain();
+        opportunities.flush_log().await;
+    }
+
+    // 5. Close the WS
+    poly_handle.abort();
+    let _ = poly_handle.await;
+    info!("👋 Shutdown complete");
 
     Ok(())
 }
+
+/// Resolves on SIGTERM or Ctrl-C
+async fn shutdown_signal() {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut term = match signal(SignalKind::terminate()) {
+        Ok(term) => term,
+        Err(e) => {
+            warn!("[SHUTDOWN] No SIGTERM handler ({}), Ctrl-C only", e);
+            let _ = tokio::signal::ctrl_c().await;
+            return;
+        }
+    };
+    tokio::select! {
+        _ = term.recv() => {}
+        _ = tokio::signal::ctrl_c() => {}
+    }
+}
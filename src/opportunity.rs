@@ -0,0 +1,514 @@
+// src/opportunity.rs
+// Opportunity lifecycle tracking - one episode per arb from first crossing until it's gone
+//
+// Detection reports every check_arbs result for a market; the engine reports orders and
+// fills. An episode opens when a market crosses the threshold, tracks its peak edge and
+// size while open, closes when the market prices back above the threshold, and is
+// finalized once any order placed during it has a result. Finalized episodes go to a
+// JSONL log that load_log/query/daily_summaries read back.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::BufRead;
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{info, warn};
+
+use crate::execution::NanoClock;
+use crate::types::{AtomicMarketState, MAX_MARKETS, poly_fee_cents};
+
+/// Opportunity tracking configuration from environment
+#[derive(Debug, Clone)]
+pub struct OpportunityConfig {
+    pub enabled: bool,
+    /// Episode log (JSON lines, appended)
+    pub log_file: String,
+}
+
+impl OpportunityConfig {
+    pub fn from_env() -> Self {
+        Self {
+            enabled: std::env::var("OPPORTUNITY_TRACKING")
+                .map(|v| v != "0" && v != "false")
+                .unwrap_or(true),
+
+            log_file: std::env::var("OPPORTUNITY_LOG")
+                .unwrap_or_else(|_| "opportunities.jsonl".to_string()),
+        }
+    }
+}
+
+/// What we got out of an episode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    /// No order was placed (in-flight, breaker, dry run, gone before execution...)
+    NotAttempted,
+    /// Orders placed, nothing filled
+    Missed,
+    /// Some fills, but legs didn't match
+    Partial,
+    /// Matched fills on both legs
+    Captured,
+}
+
+impl std::str::FromStr for Outcome {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "not_attempted" => Ok(Self::NotAttempted),
+            "missed" => Ok(Self::Missed),
+            "partial" => Ok(Self::Partial),
+            "captured" => Ok(Self::Captured),
+            _ => anyhow::bail!("unknown outcome {s:?} (not_attempted, missed, partial, captured)"),
+        }
+    }
+}
+
+/// One arb opportunity from crossing the threshold until it's gone
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Episode {
+    pub market_id: u16,
+    pub pair_id: String,
+    pub description: String,
+    /// First crossing (Unix nanoseconds)
+    pub start_ns: u64,
+    /// First update that priced the market back above the threshold (Unix nanoseconds)
+    pub end_ns: u64,
+    /// Updates that showed the arb while open
+    pub detections: u32,
+    /// Best 100 - (YES + NO + fees) seen (cents)
+    pub peak_edge_cents: i16,
+    /// Largest size available on both legs at once (contracts)
+    pub max_contracts: f64,
+    /// Largest profit available at once: contracts x edge (dollars)
+    pub max_capturable: f64,
+    /// First order sent (Unix nanoseconds)
+    pub first_order_ns: Option<u64>,
+    pub orders: u32,
+    pub yes_filled: i64,
+    pub no_filled: i64,
+    pub matched: i64,
+    /// Profit on matched contracts less the cost of unmatched ones (cents)
+    pub profit_cents: i64,
+    pub outcome: Outcome,
+    /// Still open when tracking stopped
+    pub open_at_end: bool,
+    /// Orders sent without a result yet
+    #[serde(skip)]
+    pending: u32,
+}
+
+impl Episode {
+    #[allow(dead_code)]
+    pub fn duration_ms(&self) -> f64 {
+        self.end_ns.saturating_sub(self.start_ns) as f64 / 1_000_000.0
+    }
+
+    #[allow(dead_code)]
+    pub fn time_to_order_ms(&self) -> Option<f64> {
+        self.first_order_ns.map(|ns| ns.saturating_sub(self.start_ns) as f64 / 1_000_000.0)
+    }
+
+    fn finalize(&mut self) {
+        self.outcome = if self.orders == 0 {
+            Outcome::NotAttempted
+        } else if self.matched > 0 && self.yes_filled == self.no_filled {
+            Outcome::Captured
+        } else if self.yes_filled > 0 || self.no_filled > 0 {
+            Outcome::Partial
+        } else {
+            Outcome::Missed
+        };
+    }
+}
+
+/// Counters since start
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OpportunityStats {
+    pub opened: u64,
+    pub finalized: u64,
+    pub captured: u64,
+}
+
+impl std::fmt::Display for OpportunityStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Opportunities: {} opened, {} finished, {} captured", self.opened, self.finalized, self.captured)
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    open: FxHashMap<u16, Episode>,
+    /// Closed episodes waiting for an order result
+    awaiting: FxHashMap<u16, Episode>,
+    /// Finalized episodes when there is no log sink
+    finished: Vec<Episode>,
+    stats: OpportunityStats,
+}
+
+/// Tracks arb episodes per market
+pub struct OpportunityTracker {
+    clock: NanoClock,
+    /// Unix nanoseconds at clock zero
+    epoch_ns: u64,
+    /// Lock-free check on the no-arb path
+    open_flags: Box<[AtomicBool]>,
+    inner: Mutex<Inner>,
+    sink: Option<mpsc::UnboundedSender<LogCommand>>,
+}
+
+/// Message to the episode log writer
+enum LogCommand {
+    Episode(Episode),
+    /// Ack once everything sent before has been written
+    Flush(oneshot::Sender<()>),
+}
+
+impl OpportunityTracker {
+    /// Episodes are timed with `clock`; `epoch_ns` maps its zero to Unix time
+    pub fn new(clock: NanoClock, epoch_ns: u64) -> Self {
+        Self {
+            clock,
+            epoch_ns,
+            open_flags: (0..MAX_MARKETS).map(|_| AtomicBool::new(false)).collect(),
+            inner: Mutex::new(Inner::default()),
+            sink: None,
+        }
+    }
+
+    /// Append finalized episodes to a JSONL file from a background task
+    pub fn with_log(mut self, path: &str) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(log_writer_loop(rx, path.to_string()));
+        self.sink = Some(tx);
+        self
+    }
+
+    fn now_ns(&self) -> u64 {
+        self.epoch_ns + self.clock.now_ns()
+    }
+
+    /// Report a check_arbs result for a market
+    #[inline]
+    pub fn observe(&self, market_id: u16, market: &AtomicMarketState, arb_mask: u8) {
+        let Some(flag) = self.open_flags.get(market_id as usize) else { return };
+        if arb_mask == 0 {
+            if flag.load(Ordering::Relaxed) {
+                self.close(market_id);
+            }
+            return;
+        }
+
+        let (yes, no, yes_size, no_size) = market.poly.load();
+        let (yes_fee_bps, no_fee_bps) = market.fee_rates();
+        let cost = yes + no + poly_fee_cents(yes, yes_fee_bps) + poly_fee_cents(no, no_fee_bps);
+        let edge = 100 - cost as i16;
+        let contracts = (yes_size.min(no_size) / 100) as f64;
+        let capturable = contracts * edge.max(0) as f64 / 100.0;
+        let now = self.now_ns();
+
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(episode) = inner.open.get_mut(&market_id) {
+            episode.detections += 1;
+            episode.peak_edge_cents = episode.peak_edge_cents.max(edge);
+            episode.max_contracts = episode.max_contracts.max(contracts);
+            episode.max_capturable = episode.max_capturable.max(capturable);
+            return;
+        }
+
+        let (pair_id, description) = market.pair.as_ref()
+            .map(|p| (p.pair_id.to_string(), p.description.to_string()))
+            .unwrap_or_default();
+        inner.open.insert(market_id, Episode {
+            market_id,
+            pair_id,
+            description,
+            start_ns: now,
+            end_ns: now,
+            detections: 1,
+            peak_edge_cents: edge,
+            max_contracts: contracts,
+            max_capturable: capturable,
+            first_order_ns: None,
+            orders: 0,
+            yes_filled: 0,
+            no_filled: 0,
+            matched: 0,
+            profit_cents: 0,
+            outcome: Outcome::NotAttempted,
+            open_at_end: false,
+            pending: 0,
+        });
+        inner.stats.opened += 1;
+        flag.store(true, Ordering::Relaxed);
+    }
+
+    /// Close a market's open episode (the arb is gone)
+    pub fn close(&self, market_id: u16) {
+        self.close_episode(market_id, false);
+    }
+
+    fn close_episode(&self, market_id: u16, open_at_end: bool) {
+        let now = self.now_ns();
+        let mut inner = self.inner.lock().unwrap();
+        let Some(mut episode) = inner.open.remove(&market_id) else { return };
+        if let Some(flag) = self.open_flags.get(market_id as usize) {
+            flag.store(false, Ordering::Relaxed);
+        }
+        episode.end_ns = now;
+        episode.open_at_end = open_at_end;
+
+        // An order placed during the episode is still out: finalize on its result
+        if episode.pending > 0 {
+            inner.awaiting.insert(market_id, episode);
+        } else {
+            self.finish(&mut inner, episode);
+        }
+    }
+
+    /// The engine is sending orders for a market
+    pub fn on_order(&self, market_id: u16) {
+        let now = self.now_ns();
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(episode) = inner.open.get_mut(&market_id) {
+            episode.first_order_ns.get_or_insert(now);
+            episode.orders += 1;
+            episode.pending += 1;
+        }
+    }
+
+    /// Fills for orders sent with on_order; finalizes the episode if it already closed
+    pub fn on_result(&self, market_id: u16, yes_filled: i64, no_filled: i64, profit_cents: i64) {
+        let mut inner = self.inner.lock().unwrap();
+        let awaiting = inner.awaiting.contains_key(&market_id);
+        let episode = if awaiting { inner.awaiting.get_mut(&market_id) } else { inner.open.get_mut(&market_id) };
+        let Some(episode) = episode else { return };
+
+        episode.pending = episode.pending.saturating_sub(1);
+        episode.yes_filled += yes_filled;
+        episode.no_filled += no_filled;
+        episode.matched += yes_filled.min(no_filled);
+        episode.profit_cents += profit_cents;
+
+        if awaiting && episode.pending == 0 {
+            let episode = inner.awaiting.remove(&market_id).unwrap();
+            self.finish(&mut inner, episode);
+        }
+    }
+
+    fn finish(&self, inner: &mut Inner, mut episode: Episode) {
+        episode.finalize();
+        inner.stats.finalized += 1;
+        if episode.outcome == Outcome::Captured {
+            inner.stats.captured += 1;
+        }
+        match &self.sink {
+            Some(sink) => { let _ = sink.send(LogCommand::Episode(episode)); }
+            None => inner.finished.push(episode),
+        }
+    }
+
+    /// Whether a market has an open episode (lock-free, for the feed hot path)
+    pub fn is_open(&self, market_id: u16) -> bool {
+        self.open_flags.get(market_id as usize).is_some_and(|f| f.load(Ordering::Relaxed))
+    }
+
+    /// Open market ids (for callers that close episodes on their own book view)
+    #[allow(dead_code)]
+    pub fn open_markets(&self) -> Vec<u16> {
+        let mut ids: Vec<u16> = self.inner.lock().unwrap().open.keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    pub fn stats(&self) -> OpportunityStats {
+        self.inner.lock().unwrap().stats
+    }
+
+    /// Close every open episode now, marked open_at_end (tracking is stopping)
+    pub fn stop(&self) {
+        for market_id in self.open_markets() {
+            self.close_episode(market_id, true);
+        }
+    }
+
+    /// Finalize episodes still waiting on order results and return those not sent to the log
+    pub fn drain(&self) -> Vec<Episode> {
+        let mut inner = self.inner.lock().unwrap();
+        let mut awaiting: Vec<Episode> = inner.awaiting.drain().map(|(_, e)| e).collect();
+        awaiting.sort_by_key(|e| (e.start_ns, e.market_id));
+        for episode in awaiting {
+            self.finish(&mut inner, episode);
+        }
+        let mut finished = std::mem::take(&mut inner.finished);
+        finished.sort_by_key(|e| (e.start_ns, e.market_id));
+        finished
+    }
+
+    /// Wait until every finalized episode has been written to the log
+    pub async fn flush_log(&self) {
+        let Some(sink) = &self.sink else { return };
+        let (ack_tx, ack_rx) = oneshot::channel();
+        if sink.send(LogCommand::Flush(ack_tx)).is_ok() {
+            let _ = ack_rx.await;
+        }
+    }
+}
+
+async fn log_writer_loop(mut rx: mpsc::UnboundedReceiver<LogCommand>, path: String) {
+    let mut file = match tokio::fs::OpenOptions::new().create(true).append(true).open(&path).await {
+        Ok(file) => file,
+        Err(e) => {
+            warn!("[OPP] Cannot open {}: {} - episodes will not be logged", path, e);
+            while let Some(cmd) = rx.recv().await {
+                if let LogCommand::Flush(ack) = cmd {
+                    let _ = ack.send(());
+                }
+            }
+            return;
+        }
+    };
+    info!("[OPP] Logging episodes to {}", path);
+
+    while let Some(cmd) = rx.recv().await {
+        match cmd {
+            LogCommand::Episode(episode) => {
+                let Ok(mut line) = serde_json::to_vec(&episode) else { continue };
+                line.push(b'\n');
+                if let Err(e) = file.write_all(&line).await {
+                    warn!("[OPP] Write to {} failed: {}", path, e);
+                }
+            }
+            LogCommand::Flush(ack) => {
+                let _ = file.flush().await;
+                let _ = ack.send(());
+            }
+        }
+    }
+    let _ = file.flush().await;
+}
+
+/// Read an episode log, skipping lines that don't parse (e.g. a torn last write)
+#[allow(dead_code)]
+pub fn load_log<P: AsRef<Path>>(path: P) -> Result<Vec<Episode>> {
+    let path = path.as_ref();
+    let file = std::fs::File::open(path).with_context(|| format!("opening {}", path.display()))?;
+    let mut episodes = Vec::new();
+    for line in std::io::BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(episode) => episodes.push(episode),
+            Err(e) => warn!("[OPP] Skipping bad log line: {}", e),
+        }
+    }
+    Ok(episodes)
+}
+
+/// Filter over logged episodes; unset fields match everything
+#[derive(Debug, Clone, Default)]
+#[allow(dead_code)]
+pub struct EpisodeQuery {
+    /// Episodes starting in [from_ns, to_ns] (Unix nanoseconds)
+    pub from_ns: Option<u64>,
+    pub to_ns: Option<u64>,
+    /// Substring of pair_id or description
+    pub market: Option<String>,
+    pub min_edge_cents: Option<i16>,
+    pub min_duration_ms: Option<f64>,
+    pub outcome: Option<Outcome>,
+}
+
+impl EpisodeQuery {
+    #[allow(dead_code)]
+    pub fn matches(&self, episode: &Episode) -> bool {
+        self.from_ns.is_none_or(|t| episode.start_ns >= t)
+            && self.to_ns.is_none_or(|t| episode.start_ns <= t)
+            && self.market.as_ref().is_none_or(|m| episode.pair_id.contains(m.as_str()) || episode.description.contains(m.as_str()))
+            && self.min_edge_cents.is_none_or(|e| episode.peak_edge_cents >= e)
+            && self.min_duration_ms.is_none_or(|d| episode.duration_ms() >= d)
+            && self.outcome.is_none_or(|o| episode.outcome == o)
+    }
+}
+
+/// Logged episodes matching a query
+#[allow(dead_code)]
+pub fn query<P: AsRef<Path>>(path: P, q: &EpisodeQuery) -> Result<Vec<Episode>> {
+    Ok(load_log(path)?.into_iter().filter(|e| q.matches(e)).collect())
+}
+
+/// Totals for one UTC day of episodes
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[allow(dead_code)]
+pub struct DailySummary {
+    /// YYYY-MM-DD (UTC)
+    pub date: String,
+    pub episodes: usize,
+    pub attempted: usize,
+    pub captured: usize,
+    pub partial: usize,
+    pub missed: usize,
+    /// Captured / episodes
+    pub capture_rate: f64,
+    pub avg_duration_ms: f64,
+    pub max_duration_ms: f64,
+    /// Over attempted episodes
+    pub avg_time_to_order_ms: f64,
+    pub avg_peak_edge_cents: f64,
+    /// Sum of each episode's largest available profit (dollars)
+    pub capturable: f64,
+    /// Realized profit on episodes we traded (dollars)
+    pub profit: f64,
+}
+
+impl std::fmt::Display for DailySummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} | {} episodes, {} attempted, {} captured ({:.0}%), {} partial, {} missed | \
+                   avg {:.0}ms (max {:.0}ms), {:.1}ms to order, edge {:.1}¢ | capturable ${:.2}, profit ${:.2}",
+               self.date, self.episodes, self.attempted, self.captured, self.capture_rate * 100.0,
+               self.partial, self.missed, self.avg_duration_ms, self.max_duration_ms,
+               self.avg_time_to_order_ms, self.avg_peak_edge_cents, self.capturable, self.profit)
+    }
+}
+
+/// Per-day summaries (UTC, by episode start), oldest first
+#[allow(dead_code)]
+pub fn daily_summaries(episodes: &[Episode]) -> Vec<DailySummary> {
+    let mut days: BTreeMap<String, Vec<&Episode>> = BTreeMap::new();
+    for episode in episodes {
+        let date = DateTime::<Utc>::from_timestamp_nanos(episode.start_ns as i64).format("%Y-%m-%d").to_string();
+        days.entry(date).or_default().push(episode);
+    }
+
+    days.into_iter().map(|(date, eps)| {
+        let n = eps.len() as f64;
+        let count = |o: Outcome| eps.iter().filter(|e| e.outcome == o).count();
+        let to_order: Vec<f64> = eps.iter().filter_map(|e| e.time_to_order_ms()).collect();
+        let captured = count(Outcome::Captured);
+        DailySummary {
+            episodes: eps.len(),
+            attempted: eps.iter().filter(|e| e.orders > 0).count(),
+            captured,
+            partial: count(Outcome::Partial),
+            missed: count(Outcome::Missed),
+            capture_rate: captured as f64 / n,
+            avg_duration_ms: eps.iter().map(|e| e.duration_ms()).sum::<f64>() / n,
+            max_duration_ms: eps.iter().map(|e| e.duration_ms()).fold(0.0, f64::max),
+            avg_time_to_order_ms: if to_order.is_empty() { 0.0 } else { to_order.iter().sum::<f64>() / to_order.len() as f64 },
+            avg_peak_edge_cents: eps.iter().map(|e| e.peak_edge_cents as f64).sum::<f64>() / n,
+            capturable: eps.iter().map(|e| e.max_capturable).sum(),
+            profit: eps.iter().map(|e| e.profit_cents as f64 / 100.0).sum(),
+            date,
+        }
+    }).collect()
+}
@@ -0,0 +1,484 @@
+// src/paper.rs
+// Paper trading - simulated fills against the live depth book
+
+use anyhow::{Result, anyhow};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rustc_hash::FxHashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tracing::{debug, info};
+
+use crate::balance::CollateralSnapshot;
+use crate::clob_error::ClobError;
+use crate::exchange::{CancelResult, ExchangeClient, Fill, OrderLeg, OrderStatus};
+use crate::polymarket_clob::{PolyOrderType, fee_for_fill};
+use crate::position_tracker::{FillRecord, PositionTracker, SharedPositionTracker};
+use crate::types::{GlobalState, PriceCents, cents_to_price, fxhash_str, price_to_cents};
+
+/// Paper trading configuration from environment
+#[derive(Debug, Clone)]
+pub struct PaperConfig {
+    /// Route execution to the paper exchange instead of the CLOB
+    pub enabled: bool,
+    /// Delay between sending an order and it reaching the book (milliseconds)
+    pub latency_ms: u64,
+    /// Uniform random extra delay on top of latency_ms (milliseconds)
+    pub latency_jitter_ms: u64,
+    /// Share of the displayed size at each level we get to take (0-1].
+    /// Models other takers and cancels racing us to the same liquidity.
+    pub queue_share: f64,
+    /// Simulated USDC balance at start
+    pub starting_usdc: f64,
+    /// Positions file for paper fills (kept apart from live positions).
+    /// Empty keeps positions in memory only (replay, backtests).
+    pub positions_file: String,
+    /// Seed for latency jitter (None = random). Set for reproducible runs.
+    pub seed: Option<u64>,
+}
+
+impl PaperConfig {
+    pub fn from_env() -> Self {
+        Self {
+            enabled: std::env::var("PAPER_TRADING")
+                .map(|v| v == "1" || v == "true")
+                .unwrap_or(false),
+
+            latency_ms: std::env::var("PAPER_LATENCY_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(50),
+
+            latency_jitter_ms: std::env::var("PAPER_LATENCY_JITTER_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+
+            queue_share: std::env::var("PAPER_QUEUE_SHARE")
+                .ok()
+                .and_then(|v| v.parse::<f64>().ok())
+                .filter(|v| *v > 0.0)
+                .map(|v| v.min(1.0))
+                .unwrap_or(1.0),
+
+            starting_usdc: std::env::var("PAPER_STARTING_USDC")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1000.0),
+
+            positions_file: std::env::var("PAPER_POSITIONS_FILE")
+                .unwrap_or_else(|_| "paper_positions.json".to_string()),
+
+            seed: std::env::var("PAPER_SEED")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+        }
+    }
+}
+
+// =============================================================================
+// DEPTH BOOK
+// =============================================================================
+
+/// Book side
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookSide {
+    Bid,
+    Ask,
+}
+
+impl BookSide {
+    /// Parse a WS side label ("BUY"/"BID" are bids, "SELL"/"ASK" are asks)
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_uppercase().as_str() {
+            "BUY" | "BID" => Some(BookSide::Bid),
+            "SELL" | "ASK" => Some(BookSide::Ask),
+            _ => None,
+        }
+    }
+}
+
+/// Price levels for one token: (price, contracts), asks ascending, bids descending
+#[derive(Debug, Clone, Default)]
+pub struct TokenBook {
+    pub bids: Vec<(PriceCents, f64)>,
+    pub asks: Vec<(PriceCents, f64)>,
+}
+
+impl TokenBook {
+    fn side_mut(&mut self, side: BookSide) -> &mut Vec<(PriceCents, f64)> {
+        match side {
+            BookSide::Bid => &mut self.bids,
+            BookSide::Ask => &mut self.asks,
+        }
+    }
+
+    /// Set the size at one level (0 removes it), keeping the side sorted
+    fn set_level(&mut self, side: BookSide, price: PriceCents, size: f64) {
+        let levels = self.side_mut(side);
+        let pos = levels.iter().position(|&(p, _)| match side {
+            BookSide::Ask => p >= price,
+            BookSide::Bid => p <= price,
+        });
+        match pos {
+            Some(i) if levels[i].0 == price => {
+                if size > 0.0 { levels[i].1 = size } else { levels.remove(i); }
+            }
+            Some(i) if size > 0.0 => levels.insert(i, (price, size)),
+            None if size > 0.0 => levels.push((price, size)),
+            _ => {}
+        }
+    }
+}
+
+/// Full-depth order book per token, fed from the market WS
+#[derive(Default)]
+pub struct DepthBook {
+    books: RwLock<FxHashMap<u64, TokenBook>>,
+}
+
+impl DepthBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace a token's book from a snapshot. Levels are (price, size) strings as sent by the WS.
+    pub fn apply_snapshot<'a>(
+        &self,
+        token_id: &str,
+        bids: impl IntoIterator<Item = (&'a str, &'a str)>,
+        asks: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) {
+        let mut book = TokenBook::default();
+        for (side, levels) in [(BookSide::Bid, bids.into_iter().collect::<Vec<_>>()), (BookSide::Ask, asks.into_iter().collect())] {
+            for (price, size) in levels {
+                let price = crate::types::parse_price(price);
+                let size: f64 = size.parse().unwrap_or(0.0);
+                if price > 0 {
+                    book.set_level(side, price, size);
+                }
+            }
+        }
+        self.books.write().unwrap().insert(fxhash_str(token_id), book);
+    }
+
+    /// Apply a single level update (size 0 removes the level)
+    pub fn apply_change(&self, token_id: &str, side: BookSide, price: PriceCents, size: f64) {
+        if price == 0 {
+            return;
+        }
+        let mut books = self.books.write().unwrap();
+        books.entry(fxhash_str(token_id)).or_default().set_level(side, price, size);
+    }
+
+    /// Copy of a token's book
+    #[allow(dead_code)]
+    pub fn book(&self, token_id: &str) -> Option<TokenBook> {
+        self.books.read().unwrap().get(&fxhash_str(token_id)).cloned()
+    }
+
+    /// Lowest ask (price, contracts)
+    #[allow(dead_code)]
+    pub fn best_ask(&self, token_id: &str) -> Option<(PriceCents, f64)> {
+        self.books.read().unwrap().get(&fxhash_str(token_id)).and_then(|b| b.asks.first().copied())
+    }
+
+    /// Match a taker order against one side of the book: buys take the asks up to `limit`,
+    /// sells take the bids down to `limit`. Only `queue_share` of each level is available
+    /// to us and FOK orders fill completely or not at all. Taken liquidity is removed from
+    /// the book until the next update for that level. Returns the fills as (price, contracts).
+    pub fn take(
+        &self,
+        token_id: &str,
+        from: BookSide,
+        limit: PriceCents,
+        size: f64,
+        queue_share: f64,
+        order_type: PolyOrderType,
+    ) -> Vec<(PriceCents, f64)> {
+        let mut books = self.books.write().unwrap();
+        let Some(book) = books.get_mut(&fxhash_str(token_id)) else { return Vec::new() };
+        let levels = book.side_mut(from);
+        let crosses = |p: PriceCents| match from {
+            BookSide::Ask => p <= limit,
+            BookSide::Bid => p >= limit,
+        };
+
+        let mut fills = Vec::new();
+        let mut remaining = size;
+        for &(price, level_size) in levels.iter() {
+            if remaining <= 0.0 || !crosses(price) {
+                break;
+            }
+            let take = (level_size * queue_share).floor().min(remaining);
+            if take > 0.0 {
+                fills.push((price, take));
+                remaining -= take;
+            }
+        }
+
+        if order_type == PolyOrderType::FOK && remaining > 0.0 {
+            return Vec::new();
+        }
+
+        for &(price, taken) in &fills {
+            if let Some(level) = levels.iter_mut().find(|(p, _)| *p == price) {
+                level.1 -= taken;
+            }
+        }
+        levels.retain(|&(_, s)| s > 0.0);
+        fills
+    }
+}
+
+// =============================================================================
+// PAPER EXCHANGE
+// =============================================================================
+
+/// Running totals for paper execution
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PaperStats {
+    pub orders: u64,
+    pub filled: u64,
+    pub partial: u64,
+    pub no_match: u64,
+    pub rejected: u64,
+    pub contracts: f64,
+    pub fees: f64,
+    pub cash: f64,
+}
+
+impl std::fmt::Display for PaperStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Paper: {} orders ({} filled, {} partial, {} no match, {} rejected) | {:.0} contracts | fees ${:.2} | cash ${:.2}",
+               self.orders, self.filled, self.partial, self.no_match, self.rejected,
+               self.contracts, self.fees, self.cash)
+    }
+}
+
+/// Simulated account state
+#[derive(Default)]
+struct PaperAccount {
+    cash: f64,
+    /// Contracts held per token
+    holdings: FxHashMap<String, f64>,
+    /// Cost (incl. fees) of the contracts held per token
+    cost: FxHashMap<String, f64>,
+    /// Realized P&L from sells per token (proceeds - fees - average cost)
+    realized: FxHashMap<String, f64>,
+    orders: FxHashMap<String, OrderStatus>,
+    stats: PaperStats,
+}
+
+/// Exchange that fills orders against the live depth book instead of posting them
+pub struct PaperExchange {
+    config: PaperConfig,
+    depth: Arc<DepthBook>,
+    state: Arc<GlobalState>,
+    tracker: SharedPositionTracker,
+    account: Mutex<PaperAccount>,
+    next_order_id: AtomicU64,
+    rng: Mutex<StdRng>,
+}
+
+impl PaperExchange {
+    pub fn new(config: PaperConfig, depth: Arc<DepthBook>, state: Arc<GlobalState>) -> Self {
+        info!("[PAPER] Paper exchange:");
+        info!("[PAPER]   Latency: {}ms (+0-{}ms jitter) | queue share {:.0}%",
+              config.latency_ms, config.latency_jitter_ms, config.queue_share * 100.0);
+        info!("[PAPER]   Starting balance: ${:.2} | positions file: {}",
+              config.starting_usdc, config.positions_file);
+
+        let tracker = PositionTracker::new().with_path(&config.positions_file);
+        let account = PaperAccount { cash: config.starting_usdc, ..Default::default() };
+
+        Self {
+            depth,
+            state,
+            tracker: Arc::new(tokio::sync::RwLock::new(tracker)),
+            account: Mutex::new(account),
+            next_order_id: AtomicU64::new(1),
+            rng: Mutex::new(match config.seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            }),
+            config,
+        }
+    }
+
+    /// Paper positions (simulated fills only)
+    #[allow(dead_code)]
+    pub fn tracker(&self) -> SharedPositionTracker {
+        self.tracker.clone()
+    }
+
+    pub fn stats(&self) -> PaperStats {
+        let account = self.account.lock().unwrap();
+        PaperStats { cash: account.cash, ..account.stats }
+    }
+
+    /// Contracts held in a token
+    #[allow(dead_code)]
+    pub fn holdings(&self, token_id: &str) -> f64 {
+        self.account.lock().unwrap().holdings.get(token_id).copied().unwrap_or(0.0)
+    }
+
+    /// Realized P&L from selling a token (in this bot, closing out mismatched legs)
+    #[allow(dead_code)]
+    pub fn realized_pnl(&self, token_id: &str) -> f64 {
+        self.account.lock().unwrap().realized.get(token_id).copied().unwrap_or(0.0)
+    }
+
+    fn latency(&self) -> Duration {
+        let jitter = if self.config.latency_jitter_ms > 0 {
+            self.rng.lock().unwrap().gen_range(0..=self.config.latency_jitter_ms)
+        } else {
+            0
+        };
+        Duration::from_millis(self.config.latency_ms + jitter)
+    }
+
+    /// (pair_id, description, "yes"/"no", fee_bps) for a tracked token
+    fn token_info(&self, token_id: &str) -> Option<(Arc<str>, Arc<str>, &'static str, u16)> {
+        let hash = fxhash_str(token_id);
+        let (market_id, side) = match self.state.poly_yes_to_id.get(&hash) {
+            Some(&id) => (id, "yes"),
+            None => (*self.state.poly_no_to_id.get(&hash)?, "no"),
+        };
+        let market = self.state.get_by_id(market_id)?;
+        let pair = market.pair.as_ref()?;
+        let (yes_bps, no_bps) = market.fee_rates();
+        let fee_bps = if side == "yes" { yes_bps } else { no_bps };
+        Some((pair.pair_id.clone(), pair.description.clone(), side, fee_bps))
+    }
+
+    /// Fill one order against the book (no latency applied)
+    pub async fn match_order(&self, leg: &OrderLeg<'_>, order_type: PolyOrderType) -> Result<Fill> {
+        let is_buy = !leg.side.eq_ignore_ascii_case("SELL");
+        let info = self.token_info(leg.token_id);
+        let fee_bps = info.as_ref().map(|i| i.3).unwrap_or(0);
+        let limit = price_to_cents(leg.price);
+        let order_id = format!("paper-{}", self.next_order_id.fetch_add(1, Ordering::Relaxed));
+
+        let fill = {
+            let mut account = self.account.lock().unwrap();
+            account.stats.orders += 1;
+
+            // Sells are limited to what we hold; buys to what the cash covers at the limit
+            if !is_buy && account.holdings.get(leg.token_id).copied().unwrap_or(0.0) < leg.size {
+                account.stats.rejected += 1;
+                return Err(ClobError::rejected("not enough balance / allowance").into());
+            }
+            if is_buy && leg.size * leg.price + fee_for_fill(leg.price, leg.size, fee_bps) > account.cash {
+                account.stats.rejected += 1;
+                return Err(ClobError::rejected("not enough balance / allowance").into());
+            }
+
+            let from = if is_buy { BookSide::Ask } else { BookSide::Bid };
+            let fills = self.depth.take(leg.token_id, from, limit, leg.size, self.config.queue_share, order_type);
+            let filled: f64 = fills.iter().map(|(_, q)| q).sum();
+
+            if filled <= 0.0 {
+                account.stats.no_match += 1;
+                let msg = match order_type {
+                    PolyOrderType::FOK => "order couldn't be fully filled, FOK orders are fully filled or killed",
+                    _ => "no orders found to match with FAK order",
+                };
+                return Err(ClobError::rejected(msg).into());
+            }
+
+            let cost: f64 = fills.iter().map(|&(p, q)| cents_to_price(p) * q).sum();
+            let fees: f64 = fills.iter().map(|&(p, q)| fee_for_fill(cents_to_price(p), q, fee_bps)).sum();
+
+            if is_buy {
+                account.cash -= cost + fees;
+                *account.holdings.entry(leg.token_id.to_string()).or_default() += filled;
+                *account.cost.entry(leg.token_id.to_string()).or_default() += cost + fees;
+            } else {
+                account.cash += cost - fees;
+                let held = account.holdings.get(leg.token_id).copied().unwrap_or(0.0);
+                let held_cost = account.cost.get(leg.token_id).copied().unwrap_or(0.0);
+                let sold_cost = if held > 0.0 { held_cost * filled / held } else { 0.0 };
+                *account.holdings.entry(leg.token_id.to_string()).or_default() -= filled;
+                *account.cost.entry(leg.token_id.to_string()).or_default() -= sold_cost;
+                *account.realized.entry(leg.token_id.to_string()).or_default() += cost - fees - sold_cost;
+            }
+            if filled < leg.size { account.stats.partial += 1 } else { account.stats.filled += 1 }
+            account.stats.contracts += filled;
+            account.stats.fees += fees;
+            account.orders.insert(order_id.clone(), OrderStatus {
+                order_id: order_id.clone(),
+                status: "MATCHED".into(),
+                price: leg.price,
+                size_matched: filled,
+                original_size: leg.size,
+            });
+
+            Fill { order_id: order_id.clone(), filled_size: filled, fill_cost: cost, fees, fill_unknown: false }
+        };
+
+        debug!("[PAPER] {} {} {:.0}/{:.0} @{}¢ -> ${:.2}",
+               leg.side, leg.token_id, fill.filled_size, leg.size, limit, fill.fill_cost);
+
+        if let Some((pair_id, description, side, _)) = info {
+            let signed = if is_buy { fill.filled_size } else { -fill.filled_size };
+            let record = FillRecord::new(
+                &pair_id, &description, "polymarket", side,
+                signed, fill.fill_cost / fill.filled_size, fill.fees, &order_id,
+            );
+            let mut tracker = self.tracker.write().await;
+            if self.config.positions_file.is_empty() {
+                tracker.record_fill_internal(&record);
+            } else {
+                tracker.record_fill(&record);
+            }
+        }
+
+        Ok(fill)
+    }
+}
+
+impl ExchangeClient for PaperExchange {
+    async fn place_orders(&self, legs: &[OrderLeg<'_>]) -> Result<Vec<Result<Fill>>> {
+        // One request: every leg reaches the book at the same time
+        tokio::time::sleep(self.latency()).await;
+        let mut results = Vec::with_capacity(legs.len());
+        for leg in legs {
+            results.push(self.match_order(leg, PolyOrderType::FAK).await);
+        }
+        Ok(results)
+    }
+
+    async fn place_order(&self, leg: OrderLeg<'_>) -> Result<Fill> {
+        tokio::time::sleep(self.latency()).await;
+        self.match_order(&leg, PolyOrderType::FAK).await
+    }
+
+    async fn query_order(&self, order_id: &str) -> Result<OrderStatus> {
+        self.account.lock().unwrap().orders.get(order_id).cloned()
+            .ok_or_else(|| anyhow!("unknown paper order {}", order_id))
+    }
+
+    async fn cancel_orders(&self, order_ids: &[String]) -> Result<CancelResult> {
+        // Paper orders are FAK/FOK and never rest on the book
+        let mut resp = CancelResult::default();
+        for id in order_ids {
+            resp.not_canceled.insert(id.clone(), "order is not open".into());
+        }
+        Ok(resp)
+    }
+
+    fn neg_risk(&self, _token_id: &str) -> Option<bool> {
+        Some(false)
+    }
+
+    async fn collateral(&self) -> Result<CollateralSnapshot> {
+        let cash = self.account.lock().unwrap().cash.max(0.0);
+        Ok(CollateralSnapshot {
+            balance: (cash * 1_000_000.0) as u64,
+            allowance: u64::MAX,
+            neg_risk_allowance: u64::MAX,
+        })
+    }
+}
@@ -2,19 +2,25 @@
 // Polymarket WebSocket client with ping keepalive
 
 use anyhow::{Context, Result};
+use chrono::NaiveDate;
 use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::mpsc;
-use tokio::time::{interval, Instant};
+use tokio::time::{interval, interval_at, Instant};
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 use tracing::{error, info, warn};
 
-use crate::config::{POLYMARKET_WS_URL, POLY_PING_INTERVAL_SECS, GAMMA_API_BASE};
+use crate::config::{POLY_PING_INTERVAL_SECS, gamma_api_base, polymarket_ws_url, ws_quiet_resync_secs};
 use crate::execution::NanoClock;
+use crate::opportunity::OpportunityTracker;
+use crate::paper::{BookSide, DepthBook};
+use crate::rate_limit::{Endpoint, RateLimiter};
+use crate::recorder::WsRecorder;
 use crate::types::{
-    GlobalState, FastExecutionRequest, ArbType, PriceCents, SizeCents,
+    GlobalState, FastExecutionRequest, ArbType, PriceCents, SizeCents, NO_PRICE,
     parse_price, fxhash_str,
 };
 
@@ -23,7 +29,6 @@ use crate::types::{
 #[derive(Deserialize, Debug)]
 pub struct BookSnapshot {
     pub asset_id: String,
-    #[allow(dead_code)]
     pub bids: Vec<PriceLevel>,
     pub asks: Vec<PriceLevel>,
 }
@@ -45,6 +50,9 @@ pub struct PriceChangeEvent {
 pub struct PriceChangeItem {
     pub asset_id: String,
     pub price: Option<String>,
+    /// New total size at this level (0 = level removed)
+    #[serde(default)]
+    pub size: Option<String>,
     pub side: Option<String>,
 }
 
@@ -55,35 +63,59 @@ struct SubscribeCmd {
     sub_type: &'static str,
 }
 
+/// Change the asset set of an open market subscription
+#[derive(Serialize)]
+struct UpdateSubscriptionCmd<'a> {
+    assets_ids: &'a [String],
+    operation: &'static str,
+}
+
 // === Gamma API Client ===
 
 pub struct GammaClient {
     http: reqwest::Client,
+    base_url: String,
+    limiter: Arc<RateLimiter>,
 }
 
 impl GammaClient {
+    #[allow(dead_code)]
     pub fn new() -> Self {
+        Self::with_rate_limiter(Arc::new(RateLimiter::unlimited()))
+    }
+
+    /// Gamma client sharing the process-wide rate limiter
+    pub fn with_rate_limiter(limiter: Arc<RateLimiter>) -> Self {
         Self {
             http: reqwest::Client::builder()
                 .timeout(Duration::from_secs(10))
                 .build()
                 .expect("Failed to build HTTP client"),
+            base_url: gamma_api_base(),
+            limiter,
         }
     }
+
+    /// Query a different Gamma host (e.g. the local emulator)
+    #[allow(dead_code)]
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
     
-    /// Look up Polymarket market by slug, return (yes_token, no_token, description)
+    /// Look up Polymarket market by slug: tokens, description and parent event
     /// Tries both the exact date and next day (timezone handling)
-    pub async fn lookup_market(&self, slug: &str) -> Result<Option<(String, String, String)>> {
+    pub async fn lookup_market(&self, slug: &str) -> Result<Option<GammaMarketInfo>> {
         // Try exact slug first
-        if let Some((yes_token, no_token, description)) = self.try_lookup_slug(slug).await? {
-            return Ok(Some((yes_token, no_token, description)));
+        if let Some(info) = self.try_lookup_slug(slug).await? {
+            return Ok(Some(info));
         }
         
         // Try with next day (Polymarket may use local time)
         if let Some(next_day_slug) = increment_date_in_slug(slug) {
-            if let Some((yes_token, no_token, description)) = self.try_lookup_slug(&next_day_slug).await? {
+            if let Some(info) = self.try_lookup_slug(&next_day_slug).await? {
                 info!("  📅 Found with next-day slug: {}", next_day_slug);
-                return Ok(Some((yes_token, no_token, description)));
+                return Ok(Some(info));
             }
         }
         
@@ -95,13 +127,47 @@ impl GammaClient {
     /// This is a convenience method for backward compatibility
     pub async fn lookup_market_tokens(&self, slug: &str) -> Result<Option<(String, String)>> {
         self.lookup_market(slug).await.map(|opt| {
-            opt.map(|(yes, no, _)| (yes, no))
+            opt.map(|info| (info.yes_token, info.no_token))
         })
     }
     
-    async fn try_lookup_slug(&self, slug: &str) -> Result<Option<(String, String, String)>> {
-        let url = format!("{}/markets?slug={}", GAMMA_API_BASE, slug);
+    /// Outcome of a closed market (true = YES won); None while it trades or is undecided.
+    /// Tries the next-day slug too, like `lookup_market`
+    pub async fn lookup_resolution(&self, slug: &str) -> Result<Option<bool>> {
+        for slug in std::iter::once(slug.to_string()).chain(increment_date_in_slug(slug)) {
+            let url = format!("{}/markets?slug={}", self.base_url, slug);
+
+            self.limiter.acquire(Endpoint::Gamma).await;
+            let resp = self.http.get(&url).send().await?;
+            if !resp.status().is_success() {
+                continue;
+            }
+
+            let markets: Vec<GammaMarket> = resp.json().await?;
+            let Some(market) = markets.first() else { continue };
+            if market.closed != Some(true) {
+                return Ok(None);
+            }
+
+            // outcomePrices is a JSON array of strings: ["1", "0"] once YES has won
+            let prices: Vec<String> = market.outcome_prices
+                .as_ref()
+                .and_then(|s| serde_json::from_str(s).ok())
+                .unwrap_or_default();
+            let won = |i: usize| prices.get(i).and_then(|p| p.parse::<f64>().ok()).is_some_and(|p| p >= 0.99);
+            return Ok(match (won(0), won(1)) {
+                (true, false) => Some(true),
+                (false, true) => Some(false),
+                _ => None,
+            });
+        }
+        Ok(None)
+    }
+
+    async fn try_lookup_slug(&self, slug: &str) -> Result<Option<GammaMarketInfo>> {
+        let url = format!("{}/markets?slug={}", self.base_url, slug);
         
+        self.limiter.acquire(Endpoint::Gamma).await;
         let resp = self.http.get(&url).send().await?;
         
         if !resp.status().is_success() {
@@ -130,7 +196,14 @@ impl GammaClient {
         if token_ids.len() >= 2 {
             let description = market.question.clone()
                 .unwrap_or_else(|| slug.to_string());
-            Ok(Some((token_ids[0].clone(), token_ids[1].clone(), description)))
+            let event = market.events.as_ref().and_then(|e| e.first());
+            Ok(Some(GammaMarketInfo {
+                yes_token: token_ids[0].clone(),
+                no_token: token_ids[1].clone(),
+                description,
+                event_id: event.and_then(|e| e.id.clone()),
+                start_date: event.and_then(GammaEvent::start_date),
+            }))
         } else {
             Ok(None)
         }
@@ -143,10 +216,44 @@ struct GammaMarket {
     clob_token_ids: Option<String>,
     active: Option<bool>,
     closed: Option<bool>,
+    #[serde(rename = "outcomePrices")]
+    outcome_prices: Option<String>,
     #[serde(rename = "question")]
     question: Option<String>,
     #[serde(rename = "slug")]
     slug: Option<String>,
+    /// Parent event(s); sports markets have exactly one, the game
+    events: Option<Vec<GammaEvent>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GammaEvent {
+    id: Option<String>,
+    /// Kickoff for sports events
+    #[serde(rename = "startTime")]
+    start_time: Option<String>,
+    #[serde(rename = "startDate")]
+    start_date: Option<String>,
+}
+
+impl GammaEvent {
+    /// UTC date of the event's start ("2025-12-08T20:00:00Z" or "2025-12-08 20:00:00+00")
+    fn start_date(&self) -> Option<NaiveDate> {
+        let start = self.start_time.as_deref().or(self.start_date.as_deref())?;
+        NaiveDate::parse_from_str(start.get(..10)?, "%Y-%m-%d").ok()
+    }
+}
+
+/// Market found on Gamma
+#[derive(Debug, Clone)]
+pub struct GammaMarketInfo {
+    pub yes_token: String,
+    pub no_token: String,
+    pub description: String,
+    /// Gamma event (the game) the market belongs to
+    pub event_id: Option<String>,
+    /// Event start date, UTC
+    pub start_date: Option<NaiveDate>,
 }
 
 /// Increment the date in a Polymarket slug by 1 day
@@ -195,11 +302,16 @@ fn parse_size(s: &str) -> SizeCents {
         .unwrap_or(0)
 }
 
-/// WebSocket runner
+/// WebSocket runner. `depth` also maintains the full book (paper trading).
+/// `clock` stamps quotes and detections; share it with the execution engine.
 pub async fn run_ws(
     state: Arc<GlobalState>,
     exec_tx: mpsc::Sender<FastExecutionRequest>,
     threshold_cents: PriceCents,
+    clock: NanoClock,
+    depth: Option<Arc<DepthBook>>,
+    recorder: Option<Arc<WsRecorder>>,
+    opportunities: Option<Arc<OpportunityTracker>>,
 ) -> Result<()> {
     let tokens: Vec<String> = state.markets.iter()
         .take(state.market_count())
@@ -213,7 +325,7 @@ pub async fn run_ws(
         return Ok(());
     }
 
-    let (ws_stream, _) = connect_async(POLYMARKET_WS_URL)
+    let (ws_stream, _) = connect_async(polymarket_ws_url())
         .await
         .context("Failed to connect to Polymarket")?;
 
@@ -230,10 +342,16 @@ pub async fn run_ws(
     write.send(Message::Text(serde_json::to_string(&subscribe_msg)?)).await?;
     info!("[POLY] Subscribed to {} tokens", tokens.len());
 
-    let clock = NanoClock::new();
     let mut ping_interval = interval(Duration::from_secs(POLY_PING_INTERVAL_SECS));
     let mut last_message = Instant::now();
 
+    // Tokens that go quiet are resubscribed so the server sends a fresh snapshot,
+    // which refreshes the quote even when the book hasn't changed
+    let quiet_secs = ws_quiet_resync_secs();
+    let resync_period = Duration::from_secs(quiet_secs.max(1));
+    let mut resync_interval = interval_at(Instant::now() + resync_period, resync_period);
+    let mut resync = QuietResync::new(quiet_secs * 1_000_000_000);
+
     loop {
         tokio::select! {
             _ = ping_interval.tick() => {
@@ -243,31 +361,40 @@ pub async fn run_ws(
                 }
             }
 
+            _ = resync_interval.tick(), if quiet_secs > 0 => {
+                let quiet = resync.due(&state, clock.now_ns());
+                if quiet.is_empty() {
+                    continue;
+                }
+                warn!("[POLY] {} tokens quiet for {}s+, resubscribing ({} backing off)",
+                      quiet.len(), quiet_secs, resync.backing_off());
+                let unsubscribe = serde_json::to_string(&UpdateSubscriptionCmd { assets_ids: &quiet, operation: "unsubscribe" })?;
+                let subscribe = serde_json::to_string(&UpdateSubscriptionCmd { assets_ids: &quiet, operation: "subscribe" })?;
+                let sent = async {
+                    write.send(Message::Text(unsubscribe)).await?;
+                    write.send(Message::Text(subscribe)).await
+                }.await;
+                if let Err(e) = sent {
+                    error!("[POLY] Failed to resubscribe: {}", e);
+                    break;
+                }
+            }
+
             msg = read.next() => {
+                // Stamp and hand off before parsing; the recorder never blocks
+                if let Some(recorder) = &recorder {
+                    match &msg {
+                        Some(Ok(Message::Text(text))) => recorder.record(text.as_bytes()),
+                        Some(Ok(Message::Binary(data))) => recorder.record(data),
+                        _ => {}
+                    }
+                }
+
                 match msg {
                     Some(Ok(Message::Text(text))) => {
                         last_message = Instant::now();
 
-                        // Try book snapshot first
-                        if let Ok(books) = serde_json::from_str::<Vec<BookSnapshot>>(&text) {
-                            for book in &books {
-                                process_book(&state, book, &exec_tx, threshold_cents, &clock).await;
-                            }
-                        }
-                        // Try price change event
-                        else if let Ok(event) = serde_json::from_str::<PriceChangeEvent>(&text) {
-                            if event.event_type.as_deref() == Some("price_change") {
-                                if let Some(changes) = &event.price_changes {
-                                    for change in changes {
-                                        process_price_change(&state, change, &exec_tx, threshold_cents, &clock).await;
-                                    }
-                                }
-                            }
-                        }
-                        // Log unknown message types at trace level for debugging
-                        else {
-                            tracing::trace!("[POLY] Unknown WS message: {}...", &text[..text.len().min(100)]);
-                        }
+                        process_frame(&state, &text, &exec_tx, threshold_cents, &clock, depth.as_deref(), opportunities.as_deref()).await;
                     }
                     Some(Ok(Message::Ping(data))) => {
                         let _ = write.send(Message::Pong(data)).await;
@@ -302,6 +429,133 @@ pub async fn run_ws(
     Ok(())
 }
 
+/// Each subscribed token with the time since its last feed update
+/// (never-updated tokens count from clock start)
+fn token_ages(state: &GlobalState, now_ns: u64) -> impl Iterator<Item = (&str, u64)> {
+    state.markets.iter()
+        .take(state.market_count())
+        .filter_map(move |market| {
+            let pair = market.pair.as_ref()?;
+            let (yes_age, no_age) = market.poly.side_ages_ns(now_ns);
+            Some([
+                (&*pair.poly_yes_token, yes_age.unwrap_or(now_ns)),
+                (&*pair.poly_no_token, no_age.unwrap_or(now_ns)),
+            ])
+        })
+        .flatten()
+}
+
+/// Longest wait between resubscribes of a token that stays quiet, in multiples of the quiet period
+const MAX_RESYNC_BACKOFF: u64 = 16;
+
+/// Resubscribe schedule for quiet tokens. A resubscribe that brings a snapshot resets
+/// the token; one that brings nothing doubles its wait, up to `MAX_RESYNC_BACKOFF`.
+pub struct QuietResync {
+    quiet_ns: u64,
+    /// Token -> (last resubscribe, wait before the next one)
+    pending: HashMap<String, (u64, u64)>,
+}
+
+impl QuietResync {
+    pub fn new(quiet_ns: u64) -> Self {
+        Self { quiet_ns, pending: HashMap::new() }
+    }
+
+    /// Tokens with no feed update for more than the quiet period that are due a
+    /// resubscribe now; the caller is expected to resubscribe them
+    pub fn due(&mut self, state: &GlobalState, now_ns: u64) -> Vec<String> {
+        let mut due = Vec::new();
+        for (token, age) in token_ages(state, now_ns) {
+            if let Some(&(resync_ns, _)) = self.pending.get(token) {
+                // Updated since the last resubscribe: back to the normal schedule
+                if age < now_ns.saturating_sub(resync_ns) {
+                    self.pending.remove(token);
+                }
+            }
+            if age <= self.quiet_ns {
+                continue;
+            }
+            match self.pending.get_mut(token) {
+                None => {
+                    self.pending.insert(token.to_string(), (now_ns, self.quiet_ns * 2));
+                    due.push(token.to_string());
+                }
+                Some((resync_ns, wait_ns)) if now_ns.saturating_sub(*resync_ns) >= *wait_ns => {
+                    *resync_ns = now_ns;
+                    *wait_ns = (*wait_ns * 2).min(self.quiet_ns * MAX_RESYNC_BACKOFF);
+                    due.push(token.to_string());
+                }
+                Some(_) => {}
+            }
+        }
+        due
+    }
+
+    /// Tokens whose last resubscribe brought no snapshot
+    pub fn backing_off(&self) -> usize {
+        self.pending.values().filter(|&&(_, wait_ns)| wait_ns > self.quiet_ns * 2).count()
+    }
+}
+
+/// Process one text frame from the market WS (live feed or replay)
+pub async fn process_frame(
+    state: &GlobalState,
+    text: &str,
+    exec_tx: &mpsc::Sender<FastExecutionRequest>,
+    threshold_cents: PriceCents,
+    clock: &NanoClock,
+    depth: Option<&DepthBook>,
+    opportunities: Option<&OpportunityTracker>,
+) {
+    // Try book snapshot first
+    if let Ok(books) = serde_json::from_str::<Vec<BookSnapshot>>(text) {
+        for book in &books {
+            process_book(state, book, exec_tx, threshold_cents, clock, depth, opportunities).await;
+        }
+    }
+    // Try price change event
+    else if let Ok(event) = serde_json::from_str::<PriceChangeEvent>(text) {
+        if event.event_type.as_deref() == Some("price_change") {
+            if let Some(changes) = &event.price_changes {
+                for change in changes {
+                    process_price_change(state, change, exec_tx, threshold_cents, clock, depth, opportunities).await;
+                }
+            }
+        }
+    }
+    // Log unknown message types at trace level for debugging
+    else {
+        tracing::trace!("[POLY] Unknown WS message: {}...", &text[..text.len().min(100)]);
+    }
+}
+
+/// Apply a frame's snapshots and level changes to a depth book without running detection
+#[allow(dead_code)]
+pub fn apply_depth(depth: &DepthBook, text: &str) {
+    if let Ok(books) = serde_json::from_str::<Vec<BookSnapshot>>(text) {
+        for book in &books {
+            depth.apply_snapshot(
+                &book.asset_id,
+                book.bids.iter().map(|l| (l.price.as_str(), l.size.as_str())),
+                book.asks.iter().map(|l| (l.price.as_str(), l.size.as_str())),
+            );
+        }
+    } else if let Ok(event) = serde_json::from_str::<PriceChangeEvent>(text) {
+        if event.event_type.as_deref() != Some("price_change") {
+            return;
+        }
+        for change in event.price_changes.iter().flatten() {
+            if let (Some(side), Some(price), Some(size)) = (
+                change.side.as_deref().and_then(BookSide::parse),
+                change.price.as_deref(),
+                change.size.as_deref(),
+            ) {
+                depth.apply_change(&change.asset_id, side, parse_price(price), size.parse().unwrap_or(0.0));
+            }
+        }
+    }
+}
+
 /// Process book snapshot
 #[inline]
 async fn process_book(
@@ -310,7 +564,17 @@ async fn process_book(
     exec_tx: &mpsc::Sender<FastExecutionRequest>,
     threshold_cents: PriceCents,
     clock: &NanoClock,
+    depth: Option<&DepthBook>,
+    opportunities: Option<&OpportunityTracker>,
 ) {
+    if let Some(depth) = depth {
+        depth.apply_snapshot(
+            &book.asset_id,
+            book.bids.iter().map(|l| (l.price.as_str(), l.size.as_str())),
+            book.asks.iter().map(|l| (l.price.as_str(), l.size.as_str())),
+        );
+    }
+
     let token_hash = fxhash_str(&book.asset_id);
 
     // Find best ask (lowest price)
@@ -327,23 +591,15 @@ async fn process_book(
     if let Some(&market_id) = state.poly_yes_to_id.get(&token_hash) {
         let market = &state.markets[market_id as usize];
         market.poly.update_yes(best_ask, ask_size);
-
-        // Check arbs
-        let arb_mask = market.check_arbs(threshold_cents);
-        if arb_mask != 0 {
-            send_arb_request(market_id, market, arb_mask, exec_tx, clock).await;
-        }
+        market.poly.touch_yes(clock.now_ns());
+        evaluate(state, market_id, exec_tx, threshold_cents, clock, opportunities).await;
     }
     // Check if NO token
     else if let Some(&market_id) = state.poly_no_to_id.get(&token_hash) {
         let market = &state.markets[market_id as usize];
         market.poly.update_no(best_ask, ask_size);
-
-        // Check arbs
-        let arb_mask = market.check_arbs(threshold_cents);
-        if arb_mask != 0 {
-            send_arb_request(market_id, market, arb_mask, exec_tx, clock).await;
-        }
+        market.poly.touch_no(clock.now_ns());
+        evaluate(state, market_id, exec_tx, threshold_cents, clock, opportunities).await;
     }
 }
 
@@ -355,48 +611,99 @@ async fn process_price_change(
     exec_tx: &mpsc::Sender<FastExecutionRequest>,
     threshold_cents: PriceCents,
     clock: &NanoClock,
+    depth: Option<&DepthBook>,
+    opportunities: Option<&OpportunityTracker>,
 ) {
-    // Only process ASK side updates
-    if !matches!(change.side.as_deref(), Some("ASK" | "ask")) {
-        return;
+    let side = change.side.as_deref().and_then(BookSide::parse);
+    if let (Some(depth), Some(side), Some(price), Some(size)) = (
+        depth,
+        side,
+        change.price.as_deref(),
+        change.size.as_deref(),
+    ) {
+        depth.apply_change(&change.asset_id, side, parse_price(price), size.parse().unwrap_or(0.0));
     }
 
-    let Some(price_str) = &change.price else { return };
-    let price = parse_price(price_str);
-    if price == 0 { return; }
-
     let token_hash = fxhash_str(&change.asset_id);
 
-    // Check YES token
+    // Any change shows the token's feed is alive, even one that leaves the best ask alone
     if let Some(&market_id) = state.poly_yes_to_id.get(&token_hash) {
-        let market = &state.markets[market_id as usize];
-        let (current_yes, _, current_yes_size, _) = market.poly.load();
+        state.markets[market_id as usize].poly.touch_yes(clock.now_ns());
+    } else if let Some(&market_id) = state.poly_no_to_id.get(&token_hash) {
+        state.markets[market_id as usize].poly.touch_no(clock.now_ns());
+    }
 
-        // Only update if new price is better (lower)
-        if price < current_yes || current_yes == 0 {
-            // Keep existing size - it may be stale but FAK orders handle partial fills.
-            // Size is an upper bound anyway; better to attempt arb than miss it.
-            market.poly.update_yes(price, current_yes_size);
+    // Only process ASK side updates. The market channel labels levels by the order
+    // side resting there, so asks arrive as "SELL" (same parse as the depth book)
+    if side != Some(BookSide::Ask) {
+        return;
+    }
 
-            let arb_mask = market.check_arbs(threshold_cents);
-            if arb_mask != 0 {
-                send_arb_request(market_id, market, arb_mask, exec_tx, clock).await;
-            }
+    let Some(price_str) = &change.price else { return };
+    let price = parse_price(price_str);
+    if price == 0 { return; }
+
+    let (market_id, is_yes) = if let Some(&id) = state.poly_yes_to_id.get(&token_hash) {
+        (id, true)
+    } else if let Some(&id) = state.poly_no_to_id.get(&token_hash) {
+        (id, false)
+    } else {
+        return;
+    };
+    let market = &state.markets[market_id as usize];
+    let (yes, no, yes_size, no_size) = market.poly.load();
+    let (current, current_size) = if is_yes { (yes, yes_size) } else { (no, no_size) };
+
+    let best = if price < current || current == NO_PRICE {
+        // Keep existing size - it may be stale but FAK orders handle partial fills.
+        // Size is an upper bound anyway; better to attempt arb than miss it.
+        Some((price, current_size))
+    } else if price == current {
+        // The best level was resized or pulled. Leaving a pulled ask in place would
+        // keep firing an arb on liquidity that is gone; the depth book knows the next
+        // level, without one the side has no quote until the next snapshot or improvement.
+        match change.size.as_deref().map(parse_size) {
+            Some(0) => Some(depth
+                .and_then(|d| d.best_ask(&change.asset_id))
+                .map(|(p, contracts)| (p, (contracts * 100.0).round() as SizeCents))
+                .unwrap_or((NO_PRICE, 0))),
+            Some(size) => Some((price, size)),
+            None => None,
         }
+    } else {
+        None
+    };
+
+    if let Some((price, size)) = best {
+        if is_yes { market.poly.update_yes(price, size) } else { market.poly.update_no(price, size) }
     }
-    // Check NO token
-    else if let Some(&market_id) = state.poly_no_to_id.get(&token_hash) {
-        let market = &state.markets[market_id as usize];
-        let (_, current_no, _, current_no_size) = market.poly.load();
 
-        if price < current_no || current_no == 0 {
-            market.poly.update_no(price, current_no_size);
+    // An open episode is re-checked on every ask change so it closes once the arb is gone
+    if best.is_some() || opportunities.is_some_and(|o| o.is_open(market_id)) {
+        evaluate(state, market_id, exec_tx, threshold_cents, clock, opportunities).await;
+    }
+}
 
-            let arb_mask = market.check_arbs(threshold_cents);
-            if arb_mask != 0 {
-                send_arb_request(market_id, market, arb_mask, exec_tx, clock).await;
-            }
-        }
+/// Check a market after a price update: report to the opportunity tracker, send arbs
+#[inline]
+async fn evaluate(
+    state: &GlobalState,
+    market_id: u16,
+    exec_tx: &mpsc::Sender<FastExecutionRequest>,
+    threshold_cents: PriceCents,
+    clock: &NanoClock,
+    opportunities: Option<&OpportunityTracker>,
+) {
+    if !state.is_detecting() {
+        return;
+    }
+    let market = &state.markets[market_id as usize];
+    let arb_mask = market.check_fresh_arbs(threshold_cents, clock.now_ns(), state.max_quote_age_ns);
+    if let Some(opportunities) = opportunities {
+        opportunities.observe(market_id, market, arb_mask);
+    }
+    if arb_mask != 0 {
+        send_arb_request(market_id, market, arb_mask, exec_tx, clock).await;
     }
 }
 
@@ -410,6 +717,7 @@ async fn send_arb_request(
     clock: &NanoClock,
 ) {
     let (p_yes, p_no, p_yes_size, p_no_size) = market.poly.load();
+    let (yes_fee_bps, no_fee_bps) = market.fee_rates();
 
     // Only PolyOnly arb type is supported now
     if arb_mask & 4 != 0 {
@@ -426,6 +734,8 @@ async fn send_arb_request(
             no_price,
             yes_size,
             no_size,
+            yes_fee_bps,
+            no_fee_bps,
             arb_type,
             detected_ns: clock.now_ns(),
         };
@@ -1,13 +1,13 @@
 // src/polymarket_clob.rs
 // Polymarket CLOB Client
 
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use anyhow::{Result, anyhow};
 use base64::Engine;
 use base64::engine::general_purpose::URL_SAFE;
 use ethers::signers::{LocalWallet, Signer};
-use ethers::types::H256;
+use ethers::types::{Address, H256, Signature};
 use ethers::types::transaction::eip712::{Eip712, TypedData};
 use ethers::types::U256;
 use hmac::{Hmac, Mac};
@@ -16,16 +16,31 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sha2::Sha256;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
+use tiny_keccak::{Hasher, Keccak};
+
+use crate::clob_error::ClobError;
+use crate::exchange::OrderLeg;
+use crate::presign::PresignPool;
+use crate::types::price_to_cents;
+use crate::rate_limit::{Endpoint, RateLimiter};
 
 const USER_AGENT: &str = "py_clob_client";
 const MSG_TO_SIGN: &str = "This message attests that I control the given wallet";
 const ZERO_ADDRESS: &str = "0x0000000000000000000000000000000000000000";
+/// How long a fee rate lookup is trusted before asking the exchange again
+const FEE_RATE_TTL: Duration = Duration::from_secs(300);
 
 // ============================================================================
 // PRE-COMPUTED EIP712 CONSTANTS
 // ============================================================================
 
+const EIP712_DOMAIN_TYPE: &[u8] =
+    b"EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+const ORDER_TYPE: &[u8] = b"Order(uint256 salt,address maker,address signer,address taker,uint256 tokenId,uint256 makerAmount,uint256 takerAmount,uint256 expiration,uint256 nonce,uint256 feeRateBps,uint8 side,uint8 signatureType)";
+const EXCHANGE_DOMAIN_NAME: &[u8] = b"Polymarket CTF Exchange";
+const EXCHANGE_DOMAIN_VERSION: &[u8] = b"1";
+
 type HmacSha256 = Hmac<Sha256>;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -108,7 +123,8 @@ fn current_unix_ts() -> u64 {
     SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
 }
 
-fn clob_auth_digest(chain_id: u64, address_str: &str, timestamp: u64, nonce: u64) -> Result<H256> {
+/// EIP-712 digest of the ClobAuth message signed for L1 authentication
+pub fn clob_auth_digest(chain_id: u64, address_str: &str, timestamp: u64, nonce: u64) -> Result<H256> {
     let typed_json = json!({
         "types": {
             "EIP712Domain": [
@@ -131,6 +147,96 @@ fn clob_auth_digest(chain_id: u64, address_str: &str, timestamp: u64, nonce: u64
     Ok(typed.encode_eip712()?.into())
 }
 
+// ============================================================================
+// SIGNATURE TYPES
+// ============================================================================
+
+/// How the exchange verifies the order signature against the maker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureType {
+    /// Plain EOA: maker and signer are the same wallet
+    Eoa = 0,
+    /// Polymarket proxy wallet owned by the signer (email/magic accounts)
+    PolyProxy = 1,
+    /// Gnosis Safe owned by the signer (browser wallet accounts)
+    PolyGnosisSafe = 2,
+}
+
+impl SignatureType {
+    /// POLY_SIGNATURE_TYPE: 0/eoa, 1/proxy, 2/safe (default: proxy)
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("POLY_SIGNATURE_TYPE") {
+            Ok(v) if !v.trim().is_empty() => Self::parse(&v),
+            _ => Ok(SignatureType::PolyProxy),
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "0" | "eoa" => Ok(SignatureType::Eoa),
+            "1" | "proxy" | "poly_proxy" => Ok(SignatureType::PolyProxy),
+            "2" | "safe" | "gnosis_safe" | "poly_gnosis_safe" => Ok(SignatureType::PolyGnosisSafe),
+            other => Err(anyhow!("unknown signature type '{}' (expected 0/eoa, 1/proxy, 2/safe)", other)),
+        }
+    }
+
+    #[inline(always)]
+    pub fn as_i32(self) -> i32 {
+        self as i32
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignatureType::Eoa => "EOA",
+            SignatureType::PolyProxy => "POLY_PROXY",
+            SignatureType::PolyGnosisSafe => "POLY_GNOSIS_SAFE",
+        }
+    }
+
+    /// Wallet the signer's orders must name as maker: the signer itself for EOA, else the
+    /// proxy / Safe the Polymarket factory deploys for it (CREATE2, so known up front)
+    pub fn funder_address(self, chain_id: u64, signer: Address) -> Result<Address> {
+        let ((factory, init_code_hash), salt) = match self {
+            SignatureType::Eoa => return Ok(signer),
+            // Proxy factory salts with the packed address, the Safe factory with its ABI word
+            SignatureType::PolyProxy => (get_proxy_factory(chain_id)?, keccak256(signer.as_bytes())),
+            SignatureType::PolyGnosisSafe => {
+                let mut word = [0u8; 32];
+                word[12..].copy_from_slice(signer.as_bytes());
+                (get_safe_factory(chain_id)?, keccak256(&word))
+            }
+        };
+        let factory: Address = factory.parse()?;
+        let init_code_hash: H256 = init_code_hash.parse()?;
+        Ok(ethers::utils::get_create2_address_from_hash(factory, salt, init_code_hash))
+    }
+
+    /// Check the maker (funder) is the wallet this type derives from the signer
+    pub fn validate(self, chain_id: u64, signer: &str, maker: &str) -> Result<()> {
+        let maker_addr: Address = maker.parse()
+            .map_err(|_| anyhow!("funder {} is not a valid address", maker))?;
+        let signer_addr: Address = signer.parse()
+            .map_err(|_| anyhow!("signer {} is not a valid address", signer))?;
+
+        let expected = self.funder_address(chain_id, signer_addr)?;
+        if maker_addr == expected {
+            return Ok(());
+        }
+        match self {
+            SignatureType::Eoa => Err(anyhow!(
+                "EOA signature type requires POLY_FUNDER ({}) to be the signing wallet ({})", maker, signer
+            )),
+            SignatureType::PolyProxy | SignatureType::PolyGnosisSafe => Err(anyhow!(
+                "{} signature type requires POLY_FUNDER ({}) to be the signer's {} wallet ({:?})",
+                self.as_str(),
+                maker,
+                if self == SignatureType::PolyProxy { "proxy" } else { "Safe" },
+                expected
+            )),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub struct OrderArgs {
@@ -181,6 +287,163 @@ pub struct OrderStruct {
     pub signature_type: i32,
 }
 
+impl OrderStruct {
+    fn as_order_data(&self) -> OrderData<'_> {
+        OrderData {
+            maker: &self.maker,
+            taker: &self.taker,
+            token_id: &self.token_id,
+            maker_amount: &self.maker_amount,
+            taker_amount: &self.taker_amount,
+            side: self.side,
+            fee_rate_bps: &self.fee_rate_bps,
+            nonce: &self.nonce,
+            signer: &self.signer,
+            expiration: &self.expiration,
+            signature_type: self.signature_type,
+            salt: self.salt,
+        }
+    }
+}
+
+/// EIP-712 digest of an order for the given exchange (hand-rolled encoder)
+pub fn order_digest(chain_id: u64, neg_risk: bool, order: &OrderStruct) -> Result<H256> {
+    let domain = domain_separator(chain_id, neg_risk)?;
+    let struct_hash = order_struct_hash(&order.as_order_data())?;
+
+    let mut hasher = Keccak::v256();
+    hasher.update(&[0x19, 0x01]);
+    hasher.update(domain);
+    hasher.update(&struct_hash);
+    let mut digest = [0u8; 32];
+    hasher.finalize(&mut digest);
+    Ok(H256(digest))
+}
+
+/// Reference digest via ethers TypedData (slow; kept to cross-check the encoder)
+#[allow(dead_code)]
+pub fn order_digest_typed_data(chain_id: u64, neg_risk: bool, order: &OrderStruct) -> Result<H256> {
+    let exchange = get_exchange_address(chain_id, neg_risk)?;
+    let typed = order_typed_data(chain_id, &exchange, &order.as_order_data())?;
+    Ok(typed.encode_eip712()?.into())
+}
+
+#[inline]
+fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak::v256();
+    hasher.update(data);
+    let mut out = [0u8; 32];
+    hasher.finalize(&mut out);
+    out
+}
+
+fn order_type_hash() -> &'static [u8; 32] {
+    static ORDER_TYPEHASH: OnceLock<[u8; 32]> = OnceLock::new();
+    ORDER_TYPEHASH.get_or_init(|| keccak256(ORDER_TYPE))
+}
+
+/// Domain separator per (chain, exchange), computed once
+fn domain_separator(chain_id: u64, neg_risk: bool) -> Result<&'static [u8; 32]> {
+    static POLYGON: OnceLock<[[u8; 32]; 2]> = OnceLock::new();
+    static AMOY: OnceLock<[[u8; 32]; 2]> = OnceLock::new();
+
+    let cell = match chain_id {
+        137 => &POLYGON,
+        80002 => &AMOY,
+        _ => return Err(anyhow!("unsupported chain")),
+    };
+    let domains = cell.get_or_init(|| {
+        [false, true].map(|nr| {
+            let exchange = get_exchange_address(chain_id, nr).expect("known chain");
+            compute_domain_separator(chain_id, &exchange).expect("valid exchange address")
+        })
+    });
+    Ok(&domains[neg_risk as usize])
+}
+
+fn compute_domain_separator(chain_id: u64, exchange: &str) -> Result<[u8; 32]> {
+    let mut buf = [0u8; 32 * 5];
+    buf[..32].copy_from_slice(&keccak256(EIP712_DOMAIN_TYPE));
+    buf[32..64].copy_from_slice(&keccak256(EXCHANGE_DOMAIN_NAME));
+    buf[64..96].copy_from_slice(&keccak256(EXCHANGE_DOMAIN_VERSION));
+    buf[96..128].copy_from_slice(&uint_word(chain_id as u128));
+    buf[128..160].copy_from_slice(&address_word(exchange)?);
+    Ok(keccak256(&buf))
+}
+
+/// hashStruct(Order): keccak(typeHash || 12 encoded fields)
+fn order_struct_hash(data: &OrderData<'_>) -> Result<[u8; 32]> {
+    let mut buf = [0u8; 32 * 13];
+    buf[..32].copy_from_slice(order_type_hash());
+    let words: [[u8; 32]; 12] = [
+        uint_word(data.salt),
+        address_word(data.maker)?,
+        address_word(data.signer)?,
+        address_word(data.taker)?,
+        dec_word(data.token_id)?,
+        dec_word(data.maker_amount)?,
+        dec_word(data.taker_amount)?,
+        dec_word(data.expiration)?,
+        dec_word(data.nonce)?,
+        dec_word(data.fee_rate_bps)?,
+        uint_word(data.side as u8 as u128),
+        uint_word(data.signature_type as u8 as u128),
+    ];
+    for (i, word) in words.iter().enumerate() {
+        buf[32 * (i + 1)..32 * (i + 2)].copy_from_slice(word);
+    }
+    Ok(keccak256(&buf))
+}
+
+#[inline(always)]
+fn uint_word(v: u128) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[16..].copy_from_slice(&v.to_be_bytes());
+    word
+}
+
+/// Decimal uint256 string -> big-endian word
+#[inline]
+fn dec_word(s: &str) -> Result<[u8; 32]> {
+    // Amounts, nonce and fee fit in u128; only token IDs need full uint256 parsing
+    if s.len() <= 38 {
+        if let Ok(v) = s.parse::<u128>() {
+            return Ok(uint_word(v));
+        }
+    }
+    let mut word = [0u8; 32];
+    U256::from_dec_str(s)
+        .map_err(|e| anyhow!("invalid uint256 {}: {}", s, e))?
+        .to_big_endian(&mut word);
+    Ok(word)
+}
+
+/// 0x-prefixed hex address -> left-padded word
+#[inline]
+fn address_word(addr: &str) -> Result<[u8; 32]> {
+    let hex = addr.strip_prefix("0x").unwrap_or(addr).as_bytes();
+    if hex.len() != 40 {
+        return Err(anyhow!("invalid address: {}", addr));
+    }
+    let mut word = [0u8; 32];
+    for (i, pair) in hex.chunks_exact(2).enumerate() {
+        let hi = hex_nibble(pair[0]).ok_or_else(|| anyhow!("invalid address: {}", addr))?;
+        let lo = hex_nibble(pair[1]).ok_or_else(|| anyhow!("invalid address: {}", addr))?;
+        word[12 + i] = (hi << 4) | lo;
+    }
+    Ok(word)
+}
+
+#[inline(always)]
+fn hex_nibble(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct SignedOrder { 
     pub order: OrderStruct, 
@@ -188,9 +451,42 @@ pub struct SignedOrder {
 }
 
 impl SignedOrder {
+    /// Recover the address that signed this order
+    #[allow(dead_code)]
+    pub fn recover_signer(&self, chain_id: u64, neg_risk: bool) -> Result<Address> {
+        let digest = order_digest(chain_id, neg_risk, &self.order)?;
+        let sig: Signature = self.signature.trim_start_matches("0x").parse()
+            .map_err(|e| anyhow!("invalid order signature: {}", e))?;
+        Ok(sig.recover(digest)?)
+    }
+
+    /// Order hash as the CLOB reports it in orderID (0x-prefixed hex)
+    pub fn order_hash(&self, chain_id: u64, neg_risk: bool) -> Result<String> {
+        Ok(format!("{:#x}", order_digest(chain_id, neg_risk, &self.order)?))
+    }
+
     pub fn post_body(&self, owner: &str, order_type: &str) -> String {
-        let side_str = if self.order.side == 0 { "BUY" } else { "SELL" };
         let mut buf = String::with_capacity(512);
+        self.write_post_entry(&mut buf, owner, order_type);
+        buf
+    }
+
+    /// Body for POST /orders: a JSON array of post entries
+    pub fn batch_post_body(orders: &[SignedOrder], owner: &str, order_type: &str) -> String {
+        let mut buf = String::with_capacity(512 * orders.len() + 2);
+        buf.push('[');
+        for (i, order) in orders.iter().enumerate() {
+            if i > 0 {
+                buf.push(',');
+            }
+            order.write_post_entry(&mut buf, owner, order_type);
+        }
+        buf.push(']');
+        buf
+    }
+
+    fn write_post_entry(&self, buf: &mut String, owner: &str, order_type: &str) {
+        let side_str = if self.order.side == 0 { "BUY" } else { "SELL" };
         buf.push_str(r#"{"order":{"salt":"#);
         buf.push_str(&self.order.salt.to_string());
         buf.push_str(r#","maker":""#);
@@ -222,7 +518,6 @@ impl SignedOrder {
         buf.push_str(r#"","orderType":""#);
         buf.push_str(order_type);
         buf.push_str(r#""}"#);
-        buf
     }
 }
 
@@ -273,6 +568,15 @@ pub fn get_order_amounts_sell(size_micro: u64, price_bps: u64) -> (i32, u128, u1
     (1, maker, taker)
 }
 
+/// Taker fee in dollars for a fill: fee_rate × min(price, 1 - price) × size
+#[inline(always)]
+pub fn fee_for_fill(price: f64, size: f64, fee_rate_bps: u16) -> f64 {
+    if fee_rate_bps == 0 {
+        return 0.0;
+    }
+    (fee_rate_bps as f64 / 10000.0) * price.min(1.0 - price).max(0.0) * size
+}
+
 /// Validate price is within allowed range for tick=0.01
 #[inline(always)]
 pub fn price_valid(price_bps: u64) -> bool {
@@ -324,7 +628,8 @@ fn order_typed_data(chain_id: u64, exchange: &str, data: &OrderData<'_>) -> Resu
     Ok(serde_json::from_value(typed_json)?)
 }
 
-fn get_exchange_address(chain_id: u64, neg_risk: bool) -> Result<String> {
+/// Exchange contract that verifies (and spends collateral for) orders
+pub fn get_exchange_address(chain_id: u64, neg_risk: bool) -> Result<String> {
     match (chain_id, neg_risk) {
         (137, true) => Ok("0xC5d563A36AE78145C45a50134d48A1215220f80a".into()),
         (137, false) => Ok("0x4bFb41d5B3570DeFd03C39a9A4D8dE6Bd8B8982E".into()),
@@ -334,12 +639,34 @@ fn get_exchange_address(chain_id: u64, neg_risk: bool) -> Result<String> {
     }
 }
 
+/// Polymarket proxy wallet factory and the init code hash of the proxies it deploys
+pub fn get_proxy_factory(chain_id: u64) -> Result<(&'static str, &'static str)> {
+    match chain_id {
+        137 => Ok((
+            "0xaB45c5A4B0c941a2F231C04C3f49182e1A254052",
+            "0xd21df8dc65880a8606f09fe0ce3df9b8869287ab0b058be05aa9e8af6330a00b",
+        )),
+        _ => Err(anyhow!("no Polymarket proxy factory on chain {}", chain_id)),
+    }
+}
+
+/// Polymarket Safe factory and the init code hash of the Safes it deploys
+pub fn get_safe_factory(chain_id: u64) -> Result<(&'static str, &'static str)> {
+    match chain_id {
+        137 => Ok((
+            "0xaacFeEa03eb1561C4e67d661e40682Bd20E3541b",
+            "0x2bce2127ff07fb632d16c8347c4ebf501f4841168bed00d9e6ef715ddb6fcecf",
+        )),
+        _ => Err(anyhow!("no Polymarket Safe factory on chain {}", chain_id)),
+    }
+}
+
 // ============================================================================
 // ORDER TYPES FOR FAK/FOK
 // ============================================================================
 
 /// Order type for Polymarket
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[allow(dead_code)]
 pub enum PolyOrderType {
     /// Good Till Cancelled (default)
@@ -392,6 +719,153 @@ pub struct PolymarketOrderResponse {
     pub owner: Option<String>,
 }
 
+// ============================================================================
+// POST ORDER RESPONSE
+// ============================================================================
+
+/// Maximum orders the CLOB accepts in one POST /orders request
+pub const MAX_BATCH_ORDERS: usize = 15;
+
+/// Per-order result from POST /order and POST /orders
+#[derive(Debug, Clone, Default, Deserialize)]
+#[allow(dead_code)]
+pub struct PostOrderResponse {
+    #[serde(default)]
+    pub success: bool,
+    #[serde(rename = "errorMsg", default)]
+    pub error_msg: String,
+    #[serde(rename = "orderID", default)]
+    pub order_id: String,
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(rename = "makingAmount", default)]
+    pub making_amount: Option<String>,
+    #[serde(rename = "takingAmount", default)]
+    pub taking_amount: Option<String>,
+}
+
+impl PostOrderResponse {
+    /// Order was accepted by the matching engine
+    pub fn is_accepted(&self) -> bool {
+        (self.success || self.error_msg.is_empty()) && !self.order_id.is_empty()
+    }
+}
+
+// ============================================================================
+// ORDER MANAGEMENT RESPONSES
+// ============================================================================
+
+/// Cursor value the CLOB uses to start pagination
+const INITIAL_CURSOR: &str = "MA==";
+/// Cursor value the CLOB returns on the last page
+const END_CURSOR: &str = "LTE=";
+
+/// Response from DELETE /order, /orders, /cancel-market-orders and /cancel-all
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CancelOrdersResponse {
+    /// Order IDs that were cancelled
+    #[serde(default)]
+    pub canceled: Vec<String>,
+    /// Order ID -> reason for orders that could not be cancelled
+    #[serde(default)]
+    pub not_canceled: HashMap<String, String>,
+}
+
+/// A single trade from GET /data/trades
+#[derive(Debug, Clone, Deserialize)]
+#[allow(dead_code)]
+pub struct PolymarketTrade {
+    pub id: String,
+    pub taker_order_id: Option<String>,
+    pub market: Option<String>,
+    pub asset_id: String,
+    pub side: String,
+    pub size: String,
+    pub price: String,
+    #[serde(default)]
+    pub fee_rate_bps: Option<String>,
+    pub status: String,
+    #[serde(default)]
+    pub match_time: Option<serde_json::Value>,  // Can be string or integer
+    pub outcome: Option<String>,
+    pub owner: Option<String>,
+    pub maker_address: Option<String>,
+    pub trader_side: Option<String>,
+    pub transaction_hash: Option<String>,
+    #[serde(default)]
+    pub maker_orders: Vec<serde_json::Value>,
+}
+
+/// One page of a paginated /data/* response
+#[derive(Debug, Clone, Deserialize)]
+pub struct PaginatedResponse<T> {
+    pub data: Vec<T>,
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+/// GET /balance-allowance response (amounts in 6-decimal base units)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BalanceAllowanceResponse {
+    #[serde(default)]
+    pub balance: String,
+    /// Older single-allowance form
+    #[serde(default)]
+    pub allowance: Option<String>,
+    /// Allowance per spender (exchange) address
+    #[serde(default)]
+    pub allowances: HashMap<String, String>,
+}
+
+impl BalanceAllowanceResponse {
+    /// Balance in micro-USDC
+    pub fn balance_micro(&self) -> u64 {
+        parse_base_units(&self.balance)
+    }
+
+    /// Allowance granted to `spender` in micro-USDC (falls back to the single-allowance form)
+    pub fn allowance_micro(&self, spender: &str) -> u64 {
+        self.allowances.iter()
+            .find(|(addr, _)| addr.eq_ignore_ascii_case(spender))
+            .map(|(_, v)| parse_base_units(v))
+            .or_else(|| self.allowance.as_deref().map(parse_base_units))
+            .unwrap_or(0)
+    }
+}
+
+/// Parse an integer base-unit amount, saturating (max approvals exceed u64)
+fn parse_base_units(s: &str) -> u64 {
+    match s.parse::<u64>() {
+        Ok(v) => v,
+        Err(_) if !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) => u64::MAX,
+        Err(_) => 0,
+    }
+}
+
+/// Filters for open order and trade queries
+#[derive(Debug, Clone, Default)]
+pub struct OrderQuery<'a> {
+    /// Condition ID of the market
+    pub market: Option<&'a str>,
+    /// Token ID
+    pub asset_id: Option<&'a str>,
+}
+
+impl OrderQuery<'_> {
+    fn to_query_string(&self, cursor: &str) -> String {
+        let mut q = format!("?next_cursor={}", cursor);
+        if let Some(market) = self.market {
+            q.push_str("&market=");
+            q.push_str(market);
+        }
+        if let Some(asset_id) = self.asset_id {
+            q.push_str("&asset_id=");
+            q.push_str(asset_id);
+        }
+        q
+    }
+}
+
 // ============================================================================
 // ASYNC CLIENT
 // ============================================================================
@@ -405,6 +879,8 @@ pub struct PolymarketAsyncClient {
     funder: String,
     wallet_address_str: String,
     address_header: HeaderValue,
+    signature_type: SignatureType,
+    limiter: Arc<RateLimiter>,
 }
 
 impl PolymarketAsyncClient {
@@ -431,9 +907,28 @@ impl PolymarketAsyncClient {
             funder: funder.to_string(),
             wallet_address_str,
             address_header,
+            signature_type: SignatureType::PolyProxy,
+            limiter: Arc::new(RateLimiter::unlimited()),
         })
     }
 
+    /// Set the order signature type, validating the funder/signer relationship
+    pub fn with_signature_type(mut self, signature_type: SignatureType) -> Result<Self> {
+        signature_type.validate(self.chain_id, &self.wallet_address_str, &self.funder)?;
+        self.signature_type = signature_type;
+        Ok(self)
+    }
+
+    pub fn signature_type(&self) -> SignatureType {
+        self.signature_type
+    }
+
+    /// Route all CLOB requests through a shared rate limiter
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
     /// Build L1 headers for authentication (derive-api-key)
     /// wallet.sign_hash() is CPU-bound (~1ms), safe to call in async context
     fn build_l1_headers(&self, nonce: u64) -> Result<HeaderMap> {
@@ -482,6 +977,7 @@ impl PolymarketAsyncClient {
 
     /// Post order 
     pub async fn post_order_async(&self, body: String, creds: &PreparedCreds) -> Result<reqwest::Response> {
+        self.limiter.acquire(Endpoint::OrderPost).await;
         let path = "/order";
         let url = format!("{}{}", self.host, path);
         let headers = self.build_l2_headers("POST", path, Some(&body), creds)?;
@@ -496,8 +992,26 @@ impl PolymarketAsyncClient {
         Ok(resp)
     }
 
+    /// Post a batch of orders (JSON array body)
+    pub async fn post_orders_async(&self, body: String, creds: &PreparedCreds) -> Result<reqwest::Response> {
+        self.limiter.acquire(Endpoint::OrderPost).await;
+        let path = "/orders";
+        let url = format!("{}{}", self.host, path);
+        let headers = self.build_l2_headers("POST", path, Some(&body), creds)?;
+
+        let resp = self.http
+            .post(&url)
+            .headers(headers)
+            .body(body)
+            .send()
+            .await?;
+
+        Ok(resp)
+    }
+
     /// Get order by ID 
     pub async fn get_order_async(&self, order_id: &str, creds: &PreparedCreds) -> Result<PolymarketOrderResponse> {
+        self.limiter.acquire(Endpoint::OrderQuery).await;
         let path = format!("/data/order/{}", order_id);
         let url = format!("{}{}", self.host, path);
         let headers = self.build_l2_headers("GET", &path, None, creds)?;
@@ -511,14 +1025,121 @@ impl PolymarketAsyncClient {
         if !resp.status().is_success() {
             let status = resp.status();
             let body = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("get_order failed {}: {}", status, body));
+            return Err(ClobError::from_query_response(status.as_u16(), &body).into());
+        }
+
+        Ok(resp.json().await?)
+    }
+
+    /// Send an L2-authenticated request and parse the JSON response
+    /// The signature covers the path only; query params are appended after signing
+    async fn l2_json<T: serde::de::DeserializeOwned>(
+        &self,
+        endpoint: Endpoint,
+        method: reqwest::Method,
+        path: &str,
+        query: &str,
+        body: Option<String>,
+        creds: &PreparedCreds,
+    ) -> Result<T> {
+        self.limiter.acquire(endpoint).await;
+        let url = format!("{}{}{}", self.host, path, query);
+        let headers = self.build_l2_headers(method.as_str(), path, body.as_deref(), creds)?;
+
+        let mut req = self.http.request(method.clone(), &url).headers(headers);
+        if let Some(b) = body {
+            req = req.body(b);
+        }
+        let resp = req.send().await?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            tracing::debug!("[POLY-ASYNC] {} {} failed {}", method, path, status);
+            return Err(ClobError::from_query_response(status.as_u16(), &body).into());
         }
 
         Ok(resp.json().await?)
     }
 
+    /// Fetch every page of a paginated /data/* endpoint
+    async fn get_all_pages<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &OrderQuery<'_>,
+        creds: &PreparedCreds,
+    ) -> Result<Vec<T>> {
+        let mut out = Vec::new();
+        let mut cursor = INITIAL_CURSOR.to_string();
+        loop {
+            let page: PaginatedResponse<T> = self
+                .l2_json(Endpoint::OrderQuery, reqwest::Method::GET, path, &query.to_query_string(&cursor), None, creds)
+                .await?;
+            out.extend(page.data);
+            match page.next_cursor {
+                Some(next) if !next.is_empty() && next != END_CURSOR && next != cursor => cursor = next,
+                _ => break,
+            }
+        }
+        Ok(out)
+    }
+
+    /// Cancel a single order by ID
+    #[allow(dead_code)]
+    pub async fn cancel_order_async(&self, order_id: &str, creds: &PreparedCreds) -> Result<CancelOrdersResponse> {
+        let body = json!({ "orderID": order_id }).to_string();
+        self.l2_json(Endpoint::Cancel, reqwest::Method::DELETE, "/order", "", Some(body), creds).await
+    }
+
+    /// Cancel several orders by ID in one request
+    pub async fn cancel_orders_async(&self, order_ids: &[String], creds: &PreparedCreds) -> Result<CancelOrdersResponse> {
+        let body = serde_json::to_string(order_ids)?;
+        self.l2_json(Endpoint::Cancel, reqwest::Method::DELETE, "/orders", "", Some(body), creds).await
+    }
+
+    /// Cancel all orders for a market (condition ID) and/or token
+    #[allow(dead_code)]
+    pub async fn cancel_market_orders_async(
+        &self,
+        market: Option<&str>,
+        asset_id: Option<&str>,
+        creds: &PreparedCreds,
+    ) -> Result<CancelOrdersResponse> {
+        let body = json!({
+            "market": market.unwrap_or_default(),
+            "asset_id": asset_id.unwrap_or_default(),
+        }).to_string();
+        self.l2_json(Endpoint::Cancel, reqwest::Method::DELETE, "/cancel-market-orders", "", Some(body), creds).await
+    }
+
+    /// Cancel every open order for this API key
+    #[allow(dead_code)]
+    pub async fn cancel_all_async(&self, creds: &PreparedCreds) -> Result<CancelOrdersResponse> {
+        self.l2_json(Endpoint::Cancel, reqwest::Method::DELETE, "/cancel-all", "", None, creds).await
+    }
+
+    /// List open orders (all pages)
+    pub async fn get_open_orders_async(&self, query: &OrderQuery<'_>, creds: &PreparedCreds) -> Result<Vec<PolymarketOrderResponse>> {
+        self.get_all_pages("/data/orders", query, creds).await
+    }
+
+    /// Fetch trade history (all pages)
+    pub async fn get_trades_async(&self, query: &OrderQuery<'_>, creds: &PreparedCreds) -> Result<Vec<PolymarketTrade>> {
+        self.get_all_pages("/data/trades", query, creds).await
+    }
+
+    /// Collateral (USDC) balance and exchange allowances for the funder
+    pub async fn get_balance_allowance_async(&self, creds: &PreparedCreds) -> Result<BalanceAllowanceResponse> {
+        let query = format!("?asset_type=COLLATERAL&signature_type={}", self.signature_type.as_i32());
+        self.l2_json(
+            Endpoint::OrderQuery, reqwest::Method::GET, "/balance-allowance",
+            &query, None, creds,
+        ).await
+    }
+
     /// Check neg_risk for token - with caching
     pub async fn check_neg_risk(&self, token_id: &str) -> Result<bool> {
+        self.limiter.acquire(Endpoint::NegRisk).await;
         let url = format!("{}/neg-risk?token_id={}", self.host, token_id);
         let resp = self.http
             .get(&url)
@@ -530,6 +1151,26 @@ impl PolymarketAsyncClient {
         Ok(val["neg_risk"].as_bool().unwrap_or(false))
     }
 
+    /// Get taker fee rate (bps) for token
+    pub async fn get_fee_rate_bps(&self, token_id: &str) -> Result<u16> {
+        self.limiter.acquire(Endpoint::FeeRate).await;
+        let url = format!("{}/fee-rate?token_id={}", self.host, token_id);
+        let resp = self.http
+            .get(&url)
+            .header("User-Agent", USER_AGENT)
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(anyhow!("fee-rate failed {}: {}", status, body));
+        }
+
+        let val: serde_json::Value = resp.json().await?;
+        Ok(val["base_fee"].as_u64().unwrap_or(0).min(u16::MAX as u64) as u16)
+    }
+
     #[allow(dead_code)]
     pub fn wallet_address(&self) -> &str {
         &self.wallet_address_str
@@ -553,18 +1194,30 @@ pub struct SharedAsyncClient {
     chain_id: u64,
     /// Pre-cached neg_risk lookups
     neg_risk_cache: std::sync::RwLock<HashMap<String, bool>>,
+    /// Cached fee rate lookups (bps, fetched at)
+    fee_rate_cache: std::sync::RwLock<HashMap<String, (u16, Instant)>>,
+    /// Orders signed ahead of time for tracked tokens
+    presign: Option<Arc<PresignPool>>,
 }
 
 impl SharedAsyncClient {
-    pub fn new(client: PolymarketAsyncClient, creds: PreparedCreds, chain_id: u64) -> Self {
+    pub fn new(client: impl Into<Arc<PolymarketAsyncClient>>, creds: PreparedCreds, chain_id: u64) -> Self {
         Self {
-            inner: Arc::new(client),
+            inner: client.into(),
             creds,
             chain_id,
             neg_risk_cache: std::sync::RwLock::new(HashMap::new()),
+            fee_rate_cache: std::sync::RwLock::new(HashMap::new()),
+            presign: None,
         }
     }
 
+    /// Post pre-signed orders from `pool` when a batch matches them
+    pub fn with_presign_pool(mut self, pool: Arc<PresignPool>) -> Self {
+        self.presign = Some(pool);
+        self
+    }
+
     /// Load neg_risk cache from JSON file (output of build_sports_cache.py)
     pub fn load_cache(&self, path: &str) -> Result<usize> {
         let data = std::fs::read_to_string(path)?;
@@ -575,7 +1228,117 @@ impl SharedAsyncClient {
         Ok(count)
     }
 
+    /// neg_risk flag for a token if already known (no network lookup)
+    pub fn cached_neg_risk(&self, token_id: &str) -> Option<bool> {
+        self.neg_risk_cache.read().unwrap().get(token_id).copied()
+    }
+
+    /// Collateral balance and allowances as reported by the CLOB
+    pub async fn balance_allowance(&self) -> Result<BalanceAllowanceResponse> {
+        self.inner.get_balance_allowance_async(&self.creds).await
+    }
+
+    /// Address holding the collateral
+    pub fn funder(&self) -> &str {
+        self.inner.funder()
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    /// Get fee rate (bps) for token - cached for FEE_RATE_TTL
+    pub async fn fee_rate_bps(&self, token_id: &str) -> Result<u16> {
+        if let Some(bps) = self.cached_fee_rate_bps(token_id) {
+            return Ok(bps);
+        }
+
+        let bps = self.inner.get_fee_rate_bps(token_id).await?;
+        let mut cache = self.fee_rate_cache.write().unwrap();
+        cache.insert(token_id.to_string(), (bps, Instant::now()));
+        Ok(bps)
+    }
+
+    /// Fee rate from the cache if it has not expired
+    fn cached_fee_rate_bps(&self, token_id: &str) -> Option<u16> {
+        let cache = self.fee_rate_cache.read().unwrap();
+        cache.get(token_id)
+            .filter(|(_, fetched_at)| fetched_at.elapsed() < FEE_RATE_TTL)
+            .map(|&(bps, _)| bps)
+    }
+
+    /// Fetch a single order by ID
+    #[allow(dead_code)]
+    pub async fn order(&self, order_id: &str) -> Result<PolymarketOrderResponse> {
+        self.inner.get_order_async(order_id, &self.creds).await
+    }
+
+    /// Cancel a single order by ID
+    #[allow(dead_code)]
+    pub async fn cancel_order(&self, order_id: &str) -> Result<CancelOrdersResponse> {
+        self.inner.cancel_order_async(order_id, &self.creds).await
+    }
+
+    /// Cancel several orders by ID
+    pub async fn cancel_orders(&self, order_ids: &[String]) -> Result<CancelOrdersResponse> {
+        if order_ids.is_empty() {
+            return Ok(CancelOrdersResponse::default());
+        }
+        self.inner.cancel_orders_async(order_ids, &self.creds).await
+    }
+
+    /// Cancel all orders on a market and/or token
+    #[allow(dead_code)]
+    pub async fn cancel_market_orders(&self, market: Option<&str>, asset_id: Option<&str>) -> Result<CancelOrdersResponse> {
+        self.inner.cancel_market_orders_async(market, asset_id, &self.creds).await
+    }
+
+    /// Cancel every open order
+    pub async fn cancel_all(&self) -> Result<CancelOrdersResponse> {
+        self.inner.cancel_all_async(&self.creds).await
+    }
+
+    /// List open orders
+    pub async fn open_orders(&self, query: &OrderQuery<'_>) -> Result<Vec<PolymarketOrderResponse>> {
+        self.inner.get_open_orders_async(query, &self.creds).await
+    }
+
+    /// Fetch trade history
+    pub async fn trades(&self, query: &OrderQuery<'_>) -> Result<Vec<PolymarketTrade>> {
+        self.inner.get_trades_async(query, &self.creds).await
+    }
+
+    /// Startup reconciliation: list resting orders left behind by a previous run
+    /// and cancel them. We only trade FAK, so anything still open is stale.
+    /// Returns the number of stale orders found.
+    pub async fn reconcile_open_orders(&self, cancel: bool) -> Result<usize> {
+        let open = self.open_orders(&OrderQuery::default()).await?;
+        if open.is_empty() {
+            return Ok(0);
+        }
+
+        for order in &open {
+            tracing::warn!(
+                "[POLY-ASYNC] Stale open order {}: {} {} @ {} (matched {}/{})",
+                order.id, order.side, order.asset_id.as_deref().unwrap_or("?"),
+                order.price, order.size_matched, order.original_size
+            );
+        }
+
+        if cancel {
+            let ids: Vec<String> = open.iter().map(|o| o.id.clone()).collect();
+            let resp = self.cancel_orders(&ids).await?;
+            tracing::info!("[POLY-ASYNC] Cancelled {} stale orders", resp.canceled.len());
+            for (id, reason) in &resp.not_canceled {
+                tracing::warn!("[POLY-ASYNC] Could not cancel {}: {}", id, reason);
+            }
+        }
+
+        Ok(open.len())
+    }
+
     /// Execute FAK buy order - 
+    #[allow(dead_code)]
     pub async fn buy_fak(&self, token_id: &str, price: f64, size: f64) -> Result<PolyFillAsync> {
         debug_assert!(!token_id.is_empty(), "token_id must not be empty");
         debug_assert!(price > 0.0 && price < 1.0, "price must be 0 < p < 1");
@@ -592,6 +1355,217 @@ impl SharedAsyncClient {
     }
 
     async fn execute_order(&self, token_id: &str, price: f64, size: f64, side: &str) -> Result<PolyFillAsync> {
+        let (signed, fee_rate_bps) = self.prepare_order(token_id, price, size, side).await?;
+        // Owner must be the API key (not wallet address or funder!)
+        let body = signed.post_body(&self.creds.api_key, PolyOrderType::FAK.as_str());
+
+        // Post order; if it may be live without an answer, its fill is unknown until looked up
+        let resp = match self.post_signed(body, false).await {
+            Ok(resp) => resp,
+            Err(e) if maybe_posted(&e) => {
+                let hash = signed.order_hash(self.chain_id, self.cached_neg_risk(token_id).unwrap_or(false))?;
+                tracing::warn!("[POLY-ASYNC] Order {} may be live, no response: {}", hash, e);
+                return Ok(PolyFillAsync::unknown(hash));
+            }
+            Err(e) => return Err(e),
+        };
+
+        // A 200 can still carry a rejection (success=false, errorMsg)
+        let response: PostOrderResponse = resp.json().await?;
+        if !response.is_accepted() {
+            return Err(ClobError::rejected(&response.error_msg).into());
+        }
+
+        self.query_fill(response.order_id, &OrderLeg { token_id, price, size, side }, fee_rate_bps).await
+    }
+
+    /// Execute several FAK orders in one POST /orders request (e.g. both arb legs).
+    /// Returns one result per leg, in input order, so partial rejections can be
+    /// handled leg by leg. The outer error is for failures of the whole request.
+    pub async fn execute_batch(&self, legs: &[OrderLeg<'_>]) -> Result<Vec<Result<PolyFillAsync>>> {
+        for leg in legs {
+            debug_assert!(!leg.token_id.is_empty(), "token_id must not be empty");
+            debug_assert!(leg.price > 0.0 && leg.price < 1.0, "price must be 0 < p < 1");
+            debug_assert!(leg.size >= 1.0, "size must be >= 1");
+        }
+
+        // A failed chunk fails only its own legs: earlier chunks are live on the
+        // exchange and their fills must still reach the caller
+        let mut results = Vec::with_capacity(legs.len());
+        for chunk in legs.chunks(MAX_BATCH_ORDERS) {
+            match self.execute_batch_chunk(chunk).await {
+                Ok(fills) => results.extend(fills),
+                Err(e) => {
+                    let err = ClobError::from_anyhow(&e);
+                    tracing::warn!("[POLY-ASYNC] Batch chunk of {} orders failed: {}", chunk.len(), err);
+                    results.extend(chunk.iter().map(|_| Err(err.clone().into())));
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    async fn execute_batch_chunk(&self, legs: &[OrderLeg<'_>]) -> Result<Vec<Result<PolyFillAsync>>> {
+        // Sign every leg; a leg that can't be signed is failed individually
+        let prepared = futures_util::future::join_all(
+            legs.iter().map(|leg| self.prepare_order(leg.token_id, leg.price, leg.size, leg.side))
+        ).await;
+
+        self.execute_prepared(legs, prepared).await
+    }
+
+    /// Place FAK legs. A YES/NO buy pair uses pre-signed orders when the pool has them
+    /// at the current fee rates (skips signing on the hot path), possibly at a smaller
+    /// size than asked.
+    pub async fn execute_fak(&self, legs: &[OrderLeg<'_>]) -> Result<Vec<Result<PolyFillAsync>>> {
+        let presigned = match (&self.presign, legs) {
+            (Some(pool), [yes, no]) if yes.side == "BUY" && no.side == "BUY" => {
+                match (self.cached_fee_rate_bps(yes.token_id), self.cached_fee_rate_bps(no.token_id)) {
+                    (Some(yes_bps), Some(no_bps)) => pool.take_pair(
+                        (yes.token_id, price_to_cents(yes.price), yes_bps),
+                        (no.token_id, price_to_cents(no.price), no_bps),
+                        yes.size.min(no.size).min(u32::MAX as f64) as u32,
+                    ),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+
+        let Some((yes, no)) = presigned else {
+            return self.execute_batch(legs).await;
+        };
+        let size = yes.size as f64;
+        if size < legs[0].size {
+            tracing::info!("[POLY-ASYNC] Using pre-signed {}x (of {}x)", yes.size, legs[0].size);
+        }
+        let sized = [OrderLeg { size, ..legs[0] }, OrderLeg { size, ..legs[1] }];
+        let orders = vec![(yes.order, yes.fee_rate_bps), (no.order, no.fee_rate_bps)];
+        self.execute_presigned(&sized, orders).await
+    }
+
+    /// Post orders that were signed ahead of time (one per leg, same order).
+    /// Legs describe the signed orders and are used to resolve fills.
+    pub async fn execute_presigned(
+        &self,
+        legs: &[OrderLeg<'_>],
+        orders: Vec<(SignedOrder, u16)>,
+    ) -> Result<Vec<Result<PolyFillAsync>>> {
+        if legs.len() != orders.len() || legs.len() > MAX_BATCH_ORDERS {
+            return Err(anyhow!("presigned batch: {} legs for {} orders", legs.len(), orders.len()));
+        }
+        self.execute_prepared(legs, orders.into_iter().map(Ok).collect()).await
+    }
+
+    async fn execute_prepared(
+        &self,
+        legs: &[OrderLeg<'_>],
+        prepared: Vec<Result<(SignedOrder, u16)>>,
+    ) -> Result<Vec<Result<PolyFillAsync>>> {
+        let mut signed = Vec::with_capacity(legs.len());
+        let mut fee_rates = Vec::with_capacity(legs.len());
+        let mut slots: Vec<Option<Result<PolyFillAsync>>> = Vec::with_capacity(legs.len());
+        for res in prepared {
+            match res {
+                Ok((order, fee_rate_bps)) => {
+                    signed.push(order);
+                    fee_rates.push(fee_rate_bps);
+                    slots.push(None);
+                }
+                Err(e) => slots.push(Some(Err(e))),
+            }
+        }
+
+        if !signed.is_empty() {
+            let hashes: Vec<Option<String>> = signed.iter()
+                .map(|o| o.order_hash(self.chain_id, self.cached_neg_risk(&o.order.token_id).unwrap_or(false)).ok())
+                .collect();
+            let body = SignedOrder::batch_post_body(&signed, &self.creds.api_key, PolyOrderType::FAK.as_str());
+
+            // The orders are live from here on: a response we can't use must not lose their fills
+            let responses: Vec<PostOrderResponse> = match self.post_signed(body, true).await {
+                Ok(resp) => match resp.json().await {
+                    Ok(responses) => responses,
+                    Err(e) => {
+                        tracing::warn!("[POLY-ASYNC] Unreadable batch response for {} orders: {}", signed.len(), e);
+                        Vec::new()
+                    }
+                },
+                // Sent but never answered: report the fills unknown so the settle step looks them up
+                Err(e) if maybe_posted(&e) => {
+                    tracing::warn!("[POLY-ASYNC] Batch of {} orders may be live, no response: {}", signed.len(), e);
+                    let mut unknown = hashes.into_iter();
+                    for slot in slots.iter_mut().filter(|slot| slot.is_none()) {
+                        *slot = Some(match unknown.next().flatten() {
+                            Some(hash) => Ok(PolyFillAsync::unknown(hash)),
+                            None => Err(ClobError::from_anyhow(&e).into()),
+                        });
+                    }
+                    return Ok(slots.into_iter().map(|slot| slot.unwrap_or_else(|| Err(anyhow!("order not posted")))).collect());
+                }
+                Err(e) => return Err(e),
+            };
+            if responses.len() != signed.len() {
+                tracing::warn!(
+                    "[POLY-ASYNC] Batch response has {} entries for {} orders; matching by order hash",
+                    responses.len(), signed.len()
+                );
+            }
+            let matched = match_batch_responses(&hashes, responses);
+
+            // Map posted orders back to their leg slots
+            let posted: Vec<usize> = slots.iter().enumerate()
+                .filter(|(_, slot)| slot.is_none())
+                .map(|(i, _)| i)
+                .collect();
+
+            let fills = futures_util::future::join_all(posted.iter().enumerate().map(|(j, &i)| {
+                let leg = &legs[i];
+                let fee_rate_bps = fee_rates[j];
+                let response = matched[j].clone();
+                let hash = hashes[j].clone();
+                async move {
+                    match (response, hash) {
+                        (Some(r), _) if r.is_accepted() => {
+                            self.query_fill(r.order_id, leg, fee_rate_bps).await
+                        }
+                        (Some(r), _) => Err(ClobError::rejected(&r.error_msg).into()),
+                        // No answer for this order: look it up by hash instead of assuming no fill
+                        (None, Some(hash)) => self.query_fill(hash, leg, fee_rate_bps).await,
+                        (None, None) => Err(anyhow!("order missing from batch response and could not be hashed")),
+                    }
+                }
+            })).await;
+
+            for (&i, fill) in posted.iter().zip(fills) {
+                slots[i] = Some(fill);
+            }
+        }
+
+        Ok(slots.into_iter().map(|slot| slot.unwrap_or_else(|| Err(anyhow!("order not posted")))).collect())
+    }
+
+    /// POST an order (or batch) body once. FAK orders are never retried, not even on 429:
+    /// a late retry would take a book that has moved, so failures go straight to the
+    /// caller's mismatch / auto-close handling.
+    async fn post_signed(&self, body: String, batch: bool) -> Result<reqwest::Response> {
+        let resp = if batch {
+            self.inner.post_orders_async(body, &self.creds).await?
+        } else {
+            self.inner.post_order_async(body, &self.creds).await?
+        };
+
+        if resp.status().is_success() {
+            return Ok(resp);
+        }
+
+        let status = resp.status().as_u16();
+        let text = resp.text().await.unwrap_or_default();
+        Err(ClobError::from_response(status, &text).into())
+    }
+
+    /// Resolve neg_risk and fee rate for a token, then build the signed order
+    pub async fn prepare_order(&self, token_id: &str, price: f64, size: f64, side: &str) -> Result<(SignedOrder, u16)> {
         // Check neg_risk cache first
         let neg_risk = {
             let cache = self.neg_risk_cache.read().unwrap();
@@ -608,48 +1582,94 @@ impl SharedAsyncClient {
             }
         };
 
-        // Build signed order
-        let signed = self.build_signed_order(token_id, price, size, side, neg_risk)?;
-        // Owner must be the API key (not wallet address or funder!)
-        let body = signed.post_body(&self.creds.api_key, PolyOrderType::FAK.as_str());
-
-        // Post order
-        let resp = self.inner.post_order_async(body, &self.creds).await?;
+        // Fee rate must match what the exchange expects or the order is rejected
+        let fee_rate_bps = self.fee_rate_bps(token_id).await?;
 
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Polymarket order failed {}: {}", status, body));
-        }
-
-        let resp_json: serde_json::Value = resp.json().await?;
-        let order_id = resp_json["orderID"].as_str().unwrap_or("unknown").to_string();
-
-        // Query fill status
-        let order_info = self.inner.get_order_async(&order_id, &self.creds).await?;
-        let filled_size: f64 = order_info.size_matched.parse().unwrap_or(0.0);
-        let order_price: f64 = order_info.price.parse().unwrap_or(price);
+        let signed = self.build_signed_order(token_id, price, size, side, neg_risk, fee_rate_bps)?;
+        Ok((signed, fee_rate_bps))
+    }
 
-        tracing::debug!(
-            "[POLY-ASYNC] FAK {} {}: status={}, filled={:.2}/{:.2}, price={:.4}",
-            side, order_id, order_info.status, filled_size, size, order_price
-        );
+    /// Query fill status of a posted order: the order itself, then the trades it
+    /// took as taker. The order may be live, so when neither lookup answers the
+    /// fill is reported as unknown instead of failing the leg.
+    async fn query_fill(&self, order_id: String, leg: &OrderLeg<'_>, fee_rate_bps: u16) -> Result<PolyFillAsync> {
+        let (side, size) = (leg.side, leg.size);
+        let (filled_size, order_price) = match self.lookup_order(&order_id).await {
+            Ok(info) => {
+                let filled_size: f64 = info.size_matched.parse().unwrap_or(0.0);
+                let order_price: f64 = info.price.parse().unwrap_or(leg.price);
+                tracing::debug!(
+                    "[POLY-ASYNC] FAK {} {}: status={}, filled={:.2}/{:.2}, price={:.4}",
+                    side, order_id, info.status, filled_size, size, order_price
+                );
+                (filled_size, order_price)
+            }
+            Err(e) => match self.taker_trades(&order_id, leg.token_id).await {
+                Ok((filled_size, fill_cost)) => {
+                    tracing::info!(
+                        "[POLY-ASYNC] Order {} lookup failed ({}); trades show {:.2}/{:.2} filled",
+                        order_id, e, filled_size, size
+                    );
+                    let price = if filled_size > 0.0 { fill_cost / filled_size } else { leg.price };
+                    (filled_size, price)
+                }
+                Err(trades_err) => {
+                    tracing::warn!("[POLY-ASYNC] Order {} fill unknown: {} / trades: {}", order_id, e, trades_err);
+                    return Ok(PolyFillAsync::unknown(order_id));
+                }
+            },
+        };
 
         Ok(PolyFillAsync {
             order_id,
             filled_size,
             fill_cost: filled_size * order_price,
+            fees: fee_for_fill(order_price, filled_size, fee_rate_bps),
+            fill_unknown: false,
         })
     }
 
+    /// GET an order, retrying per the error class policy (the order may not be indexed yet)
+    async fn lookup_order(&self, order_id: &str) -> Result<PolymarketOrderResponse> {
+        let mut attempt = 0;
+        loop {
+            match self.inner.get_order_async(order_id, &self.creds).await {
+                Ok(info) => return Ok(info),
+                Err(e) => {
+                    let policy = ClobError::kind_of(&e).policy();
+                    if attempt >= policy.retries_for(false) {
+                        return Err(e);
+                    }
+                    attempt += 1;
+                    tracing::warn!("[POLY-ASYNC] Order query {} failed ({}), retry {}/{}", order_id, e, attempt, policy.max_retries);
+                    tokio::time::sleep(policy.backoff * attempt).await;
+                }
+            }
+        }
+    }
+
+    /// Contracts and notional our order took as taker, from the token's trade history
+    async fn taker_trades(&self, order_id: &str, token_id: &str) -> Result<(f64, f64)> {
+        let query = OrderQuery { asset_id: Some(token_id), ..Default::default() };
+        let trades = self.trades(&query).await?;
+        Ok(trades.iter()
+            .filter(|t| t.taker_order_id.as_deref().is_some_and(|id| id.eq_ignore_ascii_case(order_id)))
+            .map(|t| {
+                let size: f64 = t.size.parse().unwrap_or(0.0);
+                (size, size * t.price.parse::<f64>().unwrap_or(0.0))
+            })
+            .fold((0.0, 0.0), |(s, c), (size, cost)| (s + size, c + cost)))
+    }
+
     /// Build a signed order
-    fn build_signed_order(
+    pub fn build_signed_order(
         &self,
         token_id: &str,
         price: f64,
         size: f64,
         side: &str,
         neg_risk: bool,
+        fee_rate_bps: u16,
     ) -> Result<SignedOrder> {
         let price_bps = price_to_bps(price);
         let size_micro = size_to_micro(size);
@@ -666,56 +1686,80 @@ impl SharedAsyncClient {
             return Err(anyhow!("side must be BUY or SELL"));
         };
 
-        let salt = generate_seed();
-        let maker_amount_str = maker_amt.to_string();
-        let taker_amount_str = taker_amt.to_string();
-
-        // Use references for EIP712 signing 
-        let data = OrderData {
-            maker: &self.inner.funder,
-            taker: ZERO_ADDRESS,
-            token_id,
-            maker_amount: &maker_amount_str,
-            taker_amount: &taker_amount_str,
+        // Maker holds the collateral (EOA itself, proxy or Safe); signer is always the EOA
+        let order = OrderStruct {
+            salt: generate_seed(),
+            maker: self.inner.funder.clone(),
+            signer: self.inner.wallet_address_str.clone(),
+            taker: ZERO_ADDRESS.to_string(),
+            token_id: token_id.to_string(),
+            maker_amount: maker_amt.to_string(),
+            taker_amount: taker_amt.to_string(),
+            expiration: "0".to_string(),
+            nonce: "0".to_string(),
+            fee_rate_bps: fee_rate_bps.to_string(),
             side: side_code,
-            fee_rate_bps: "0",
-            nonce: "0",
-            signer: &self.inner.wallet_address_str,
-            expiration: "0",
-            signature_type: 1,
-            salt,
+            signature_type: self.inner.signature_type.as_i32(),
         };
-        let exchange = get_exchange_address(self.chain_id, neg_risk)?;
-        let typed = order_typed_data(self.chain_id, &exchange, &data)?;
-        let digest = typed.encode_eip712()?;
 
-        let sig = self.inner.wallet.sign_hash(H256::from(digest))?;
+        let digest = order_digest(self.chain_id, neg_risk, &order)?;
+        let sig = self.inner.wallet.sign_hash(digest)?;
 
-        // Only allocate strings once for the final OrderStruct (serialization needs owned)
         Ok(SignedOrder {
-            order: OrderStruct {
-                salt,
-                maker: self.inner.funder.clone(),
-                signer: self.inner.wallet_address_str.clone(),
-                taker: ZERO_ADDRESS.to_string(),
-                token_id: token_id.to_string(),
-                maker_amount: maker_amount_str,
-                taker_amount: taker_amount_str,
-                expiration: "0".to_string(),
-                nonce: "0".to_string(),
-                fee_rate_bps: "0".to_string(),
-                side: side_code,
-                signature_type: 1,
-            },
+            order,
             signature: format!("0x{}", sig),
         })
     }
 }
 
+/// Pair batch responses with the orders they answer: by order hash (the CLOB's
+/// orderID) first, then by position when there is exactly one entry per order.
+/// Orders left without a response get None.
+fn match_batch_responses(hashes: &[Option<String>], responses: Vec<PostOrderResponse>) -> Vec<Option<PostOrderResponse>> {
+    let by_position = responses.len() == hashes.len();
+    let mut matched: Vec<Option<PostOrderResponse>> = vec![None; hashes.len()];
+    let mut rest = Vec::new();
+    for (j, r) in responses.into_iter().enumerate() {
+        let by_hash = hashes.iter().position(|h| {
+            !r.order_id.is_empty() && h.as_deref().is_some_and(|h| h.eq_ignore_ascii_case(&r.order_id))
+        });
+        match by_hash {
+            Some(i) if matched[i].is_none() => matched[i] = Some(r),
+            _ => rest.push((j, r)),
+        }
+    }
+    if by_position {
+        for (j, r) in rest {
+            if matched[j].is_none() {
+                matched[j] = Some(r);
+            }
+        }
+    }
+    matched
+}
+
 /// Async fill result
 #[derive(Debug, Clone)]
 pub struct PolyFillAsync {
     pub order_id: String,
     pub filled_size: f64,
     pub fill_cost: f64,
+    /// Taker fees paid on this fill (dollars)
+    pub fees: f64,
+    /// Order was accepted but its fill could not be queried (sizes are zero)
+    pub fill_unknown: bool,
+}
+
+/// Whether a failed order POST may still have reached the exchange (timeout, connection
+/// dropped mid-request). An HTTP error status or a failed connect means it was not taken.
+fn maybe_posted(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<reqwest::Error>()
+        .is_some_and(|e| !e.is_connect() && !e.is_builder() && !e.is_status())
+}
+
+impl PolyFillAsync {
+    /// Accepted order whose fill is not known yet
+    pub fn unknown(order_id: String) -> Self {
+        Self { order_id, filled_size: 0.0, fill_cost: 0.0, fees: 0.0, fill_unknown: true }
+    }
 }
\ No newline at end of file
@@ -6,8 +6,9 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{mpsc, Mutex, RwLock};
 use tracing::{info, warn};
 
 const POSITION_FILE: &str = "positions.json";
@@ -188,6 +189,22 @@ pub struct PositionTracker {
 
     /// Cumulative all-time P&L
     pub all_time_pnl: f64,
+
+    /// File this tracker persists to (positions.json when unset)
+    #[serde(skip)]
+    path: Option<String>,
+
+    /// Orders background saves so an older snapshot never lands after a newer one
+    #[serde(skip)]
+    saves: Arc<SaveSequence>,
+}
+
+#[derive(Debug, Default)]
+struct SaveSequence {
+    /// Last sequence number handed out
+    issued: AtomicU64,
+    /// Sequence number of the snapshot on disk; held while writing
+    written: Mutex<u64>,
 }
 
 /// Data structure for serialization
@@ -213,8 +230,20 @@ impl PositionTracker {
             daily_realized_pnl: 0.0,
             trading_date: today_string(),
             all_time_pnl: 0.0,
+            path: None,
+            saves: Arc::default(),
         }
     }
+
+    /// Persist to `path` instead of positions.json (e.g. a separate paper-trading file)
+    pub fn with_path(mut self, path: &str) -> Self {
+        self.path = Some(path.to_string());
+        self
+    }
+
+    fn file(&self) -> &str {
+        self.path.as_deref().unwrap_or(POSITION_FILE)
+    }
     
     /// Load from file or create new
     pub fn load() -> Self {
@@ -235,24 +264,25 @@ impl PositionTracker {
                         }
                         info!("[POSITIONS] Loaded {} positions from {:?}", 
                               tracker.positions.len(), path.as_ref());
+                        tracker.path = Some(path.as_ref().to_string_lossy().into_owned());
                         tracker
                     }
                     Err(e) => {
                         warn!("[POSITIONS] Failed to parse positions file: {}", e);
-                        Self::new()
+                        Self::new().with_path(&path.as_ref().to_string_lossy())
                     }
                 }
             }
             Err(_) => {
                 info!("[POSITIONS] No positions file found, starting fresh");
-                Self::new()
+                Self::new().with_path(&path.as_ref().to_string_lossy())
             }
         }
     }
     
     /// Save to file
     pub fn save(&self) -> Result<()> {
-        self.save_to(POSITION_FILE)
+        self.save_to(self.file())
     }
     
     pub fn save_to<P: AsRef<Path>>(&self, path: P) -> Result<()> {
@@ -261,26 +291,40 @@ impl PositionTracker {
         Ok(())
     }
     
-    /// Save positions
-    pub fn save_async(&self) {
-        // Clone data for serialization
-        let data = SaveData {
+    fn save_data(&self) -> SaveData {
+        SaveData {
             positions: self.positions.clone(),
             daily_realized_pnl: self.daily_realized_pnl,
             trading_date: self.trading_date.clone(),
             all_time_pnl: self.all_time_pnl,
-        };
+        }
+    }
+
+    /// Save positions
+    pub fn save_async(&self) {
+        // Clone data for serialization
+        let data = self.save_data();
+        let file = self.file().to_string();
         // Try to spawn on runtime; if no runtime, save synchronously
         if tokio::runtime::Handle::try_current().is_ok() {
+            let seq = self.saves.issued.fetch_add(1, Ordering::SeqCst) + 1;
+            let saves = self.saves.clone();
             tokio::spawn(async move {
-                if let Ok(json) = serde_json::to_string_pretty(&data) {
-                    let _ = tokio::fs::write(POSITION_FILE, json).await;
+                if let Err(e) = write_sequenced(&saves, seq, &file, &data).await {
+                    warn!("[POSITIONS] Failed to save {}: {}", file, e);
                 }
             });
         } else if let Ok(json) = serde_json::to_string_pretty(&data) {
-            let _ = std::fs::write(POSITION_FILE, json);
+            let _ = std::fs::write(file, json);
         }
     }
+
+    /// Save now and wait until the file is on disk; background saves issued
+    /// earlier can no longer overwrite it
+    pub async fn flush(&self) -> Result<()> {
+        let seq = self.saves.issued.fetch_add(1, Ordering::SeqCst) + 1;
+        write_sequenced(&self.saves, seq, self.file(), &self.save_data()).await
+    }
     
     /// Record a fill
     pub fn record_fill(&mut self, fill: &FillRecord) {
@@ -458,6 +502,21 @@ pub fn create_position_channel() -> (PositionChannel, mpsc::UnboundedReceiver<Fi
     (PositionChannel::new(tx), rx)
 }
 
+/// Write `data` as snapshot `seq` unless a newer one is already on disk.
+/// Write-then-rename, so an interrupted save leaves the previous file intact.
+async fn write_sequenced(saves: &SaveSequence, seq: u64, file: &str, data: &SaveData) -> Result<()> {
+    let mut written = saves.written.lock().await;
+    if *written > seq {
+        return Ok(());
+    }
+    let json = serde_json::to_string_pretty(data)?;
+    let tmp = format!("{}.tmp", file);
+    tokio::fs::write(&tmp, json).await?;
+    tokio::fs::rename(&tmp, file).await?;
+    *written = seq;
+    Ok(())
+}
+
 pub async fn position_writer_loop(
     mut rx: mpsc::UnboundedReceiver<FillRecord>,
     tracker: Arc<RwLock<PositionTracker>>,
@@ -469,7 +528,18 @@ pub async fn position_writer_loop(
         tokio::select! {
             biased;
 
-            Some(fill) = rx.recv() => {
+            fill = rx.recv() => {
+                let Some(fill) = fill else {
+                    // Every sender is gone (shutdown): record the last batch and wait for the write
+                    let mut guard = tracker.write().await;
+                    for fill in batch.drain(..) {
+                        guard.record_fill_internal(&fill);
+                    }
+                    if let Err(e) = guard.flush().await {
+                        warn!("[POSITIONS] Final save failed: {}", e);
+                    }
+                    return;
+                };
                 batch.push(fill);
                 if batch.len() >= 16 {
                     let mut guard = tracker.write().await;
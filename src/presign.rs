@@ -0,0 +1,263 @@
+// src/presign.rs
+// Pre-signed order pool - keeps signing off the hot path
+
+use rustc_hash::FxHashMap;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use crate::polymarket_clob::{SharedAsyncClient, SignedOrder};
+use crate::types::{GlobalState, PriceCents, cents_to_price};
+
+/// Polymarket rejects orders below $1 notional
+const MIN_NOTIONAL_CENTS: u32 = 100;
+
+/// Pre-signing configuration from environment
+#[derive(Debug, Clone)]
+pub struct PresignConfig {
+    /// Whether the pool is used (off by default)
+    pub enabled: bool,
+    /// Contract sizes to keep signed, ascending. When the arb size has no exact
+    /// match the largest smaller size is used, trading size for latency.
+    pub sizes: Vec<u32>,
+    /// Price levels kept on each side of the current ask (cents)
+    pub price_levels: u16,
+    /// How often the pool follows the book (milliseconds)
+    pub refresh_ms: u64,
+}
+
+impl PresignConfig {
+    pub fn from_env() -> Self {
+        let mut sizes: Vec<u32> = std::env::var("PRESIGN_SIZES")
+            .ok()
+            .map(|v| v.split(',').filter_map(|s| s.trim().parse().ok()).filter(|&s| s > 0).collect())
+            .unwrap_or_else(|| vec![10, 25, 50, 100]);
+        sizes.sort_unstable();
+        sizes.dedup();
+
+        Self {
+            enabled: std::env::var("PRESIGN_ENABLED")
+                .map(|v| v == "1" || v == "true")
+                .unwrap_or(false),
+
+            sizes,
+
+            price_levels: std::env::var("PRESIGN_PRICE_LEVELS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1),
+
+            refresh_ms: std::env::var("PRESIGN_REFRESH_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(500),
+        }
+    }
+}
+
+/// A signed BUY order waiting to be posted
+#[derive(Debug, Clone)]
+pub struct PresignedOrder {
+    pub order: SignedOrder,
+    pub fee_rate_bps: u16,
+    pub price: PriceCents,
+    pub size: u32,
+}
+
+/// Orders for one token keyed by (price, size). Each order carries the fee rate
+/// it was signed with; an order whose rate no longer matches is never posted.
+type TokenPool = BTreeMap<(PriceCents, u32), PresignedOrder>;
+
+/// Counters since start
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PresignStats {
+    /// Orders currently held
+    pub held: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl std::fmt::Display for PresignStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Pre-signed: {} held, {} hits, {} misses", self.held, self.hits, self.misses)
+    }
+}
+
+/// Pool of pre-signed orders per tracked token. Taking an order removes it,
+/// so each signature is posted at most once.
+pub struct PresignPool {
+    config: PresignConfig,
+    pool: Mutex<FxHashMap<Arc<str>, TokenPool>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl PresignPool {
+    pub fn new(config: PresignConfig) -> Self {
+        info!("[PRESIGN] Pre-signed order pool:");
+        info!("[PRESIGN]   Enabled: {}", config.enabled);
+        info!("[PRESIGN]   Sizes: {:?} | ±{}¢ around ask | refresh {}ms",
+              config.sizes, config.price_levels, config.refresh_ms);
+
+        Self {
+            config,
+            pool: Mutex::new(FxHashMap::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Prices to keep signed around the current ask
+    pub fn target_prices(&self, ask: PriceCents) -> Vec<PriceCents> {
+        let lo = ask.saturating_sub(self.config.price_levels).max(1);
+        let hi = (ask + self.config.price_levels).min(99);
+        (lo..=hi).collect()
+    }
+
+    /// Whether a (price, size) order is worth signing
+    fn wanted(price: PriceCents, size: u32) -> bool {
+        (1..=99).contains(&price) && price as u32 * size >= MIN_NOTIONAL_CENTS
+    }
+
+    /// Add a signed order (replaces any order at the same price/size)
+    pub fn insert(&self, token: &Arc<str>, order: PresignedOrder) {
+        let mut pool = self.pool.lock().unwrap();
+        pool.entry(token.clone()).or_default().insert((order.price, order.size), order);
+    }
+
+    pub fn contains(&self, token: &str, price: PriceCents, size: u32) -> bool {
+        let pool = self.pool.lock().unwrap();
+        pool.get(token).is_some_and(|t| t.contains_key(&(price, size)))
+    }
+
+    /// Drop orders for `token` whose price is no longer near the book or that
+    /// were signed with a fee rate other than `fee_rate_bps`
+    pub fn retain_current(&self, token: &str, prices: &[PriceCents], fee_rate_bps: u16) -> usize {
+        let mut pool = self.pool.lock().unwrap();
+        let Some(orders) = pool.get_mut(token) else { return 0 };
+        let before = orders.len();
+        orders.retain(|(price, _), order| prices.contains(price) && order.fee_rate_bps == fee_rate_bps);
+        before - orders.len()
+    }
+
+    /// Take a matching YES/NO pair at the given prices and fee rates (token,
+    /// price, fee_rate_bps): the largest pre-signed size available for both legs
+    /// that does not exceed `max_size`.
+    pub fn take_pair(
+        &self,
+        yes: (&str, PriceCents, u16),
+        no: (&str, PriceCents, u16),
+        max_size: u32,
+    ) -> Option<(PresignedOrder, PresignedOrder)> {
+        let mut pool = self.pool.lock().unwrap();
+
+        let size = {
+            let usable = |token: &str, price: PriceCents, fee_rate_bps: u16, size: u32| {
+                pool.get(token)
+                    .and_then(|o| o.get(&(price, size)))
+                    .is_some_and(|o| o.fee_rate_bps == fee_rate_bps)
+            };
+            self.config.sizes.iter().rev()
+                .copied()
+                .filter(|&s| s <= max_size)
+                .find(|&s| usable(yes.0, yes.1, yes.2, s) && usable(no.0, no.1, no.2, s))
+        };
+
+        let Some(size) = size else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+
+        let yes_order = pool.get_mut(yes.0)?.remove(&(yes.1, size))?;
+        let no_order = pool.get_mut(no.0)?.remove(&(no.1, size))?;
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some((yes_order, no_order))
+    }
+
+    /// Orders held and take_pair hits/misses
+    pub fn stats(&self) -> PresignStats {
+        PresignStats {
+            held: self.pool.lock().unwrap().values().map(|t| t.len()).sum(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Follow the book and the fee rates: drop orders far from the ask or signed
+    /// at an outdated fee rate, sign missing ones. Returns the number of orders signed.
+    pub async fn refresh(&self, state: &GlobalState, client: &SharedAsyncClient) -> usize {
+        let mut signed = 0;
+
+        for market in state.markets.iter().take(state.market_count()) {
+            let Some(pair) = &market.pair else { continue };
+            let (yes_ask, no_ask, _, _) = market.poly.load();
+
+            // Rates expire from the client cache, so a change on the exchange shows up here
+            let (yes_bps, no_bps) = match tokio::join!(
+                client.fee_rate_bps(&pair.poly_yes_token),
+                client.fee_rate_bps(&pair.poly_no_token),
+            ) {
+                (Ok(yes_bps), Ok(no_bps)) => (yes_bps, no_bps),
+                (Err(e), _) | (_, Err(e)) => {
+                    debug!("[PRESIGN] Fee rate lookup failed for {}: {}", pair.description, e);
+                    continue;
+                }
+            };
+            if market.fee_rates() != (yes_bps, no_bps) {
+                warn!("[PRESIGN] Fee rate changed for {}: {:?} -> {:?} bps",
+                      pair.description, market.fee_rates(), (yes_bps, no_bps));
+                market.set_fee_rates(yes_bps, no_bps);
+            }
+
+            for (token, ask, fee_rate_bps) in [(&pair.poly_yes_token, yes_ask, yes_bps), (&pair.poly_no_token, no_ask, no_bps)] {
+                if ask == 0 {
+                    self.retain_current(token, &[], fee_rate_bps);
+                    continue;
+                }
+
+                let prices = self.target_prices(ask);
+                self.retain_current(token, &prices, fee_rate_bps);
+
+                for &price in &prices {
+                    for &size in &self.config.sizes {
+                        if !Self::wanted(price, size) || self.contains(token, price, size) {
+                            continue;
+                        }
+                        match client.prepare_order(token, cents_to_price(price), size as f64, "BUY").await {
+                            Ok((order, fee_rate_bps)) => {
+                                self.insert(token, PresignedOrder { order, fee_rate_bps, price, size });
+                                signed += 1;
+                            }
+                            Err(e) => {
+                                debug!("[PRESIGN] Sign failed for {} @{}¢ x{}: {}", token, price, size, e);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        signed
+    }
+}
+
+/// Keep the pool in step with the book
+pub async fn presign_loop(pool: Arc<PresignPool>, state: Arc<GlobalState>, client: Arc<SharedAsyncClient>) {
+    info!("[PRESIGN] Pre-signing loop started");
+    let mut interval = tokio::time::interval(Duration::from_millis(pool.config.refresh_ms.max(10)));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        interval.tick().await;
+        let signed = pool.refresh(&state, &client).await;
+        if signed > 0 {
+            debug!("[PRESIGN] Signed {} orders | {}", signed, pool.stats());
+        }
+    }
+}
@@ -0,0 +1,339 @@
+// src/rate_limit.rs
+// Client-side rate limiting for CLOB and Gamma requests
+
+use governor::{DefaultDirectRateLimiter, Quota};
+use nonzero_ext::nonzero;
+use serde::Serialize;
+use std::num::NonZeroU32;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// How long a low-priority request sleeps while order posts are waiting
+const LOW_PRIORITY_YIELD: Duration = Duration::from_millis(2);
+
+/// Rate-limited endpoint class, each with its own token bucket
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endpoint {
+    /// POST /order and POST /orders (highest priority)
+    OrderPost,
+    /// DELETE /order, /orders, /cancel-all, /cancel-market-orders
+    Cancel,
+    /// GET /data/order, /data/orders, /data/trades
+    OrderQuery,
+    /// GET /neg-risk lookups
+    NegRisk,
+    /// GET /fee-rate lookups
+    FeeRate,
+    /// Gamma API market discovery (separate host)
+    Gamma,
+}
+
+impl Endpoint {
+    pub const ALL: [Endpoint; 6] = [
+        Endpoint::OrderPost, Endpoint::Cancel, Endpoint::OrderQuery,
+        Endpoint::NegRisk, Endpoint::FeeRate, Endpoint::Gamma,
+    ];
+
+    #[inline(always)]
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Order posts go first; everything else (cancel sweeps included) yields to them
+    #[inline(always)]
+    fn is_high_priority(self) -> bool {
+        matches!(self, Endpoint::OrderPost)
+    }
+
+    /// Whether this endpoint counts against the shared CLOB budget
+    #[inline(always)]
+    fn is_clob(self) -> bool {
+        !matches!(self, Endpoint::Gamma)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Endpoint::OrderPost => "order_post",
+            Endpoint::Cancel => "cancel",
+            Endpoint::OrderQuery => "order_query",
+            Endpoint::NegRisk => "neg_risk",
+            Endpoint::FeeRate => "fee_rate",
+            Endpoint::Gamma => "gamma",
+        }
+    }
+}
+
+/// Requests per second and burst size for one bucket (None = unlimited)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BucketConfig {
+    pub per_second: Option<NonZeroU32>,
+    pub burst: Option<NonZeroU32>,
+}
+
+impl BucketConfig {
+    pub const UNLIMITED: BucketConfig = BucketConfig { per_second: None, burst: None };
+
+    pub fn new(per_second: NonZeroU32, burst: NonZeroU32) -> Self {
+        Self { per_second: Some(per_second), burst: Some(burst) }
+    }
+
+    fn from_env(prefix: &str, default: BucketConfig) -> Self {
+        let per_second = match std::env::var(format!("{}_PER_SEC", prefix)).ok().and_then(|v| v.parse::<u32>().ok()) {
+            Some(v) => NonZeroU32::new(v), // 0 disables the bucket
+            None => default.per_second,
+        };
+        let burst = std::env::var(format!("{}_BURST", prefix))
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .and_then(NonZeroU32::new)
+            .or(default.burst);
+        Self { per_second, burst }
+    }
+
+    fn build(&self) -> Option<DefaultDirectRateLimiter> {
+        let per_second = self.per_second?;
+        let burst = self.burst.unwrap_or(per_second);
+        Some(governor::RateLimiter::direct(Quota::per_second(per_second).allow_burst(burst)))
+    }
+}
+
+/// Rate limit configuration from environment
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    pub order_post: BucketConfig,
+    pub cancel: BucketConfig,
+    pub order_query: BucketConfig,
+    pub neg_risk: BucketConfig,
+    pub fee_rate: BucketConfig,
+    pub gamma: BucketConfig,
+    /// Shared budget across all CLOB endpoints
+    pub clob_global: BucketConfig,
+    /// Whether rate limiting is enabled
+    pub enabled: bool,
+}
+
+impl RateLimitConfig {
+    pub fn from_env() -> Self {
+        Self {
+            order_post: BucketConfig::from_env("RL_ORDER_POST", BucketConfig::new(nonzero!(20u32), nonzero!(40u32))),
+            cancel: BucketConfig::from_env("RL_CANCEL", BucketConfig::new(nonzero!(10u32), nonzero!(20u32))),
+            order_query: BucketConfig::from_env("RL_ORDER_QUERY", BucketConfig::new(nonzero!(30u32), nonzero!(60u32))),
+            neg_risk: BucketConfig::from_env("RL_NEG_RISK", BucketConfig::new(nonzero!(10u32), nonzero!(20u32))),
+            fee_rate: BucketConfig::from_env("RL_FEE_RATE", BucketConfig::new(nonzero!(10u32), nonzero!(20u32))),
+            gamma: BucketConfig::from_env("RL_GAMMA", BucketConfig::new(nonzero!(10u32), nonzero!(20u32))),
+            clob_global: BucketConfig::from_env("RL_CLOB_GLOBAL", BucketConfig::new(nonzero!(50u32), nonzero!(100u32))),
+            enabled: std::env::var("RL_ENABLED")
+                .map(|v| v == "1" || v == "true")
+                .unwrap_or(true),
+        }
+    }
+
+    /// No limits at all (tests, emulator)
+    pub fn unlimited() -> Self {
+        Self {
+            order_post: BucketConfig::UNLIMITED,
+            cancel: BucketConfig::UNLIMITED,
+            order_query: BucketConfig::UNLIMITED,
+            neg_risk: BucketConfig::UNLIMITED,
+            fee_rate: BucketConfig::UNLIMITED,
+            gamma: BucketConfig::UNLIMITED,
+            clob_global: BucketConfig::UNLIMITED,
+            enabled: false,
+        }
+    }
+
+    fn bucket(&self, endpoint: Endpoint) -> BucketConfig {
+        match endpoint {
+            Endpoint::OrderPost => self.order_post,
+            Endpoint::Cancel => self.cancel,
+            Endpoint::OrderQuery => self.order_query,
+            Endpoint::NegRisk => self.neg_risk,
+            Endpoint::FeeRate => self.fee_rate,
+            Endpoint::Gamma => self.gamma,
+        }
+    }
+}
+
+/// Throttling counters for one endpoint
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct EndpointMetrics {
+    /// Requests that passed through the limiter
+    pub requests: u64,
+    /// Requests that had to wait for a token
+    pub throttled: u64,
+    /// Total time spent waiting (microseconds)
+    pub wait_us: u64,
+}
+
+/// Snapshot of limiter metrics
+#[derive(Debug, Clone, Default)]
+pub struct RateLimitMetrics {
+    pub endpoints: Vec<(Endpoint, EndpointMetrics)>,
+}
+
+impl RateLimitMetrics {
+    #[allow(dead_code)]
+    pub fn get(&self, endpoint: Endpoint) -> EndpointMetrics {
+        self.endpoints.iter()
+            .find(|(e, _)| *e == endpoint)
+            .map(|(_, m)| *m)
+            .unwrap_or_default()
+    }
+
+    pub fn total_throttled(&self) -> u64 {
+        self.endpoints.iter().map(|(_, m)| m.throttled).sum()
+    }
+
+    /// Counters keyed by endpoint name, for status output
+    pub fn to_json(&self) -> serde_json::Value {
+        self.endpoints.iter()
+            .map(|(endpoint, m)| (endpoint.as_str().to_string(), serde_json::json!(m)))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+}
+
+impl std::fmt::Display for RateLimitMetrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Rate limits:")?;
+        for (endpoint, m) in &self.endpoints {
+            write!(f, " {}={}/{} throttled ({}ms waited)",
+                   endpoint.as_str(), m.throttled, m.requests, m.wait_us / 1000)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    requests: AtomicU64,
+    throttled: AtomicU64,
+    wait_us: AtomicU64,
+}
+
+/// Holds one slot in a waiting counter until dropped
+struct WaitingGuard<'a>(&'a AtomicUsize);
+
+impl<'a> WaitingGuard<'a> {
+    fn new(counter: &'a AtomicUsize) -> Self {
+        counter.fetch_add(1, Ordering::AcqRel);
+        Self(counter)
+    }
+}
+
+impl Drop for WaitingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Token-bucket rate limiter with per-endpoint buckets and order-post priority
+pub struct RateLimiter {
+    buckets: [Option<DefaultDirectRateLimiter>; Endpoint::ALL.len()],
+    clob_global: Option<DefaultDirectRateLimiter>,
+    counters: [Counters; Endpoint::ALL.len()],
+    /// Order posts currently waiting for a token
+    high_waiting: AtomicUsize,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        if config.enabled {
+            info!("[RATE] Rate limiter initialized:");
+            for endpoint in Endpoint::ALL {
+                let b = config.bucket(endpoint);
+                match b.per_second {
+                    Some(rate) => info!("[RATE]   {}: {}/s (burst {})",
+                                        endpoint.as_str(), rate, b.burst.unwrap_or(rate)),
+                    None => info!("[RATE]   {}: unlimited", endpoint.as_str()),
+                }
+            }
+        }
+
+        let build = |endpoint: Endpoint| {
+            if config.enabled { config.bucket(endpoint).build() } else { None }
+        };
+
+        Self {
+            buckets: Endpoint::ALL.map(build),
+            clob_global: if config.enabled { config.clob_global.build() } else { None },
+            counters: Default::default(),
+            high_waiting: AtomicUsize::new(0),
+        }
+    }
+
+    /// Limiter that never waits
+    pub fn unlimited() -> Self {
+        Self::new(RateLimitConfig::unlimited())
+    }
+
+    pub fn shared(config: RateLimitConfig) -> Arc<Self> {
+        Arc::new(Self::new(config))
+    }
+
+    /// Wait until a request to `endpoint` is allowed
+    pub async fn acquire(&self, endpoint: Endpoint) {
+        let counters = &self.counters[endpoint.index()];
+        counters.requests.fetch_add(1, Ordering::Relaxed);
+
+        let start = Instant::now();
+        let mut throttled = false;
+
+        if let Some(bucket) = &self.buckets[endpoint.index()] {
+            if bucket.check().is_err() {
+                throttled = true;
+                bucket.until_ready().await;
+            }
+        }
+
+        if endpoint.is_clob() {
+            if let Some(global) = &self.clob_global {
+                if endpoint.is_high_priority() {
+                    if global.check().is_err() {
+                        throttled = true;
+                        // Held only while contending for the shared budget; released on
+                        // return or when the caller drops this future mid-wait
+                        let _queued = WaitingGuard::new(&self.high_waiting);
+                        global.until_ready().await;
+                    }
+                } else {
+                    // Lower-value queries only take a shared token when no order post is queued
+                    loop {
+                        if self.high_waiting.load(Ordering::Acquire) == 0 && global.check().is_ok() {
+                            break;
+                        }
+                        throttled = true;
+                        tokio::time::sleep(LOW_PRIORITY_YIELD).await;
+                    }
+                }
+            }
+        }
+
+        if throttled {
+            counters.throttled.fetch_add(1, Ordering::Relaxed);
+            counters.wait_us.fetch_add(start.elapsed().as_micros() as u64, Ordering::Relaxed);
+        }
+    }
+
+    /// Snapshot of throttling metrics
+    pub fn metrics(&self) -> RateLimitMetrics {
+        RateLimitMetrics {
+            endpoints: Endpoint::ALL.iter().map(|&endpoint| {
+                let c = &self.counters[endpoint.index()];
+                (endpoint, EndpointMetrics {
+                    requests: c.requests.load(Ordering::Relaxed),
+                    throttled: c.throttled.load(Ordering::Relaxed),
+                    wait_us: c.wait_us.load(Ordering::Relaxed),
+                })
+            }).collect(),
+        }
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::unlimited()
+    }
+}
@@ -0,0 +1,542 @@
+// src/recorder.rs
+// Market data recorder - raw WS frames to rotating gzip segments with a block index
+//
+// Segment layout: `ws-<first_ns>.gz` is a sequence of gzip members (one per block),
+// `ws-<first_ns>.idx` has one JSON line per block with its byte range and time span.
+// Decompressed, a block is a run of records: recv_ns (u64 LE), len (u32 LE), payload.
+
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, NaiveDate, Utc};
+use flate2::Compression;
+use flate2::read::{GzDecoder, MultiGzDecoder};
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, SyncSender, sync_channel};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{error, info};
+
+use crate::types::{GlobalState, MarketPair};
+
+/// Market list written next to the segments so a recording can be replayed on its own
+pub const MARKETS_FILE: &str = "markets.json";
+
+/// Record header: recv_ns (8) + payload length (4)
+#[allow(dead_code)]
+const RECORD_HEADER_LEN: usize = 12;
+
+/// Recorder configuration from environment
+#[derive(Debug, Clone)]
+pub struct RecorderConfig {
+    /// Record inbound WS frames
+    pub enabled: bool,
+    /// Directory for segment and index files
+    pub dir: String,
+    /// Start a new segment after this long (seconds)
+    pub rotate_secs: u64,
+    /// Start a new segment once the current one reaches this many compressed bytes
+    pub rotate_bytes: u64,
+    /// Frames per compressed block
+    pub block_frames: usize,
+    /// Write a partial block after this long without one (milliseconds)
+    pub flush_ms: u64,
+    /// Frames buffered between the WS reader and the writer thread.
+    /// When full, frames are dropped (and counted) rather than blocking the reader.
+    pub queue_size: usize,
+}
+
+impl RecorderConfig {
+    pub fn from_env() -> Self {
+        Self {
+            enabled: std::env::var("RECORD_WS")
+                .map(|v| v == "1" || v == "true")
+                .unwrap_or(false),
+
+            dir: std::env::var("RECORD_DIR").unwrap_or_else(|_| "recordings".to_string()),
+
+            rotate_secs: std::env::var("RECORD_ROTATE_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3600),
+
+            rotate_bytes: std::env::var("RECORD_ROTATE_MB")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(256)
+                * 1024 * 1024,
+
+            block_frames: std::env::var("RECORD_BLOCK_FRAMES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|v| *v > 0)
+                .unwrap_or(1024),
+
+            flush_ms: std::env::var("RECORD_FLUSH_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1000),
+
+            queue_size: std::env::var("RECORD_QUEUE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|v| *v > 0)
+                .unwrap_or(65_536),
+        }
+    }
+}
+
+/// One inbound WS frame as received
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedFrame {
+    /// Receive time, nanoseconds since the Unix epoch
+    pub recv_ns: u64,
+    pub payload: Vec<u8>,
+}
+
+impl RecordedFrame {
+    /// Payload as text (market WS frames are JSON)
+    #[allow(dead_code)]
+    pub fn text(&self) -> Option<&str> {
+        std::str::from_utf8(&self.payload).ok()
+    }
+}
+
+/// Index entry for one compressed block of a segment
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockIndex {
+    /// Byte offset of the block's gzip member in the segment
+    pub offset: u64,
+    /// Compressed length
+    pub len: u64,
+    pub first_ns: u64,
+    pub last_ns: u64,
+    pub frames: u32,
+}
+
+#[derive(Default)]
+struct Counters {
+    frames: AtomicU64,
+    dropped: AtomicU64,
+    raw_bytes: AtomicU64,
+    written_bytes: AtomicU64,
+    blocks: AtomicU64,
+    segments: AtomicU64,
+}
+
+/// Recorder counters for the heartbeat
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RecorderStats {
+    /// Frames written to disk
+    pub frames: u64,
+    /// Frames dropped because the writer fell behind (or failed to write)
+    pub dropped: u64,
+    pub raw_bytes: u64,
+    pub written_bytes: u64,
+    pub blocks: u64,
+    pub segments: u64,
+}
+
+impl std::fmt::Display for RecorderStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Recorder: {} frames ({} dropped) | {} blocks in {} segments | {:.1}MB -> {:.1}MB",
+            self.frames, self.dropped, self.blocks, self.segments,
+            self.raw_bytes as f64 / 1_048_576.0, self.written_bytes as f64 / 1_048_576.0
+        )
+    }
+}
+
+enum Command {
+    Frame(RecordedFrame),
+    #[allow(dead_code)]
+    Flush(SyncSender<()>),
+}
+
+/// Non-blocking WS frame recorder. Frames are handed to a writer thread;
+/// compression and file I/O never run on the reader task.
+pub struct WsRecorder {
+    tx: SyncSender<Command>,
+    counters: Arc<Counters>,
+}
+
+impl WsRecorder {
+    /// Create the recording directory and start the writer thread
+    pub fn start(config: &RecorderConfig) -> Result<Self> {
+        std::fs::create_dir_all(&config.dir)
+            .with_context(|| format!("creating recording dir {}", config.dir))?;
+
+        let (tx, rx) = sync_channel(config.queue_size);
+        let counters = Arc::new(Counters::default());
+        let writer = SegmentWriter::new(config.clone(), counters.clone());
+        std::thread::Builder::new()
+            .name("ws-recorder".into())
+            .spawn(move || writer.run(rx))
+            .context("spawning recorder thread")?;
+
+        info!("[REC] Recording WS frames to {} (rotate {}s / {}MB)",
+              config.dir, config.rotate_secs, config.rotate_bytes / (1024 * 1024));
+        Ok(Self { tx, counters })
+    }
+
+    /// Record a frame received now
+    #[inline]
+    pub fn record(&self, payload: &[u8]) {
+        self.record_at(now_ns(), payload);
+    }
+
+    /// Record a frame with an explicit receive time (nanoseconds since the Unix epoch)
+    pub fn record_at(&self, recv_ns: u64, payload: &[u8]) {
+        let frame = RecordedFrame { recv_ns, payload: payload.to_vec() };
+        // Full queue: drop the frame rather than stall the WS reader
+        if self.tx.try_send(Command::Frame(frame)).is_err() {
+            self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Write out everything queued so far and wait for it to reach disk.
+    /// Blocks the calling thread - for shutdown and tests, not the reader.
+    pub fn flush(&self) -> Result<()> {
+        let (ack_tx, ack_rx) = sync_channel(1);
+        self.tx.send(Command::Flush(ack_tx)).map_err(|_| anyhow!("recorder thread stopped"))?;
+        ack_rx.recv().map_err(|_| anyhow!("recorder thread stopped"))
+    }
+
+    pub fn stats(&self) -> RecorderStats {
+        let c = &self.counters;
+        RecorderStats {
+            frames: c.frames.load(Ordering::Relaxed),
+            dropped: c.dropped.load(Ordering::Relaxed),
+            raw_bytes: c.raw_bytes.load(Ordering::Relaxed),
+            written_bytes: c.written_bytes.load(Ordering::Relaxed),
+            blocks: c.blocks.load(Ordering::Relaxed),
+            segments: c.segments.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Open segment: data file, index file and how much has been written
+struct Segment {
+    data: File,
+    index: File,
+    len: u64,
+    opened: Instant,
+}
+
+/// Writer thread state
+struct SegmentWriter {
+    config: RecorderConfig,
+    counters: Arc<Counters>,
+    segment: Option<Segment>,
+    block: Vec<u8>,
+    block_frames: u32,
+    first_ns: u64,
+    last_ns: u64,
+}
+
+impl SegmentWriter {
+    fn new(config: RecorderConfig, counters: Arc<Counters>) -> Self {
+        Self {
+            config,
+            counters,
+            segment: None,
+            block: Vec::with_capacity(64 * 1024),
+            block_frames: 0,
+            first_ns: 0,
+            last_ns: 0,
+        }
+    }
+
+    fn run(mut self, rx: Receiver<Command>) {
+        let flush_interval = Duration::from_millis(self.config.flush_ms.max(1));
+        loop {
+            match rx.recv_timeout(flush_interval) {
+                Ok(Command::Frame(frame)) => {
+                    self.push(frame);
+                    if self.block_frames as usize >= self.config.block_frames {
+                        self.write_block();
+                    }
+                }
+                Ok(Command::Flush(ack)) => {
+                    self.write_block();
+                    let _ = ack.send(());
+                }
+                Err(RecvTimeoutError::Timeout) => self.write_block(),
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.write_block();
+                    return;
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, frame: RecordedFrame) {
+        if self.block_frames == 0 {
+            self.first_ns = frame.recv_ns;
+        }
+        self.last_ns = frame.recv_ns;
+        self.block_frames += 1;
+        self.block.extend_from_slice(&frame.recv_ns.to_le_bytes());
+        self.block.extend_from_slice(&(frame.payload.len() as u32).to_le_bytes());
+        self.block.extend_from_slice(&frame.payload);
+    }
+
+    /// Compress the pending block, append it to the segment and index it
+    fn write_block(&mut self) {
+        if self.block_frames == 0 {
+            return;
+        }
+        let frames = self.block_frames;
+        let raw_len = self.block.len() as u64;
+
+        if let Err(e) = self.try_write_block() {
+            error!("[REC] Failed to write {} frames: {}", frames, e);
+            self.counters.dropped.fetch_add(frames as u64, Ordering::Relaxed);
+            // Start a fresh segment on the next block
+            self.segment = None;
+        } else {
+            self.counters.frames.fetch_add(frames as u64, Ordering::Relaxed);
+            self.counters.raw_bytes.fetch_add(raw_len, Ordering::Relaxed);
+            self.counters.blocks.fetch_add(1, Ordering::Relaxed);
+        }
+
+        self.block.clear();
+        self.block_frames = 0;
+    }
+
+    fn try_write_block(&mut self) -> Result<()> {
+        let rotate = match &self.segment {
+            Some(s) => s.len >= self.config.rotate_bytes
+                || s.opened.elapsed() >= Duration::from_secs(self.config.rotate_secs),
+            None => true,
+        };
+        if rotate {
+            self.segment = Some(self.open_segment()?);
+            self.counters.segments.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let mut encoder = GzEncoder::new(Vec::with_capacity(self.block.len() / 4), Compression::fast());
+        encoder.write_all(&self.block)?;
+        let compressed = encoder.finish()?;
+
+        let segment = self.segment.as_mut().expect("segment opened above");
+        segment.data.write_all(&compressed)?;
+
+        // Index after data: an index entry always points at a complete block
+        let entry = BlockIndex {
+            offset: segment.len,
+            len: compressed.len() as u64,
+            first_ns: self.first_ns,
+            last_ns: self.last_ns,
+            frames: self.block_frames,
+        };
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        segment.index.write_all(&line)?;
+
+        segment.len += compressed.len() as u64;
+        self.counters.written_bytes.fetch_add(compressed.len() as u64, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn open_segment(&self) -> Result<Segment> {
+        let data_path = Path::new(&self.config.dir).join(format!("ws-{}.gz", self.first_ns));
+        let index_path = data_path.with_extension("idx");
+        let open = |path: &Path| {
+            OpenOptions::new().create(true).append(true).open(path)
+                .with_context(|| format!("opening {}", path.display()))
+        };
+        let data = open(&data_path)?;
+        let index = open(&index_path)?;
+        let len = data.metadata()?.len();
+        info!("[REC] New segment {}", data_path.display());
+        Ok(Segment { data, index, len, opened: Instant::now() })
+    }
+}
+
+// === Market list ===
+
+/// Tracked market with the fee rates detection used
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketSnapshot {
+    pub pair: MarketPair,
+    #[serde(default)]
+    pub yes_fee_bps: u16,
+    #[serde(default)]
+    pub no_fee_bps: u16,
+}
+
+/// Tracked markets in market_id order
+pub fn snapshot_markets(state: &GlobalState) -> Vec<MarketSnapshot> {
+    state.markets.iter()
+        .take(state.market_count())
+        .filter_map(|m| m.pair.as_ref().map(|pair| {
+            let (yes_fee_bps, no_fee_bps) = m.fee_rates();
+            MarketSnapshot { pair: (**pair).clone(), yes_fee_bps, no_fee_bps }
+        }))
+        .collect()
+}
+
+/// Write the tracked markets to `<dir>/markets.json`
+pub fn save_markets(dir: &str, state: &GlobalState) -> Result<()> {
+    let path = Path::new(dir).join(MARKETS_FILE);
+    let data = serde_json::to_string_pretty(&snapshot_markets(state))?;
+    std::fs::write(&path, data).with_context(|| format!("writing {}", path.display()))
+}
+
+/// Load a market list written by save_markets
+#[allow(dead_code)]
+pub fn load_markets(path: &str) -> Result<Vec<MarketSnapshot>> {
+    let data = std::fs::read_to_string(path).with_context(|| format!("reading {}", path))?;
+    serde_json::from_str(&data).with_context(|| format!("parsing {}", path))
+}
+
+/// Rebuild detection state (market ids, token lookups, fee rates) from a market list
+#[allow(dead_code)]
+pub fn build_state(markets: &[MarketSnapshot]) -> GlobalState {
+    let mut state = GlobalState::new();
+    for market in markets {
+        if let Some(id) = state.add_pair(market.pair.clone()) {
+            state.markets[id as usize].set_fee_rates(market.yes_fee_bps, market.no_fee_bps);
+        }
+    }
+    state
+}
+
+// === Reading recordings ===
+
+/// Segment files in a recording directory, oldest first
+#[allow(dead_code)]
+pub fn segments(dir: &str) -> Result<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("reading {}", dir))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.extension().is_some_and(|ext| ext == "gz")
+                && p.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with("ws-"))
+        })
+        .collect();
+    // Names are ws-<first_ns>; compare numerically in case digit counts differ
+    paths.sort_by_key(|p| {
+        p.file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|s| s.trim_start_matches("ws-").parse::<u64>().ok())
+            .unwrap_or(0)
+    });
+    Ok(paths)
+}
+
+/// Block index of a segment
+#[allow(dead_code)]
+pub fn read_index(segment: &Path) -> Result<Vec<BlockIndex>> {
+    let path = segment.with_extension("idx");
+    let data = std::fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+    data.lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| serde_json::from_str(l).with_context(|| format!("bad index line in {}", path.display())))
+        .collect()
+}
+
+/// Frames of one indexed block
+#[allow(dead_code)]
+pub fn read_block(segment: &Path, block: &BlockIndex) -> Result<Vec<RecordedFrame>> {
+    let mut file = File::open(segment).with_context(|| format!("opening {}", segment.display()))?;
+    file.seek(SeekFrom::Start(block.offset))?;
+    let mut raw = Vec::new();
+    GzDecoder::new(file.take(block.len)).read_to_end(&mut raw)?;
+    decode_records(&raw)
+}
+
+/// All frames of a segment. Reads the data file directly (no index needed) and
+/// keeps whatever decodes before a truncated tail, e.g. after a crash.
+#[allow(dead_code)]
+pub fn read_segment(segment: &Path) -> Result<Vec<RecordedFrame>> {
+    let file = File::open(segment).with_context(|| format!("opening {}", segment.display()))?;
+    let mut raw = Vec::new();
+    let mut decoder = MultiGzDecoder::new(file);
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        match decoder.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => raw.extend_from_slice(&buf[..n]),
+            Err(e) => {
+                error!("[REC] {} truncated after {} bytes: {}", segment.display(), raw.len(), e);
+                break;
+            }
+        }
+    }
+    Ok(decode_complete_records(&raw))
+}
+
+/// Parse "YYYY-MM-DD" (UTC day; `end_of_day` picks its last nanosecond),
+/// an RFC 3339 timestamp, or raw Unix nanoseconds
+#[allow(dead_code)]
+pub fn parse_time(s: &str, end_of_day: bool) -> Result<u64> {
+    let s = s.trim();
+    if let Ok(ns) = s.parse::<u64>() {
+        return Ok(ns);
+    }
+    let ts = if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        let day = date.and_hms_opt(0, 0, 0).unwrap().and_utc();
+        if end_of_day { day + chrono::Duration::days(1) - chrono::Duration::nanoseconds(1) } else { day }
+    } else {
+        DateTime::parse_from_rfc3339(s)
+            .with_context(|| format!("invalid time {s:?} (expected YYYY-MM-DD, RFC 3339 or Unix ns)"))?
+            .with_timezone(&Utc)
+    };
+    ts.timestamp_nanos_opt()
+        .map(|ns| ns.max(0) as u64)
+        .with_context(|| format!("time {s:?} out of range"))
+}
+
+/// Frames received in [from_ns, to_ns] across a recording directory, using
+/// the index to skip blocks outside the range
+#[allow(dead_code)]
+pub fn read_range(dir: &str, from_ns: u64, to_ns: u64) -> Result<Vec<RecordedFrame>> {
+    let mut frames = Vec::new();
+    for segment in segments(dir)? {
+        for block in read_index(&segment)? {
+            if block.last_ns < from_ns || block.first_ns > to_ns {
+                continue;
+            }
+            frames.extend(
+                read_block(&segment, &block)?
+                    .into_iter()
+                    .filter(|f| f.recv_ns >= from_ns && f.recv_ns <= to_ns),
+            );
+        }
+    }
+    Ok(frames)
+}
+
+#[allow(dead_code)]
+fn decode_records(raw: &[u8]) -> Result<Vec<RecordedFrame>> {
+    let frames = decode_complete_records(raw);
+    let decoded: usize = frames.iter().map(|f| RECORD_HEADER_LEN + f.payload.len()).sum();
+    if decoded != raw.len() {
+        return Err(anyhow!("corrupt block: {} trailing bytes", raw.len() - decoded));
+    }
+    Ok(frames)
+}
+
+#[allow(dead_code)]
+fn decode_complete_records(mut raw: &[u8]) -> Vec<RecordedFrame> {
+    let mut frames = Vec::new();
+    while raw.len() >= RECORD_HEADER_LEN {
+        let recv_ns = u64::from_le_bytes(raw[0..8].try_into().unwrap());
+        let len = u32::from_le_bytes(raw[8..12].try_into().unwrap()) as usize;
+        let Some(payload) = raw.get(RECORD_HEADER_LEN..RECORD_HEADER_LEN + len) else { break };
+        frames.push(RecordedFrame { recv_ns, payload: payload.to_vec() });
+        raw = &raw[RECORD_HEADER_LEN + len..];
+    }
+    frames
+}
+
+/// Wall clock in nanoseconds since the Unix epoch
+pub fn now_ns() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0)
+}
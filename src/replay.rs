@@ -0,0 +1,311 @@
+// src/replay.rs
+// Deterministic replay - recorded WS frames through the live detection and execution path
+//
+// Runs on a single-threaded tokio runtime with paused time: the driver sleeps until each
+// frame's recorded offset, so paper latency, auto-close waits and in-flight holds all
+// elapse in recorded time and the same input always produces the same decisions.
+
+use anyhow::{Context, Result};
+use rustc_hash::FxHasher;
+use serde::Serialize;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+use tracing::info;
+
+use crate::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig};
+use crate::config::{ARB_THRESHOLD, max_quote_age_ms};
+use crate::execution::{ExecutionEngine, NanoClock, create_execution_channel};
+use crate::opportunity::{Episode, OpportunityTracker};
+use crate::paper::{DepthBook, PaperConfig, PaperExchange, PaperStats};
+use crate::polymarket::{apply_depth, process_frame};
+use crate::position_tracker::create_position_channel;
+use crate::recorder::{MARKETS_FILE, MarketSnapshot, RecordedFrame, build_state, load_markets, read_range};
+use crate::types::{AtomicMarketState, FastExecutionRequest, GlobalState, PriceCents, poly_fee_cents};
+
+/// Virtual time allowed after the last frame for fills, auto-closes and in-flight releases
+const SETTLE_SECS: u64 = 30;
+
+/// Replay configuration from environment
+#[derive(Debug, Clone)]
+pub struct ReplayConfig {
+    /// Recording directory
+    pub dir: String,
+    /// Market list (defaults to the one saved with the recording)
+    pub markets_file: String,
+    /// Replay frames received in [from_ns, to_ns] (Unix nanoseconds)
+    pub from_ns: u64,
+    pub to_ns: u64,
+    pub threshold_cents: PriceCents,
+    /// Oldest quote detection and execution act on (milliseconds, 0 = no limit)
+    pub max_quote_age_ms: u64,
+    /// Detection only: the engine runs in dry-run mode and places no (paper) orders
+    pub dry_run: bool,
+    pub paper: PaperConfig,
+    pub breaker: CircuitBreakerConfig,
+}
+
+impl ReplayConfig {
+    pub fn from_env() -> Self {
+        let dir = std::env::var("REPLAY_DIR").unwrap_or_else(|_| "recordings".to_string());
+        let markets_file = std::env::var("REPLAY_MARKETS")
+            .unwrap_or_else(|_| Path::new(&dir).join(MARKETS_FILE).to_string_lossy().into_owned());
+
+        // Replays never restore or overwrite the live breaker's saved state
+        let mut breaker = CircuitBreakerConfig::from_env();
+        breaker.state_file = String::new();
+
+        let mut paper = PaperConfig::from_env();
+        // Reproducible jitter unless a seed is given explicitly
+        paper.seed = paper.seed.or(Some(0));
+        // Replayed fills never touch the live paper positions file
+        paper.positions_file = String::new();
+
+        Self {
+            from_ns: std::env::var("REPLAY_FROM_NS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+
+            to_ns: std::env::var("REPLAY_TO_NS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(u64::MAX),
+
+            threshold_cents: std::env::var("REPLAY_THRESHOLD_CENTS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(((ARB_THRESHOLD * 100.0).round() as PriceCents).max(1)),
+
+            max_quote_age_ms: max_quote_age_ms(),
+
+            dry_run: std::env::var("REPLAY_DRY_RUN")
+                .map(|v| v == "1" || v == "true")
+                .unwrap_or(false),
+
+            paper,
+            breaker,
+            dir,
+            markets_file,
+        }
+    }
+}
+
+/// One arb sent to execution and what execution did with it
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct Decision {
+    /// Detection time on the simulated clock (ns since the first replayed frame)
+    pub detected_ns: u64,
+    /// Detection time on the recording's clock (Unix nanoseconds)
+    pub recv_ns: u64,
+    pub market_id: u16,
+    pub pair_id: String,
+    pub yes_price: PriceCents,
+    pub no_price: PriceCents,
+    pub yes_size: u16,
+    pub no_size: u16,
+    pub executed: bool,
+    pub profit_cents: i64,
+    pub error: Option<String>,
+}
+
+fn book_has_arb(market: &AtomicMarketState, book: &DepthBook, threshold_cents: PriceCents) -> bool {
+    let Some(pair) = &market.pair else { return false };
+    let (Some((yes, _)), Some((no, _))) = (book.best_ask(&pair.poly_yes_token), book.best_ask(&pair.poly_no_token)) else {
+        return false;
+    };
+    let (yes_fee_bps, no_fee_bps) = market.fee_rates();
+    yes + no + poly_fee_cents(yes, yes_fee_bps) + poly_fee_cents(no, no_fee_bps) < threshold_cents
+}
+
+/// Paper position in one market at the end of a replay
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MarketOutcome {
+    pub pair_id: String,
+    pub matched_contracts: f64,
+    pub unmatched_contracts: f64,
+    /// Net cost incl. fees (buys minus close-out proceeds)
+    pub cost: f64,
+    pub fees: f64,
+    /// Matched payout minus net cost; unmatched contracts are valued at zero
+    pub pnl: f64,
+    /// Loss realized selling off mismatched legs
+    pub mismatch_loss: f64,
+}
+
+/// Outcome of a replay run
+#[derive(Debug, Clone)]
+pub struct ReplayReport {
+    pub frames: usize,
+    /// Decisions in detection order
+    pub decisions: Vec<Decision>,
+    /// Arb episodes in start order. Closed by the recorded book, so a level pulled
+    /// without a new ask also ends one (live detection only sees better asks).
+    pub episodes: Vec<Episode>,
+    /// Paper positions for markets that traded
+    pub markets: Vec<MarketOutcome>,
+    pub paper: PaperStats,
+}
+
+impl ReplayReport {
+    /// Fingerprint of the decisions; equal across runs of the same input
+    pub fn digest(&self) -> u64 {
+        let mut hasher = FxHasher::default();
+        self.decisions.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    pub fn executed(&self) -> usize {
+        self.decisions.iter().filter(|d| d.executed).count()
+    }
+
+    pub fn pnl(&self) -> f64 {
+        self.markets.iter().map(|m| m.pnl).sum()
+    }
+}
+
+impl std::fmt::Display for ReplayReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Replay: {} frames | {} episodes, {} arbs detected, {} executed | P&L ${:.2} | digest {:016x} | {}",
+               self.frames, self.episodes.len(), self.decisions.len(), self.executed(), self.pnl(),
+               self.digest(), self.paper)
+    }
+}
+
+/// Replay frames received in the configured range of a recording directory
+pub fn replay_recording(config: &ReplayConfig) -> Result<ReplayReport> {
+    let markets = load_markets(&config.markets_file)
+        .with_context(|| format!("loading market list {}", config.markets_file))?;
+    let frames = read_range(&config.dir, config.from_ns, config.to_ns)?;
+    info!("[REPLAY] {} frames, {} markets from {}", frames.len(), markets.len(), config.dir);
+    replay(config, &frames, &markets)
+}
+
+/// Replay frames on a fresh single-threaded runtime with paused time.
+/// Blocks the calling thread; don't call from inside another runtime's async context.
+pub fn replay(config: &ReplayConfig, frames: &[RecordedFrame], markets: &[MarketSnapshot]) -> Result<ReplayReport> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .start_paused(true)
+        .build()
+        .context("building replay runtime")?;
+    runtime.block_on(run(config, frames, markets))
+}
+
+async fn run(config: &ReplayConfig, frames: &[RecordedFrame], markets: &[MarketSnapshot]) -> Result<ReplayReport> {
+    let mut state = build_state(markets);
+    state.max_quote_age_ns = config.max_quote_age_ms * 1_000_000;
+    let state = Arc::new(state);
+    let depth = Arc::new(DepthBook::new());
+    let paper = Arc::new(PaperExchange::new(config.paper.clone(), depth.clone(), state.clone()));
+    let breaker = Arc::new(CircuitBreaker::new(config.breaker.clone())
+        .with_markets(state.markets.iter().filter_map(|m| m.pair.as_deref())));
+
+    let (position_channel, mut position_rx) = create_position_channel();
+    tokio::spawn(async move { while position_rx.recv().await.is_some() {} });
+
+    let clock = NanoClock::simulated();
+    let base_ns = frames.first().map(|f| f.recv_ns).unwrap_or(0);
+    let opportunities = Arc::new(OpportunityTracker::new(clock, base_ns));
+    let engine = ExecutionEngine::new(paper.clone(), state.clone(), breaker, position_channel, config.dry_run)
+        .with_clock(clock)
+        .with_opportunities(opportunities.clone());
+    let (exec_tx, exec_rx) = create_execution_channel();
+    let exec = tokio::spawn(collect_decisions(exec_rx, Arc::new(engine), state.clone()));
+
+    // The market's book as recorded, unaffected by liquidity the paper fills take
+    let market_book = DepthBook::new();
+
+    let start = tokio::time::Instant::now();
+    for frame in frames {
+        // Everything due before this frame (fills, closes, releases) runs first
+        tokio::time::sleep_until(start + Duration::from_nanos(frame.recv_ns.saturating_sub(base_ns))).await;
+        if let Some(text) = frame.text() {
+            process_frame(&state, text, &exec_tx, config.threshold_cents, &clock, Some(&depth), Some(&opportunities)).await;
+            apply_depth(&market_book, text);
+            for market_id in opportunities.open_markets() {
+                if !state.get_by_id(market_id).is_some_and(|m| book_has_arb(m, &market_book, config.threshold_cents)) {
+                    opportunities.close(market_id);
+                }
+            }
+        }
+    }
+    drop(exec_tx);
+    opportunities.stop();
+
+    let mut decisions = exec.await.context("replay executor panicked")?;
+    tokio::time::sleep(Duration::from_secs(SETTLE_SECS)).await;
+
+    for decision in &mut decisions {
+        decision.recv_ns = base_ns + decision.detected_ns;
+    }
+    decisions.sort();
+
+    let episodes = opportunities.drain();
+    let markets = market_outcomes(&paper, markets).await;
+
+    Ok(ReplayReport { frames: frames.len(), decisions, episodes, markets, paper: paper.stats() })
+}
+
+async fn market_outcomes(paper: &PaperExchange, markets: &[MarketSnapshot]) -> Vec<MarketOutcome> {
+    let tracker = paper.tracker();
+    let tracker = tracker.read().await;
+    markets.iter()
+        .filter_map(|m| {
+            let position = tracker.get(&m.pair.pair_id)?;
+            let realized = paper.realized_pnl(&m.pair.poly_yes_token) + paper.realized_pnl(&m.pair.poly_no_token);
+            Some(MarketOutcome {
+                pair_id: m.pair.pair_id.to_string(),
+                matched_contracts: position.matched_contracts(),
+                unmatched_contracts: position.unmatched_exposure(),
+                cost: position.total_cost(),
+                fees: position.total_fees,
+                pnl: position.guaranteed_profit(),
+                mismatch_loss: (-realized).max(0.0),
+            })
+        })
+        .collect()
+}
+
+/// Execution loop that keeps each request's result (run_execution_loop only logs)
+async fn collect_decisions(
+    mut rx: mpsc::Receiver<FastExecutionRequest>,
+    engine: Arc<ExecutionEngine<PaperExchange>>,
+    state: Arc<GlobalState>,
+) -> Vec<Decision> {
+    let mut tasks = JoinSet::new();
+    while let Some(req) = rx.recv().await {
+        let engine = engine.clone();
+        tasks.spawn(async move { (req, engine.process(req).await) });
+    }
+
+    let mut decisions = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        let Ok((req, result)) = joined else { continue };
+        let pair_id = state.get_by_id(req.market_id)
+            .and_then(|m| m.pair.as_ref())
+            .map(|p| p.pair_id.to_string())
+            .unwrap_or_default();
+        let (executed, profit_cents, error) = match result {
+            Ok(r) => (r.success && r.error.is_none(), r.profit_cents, r.error.map(str::to_string)),
+            Err(e) => (false, 0, Some(e.to_string())),
+        };
+        decisions.push(Decision {
+            detected_ns: req.detected_ns,
+            recv_ns: 0,
+            market_id: req.market_id,
+            pair_id,
+            yes_price: req.yes_price,
+            no_price: req.no_price,
+            yes_size: req.yes_size,
+            no_size: req.no_size,
+            executed,
+            profit_cents,
+            error,
+        });
+    }
+    decisions
+}
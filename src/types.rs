@@ -1,8 +1,9 @@
 // src/types.rs
 // Shared data structures
 
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU64, Ordering};
 use std::sync::Arc;
 use rustc_hash::FxHashMap;
 
@@ -40,6 +41,50 @@ pub struct MarketPair {
     pub poly_no_token: Arc<str>,
     pub line_value: Option<f64>,
     pub team_suffix: Option<Arc<str>>,
+    /// Gamma event the market belongs to; moneyline, spread and total markets on one
+    /// game share it. None when discovery had no event metadata.
+    #[serde(default)]
+    pub event_id: Option<Arc<str>>,
+    /// Game date: the Gamma event's start, UTC
+    #[serde(default)]
+    pub game_date: Option<NaiveDate>,
+}
+
+impl MarketPair {
+    /// Event (game) the market belongs to by its slug: the slug up to the game date, so
+    /// moneyline, spread and total markets on one game share it
+    /// ("epl-che-avl-2025-12-08-total-2pt5" -> "epl-che-avl-2025-12-08").
+    /// The whole slug when it carries no date.
+    pub fn event_slug(&self) -> &str {
+        match slug_date(&self.poly_slug) {
+            Some((end, _)) => &self.poly_slug[..end],
+            None => &self.poly_slug,
+        }
+    }
+
+    /// Game date parsed from the slug, for pairs discovered without Gamma event metadata
+    pub fn slug_game_date(&self) -> Option<NaiveDate> {
+        slug_date(&self.poly_slug).map(|(_, date)| date)
+    }
+}
+
+/// First `YYYY-MM-DD` slug segment: its end offset and the date
+fn slug_date(slug: &str) -> Option<(usize, NaiveDate)> {
+    let bytes = slug.as_bytes();
+    let mut start = 0;
+    while start + 10 <= bytes.len() {
+        let at_boundary = start == 0 || bytes[start - 1] == b'-';
+        let ends_segment = start + 10 == bytes.len() || bytes[start + 10] == b'-';
+        if at_boundary && ends_segment {
+            let date = slug.get(start..start + 10)
+                .and_then(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok());
+            if let Some(date) = date {
+                return Some((start + 10, date));
+            }
+        }
+        start += 1;
+    }
+    None
 }
 
 /// Price in cents (1-99 for 0.01-0.99), 0 = no price available
@@ -70,16 +115,27 @@ pub fn unpack_orderbook(packed: u64) -> (PriceCents, PriceCents, SizeCents, Size
     (yes_ask, no_ask, yes_size, no_size)
 }
 
+/// Timestamp of a side that has never been updated
+const NEVER_UPDATED: u64 = u64::MAX;
+
 /// Orderbook state for a single platform
 #[repr(align(64))]
 pub struct AtomicOrderbook {
     /// Packed state: [yes_ask:16][no_ask:16][yes_size:16][no_size:16]
     packed: AtomicU64,
+    /// Last feed update for the YES token (clock nanoseconds)
+    yes_updated_ns: AtomicU64,
+    /// Last feed update for the NO token (clock nanoseconds)
+    no_updated_ns: AtomicU64,
 }
 
 impl AtomicOrderbook {
     pub const fn new() -> Self {
-        Self { packed: AtomicU64::new(0) }
+        Self {
+            packed: AtomicU64::new(0),
+            yes_updated_ns: AtomicU64::new(NEVER_UPDATED),
+            no_updated_ns: AtomicU64::new(NEVER_UPDATED),
+        }
     }
 
     /// Load current state
@@ -121,6 +177,41 @@ impl AtomicOrderbook {
             }
         }
     }
+
+    /// Record that the feed reported on the YES token at `now_ns`
+    #[inline(always)]
+    pub fn touch_yes(&self, now_ns: u64) {
+        self.yes_updated_ns.store(now_ns, Ordering::Release);
+    }
+
+    /// Record that the feed reported on the NO token at `now_ns`
+    #[inline(always)]
+    pub fn touch_no(&self, now_ns: u64) {
+        self.no_updated_ns.store(now_ns, Ordering::Release);
+    }
+
+    /// Time since the last (yes, no) update; None for a side never updated
+    #[inline(always)]
+    pub fn side_ages_ns(&self, now_ns: u64) -> (Option<u64>, Option<u64>) {
+        let age = |stamp: u64| (stamp != NEVER_UPDATED).then(|| now_ns.saturating_sub(stamp));
+        (
+            age(self.yes_updated_ns.load(Ordering::Acquire)),
+            age(self.no_updated_ns.load(Ordering::Acquire)),
+        )
+    }
+
+    /// Age of the older side; None when neither side was ever updated by the feed
+    #[inline(always)]
+    pub fn age_ns(&self, now_ns: u64) -> Option<u64> {
+        let (yes, no) = self.side_ages_ns(now_ns);
+        yes.max(no)
+    }
+
+    /// Either side is older than `max_age_ns` (0 disables the check)
+    #[inline(always)]
+    pub fn is_stale(&self, now_ns: u64, max_age_ns: u64) -> bool {
+        max_age_ns > 0 && self.age_ns(now_ns).is_some_and(|age| age > max_age_ns)
+    }
 }
 
 impl Default for AtomicOrderbook {
@@ -132,6 +223,10 @@ impl Default for AtomicOrderbook {
 /// Per-market state (Polymarket orderbook)
 pub struct AtomicMarketState {
     pub poly: AtomicOrderbook,
+    /// Taker fee rate for the YES token (basis points)
+    pub yes_fee_bps: AtomicU16,
+    /// Taker fee rate for the NO token (basis points)
+    pub no_fee_bps: AtomicU16,
     /// Market pair data (immutable after discovery)
     pub pair: Option<Arc<MarketPair>>,
     /// Market ID for lookups
@@ -142,11 +237,34 @@ impl AtomicMarketState {
     pub fn new(market_id: u16) -> Self {
         Self {
             poly: AtomicOrderbook::new(),
+            yes_fee_bps: AtomicU16::new(0),
+            no_fee_bps: AtomicU16::new(0),
             pair: None,
             market_id,
         }
     }
 
+    /// Load fee rates (yes_fee_bps, no_fee_bps)
+    #[inline(always)]
+    pub fn fee_rates(&self) -> (u16, u16) {
+        (self.yes_fee_bps.load(Ordering::Relaxed), self.no_fee_bps.load(Ordering::Relaxed))
+    }
+
+    /// Store fee rates fetched from the CLOB
+    pub fn set_fee_rates(&self, yes_fee_bps: u16, no_fee_bps: u16) {
+        self.yes_fee_bps.store(yes_fee_bps, Ordering::Relaxed);
+        self.no_fee_bps.store(no_fee_bps, Ordering::Relaxed);
+    }
+
+    /// Arb mask for the current quotes; 0 when either side is older than `max_age_ns`
+    #[inline(always)]
+    pub fn check_fresh_arbs(&self, threshold_cents: PriceCents, now_ns: u64, max_age_ns: u64) -> u8 {
+        if self.poly.is_stale(now_ns, max_age_ns) {
+            return 0;
+        }
+        self.check_arbs(threshold_cents)
+    }
+
     #[inline(always)]
     pub fn check_arbs(&self, threshold_cents: PriceCents) -> u8 {
         let (p_yes, p_no, _, _) = self.poly.load();
@@ -155,8 +273,10 @@ impl AtomicMarketState {
             return 0;
         }
 
-        // Poly-only arb: YES + NO < threshold (no fees)
-        let cost = (p_yes + p_no) as i16;
+        // Poly-only arb: YES + NO + taker fees < threshold
+        let (yes_fee_bps, no_fee_bps) = self.fee_rates();
+        let fees = poly_fee_cents(p_yes, yes_fee_bps) + poly_fee_cents(p_no, no_fee_bps);
+        let cost = (p_yes + p_no + fees) as i16;
         if cost < threshold_cents as i16 {
             return 4; // Bit 2 = PolyOnly
         }
@@ -165,6 +285,17 @@ impl AtomicMarketState {
 }
 
 
+/// Polymarket taker fee per contract in cents, rounded up
+/// fee = fee_rate × min(P, 100-P), so it is symmetric around 50¢
+#[inline(always)]
+pub fn poly_fee_cents(price_cents: PriceCents, fee_bps: u16) -> PriceCents {
+    if fee_bps == 0 || price_cents == 0 || price_cents >= 100 {
+        return 0;
+    }
+    let p = price_cents.min(100 - price_cents) as u32;
+    ((fee_bps as u32 * p).div_ceil(10000)) as PriceCents
+}
+
 /// Convert f64 price (0.01-0.99) to PriceCents (1-99)
 #[inline(always)]
 pub fn price_to_cents(price: f64) -> PriceCents {
@@ -223,6 +354,10 @@ pub struct FastExecutionRequest {
     pub yes_size: SizeCents,
     /// NO size in cents
     pub no_size: SizeCents,
+    /// YES token fee rate in basis points
+    pub yes_fee_bps: u16,
+    /// NO token fee rate in basis points
+    pub no_fee_bps: u16,
     /// Type of arb (determines execution strategy)
     pub arb_type: ArbType,
     /// Detection timestamp (nanoseconds since start)
@@ -235,10 +370,10 @@ impl FastExecutionRequest {
         100 - (self.yes_price as i16 + self.no_price as i16 + self.estimated_fee_cents() as i16)
     }
 
+    /// Taker fees for one YES + one NO contract (0 on fee-free markets)
     #[inline(always)]
     pub fn estimated_fee_cents(&self) -> PriceCents {
-        // Polymarket has no trading fees
-        0
+        poly_fee_cents(self.yes_price, self.yes_fee_bps) + poly_fee_cents(self.no_price, self.no_fee_bps)
     }
 }
 
@@ -255,6 +390,12 @@ pub struct GlobalState {
 
     /// O(1) lookup: pre-hashed Poly NO token → market_id
     pub poly_no_to_id: FxHashMap<u64, u16>,
+
+    /// Oldest quote detection and execution will act on (nanoseconds, 0 = no limit)
+    pub max_quote_age_ns: u64,
+
+    /// Cleared at shutdown: price updates keep flowing but no new arbs are sent
+    detecting: AtomicBool,
 }
 
 impl GlobalState {
@@ -269,6 +410,8 @@ impl GlobalState {
             next_market_id: 0,
             poly_yes_to_id: FxHashMap::default(),
             poly_no_to_id: FxHashMap::default(),
+            max_quote_age_ns: 0,
+            detecting: AtomicBool::new(true),
         }
     }
 
@@ -295,6 +438,17 @@ impl GlobalState {
         Some(market_id)
     }
 
+    /// Arb detection is running
+    #[inline(always)]
+    pub fn is_detecting(&self) -> bool {
+        self.detecting.load(Ordering::Acquire)
+    }
+
+    /// Stop sending arbs to execution (first step of shutdown)
+    pub fn stop_detection(&self) {
+        self.detecting.store(false, Ordering::Release);
+    }
+
     /// Get market by Poly YES token hash (O(1))
     #[inline(always)]
     #[allow(dead_code)]
@@ -835,6 +989,8 @@ mod tests {
             no_price: 50,
             yes_size: 1000,
             no_size: 1000,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
             arb_type: ArbType::PolyYesKalshiNo,
             detected_ns: 0,
         };
@@ -853,6 +1009,8 @@ mod tests {
             no_price: 50,
             yes_size: 1000,
             no_size: 1000,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
             arb_type: ArbType::KalshiYesPolyNo,
             detected_ns: 0,
         };
@@ -871,6 +1029,8 @@ mod tests {
             no_price: 48,
             yes_size: 1000,
             no_size: 1000,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
             arb_type: ArbType::PolyOnly,
             detected_ns: 0,
         };
@@ -890,6 +1050,8 @@ mod tests {
             no_price: 44,
             yes_size: 1000,
             no_size: 1000,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
             arb_type: ArbType::KalshiOnly,
             detected_ns: 0,
         };
@@ -907,6 +1069,8 @@ mod tests {
             no_price: 52,
             yes_size: 1000,
             no_size: 1000,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
             arb_type: ArbType::PolyYesKalshiNo,
             detected_ns: 0,
         };
@@ -923,6 +1087,8 @@ mod tests {
             no_price: 50,
             yes_size: 1000,
             no_size: 1000,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
             arb_type: ArbType::PolyYesKalshiNo,
             detected_ns: 0,
         };
@@ -935,6 +1101,8 @@ mod tests {
             no_price: 50,
             yes_size: 1000,
             no_size: 1000,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
             arb_type: ArbType::KalshiYesPolyNo,
             detected_ns: 0,
         };
@@ -947,6 +1115,8 @@ mod tests {
             no_price: 50,
             yes_size: 1000,
             no_size: 1000,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
             arb_type: ArbType::PolyOnly,
             detected_ns: 0,
         };
@@ -959,6 +1129,8 @@ mod tests {
             no_price: 50,
             yes_size: 1000,
             no_size: 1000,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
             arb_type: ArbType::KalshiOnly,
             detected_ns: 0,
         };
@@ -1042,6 +1214,8 @@ mod tests {
             no_price: k_no,
             yes_size: p_yes_sz,
             no_size: k_no_sz,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
             arb_type: ArbType::PolyYesKalshiNo,
             detected_ns: 0,
         };
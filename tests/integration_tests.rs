@@ -7,17 +7,134 @@
 // 3. Circuit breaker behavior
 // 4. End-to-end scenarios
 
+// ============================================================================
+// FIXTURES - Configs shared by the test modules
+// ============================================================================
+
+mod fixtures {
+    use arb_bot::balance::BalanceConfig;
+    use arb_bot::behaviour::BehaviourConfig;
+    use arb_bot::circuit_breaker::CircuitBreakerConfig;
+    use arb_bot::paper::PaperConfig;
+    use arb_bot::position_tracker::PositionTracker;
+    use arb_bot::presign::PresignConfig;
+    use arb_bot::recorder::RecorderConfig;
+    use arb_bot::types::{MarketPair, MarketType};
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    /// Every breaker check enabled but with limits far above what tests and replays
+    /// trade, nothing persisted. Override the fields a scenario cares about.
+    pub fn breaker_config() -> CircuitBreakerConfig {
+        CircuitBreakerConfig {
+            max_position_per_market: 100,
+            max_total_position: 500,
+            max_unmatched_per_market: 100,
+            max_unmatched_total: 500,
+            max_trade_notional: 10_000.0,
+            max_market_cost: 10_000.0,
+            max_total_deployed: 100_000.0,
+            max_locked_capital: 100_000.0,
+            max_event_cost: 100_000.0,
+            max_date_cost: 100_000.0,
+            max_league_cost: 100_000.0,
+            league_cost_limits: HashMap::new(),
+            max_daily_loss: 50.0,
+            max_consecutive_errors: 5,
+            behaviour: BehaviourConfig::from_env(),
+            cooldown_secs: 60,
+            auto_reset: vec!["consecutive_errors".to_string()],
+            rollover_tz: chrono_tz::Tz::UTC,
+            enabled: true,
+            state_file: String::new(),
+        }
+    }
+
+    /// One pair per market ID, each on its own event on the same league and date, for
+    /// registering with a breaker (`with_markets`) - it rejects markets it doesn't know
+    pub fn market_pairs(ids: &[&str]) -> Vec<MarketPair> {
+        ids.iter()
+            .map(|id| MarketPair {
+                pair_id: (*id).into(),
+                league: "epl".into(),
+                market_type: MarketType::Moneyline,
+                description: (*id).into(),
+                poly_slug: (*id).into(),
+                poly_yes_token: format!("{}-yes", id).into(),
+                poly_no_token: format!("{}-no", id).into(),
+                line_value: None,
+                team_suffix: None,
+                event_id: Some((*id).into()),
+                game_date: chrono::NaiveDate::from_ymd_opt(2025, 12, 8),
+            })
+            .collect()
+    }
+
+    pub fn balance_config() -> BalanceConfig {
+        BalanceConfig {
+            rpc_url: None,
+            refresh_secs: 30,
+            reserve_usdc: 0.0,
+            enabled: true,
+        }
+    }
+
+    pub fn presign_config() -> PresignConfig {
+        PresignConfig {
+            enabled: true,
+            sizes: vec![10, 25, 50],
+            price_levels: 1,
+            refresh_ms: 500,
+        }
+    }
+
+    /// Instant fills, $100 of USDC, positions saved to a per-test temp file
+    pub fn paper_config(name: &str) -> PaperConfig {
+        PaperConfig {
+            enabled: true,
+            latency_ms: 0,
+            latency_jitter_ms: 0,
+            queue_share: 1.0,
+            starting_usdc: 100.0,
+            positions_file: std::env::temp_dir()
+                .join(format!("paper_{}_{}.json", name, std::process::id()))
+                .to_string_lossy()
+                .into_owned(),
+            seed: None,
+        }
+    }
+
+    /// Empty tracker whose saves go to a temp file instead of positions.json
+    pub fn position_tracker() -> PositionTracker {
+        let path = std::env::temp_dir().join(format!("arb_positions_scratch_{}.json", std::process::id()));
+        PositionTracker::new().with_path(path.to_str().unwrap())
+    }
+
+    pub fn recorder_config(dir: &Path) -> RecorderConfig {
+        RecorderConfig {
+            enabled: true,
+            dir: dir.to_str().unwrap().to_string(),
+            rotate_secs: 3600,
+            rotate_bytes: 256 * 1024 * 1024,
+            block_frames: 4,
+            flush_ms: 60_000,
+            queue_size: 1024,
+        }
+    }
+}
+
 // ============================================================================
 // POSITION TRACKER TESTS - Verify fill recording and P&L calculation
 // ============================================================================
 
 mod position_tracker_tests {
+    use super::fixtures::position_tracker;
     use arb_bot::position_tracker::*;
     
     /// Test: Recording fills updates position correctly
     #[test]
     fn test_record_fills_updates_position() {
-        let mut tracker = PositionTracker::new();
+        let mut tracker = position_tracker();
         
         // Record a Polymarket YES fill
         tracker.record_fill(&FillRecord::new(
@@ -113,7 +230,7 @@ mod position_tracker_tests {
     /// Test: Daily P&L resets
     #[test]
     fn test_daily_pnl_persistence() {
-        let mut tracker = PositionTracker::new();
+        let mut tracker = position_tracker();
         
         // Simulate some activity
         tracker.all_time_pnl = 100.0;
@@ -125,6 +242,33 @@ mod position_tracker_tests {
         assert_eq!(tracker.daily_realized_pnl, 0.0, "Daily should reset");
         assert_eq!(tracker.all_time_pnl, 100.0, "All-time should persist");
     }
+
+    /// Test: the writer saves its last batch to disk when the channel closes (shutdown)
+    #[tokio::test]
+    async fn test_writer_flushes_on_channel_close() {
+        use std::sync::Arc;
+        use tokio::sync::RwLock;
+
+        let path = std::env::temp_dir().join(format!("arb_positions_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let tracker = Arc::new(RwLock::new(PositionTracker::new().with_path(path.to_str().unwrap())));
+        let (channel, rx) = create_position_channel();
+        let writer = tokio::spawn(position_writer_loop(rx, tracker));
+
+        channel.record_fill(FillRecord::new("TEST-MARKET", "Test", "polymarket", "yes", 10.0, 0.45, 0.0, "y1"));
+        channel.record_fill(FillRecord::new("TEST-MARKET", "Test", "polymarket", "no", 10.0, 0.50, 0.0, "n1"));
+        drop(channel);
+
+        tokio::time::timeout(std::time::Duration::from_secs(5), writer)
+            .await
+            .expect("writer exits once the channel closes")
+            .unwrap();
+        let saved = PositionTracker::load_from(&path).summary();
+        assert_eq!(saved.open_positions, 1);
+        assert!((saved.total_cost_basis - 9.5).abs() < 1e-9);
+        assert!(!path.with_extension("json.tmp").exists(), "Temp file renamed into place");
+        let _ = std::fs::remove_file(&path);
+    }
 }
 
 // ============================================================================
@@ -132,33 +276,52 @@ mod position_tracker_tests {
 // ============================================================================
 
 mod circuit_breaker_tests {
+    use super::fixtures::{breaker_config, market_pairs};
     use arb_bot::circuit_breaker::*;
+    use arb_bot::position_tracker::FillRecord;
+
+    fn fill(market: &str, side: &str, contracts: f64) -> FillRecord {
+        FillRecord::new(market, market, "polymarket", side, contracts, 0.50, 0.0, "order")
+    }
+
+    /// Record a fully matched execution (both legs filled) with its P&L
+    pub async fn record_matched(cb: &CircuitBreaker, market: &str, contracts: i64, pnl: f64) {
+        cb.record_fill(&fill(market, "yes", contracts as f64)).await;
+        cb.record_fill(&fill(market, "no", contracts as f64)).await;
+        cb.record_success(pnl);
+    }
     
     fn test_config() -> CircuitBreakerConfig {
         CircuitBreakerConfig {
             max_position_per_market: 50,
             max_total_position: 200,
+            max_unmatched_per_market: 20,
+            max_unmatched_total: 30,
+            max_trade_notional: 100.0,
+            max_market_cost: 150.0,
+            max_total_deployed: 400.0,
+            max_locked_capital: 300.0,
             max_daily_loss: 25.0,
             max_consecutive_errors: 3,
-            cooldown_secs: 60,
-            enabled: true,
+            ..breaker_config()
         }
     }
     
     /// Test: Allows trades within limits
     #[tokio::test]
     async fn test_allows_trades_within_limits() {
-        let cb = CircuitBreaker::new(test_config());
+        let cb = CircuitBreaker::new(test_config())
+            .with_markets(&market_pairs(&["market1"]));
         
         // First trade should be allowed
-        let result = cb.can_execute("market1", 10).await;
+        let result = cb.can_execute("market1", 10, 45, 50).await;
         assert!(result.is_ok(), "Should allow first trade");
         
         // Record success
-        cb.record_success("market1", 10, 10, 0.50).await;
+        record_matched(&cb, "market1", 10, 0.50).await;
         
         // Second trade on same market should still be allowed
-        let result = cb.can_execute("market1", 10).await;
+        let result = cb.can_execute("market1", 10, 45, 50).await;
         assert!(result.is_ok(), "Should allow second trade within limit");
     }
     
@@ -168,10 +331,10 @@ mod circuit_breaker_tests {
         let cb = CircuitBreaker::new(test_config());
         
         // Fill up the market
-        cb.record_success("market1", 45, 45, 1.0).await;
+        record_matched(&cb, "market1", 45, 1.0).await;
         
         // Try to add 10 more (would exceed 50 limit)
-        let result = cb.can_execute("market1", 10).await;
+        let result = cb.can_execute("market1", 10, 45, 50).await;
         
         assert!(matches!(result, Err(TripReason::MaxPositionPerMarket { .. })),
             "Should block trade exceeding per-market limit");
@@ -183,18 +346,335 @@ mod circuit_breaker_tests {
         let cb = CircuitBreaker::new(test_config());
         
         // Fill up multiple markets
-        cb.record_success("market1", 50, 50, 1.0).await;
-        cb.record_success("market2", 50, 50, 1.0).await;
-        cb.record_success("market3", 50, 50, 1.0).await;
-        cb.record_success("market4", 45, 45, 1.0).await;  // Total: 195
+        record_matched(&cb, "market1", 50, 1.0).await;
+        record_matched(&cb, "market2", 50, 1.0).await;
+        record_matched(&cb, "market3", 50, 1.0).await;
+        record_matched(&cb, "market4", 45, 1.0).await;  // Total: 195
         
         // Try to add 10 more (would exceed 200 total limit)
-        let result = cb.can_execute("market5", 10).await;
+        let result = cb.can_execute("market5", 10, 45, 50).await;
         
         assert!(matches!(result, Err(TripReason::MaxTotalPosition { .. })),
             "Should block trade exceeding total position limit");
     }
     
+    /// Test: Fills build true per-leg holdings; sells reduce them
+    #[tokio::test]
+    async fn test_fills_track_per_leg_holdings() {
+        let cb = CircuitBreaker::new(test_config());
+
+        cb.record_fill(&fill("market1", "yes", 10.0)).await;
+        cb.record_fill(&fill("market1", "no", 7.0)).await;
+
+        let pos = cb.position("market1").await;
+        assert_eq!((pos.poly_yes, pos.poly_no, pos.kalshi_yes, pos.kalshi_no), (10.0, 7.0, 0.0, 0.0));
+        assert_eq!(pos.unmatched(), 3);
+
+        // Auto-close sells the excess
+        cb.record_fill(&fill("market1", "yes", -3.0)).await;
+        let pos = cb.position("market1").await;
+        assert_eq!((pos.poly_yes, pos.poly_no), (7.0, 7.0));
+        assert_eq!(pos.unmatched(), 0);
+
+        let status = cb.status().await;
+        assert_eq!(status.total_position, 14);
+        assert_eq!(status.unmatched_position, 0);
+    }
+
+    /// Test: Fractional fills accumulate exactly instead of rounding each fill
+    #[tokio::test]
+    async fn test_fractional_fills_accumulate_exactly() {
+        let cb = CircuitBreaker::new(test_config());
+
+        for _ in 0..5 {
+            cb.record_fill(&fill("market1", "yes", 0.4)).await;
+        }
+        let pos = cb.position("market1").await;
+        assert!((pos.poly_yes - 2.0).abs() < 1e-9, "Five 0.4 fills are 2 contracts, not 0");
+        assert_eq!(pos.unmatched(), 2);
+
+        // Selling the exact holdings leaves the market flat
+        cb.record_fill(&fill("market1", "yes", -2.0)).await;
+        assert_eq!(cb.status().await.market_count, 0);
+    }
+
+    /// Test: Unhedged exposure above the per-market limit blocks that market only
+    #[tokio::test]
+    async fn test_blocks_unmatched_per_market_limit() {
+        let cb = CircuitBreaker::new(test_config())
+            .with_markets(&market_pairs(&["market1", "market2"]));
+
+        cb.record_fill(&fill("market1", "yes", 25.0)).await;
+
+        assert!(matches!(
+            cb.can_execute("market1", 1, 45, 50).await,
+            Err(TripReason::MaxUnmatchedPerMarket { unmatched: 25, limit: 20, .. })
+        ));
+        assert!(cb.can_execute("market2", 1, 45, 50).await.is_ok(), "Other markets keep trading");
+
+        // Closing part of the excess brings the market back under the limit
+        cb.record_fill(&fill("market1", "yes", -10.0)).await;
+        assert!(cb.can_execute("market1", 1, 45, 50).await.is_ok());
+    }
+
+    /// Test: Unhedged exposure summed across markets blocks every market
+    #[tokio::test]
+    async fn test_blocks_unmatched_total_limit() {
+        let cb = CircuitBreaker::new(test_config())
+            .with_markets(&market_pairs(&["market4"]));
+
+        cb.record_fill(&fill("market1", "yes", 15.0)).await;
+        cb.record_fill(&fill("market2", "no", 12.0)).await;
+        cb.record_fill(&fill("market3", "yes", 5.0)).await;  // Total unmatched: 32
+
+        assert!(matches!(
+            cb.can_execute("market4", 1, 45, 50).await,
+            Err(TripReason::MaxUnmatchedTotal { unmatched: 32, limit: 30 })
+        ));
+        assert!(!cb.status().await.halted, "Exposure limits block trades without halting");
+
+        // Matching the NO excess in market2 frees room
+        cb.record_fill(&fill("market2", "yes", 12.0)).await;
+        assert!(cb.can_execute("market4", 1, 45, 50).await.is_ok());
+    }
+
+    /// Contract limits out of the way so only the dollar limits apply
+    fn notional_config() -> CircuitBreakerConfig {
+        CircuitBreakerConfig {
+            max_position_per_market: 10_000,
+            max_total_position: 10_000,
+            ..test_config()
+        }
+    }
+
+    /// Test: Per-trade notional is priced, not counted
+    #[tokio::test]
+    async fn test_blocks_trade_notional() {
+        let cb = CircuitBreaker::new(notional_config())
+            .with_markets(&market_pairs(&["market1"]));
+
+        // 100 contracts at 2¢ + 3¢ is $5; at 60¢ + 45¢ it is $105
+        assert!(cb.can_execute("market1", 100, 2, 3).await.is_ok());
+        let result = cb.can_execute("market1", 100, 60, 45).await;
+        assert!(matches!(result, Err(TripReason::MaxTradeNotional { notional, .. }) if (notional - 105.0).abs() < 1e-9),
+            "Should block trade above the notional limit: {:?}", result);
+    }
+
+    /// Test: Per-market cost basis and total deployed capital
+    #[tokio::test]
+    async fn test_blocks_market_cost_and_total_deployed() {
+        let cb = CircuitBreaker::new(notional_config())
+            .with_markets(&market_pairs(&["market1", "market4"]));
+
+        record_matched(&cb, "market1", 120, 0.0).await;  // $120 cost basis
+        assert!(cb.can_execute("market1", 20, 45, 50).await.is_ok());
+        assert!(matches!(
+            cb.can_execute("market1", 40, 45, 50).await,
+            Err(TripReason::MaxMarketCost { .. })
+        ), "$120 + $38 exceeds the $150 market limit");
+
+        record_matched(&cb, "market2", 120, 0.0).await;
+        record_matched(&cb, "market3", 120, 0.0).await;
+
+        // Resolved markets are not locked, but their capital is still deployed
+        cb.resolve_market("market1").await;
+        cb.resolve_market("market2").await;
+        let status = cb.status().await;
+        assert!((status.deployed - 360.0).abs() < 1e-9);
+        assert!((status.locked - 120.0).abs() < 1e-9);
+
+        assert!(matches!(
+            cb.can_execute("market4", 50, 45, 50).await,
+            Err(TripReason::MaxTotalDeployed { .. })
+        ), "$360 + $47.50 exceeds the $400 deployed limit");
+    }
+
+    /// Test: Capital locked in unresolved markets, released by resolution and redemption
+    #[tokio::test]
+    async fn test_locked_capital_released_on_resolution() {
+        let cb = CircuitBreaker::new(notional_config())
+            .with_markets(&market_pairs(&["market3"]));
+
+        record_matched(&cb, "market1", 140, 0.0).await;
+        record_matched(&cb, "market2", 140, 0.0).await;  // $280 locked
+
+        assert!(matches!(
+            cb.can_execute("market3", 40, 45, 50).await,
+            Err(TripReason::MaxLockedCapital { .. })
+        ), "$280 + $38 exceeds the $300 locked limit");
+
+        cb.resolve_market("market1").await;
+        assert!(cb.can_execute("market3", 40, 45, 50).await.is_ok());
+
+        // Redemption: the winning side pays $1, the losing side nothing
+        cb.record_fill(&FillRecord::new("market1", "market1", "polymarket", "yes", -140.0, 1.0, 0.0, "redeem")).await;
+        cb.record_fill(&FillRecord::new("market1", "market1", "polymarket", "no", -140.0, 0.0, 0.0, "redeem")).await;
+
+        let status = cb.status().await;
+        assert_eq!(status.market_count, 1, "Redeemed market is flat");
+        assert!((status.deployed - 140.0).abs() < 1e-9);
+        assert!((status.locked - 140.0).abs() < 1e-9);
+    }
+
+    /// Pair on Gamma event `event` starting on `date` (YYYY-MM-DD)
+    fn pair(slug: &str, league: &str, event: &str, date: &str) -> arb_bot::types::MarketPair {
+        arb_bot::types::MarketPair {
+            pair_id: format!("poly-{}", slug).into(),
+            league: league.into(),
+            market_type: arb_bot::types::MarketType::Moneyline,
+            description: slug.into(),
+            poly_slug: slug.into(),
+            poly_yes_token: format!("{}-yes", slug).into(),
+            poly_no_token: format!("{}-no", slug).into(),
+            line_value: None,
+            team_suffix: None,
+            event_id: Some(event.into()),
+            game_date: chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").ok(),
+        }
+    }
+
+    /// Test: Event and game date come from Gamma event metadata, else from the slug
+    #[test]
+    fn test_market_groups_from_gamma_event() {
+        // A late kickoff starts the next day UTC whatever date the slug carries
+        let group = MarketGroup::of(&pair("epl-che-avl-2025-12-08-total-2pt5", "epl", "30615", "2025-12-09")).unwrap();
+        assert_eq!(group.event, "30615");
+        assert_eq!(group.league, "epl");
+        assert_eq!(group.game_date, chrono::NaiveDate::from_ymd_opt(2025, 12, 9).unwrap());
+
+        // Poly prefix maps to the league code
+        let bun = pair("bun-fcb-bvb-2025-12-08", "bun", "30616", "2025-12-08");
+        assert_eq!(MarketGroup::of(&bun).unwrap().league, "bundesliga");
+
+        // No event metadata: event and date from the slug
+        let mut from_slug = pair("epl-che-avl-2025-12-08-total-2pt5", "epl", "", "");
+        from_slug.event_id = None;
+        assert_eq!(from_slug.game_date, None);
+        let group = MarketGroup::of(&from_slug).unwrap();
+        assert_eq!(group.event, "epl-che-avl-2025-12-08");
+        assert_eq!(group.league, "epl");
+        assert_eq!(group.game_date, chrono::NaiveDate::from_ymd_opt(2025, 12, 8).unwrap());
+        let undated = pair("epl-che-avl-2025-12-08", "epl", "30615", "");
+        assert_eq!(MarketGroup::of(&undated).unwrap().event, "30615");
+
+        // Nor a date in the slug: no group
+        let mut ungrouped = pair("will-it-rain", "misc", "", "");
+        ungrouped.event_id = None;
+        assert_eq!(MarketGroup::of(&ungrouped), None);
+    }
+
+    /// Test: The league table is checked at startup, per-league caps included
+    #[test]
+    fn test_validate_league_configs() {
+        use arb_bot::config::{LeagueConfig, get_league_configs, validate_league_configs};
+
+        assert!(validate_league_configs(&get_league_configs()).is_ok());
+
+        let league = |code, prefix, max_cost| LeagueConfig { league_code: code, poly_prefix: prefix, max_cost };
+        assert!(validate_league_configs(&[league("epl", "epl", Some(5000.0)), league("bundesliga", "bun", None)]).is_ok());
+        for bad in [0.0, -10.0, f64::NAN] {
+            assert!(validate_league_configs(&[league("epl", "epl", Some(bad))]).is_err(), "max_cost {}", bad);
+        }
+        let err = validate_league_configs(&[league("epl", "epl", None), league("eflc", "epl", None)]).unwrap_err();
+        assert!(err.to_string().contains("\"epl\""), "{}", err);
+    }
+
+    /// Test: A market with no event or date to group it by is rejected, not left unlimited
+    #[tokio::test]
+    async fn test_rejects_ungrouped_market() {
+        let mut ungrouped = pair("will-it-rain", "misc", "", "");
+        ungrouped.event_id = None;
+        let markets = [ungrouped, pair("epl-che-avl-2025-12-08", "epl", "30615", "2025-12-08")];
+        let cb = CircuitBreaker::new(notional_config()).with_markets(&markets);
+
+        assert!(matches!(
+            cb.can_execute("poly-will-it-rain", 10, 45, 50).await,
+            Err(TripReason::UngroupedMarket { ref market }) if market == "poly-will-it-rain"
+        ));
+        assert!(matches!(
+            cb.can_execute("unregistered", 10, 45, 50).await,
+            Err(TripReason::UngroupedMarket { ref market }) if market == "unregistered"
+        ));
+        assert!(cb.can_execute("poly-epl-che-avl-2025-12-08", 10, 45, 50).await.is_ok());
+        assert!(!cb.status().await.halted, "Rejects the trade without halting");
+    }
+
+    /// Test: Markets discovered without event metadata share their slug's event limit
+    #[tokio::test]
+    async fn test_slug_grouped_markets_share_event_limit() {
+        let mut config = notional_config();
+        config.max_event_cost = 100.0;
+        let mut markets = [
+            pair("epl-che-avl-2025-12-08", "epl", "", ""),
+            pair("epl-che-avl-2025-12-08-total-2pt5", "epl", "", ""),
+            pair("epl-mci-liv-2025-12-08", "epl", "", ""),
+        ];
+        for market in &mut markets {
+            market.event_id = None;
+        }
+        let cb = CircuitBreaker::new(config).with_markets(&markets);
+
+        record_matched(&cb, "poly-epl-che-avl-2025-12-08", 80, 0.0).await;
+        assert!(matches!(
+            cb.can_execute("poly-epl-che-avl-2025-12-08-total-2pt5", 30, 45, 50).await,
+            Err(TripReason::MaxEventExposure { ref event, .. }) if event == "epl-che-avl-2025-12-08"
+        ));
+        assert!(cb.can_execute("poly-epl-mci-liv-2025-12-08", 30, 45, 50).await.is_ok());
+    }
+
+    /// Test: Correlated exposure is limited per event, league and game date
+    #[tokio::test]
+    async fn test_blocks_correlated_exposure() {
+        let mut config = notional_config();
+        config.max_event_cost = 100.0;
+        config.max_league_cost = 180.0;
+        config.max_date_cost = 250.0;
+        config.league_cost_limits.insert("bundesliga".into(), 50.0);
+
+        let markets = [
+            pair("epl-che-avl-2025-12-08", "epl", "30615", "2025-12-08"),
+            pair("epl-che-avl-2025-12-08-total-2pt5", "epl", "30615", "2025-12-08"),
+            pair("epl-mci-liv-2025-12-08", "epl", "30617", "2025-12-08"),
+            pair("epl-ars-tot-2025-12-09", "epl", "30618", "2025-12-09"),
+            pair("bun-fcb-bvb-2025-12-08", "bun", "30616", "2025-12-08"),
+            pair("nba-lal-bos-2025-12-08", "nba", "30620", "2025-12-08"),
+        ];
+        let cb = CircuitBreaker::new(config).with_markets(&markets);
+
+        // $80 on Chelsea-Villa moneyline: the total on the same game shares the event limit
+        record_matched(&cb, "poly-epl-che-avl-2025-12-08", 80, 0.0).await;
+        assert!(matches!(
+            cb.can_execute("poly-epl-che-avl-2025-12-08-total-2pt5", 30, 45, 50).await,
+            Err(TripReason::MaxEventExposure { ref event, .. }) if event == "30615"
+        ));
+        assert!(cb.can_execute("poly-epl-mci-liv-2025-12-08", 30, 45, 50).await.is_ok(), "Other game, same league");
+
+        // $170 in the EPL leaves no room for another $19 EPL game on a different date
+        record_matched(&cb, "poly-epl-mci-liv-2025-12-08", 90, 0.0).await;
+        assert!(matches!(
+            cb.can_execute("poly-epl-ars-tot-2025-12-09", 20, 45, 50).await,
+            Err(TripReason::MaxLeagueExposure { ref league, .. }) if league == "epl"
+        ));
+
+        // Per-league override
+        assert!(matches!(
+            cb.can_execute("poly-bun-fcb-bvb-2025-12-08", 60, 45, 50).await,
+            Err(TripReason::MaxLeagueExposure { ref league, limit, .. }) if league == "bundesliga" && limit == 50.0
+        ));
+
+        // $170 on 2025-12-08 games + $85.50 of NBA on the same date
+        assert!(matches!(
+            cb.can_execute("poly-nba-lal-bos-2025-12-08", 90, 45, 50).await,
+            Err(TripReason::MaxDateExposure { ref date, .. }) if date == "2025-12-08"
+        ));
+
+        // Resolved games no longer count
+        cb.resolve_market("poly-epl-che-avl-2025-12-08").await;
+        cb.resolve_market("poly-epl-mci-liv-2025-12-08").await;
+        assert!(cb.can_execute("poly-nba-lal-bos-2025-12-08", 90, 45, 50).await.is_ok());
+        assert!(cb.can_execute("poly-epl-ars-tot-2025-12-09", 20, 45, 50).await.is_ok());
+    }
+
     /// Test: Consecutive errors trip the breaker
     #[tokio::test]
     async fn test_consecutive_errors_trip() {
@@ -226,7 +706,7 @@ mod circuit_breaker_tests {
         cb.record_error().await;
         
         // Record success
-        cb.record_success("market1", 10, 10, 0.50).await;
+        record_matched(&cb, "market1", 10, 0.50).await;
         
         // Error count should be reset
         let status = cb.status().await;
@@ -266,7 +746,7 @@ mod circuit_breaker_tests {
         let cb = CircuitBreaker::new(config);
         
         // Should allow even excessive trades
-        let result = cb.can_execute("market1", 1000).await;
+        let result = cb.can_execute("market1", 1000, 45, 50).await;
         assert!(result.is_ok(), "Disabled CB should allow all trades");
         
         // Errors shouldn't trip it
@@ -276,6 +756,269 @@ mod circuit_breaker_tests {
         cb.record_error().await;
         assert!(cb.is_trading_allowed(), "Disabled CB should never halt");
     }
+    /// Test: Disabled market is rejected without halting other markets
+    #[tokio::test]
+    async fn test_disabled_market_does_not_halt() {
+        let cb = CircuitBreaker::new(test_config())
+            .with_markets(&market_pairs(&["closed-market", "other-market"]));
+
+        cb.disable_market("closed-market", "Market closed").await;
+
+        let result = cb.can_execute("closed-market", 1, 45, 50).await;
+        assert!(matches!(result, Err(TripReason::MarketDisabled { .. })));
+        assert!(cb.can_execute("other-market", 1, 45, 50).await.is_ok());
+        assert!(cb.is_trading_allowed(), "Market-specific error must not halt trading");
+
+        assert!(cb.enable_market("closed-market").await);
+        assert!(cb.can_execute("closed-market", 1, 45, 50).await.is_ok());
+    }
+
+    fn persisted_config(name: &str) -> CircuitBreakerConfig {
+        let path = std::env::temp_dir().join(format!("arb_cb_{}_{}.json", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        CircuitBreakerConfig { state_file: path.to_string_lossy().into_owned(), ..test_config() }
+    }
+
+    fn now_ms() -> u64 {
+        std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_millis() as u64
+    }
+
+    fn write_state(config: &CircuitBreakerConfig, state: &BreakerState) {
+        std::fs::write(&config.state_file, serde_json::to_string(state).unwrap()).unwrap();
+    }
+
+    /// Test: Positions, P&L and disabled markets survive a restart
+    #[tokio::test]
+    async fn test_state_survives_restart() {
+        let config = persisted_config("restart");
+        let cb = CircuitBreaker::load(config.clone());
+        record_matched(&cb, "market1", 30, 1.25).await;
+        cb.record_pnl(-3.0);
+        cb.disable_market("closed-market", "Market closed").await;
+        cb.save().await.unwrap();
+
+        let restored = CircuitBreaker::load(config.clone());
+        let status = restored.status().await;
+        assert!(!status.halted);
+        assert!((status.daily_pnl + 1.75).abs() < 1e-9);
+        assert_eq!(status.total_position, 60);
+        assert_eq!(restored.snapshot().await, cb.snapshot().await);
+
+        // Restored exposure still counts against the limits
+        let result = restored.can_execute("market1", 10, 45, 50).await;
+        assert!(matches!(result, Err(TripReason::MaxPositionPerMarket { .. })));
+        assert!(matches!(restored.can_execute("closed-market", 1, 45, 50).await, Err(TripReason::MarketDisabled { .. })));
+        let _ = std::fs::remove_file(&config.state_file);
+    }
+
+    /// Test: A trip is saved immediately and stays halted across restarts until reset
+    #[tokio::test]
+    async fn test_halt_survives_restart_until_reset() {
+        let config = persisted_config("halt");
+        let cb = CircuitBreaker::load(config.clone());
+        cb.trip(TripReason::MaxDailyLoss { loss: 30.0, limit: 25.0 }).await;
+
+        let restored = CircuitBreaker::load(config.clone());
+        assert!(!restored.is_trading_allowed());
+        let result = restored.can_execute("market1", 1, 45, 50).await;
+        assert!(matches!(result, Err(TripReason::MaxDailyLoss { .. })));
+        assert!(!restored.check_cooldown().await, "Cooldown runs from the original trip");
+
+        restored.reset().await;
+        assert!(CircuitBreaker::load(config.clone()).is_trading_allowed());
+
+        // A manual halt is saved on its own and only clearing it resumes
+        restored.halt("test").await;
+        let restored = CircuitBreaker::load(config.clone());
+        assert!(restored.status().await.manual_halt);
+        assert!(restored.clear_manual_halt().await);
+        assert!(CircuitBreaker::load(config.clone()).is_trading_allowed());
+        let _ = std::fs::remove_file(&config.state_file);
+    }
+
+    /// Test: Cooldown counts from the original trip time, not the restart
+    #[tokio::test]
+    async fn test_cooldown_continues_after_restart() {
+        let config = persisted_config("cooldown");
+        write_state(&config, &BreakerState {
+            halted: true,
+            trip_reason: Some(TripReason::ConsecutiveErrors { count: 3, limit: 3 }),
+            tripped_at_ms: Some(now_ms() - 120_000),
+            ..Default::default()
+        });
+
+        let restored = CircuitBreaker::load(config.clone());
+        assert!(!restored.is_trading_allowed());
+        assert!(restored.check_cooldown().await, "60s cooldown already elapsed before restart");
+        assert!(restored.is_trading_allowed());
+        let _ = std::fs::remove_file(&config.state_file);
+    }
+
+    /// Test: A new trading day clears daily P&L but not a halt
+    #[tokio::test]
+    async fn test_restore_applies_trading_day_rollover() {
+        let config = persisted_config("rollover");
+        write_state(&config, &BreakerState {
+            halted: true,
+            trip_reason: Some(TripReason::MaxDailyLoss { loss: 50.0, limit: 25.0 }),
+            tripped_at_ms: Some(now_ms() - 1_000),
+            daily_pnl_cents: -5_000,
+            trading_date: "2020-01-01".to_string(),
+            ..Default::default()
+        });
+
+        let restored = CircuitBreaker::load(config.clone());
+        let status = restored.status().await;
+        assert_eq!(status.daily_pnl, 0.0);
+        assert!(status.halted, "Halt stays until reset or cooldown");
+        assert!(!restored.roll_trading_day(), "Already rolled to today on restore");
+        let _ = std::fs::remove_file(&config.state_file);
+    }
+
+    /// Test: Only recoverable reason classes reset after the cooldown
+    #[tokio::test]
+    async fn test_cooldown_resets_recoverable_reasons_only() {
+        let mut config = test_config();
+        config.cooldown_secs = 0;
+        config.auto_reset = vec!["consecutive_errors".into(), "manual_halt".into()];
+        let cb = CircuitBreaker::new(config);
+
+        cb.trip(TripReason::ConsecutiveErrors { count: 3, limit: 3 }).await;
+        assert!(cb.check_cooldown().await, "consecutive_errors is recoverable");
+        assert!(cb.is_trading_allowed());
+
+        cb.trip(TripReason::ExchangeError { kind: "auth".into(), message: "invalid api key".into() }).await;
+        assert!(!cb.check_cooldown().await, "exchange_error is not listed");
+
+        cb.reset().await;
+        cb.trip(TripReason::ManualHalt).await;
+        assert!(!cb.check_cooldown().await, "Hard halts ignore auto_reset");
+        assert!(!cb.is_trading_allowed());
+    }
+
+    /// Test: Hitting the daily loss limit halts, and the halt outlives the cooldown
+    #[tokio::test]
+    async fn test_daily_loss_halt_is_sticky() {
+        let mut config = test_config();
+        config.cooldown_secs = 0;
+        config.auto_reset = vec!["max_daily_loss".into()];
+        let cb = CircuitBreaker::new(config)
+            .with_markets(&market_pairs(&["market1"]));
+
+        cb.record_pnl(-30.0);
+        assert!(matches!(cb.can_execute("market1", 1, 45, 50).await, Err(TripReason::MaxDailyLoss { .. })));
+        assert!(!cb.is_trading_allowed(), "Daily loss is a halt, not just a rejection");
+
+        cb.reset_daily_pnl();
+        assert!(!cb.check_cooldown().await);
+        assert!(matches!(cb.can_execute("market1", 1, 45, 50).await, Err(TripReason::MaxDailyLoss { .. })));
+    }
+
+    /// Test: The supervisor task resets a recoverable halt without any trading activity
+    #[tokio::test]
+    async fn test_supervisor_auto_resets_after_cooldown() {
+        let mut config = test_config();
+        config.cooldown_secs = 0;
+        let cb = std::sync::Arc::new(CircuitBreaker::new(config));
+        let supervisor = tokio::spawn(supervisor_loop(cb.clone()));
+
+        cb.record_error().await;
+        cb.record_error().await;
+        cb.record_error().await;
+        assert!(!cb.is_trading_allowed());
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while !cb.is_trading_allowed() && std::time::Instant::now() < deadline {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        supervisor.abort();
+        assert!(cb.is_trading_allowed(), "Supervisor should auto-reset after the cooldown");
+        assert_eq!(cb.status().await.consecutive_errors, 0);
+    }
+
+    /// Test: Rollover timezone offsets
+    #[test]
+    fn test_parse_rollover_tz() {
+        assert_eq!(parse_rollover_tz("UTC"), Some(chrono_tz::Tz::UTC));
+        assert_eq!(parse_rollover_tz(" America/New_York "), Some(chrono_tz::Tz::America__New_York));
+        assert!(parse_rollover_tz("+02:00").is_none());
+        assert!(parse_rollover_tz("Mars/Olympus_Mons").is_none());
+    }
+
+    /// Test: The trading day rolls at local midnight on both sides of a DST change
+    #[test]
+    fn test_trading_date_follows_dst() {
+        let tz = parse_rollover_tz("America/New_York").unwrap();
+        let at = |s: &str| s.parse::<chrono::DateTime<chrono::Utc>>().unwrap();
+        let date = |s: &str| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+
+        // Summer (EDT, UTC-4): midnight is 04:00 UTC
+        assert_eq!(trading_date(tz, at("2026-07-01T03:59:00Z")), date("2026-06-30"));
+        assert_eq!(trading_date(tz, at("2026-07-01T04:00:00Z")), date("2026-07-01"));
+        // Winter (EST, UTC-5): midnight is 05:00 UTC
+        assert_eq!(trading_date(tz, at("2026-01-01T04:30:00Z")), date("2025-12-31"));
+        assert_eq!(trading_date(tz, at("2026-01-01T05:00:00Z")), date("2026-01-01"));
+    }
+
+    /// Test: The trading day follows the configured timezone
+    #[tokio::test]
+    async fn test_trading_day_uses_rollover_tz() {
+        // UTC+14 and UTC-12 are always on different calendar days
+        let ahead = trading_date(parse_rollover_tz("Pacific/Kiritimati").unwrap(), chrono::Utc::now())
+            .format("%Y-%m-%d")
+            .to_string();
+
+        for (tz, kept) in [("Pacific/Kiritimati", true), ("Etc/GMT+12", false)] {
+            let mut config = persisted_config(&format!("tz_{}", tz.replace(['/', '+'], "_")));
+            config.rollover_tz = parse_rollover_tz(tz).unwrap();
+            write_state(&config, &BreakerState {
+                daily_pnl_cents: -1_000,
+                trading_date: ahead.clone(),
+                ..Default::default()
+            });
+
+            let restored = CircuitBreaker::load(config.clone());
+            let expected = if kept { -10.0 } else { 0.0 };
+            assert_eq!(restored.status().await.daily_pnl, expected, "rollover_tz {}", tz);
+            let _ = std::fs::remove_file(&config.state_file);
+        }
+    }
+
+    /// Test: Missing or unreadable state starts fresh
+    #[tokio::test]
+    async fn test_unreadable_state_starts_fresh() {
+        let config = persisted_config("corrupt");
+        assert!(CircuitBreaker::load(config.clone()).is_trading_allowed());
+
+        std::fs::write(&config.state_file, "{not json").unwrap();
+        let cb = CircuitBreaker::load(config.clone());
+        assert!(cb.is_trading_allowed());
+        assert_eq!(cb.status().await.total_position, 0);
+        let _ = std::fs::remove_file(&config.state_file);
+    }
+
+    /// Test: The persist loop flushes changes without an explicit save
+    #[tokio::test]
+    async fn test_persist_loop_flushes_changes() {
+        let config = persisted_config("loop");
+        let cb = std::sync::Arc::new(CircuitBreaker::load(config.clone()));
+        let handle = tokio::spawn(persist_loop(cb.clone()));
+        record_matched(&cb, "market1", 5, 0.10).await;
+
+        let mut saved = None;
+        for _ in 0..50 {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            if let Ok(json) = std::fs::read_to_string(&config.state_file) {
+                saved = serde_json::from_str::<BreakerState>(&json).ok();
+                if saved.is_some() { break; }
+            }
+        }
+        handle.abort();
+        let saved = saved.expect("state flushed");
+        assert_eq!(saved.positions["market1"].total_contracts(), 10);
+        assert_eq!(saved.daily_pnl_cents, 10);
+        let _ = std::fs::remove_file(&config.state_file);
+    }
 }
 
 // ============================================================================
@@ -283,36 +1026,35 @@ mod circuit_breaker_tests {
 // ============================================================================
 
 mod e2e_tests {
+    use super::fixtures::{breaker_config, market_pairs, position_tracker};
     use arb_bot::position_tracker::*;
     use arb_bot::circuit_breaker::*;
+    use super::circuit_breaker_tests::record_matched;
 
     /// Scenario: Circuit breaker halts trading after losses
     #[tokio::test]
     async fn test_circuit_breaker_halts_on_losses() {
         let config = CircuitBreakerConfig {
-            max_position_per_market: 100,
-            max_total_position: 500,
             max_daily_loss: 10.0,  // Low threshold for test
-            max_consecutive_errors: 5,
-            cooldown_secs: 60,
-            enabled: true,
+            ..breaker_config()
         };
         
-        let cb = CircuitBreaker::new(config);
+        let cb = CircuitBreaker::new(config)
+            .with_markets(&market_pairs(&["market3", "market4"]));
         
         // Simulate a series of losing trades
         // (In reality this would come from actual fill data)
-        cb.record_success("market1", 10, 10, -3.0).await;  // -$3
-        cb.record_success("market2", 10, 10, -4.0).await;  // -$7 cumulative
+        record_matched(&cb, "market1", 10, -3.0).await;  // -$3
+        record_matched(&cb, "market2", 10, -4.0).await;  // -$7 cumulative
         
         // Should still be allowed
-        assert!(cb.can_execute("market3", 10).await.is_ok());
+        assert!(cb.can_execute("market3", 10, 45, 50).await.is_ok());
         
         // One more loss pushes over the limit
-        cb.record_success("market3", 10, 10, -5.0).await;  // -$12 cumulative
+        record_matched(&cb, "market3", 10, -5.0).await;  // -$12 cumulative
         
         // Now should be blocked due to max daily loss
-        let result = cb.can_execute("market4", 10).await;
+        let result = cb.can_execute("market4", 10, 45, 50).await;
         assert!(matches!(result, Err(TripReason::MaxDailyLoss { .. })),
             "Should halt due to max daily loss");
     }
@@ -320,7 +1062,7 @@ mod e2e_tests {
     /// Scenario: Partial fill creates exposure warning (Poly YES vs Poly NO)
     #[tokio::test]
     async fn test_partial_fill_exposure_tracking() {
-        let mut tracker = PositionTracker::new();
+        let mut tracker = position_tracker();
         
         // Full fill on YES side
         tracker.record_fill(&FillRecord::new(
@@ -367,12 +1109,13 @@ mod e2e_tests {
 // ============================================================================
 
 mod fill_accuracy_tests {
+    use super::fixtures::position_tracker;
     use arb_bot::position_tracker::*;
     
     /// Test: Actual fill price different from expected
     #[test]
     fn test_fill_price_slippage() {
-        let mut tracker = PositionTracker::new();
+        let mut tracker = position_tracker();
         
         // Expected: buy at 45¢, but actually filled at 47¢ (slippage)
         tracker.record_fill(&FillRecord::new(
@@ -413,7 +1156,7 @@ mod fill_accuracy_tests {
     /// Test: Polymarket has no fees
     #[test]
     fn test_polymarket_no_fees() {
-        let mut tracker = PositionTracker::new();
+        let mut tracker = position_tracker();
         
         // Polymarket reports no fees
         tracker.record_fill(&FillRecord::new(
@@ -456,6 +1199,8 @@ mod infra_integration_tests {
             poly_no_token: "arb_no_token".into(),
             line_value: None,
             team_suffix: Some("CFC".into()),
+            event_id: None,
+            game_date: None,
         };
 
         let market_id = state.add_pair(pair).unwrap();
@@ -518,53 +1263,353 @@ mod infra_integration_tests {
         assert_eq!(arb_mask, 0, "Should return 0 when any price is missing");
     }
 
-    // =========================================================================
-    // FastExecutionRequest Tests
-    // =========================================================================
-
-    /// Test: FastExecutionRequest calculates profit correctly (PolyOnly)
+    /// Test: a side the feed stopped updating blocks detection until it's refreshed
     #[test]
-    fn test_execution_request_profit_calculation() {
-        // Poly YES 45¢ + Poly NO 50¢ = 95¢
-        // Profit = 100 - 95 = 5¢ (no fees!)
-        let req = FastExecutionRequest {
-            market_id: 0,
-            yes_price: 45,
-            no_price: 50,
-            yes_size: 1000,
-            no_size: 1000,
-            arb_type: ArbType::PolyOnly,
-            detected_ns: 0,
-        };
+    fn test_check_fresh_arbs_skips_stale_side() {
+        let (state, market_id) = setup_market(48, 50);
+        let market = state.get_by_id(market_id).unwrap();
+        const SEC: u64 = 1_000_000_000;
 
-        assert_eq!(req.profit_cents(), 5, "Profit should be 5¢");
-    }
+        // Never stamped (prices stored directly): no age, so nothing is stale
+        assert_eq!(market.poly.age_ns(10 * SEC), None);
+        assert!(market.check_fresh_arbs(100, 10 * SEC, SEC) & 4 != 0);
 
-    /// Test: FastExecutionRequest handles negative profit
-    #[test]
-    fn test_execution_request_negative_profit() {
-        // Prices too high - no profit
-        let req = FastExecutionRequest {
-            market_id: 0,
-            yes_price: 52,
-            no_price: 52,
-            yes_size: 1000,
-            no_size: 1000,
-            arb_type: ArbType::PolyOnly,
-            detected_ns: 0,
-        };
+        market.poly.touch_yes(SEC);
+        market.poly.touch_no(5 * SEC);
+        assert_eq!(market.poly.side_ages_ns(6 * SEC), (Some(5 * SEC), Some(SEC)));
+        assert_eq!(market.poly.age_ns(6 * SEC), Some(5 * SEC), "Age is the older side's");
 
-        assert!(req.profit_cents() < 0, "Should calculate negative profit");
-    }
+        assert_eq!(market.check_fresh_arbs(100, 6 * SEC, 2 * SEC), 0, "YES is 5s old");
+        assert!(market.check_fresh_arbs(100, 6 * SEC, 0) & 4 != 0, "0 disables the limit");
 
-    // =========================================================================
-    // GlobalStateLookup Tests
-    // =========================================================================
+        market.poly.touch_yes(6 * SEC);
+        assert!(market.check_fresh_arbs(100, 6 * SEC, 2 * SEC) & 4 != 0, "Refreshed YES");
+    }
 
-    /// Test: GlobalState lookup by Poly token hashes
+    /// Test: each side whose feed has been silent too long is due a resubscribe
     #[test]
-    fn test_lookup_by_poly_hashes() {
-        let (state, market_id) = setup_market(50, 50);
+    fn test_quiet_tokens() {
+        use arb_bot::polymarket::QuietResync;
+        const SEC: u64 = 1_000_000_000;
+
+        let (state, market_id) = setup_market(48, 50);
+        let market = state.get_by_id(market_id).unwrap();
+        let quiet_tokens = |now_ns| QuietResync::new(30 * SEC).due(&state, now_ns);
+
+        // Never updated: quiet once the clock is past the threshold
+        assert!(quiet_tokens(10 * SEC).is_empty());
+        assert_eq!(quiet_tokens(40 * SEC), vec!["arb_yes_token", "arb_no_token"]);
+
+        market.poly.touch_yes(35 * SEC);
+        market.poly.touch_no(5 * SEC);
+        assert_eq!(quiet_tokens(40 * SEC), vec!["arb_no_token"]);
+    }
+
+    /// Test: a token is resubscribed once quiet, backs off while nothing arrives, and
+    /// returns to the normal schedule once a snapshot refreshes it
+    #[test]
+    fn test_quiet_resync_backs_off_per_token() {
+        use arb_bot::polymarket::QuietResync;
+        const SEC: u64 = 1_000_000_000;
+
+        let (state, market_id) = setup_market(48, 50);
+        let market = state.get_by_id(market_id).unwrap();
+        let mut resync = QuietResync::new(30 * SEC);
+        market.poly.touch_yes(0);
+        market.poly.touch_no(0);
+
+        assert!(resync.due(&state, 20 * SEC).is_empty());
+        assert_eq!(resync.due(&state, 40 * SEC), vec!["arb_yes_token", "arb_no_token"]);
+
+        // Only YES gets a snapshot back; NO waits 60s, then 120s
+        market.poly.touch_yes(41 * SEC);
+        assert!(resync.due(&state, 70 * SEC).is_empty());
+        assert_eq!(resync.due(&state, 80 * SEC), vec!["arb_yes_token"], "Refreshed YES is on the normal schedule");
+        assert_eq!(resync.due(&state, 100 * SEC), vec!["arb_no_token"]);
+        assert_eq!(resync.backing_off(), 1);
+        assert_eq!(resync.due(&state, 200 * SEC), vec!["arb_yes_token"], "NO waits 120s now");
+        assert_eq!(resync.due(&state, 220 * SEC), vec!["arb_no_token"]);
+
+        // Capped at 16x the quiet period
+        for now in [460, 940, 1420] {
+            market.poly.touch_yes(now * SEC);
+            assert_eq!(resync.due(&state, now * SEC), vec!["arb_no_token"], "t={}s", now);
+        }
+    }
+
+    /// Test: an unchanged book kept alive only by resubscribe snapshots stays tradable
+    #[cfg(feature = "replay")]
+    #[tokio::test(start_paused = true)]
+    async fn test_resnapshot_keeps_quiet_book_fresh() {
+        use arb_bot::execution::{NanoClock, create_execution_channel};
+        use arb_bot::polymarket::process_frame;
+        use std::time::Duration;
+
+        let (mut state, _) = setup_market(NO_PRICE, NO_PRICE);
+        state.max_quote_age_ns = 60_000_000_000;
+        let clock = NanoClock::simulated();
+        let (exec_tx, mut exec_rx) = create_execution_channel();
+        let snapshot = r#"[{"asset_id":"arb_yes_token","bids":[],"asks":[{"price":"0.48","size":"100"}]},
+                           {"asset_id":"arb_no_token","bids":[],"asks":[{"price":"0.50","size":"100"}]}]"#;
+
+        process_frame(&state, snapshot, &exec_tx, 100, &clock, None, None).await;
+        assert!(exec_rx.try_recv().is_ok());
+        for _ in 0..3 {
+            tokio::time::advance(Duration::from_secs(30)).await;
+            process_frame(&state, snapshot, &exec_tx, 100, &clock, None, None).await;
+            let req = exec_rx.try_recv().expect("resnapshotted book is fresh");
+            assert_eq!((req.yes_price, req.no_price), (48, 50));
+        }
+
+        // 90s since the book last changed; without another snapshot it goes stale
+        let market = &state.markets[0];
+        tokio::time::advance(Duration::from_secs(61)).await;
+        assert_eq!(market.check_fresh_arbs(100, clock.now_ns(), state.max_quote_age_ns), 0);
+    }
+
+    /// Test: detection from WS frames ignores an arb whose other side has gone quiet
+    #[cfg(feature = "replay")]
+    #[tokio::test(start_paused = true)]
+    async fn test_process_frame_skips_stale_quote() {
+        use arb_bot::execution::{NanoClock, create_execution_channel};
+        use arb_bot::polymarket::process_frame;
+        use std::time::Duration;
+
+        let (mut state, _) = setup_market(NO_PRICE, NO_PRICE);
+        state.max_quote_age_ns = 1_000_000_000;
+        let clock = NanoClock::simulated();
+        let (exec_tx, mut exec_rx) = create_execution_channel();
+        let book = |token: &str, price: &str| format!(
+            r#"[{{"asset_id":"{}","bids":[],"asks":[{{"price":"{}","size":"100"}}]}}]"#, token, price,
+        );
+
+        process_frame(&state, &book("arb_yes_token", "0.48"), &exec_tx, 100, &clock, None, None).await;
+        tokio::time::advance(Duration::from_secs(2)).await;
+        process_frame(&state, &book("arb_no_token", "0.50"), &exec_tx, 100, &clock, None, None).await;
+        assert!(exec_rx.try_recv().is_err(), "YES quote is 2s old - no arb");
+
+        // Any change on the quiet token counts as an update, even one that leaves the ask alone
+        let bid = r#"{"event_type":"price_change","price_changes":[{"asset_id":"arb_yes_token","price":"0.40","size":"10","side":"BUY"}]}"#;
+        process_frame(&state, bid, &exec_tx, 100, &clock, None, None).await;
+        process_frame(&state, &book("arb_no_token", "0.50"), &exec_tx, 100, &clock, None, None).await;
+        let req = exec_rx.try_recv().expect("arb once both sides are fresh");
+        assert_eq!((req.yes_price, req.no_price), (48, 50));
+        assert_eq!(req.detected_ns, clock.now_ns());
+
+        // Shutdown: books still update but no arbs are sent
+        state.stop_detection();
+        process_frame(&state, &book("arb_no_token", "0.49"), &exec_tx, 100, &clock, None, None).await;
+        assert!(exec_rx.try_recv().is_err());
+        assert_eq!(state.markets[0].poly.load().1, 49);
+    }
+
+    /// Price change frame for one level on the ask side, as the market channel sends it
+    fn ask_change(token: &str, price: &str, size: &str) -> String {
+        format!(
+            r#"{{"event_type":"price_change","price_changes":[{{"asset_id":"{}","price":"{}","size":"{}","side":"SELL"}}]}}"#,
+            token, price, size,
+        )
+    }
+
+    /// Test: asks arrive as "SELL" on the wire and improve the quote; "BUY" levels never do
+    #[tokio::test]
+    async fn test_price_change_sell_is_ask_side() {
+        use arb_bot::execution::{NanoClock, create_execution_channel};
+        use arb_bot::polymarket::process_frame;
+
+        let (state, _) = setup_market(48, 50);
+        let clock = NanoClock::new();
+        let (exec_tx, mut exec_rx) = create_execution_channel();
+
+        let bid = r#"{"event_type":"price_change","price_changes":[{"asset_id":"arb_yes_token","price":"0.30","size":"10","side":"BUY"}]}"#;
+        process_frame(&state, bid, &exec_tx, 100, &clock, None, None).await;
+        assert_eq!(state.markets[0].poly.load().0, 48, "A bid is not an ask");
+
+        process_frame(&state, &ask_change("arb_yes_token", "0.45", "10"), &exec_tx, 100, &clock, None, None).await;
+        assert_eq!(state.markets[0].poly.load().0, 45);
+        let req = exec_rx.try_recv().expect("improved ask re-evaluated");
+        assert_eq!((req.yes_price, req.no_price), (45, 50));
+    }
+
+    /// Test: a resized best ask keeps its price and takes the new size
+    #[tokio::test]
+    async fn test_price_change_resizes_best_ask() {
+        use arb_bot::execution::{NanoClock, create_execution_channel};
+        use arb_bot::polymarket::process_frame;
+
+        let (state, _) = setup_market(48, 50);
+        let (exec_tx, _exec_rx) = create_execution_channel();
+        process_frame(&state, &ask_change("arb_no_token", "0.50", "7"), &exec_tx, 100, &NanoClock::new(), None, None).await;
+        let (_, no, _, no_size) = state.markets[0].poly.load();
+        assert_eq!((no, no_size), (50, 700));
+    }
+
+    /// Test: a pulled best ask without a depth book leaves the side unquoted, so no arb fires
+    #[tokio::test]
+    async fn test_price_change_pulled_ask_without_depth() {
+        use arb_bot::execution::{NanoClock, create_execution_channel};
+        use arb_bot::polymarket::process_frame;
+
+        let (state, _) = setup_market(48, 50);
+        let clock = NanoClock::new();
+        let (exec_tx, mut exec_rx) = create_execution_channel();
+
+        process_frame(&state, &ask_change("arb_yes_token", "0.48", "0"), &exec_tx, 100, &clock, None, None).await;
+        let (yes, _, yes_size, _) = state.markets[0].poly.load();
+        assert_eq!((yes, yes_size), (NO_PRICE, 0));
+        assert!(exec_rx.try_recv().is_err(), "No arb on a pulled level");
+
+        // A worse level elsewhere in the book is not mistaken for the best one
+        process_frame(&state, &ask_change("arb_no_token", "0.60", "0"), &exec_tx, 100, &clock, None, None).await;
+        assert_eq!(state.markets[0].poly.load().1, 50);
+    }
+
+    /// Test: a pulled best ask falls back to the next level in the depth book
+    #[tokio::test]
+    async fn test_price_change_pulled_ask_uses_depth_book() {
+        use arb_bot::execution::{NanoClock, create_execution_channel};
+        use arb_bot::paper::DepthBook;
+        use arb_bot::polymarket::process_frame;
+
+        let (state, _) = setup_market(NO_PRICE, NO_PRICE);
+        let clock = NanoClock::new();
+        let depth = DepthBook::new();
+        let (exec_tx, mut exec_rx) = create_execution_channel();
+
+        let yes = r#"[{"asset_id":"arb_yes_token","bids":[],"asks":[{"price":"0.45","size":"100"},{"price":"0.47","size":"20"}]}]"#;
+        let no = r#"[{"asset_id":"arb_no_token","bids":[],"asks":[{"price":"0.50","size":"100"}]}]"#;
+        process_frame(&state, yes, &exec_tx, 100, &clock, Some(&depth), None).await;
+        process_frame(&state, no, &exec_tx, 100, &clock, Some(&depth), None).await;
+        assert_eq!(exec_rx.try_recv().unwrap().yes_price, 45);
+
+        process_frame(&state, &ask_change("arb_yes_token", "0.45", "0"), &exec_tx, 100, &clock, Some(&depth), None).await;
+        let (yes, _, yes_size, _) = state.markets[0].poly.load();
+        assert_eq!((yes, yes_size), (47, 2000));
+        let req = exec_rx.try_recv().expect("arb re-evaluated on the next level");
+        assert_eq!((req.yes_price, req.yes_size), (47, 2000));
+    }
+
+    // =========================================================================
+    // FastExecutionRequest Tests
+    // =========================================================================
+
+    /// Test: FastExecutionRequest calculates profit correctly (PolyOnly)
+    #[test]
+    fn test_execution_request_profit_calculation() {
+        // Poly YES 45¢ + Poly NO 50¢ = 95¢
+        // Profit = 100 - 95 = 5¢ (no fees!)
+        let req = FastExecutionRequest {
+            market_id: 0,
+            yes_price: 45,
+            no_price: 50,
+            yes_size: 1000,
+            no_size: 1000,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
+            arb_type: ArbType::PolyOnly,
+            detected_ns: 0,
+        };
+
+        assert_eq!(req.profit_cents(), 5, "Profit should be 5¢");
+    }
+
+    /// Test: FastExecutionRequest handles negative profit
+    #[test]
+    fn test_execution_request_negative_profit() {
+        // Prices too high - no profit
+        let req = FastExecutionRequest {
+            market_id: 0,
+            yes_price: 52,
+            no_price: 52,
+            yes_size: 1000,
+            no_size: 1000,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
+            arb_type: ArbType::PolyOnly,
+            detected_ns: 0,
+        };
+
+        assert!(req.profit_cents() < 0, "Should calculate negative profit");
+    }
+
+    // =========================================================================
+    // Fee Rate Tests
+    // =========================================================================
+
+    /// Test: poly_fee_cents is symmetric and rounds up
+    #[test]
+    fn test_poly_fee_cents() {
+        // Fee-free markets
+        assert_eq!(poly_fee_cents(50, 0), 0);
+
+        // 200bps at 50¢: 2% × 50¢ = 1¢
+        assert_eq!(poly_fee_cents(50, 200), 1);
+
+        // 200bps at 48¢: 2% × 48¢ = 0.96¢ → rounds up to 1¢
+        assert_eq!(poly_fee_cents(48, 200), 1);
+
+        // Symmetric around 50¢: 10¢ and 90¢ pay the same
+        assert_eq!(poly_fee_cents(10, 1000), poly_fee_cents(90, 1000));
+        assert_eq!(poly_fee_cents(10, 1000), 1);
+    }
+
+    /// Test: fees in the request reduce profit
+    #[test]
+    fn test_execution_request_profit_with_fees() {
+        // Poly YES 45¢ + Poly NO 50¢ = 95¢, 200bps on both legs
+        // Fees: ceil(0.9¢) + ceil(1.0¢) = 2¢ → profit = 3¢
+        let req = FastExecutionRequest {
+            market_id: 0,
+            yes_price: 45,
+            no_price: 50,
+            yes_size: 1000,
+            no_size: 1000,
+            yes_fee_bps: 200,
+            no_fee_bps: 200,
+            arb_type: ArbType::PolyOnly,
+            detected_ns: 0,
+        };
+
+        assert_eq!(req.estimated_fee_cents(), 2);
+        assert_eq!(req.profit_cents(), 3);
+    }
+
+    /// Test: fees eliminate a marginal arb in check_arbs
+    #[test]
+    fn test_check_arbs_includes_fees() {
+        // 48 + 50 = 98¢ → arb at 99¢ threshold without fees
+        let (state, market_id) = setup_market(48, 50);
+        let market = state.get_by_id(market_id).unwrap();
+        assert!(market.check_arbs(99) & 4 != 0, "Fee-free market should be an arb");
+
+        // With 200bps on both sides: 98 + 1 + 1 = 100¢ → no arb
+        market.set_fee_rates(200, 200);
+        assert_eq!(market.fee_rates(), (200, 200));
+        assert_eq!(market.check_arbs(99), 0, "Fees should eliminate marginal arb");
+    }
+
+    /// Test: fee_for_fill charges on the cheaper side of the price
+    #[test]
+    fn test_fee_for_fill() {
+        use arb_bot::polymarket_clob::fee_for_fill;
+
+        assert_eq!(fee_for_fill(0.45, 10.0, 0), 0.0);
+
+        // 100bps × min(0.45, 0.55) × 10 = $0.045
+        assert!((fee_for_fill(0.45, 10.0, 100) - 0.045).abs() < 1e-9);
+
+        // 100bps × min(0.80, 0.20) × 10 = $0.02
+        assert!((fee_for_fill(0.80, 10.0, 100) - 0.02).abs() < 1e-9);
+    }
+
+    // =========================================================================
+    // GlobalStateLookup Tests
+    // =========================================================================
+
+    /// Test: GlobalState lookup by Poly token hashes
+    #[test]
+    fn test_lookup_by_poly_hashes() {
+        let (state, market_id) = setup_market(50, 50);
 
         let poly_yes_hash = fxhash_str("arb_yes_token");
         let poly_no_hash = fxhash_str("arb_no_token");
@@ -590,6 +1635,8 @@ mod infra_integration_tests {
                 poly_no_token: format!("no_{}", i).into(),
                 line_value: None,
                 team_suffix: None,
+                event_id: None,
+                game_date: None,
             };
 
             let id = state.add_pair(pair).unwrap();
@@ -654,6 +1701,8 @@ mod infra_integration_tests {
             no_price: p_no,
             yes_size: p_yes_sz,
             no_size: p_no_sz,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
             arb_type: ArbType::PolyOnly,
             detected_ns: 0,
         };
@@ -675,9 +1724,11 @@ mod infra_integration_tests {
 // ============================================================================
 
 mod execution_tests {
+    use super::fixtures::{breaker_config, position_tracker};
     use arb_bot::types::*;
     use arb_bot::circuit_breaker::*;
     use arb_bot::position_tracker::*;
+    use super::circuit_breaker_tests::record_matched;
 
     /// Test: ExecutionEngine correctly filters low-profit opportunities
     #[tokio::test]
@@ -689,6 +1740,8 @@ mod execution_tests {
             no_price: 50,
             yes_size: 1000,
             no_size: 1000,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
             arb_type: ArbType::PolyOnly,
             detected_ns: 0,
         };
@@ -703,26 +1756,27 @@ mod execution_tests {
         let config = CircuitBreakerConfig {
             max_position_per_market: 50,
             max_total_position: 200,
+            max_unmatched_per_market: 20,
+            max_unmatched_total: 30,
             max_daily_loss: 25.0,
             max_consecutive_errors: 3,
-            cooldown_secs: 60,
-            enabled: true,
+            ..breaker_config()
         };
 
         let cb = CircuitBreaker::new(config);
 
         // Fill up market position
-        cb.record_success("market1", 45, 45, 1.0).await;
+        record_matched(&cb, "market1", 45, 1.0).await;
 
         // Should block when adding more
-        let result = cb.can_execute("market1", 10).await;
+        let result = cb.can_execute("market1", 10, 45, 50).await;
         assert!(matches!(result, Err(TripReason::MaxPositionPerMarket { .. })));
     }
 
     /// Test: Position tracker records fills correctly (PolyOnly)
     #[tokio::test]
     async fn test_position_tracker_integration() {
-        let mut tracker = PositionTracker::new();
+        let mut tracker = position_tracker();
 
         // Simulate fill recording (what ExecutionEngine does)
         tracker.record_fill(&FillRecord::new(
@@ -775,13 +1829,14 @@ mod execution_tests {
 // the system correctly handles the unmatched exposure.
 
 mod mismatched_fill_tests {
+    use super::fixtures::position_tracker;
     use arb_bot::position_tracker::*;
 
     /// Test: When Poly YES fills more than Poly NO, we have excess YES exposure
     /// that needs to be sold to close the position.
     #[test]
     fn test_poly_yes_fills_more_than_no_creates_exposure() {
-        let mut tracker = PositionTracker::new();
+        let mut tracker = position_tracker();
 
         // Scenario: Requested 10 contracts
         // Poly NO filled: 7 contracts at 50¢
@@ -828,7 +1883,7 @@ mod mismatched_fill_tests {
     /// that needs to be sold to close the position.
     #[test]
     fn test_poly_no_fills_more_than_yes_creates_exposure() {
-        let mut tracker = PositionTracker::new();
+        let mut tracker = position_tracker();
 
         // Scenario: Requested 10 contracts
         // Poly NO filled: 10 contracts at 50¢
@@ -871,7 +1926,7 @@ mod mismatched_fill_tests {
     /// Test: After auto-closing excess Poly YES, position should be balanced
     #[test]
     fn test_auto_close_poly_yes_excess_balances_position() {
-        let mut tracker = PositionTracker::new();
+        let mut tracker = position_tracker();
 
         // Initial mismatched fill
         tracker.record_fill(&FillRecord::new(
@@ -926,7 +1981,7 @@ mod mismatched_fill_tests {
     /// Test: After auto-closing excess Poly NO, position should be balanced
     #[test]
     fn test_auto_close_poly_no_excess_balances_position() {
-        let mut tracker = PositionTracker::new();
+        let mut tracker = position_tracker();
 
         // Initial mismatched fill
         tracker.record_fill(&FillRecord::new(
@@ -982,7 +2037,7 @@ mod mismatched_fill_tests {
     /// (e.g., Poly YES fills 10, Poly NO fills 0)
     #[test]
     fn test_complete_one_side_failure_full_exposure() {
-        let mut tracker = PositionTracker::new();
+        let mut tracker = position_tracker();
 
         // Poly YES succeeds
         tracker.record_fill(&FillRecord::new(
@@ -1011,7 +2066,7 @@ mod mismatched_fill_tests {
     /// Test: Auto-close after complete one-side failure
     #[test]
     fn test_auto_close_after_complete_failure() {
-        let mut tracker = PositionTracker::new();
+        let mut tracker = position_tracker();
 
         // Poly YES fills, Poly NO fails completely
         tracker.record_fill(&FillRecord::new(
@@ -1050,7 +2105,7 @@ mod mismatched_fill_tests {
     /// Test: Profit calculation with partial fill and auto-close
     #[test]
     fn test_profit_with_partial_fill_and_auto_close() {
-        let mut tracker = PositionTracker::new();
+        let mut tracker = position_tracker();
 
         // Requested 10 contracts
         // Poly NO fills 8 @ 50¢ (cost: $4.00)
@@ -1123,6 +2178,7 @@ mod mismatched_fill_tests {
 // 4. Captures order IDs from Polymarket
 
 mod process_mock_tests {
+    use super::fixtures::{breaker_config, position_tracker};
     use arb_bot::types::*;
     use arb_bot::circuit_breaker::*;
     use arb_bot::position_tracker::*;
@@ -1151,44 +2207,34 @@ mod process_mock_tests {
         let matched = result.yes_filled.min(result.no_filled);
         let actual_profit = matched as i16 * 100 - (result.yes_cost + result.no_cost) as i16;
 
-        // Record success to circuit breaker
-        if matched > 0 {
-            circuit_breaker.record_success(&pair.pair_id, matched, matched, actual_profit as f64 / 100.0).await;
-        }
-
-        // === UPDATE POSITION TRACKER (mirrors process logic) ===
-        if matched > 0 || result.yes_filled > 0 || result.no_filled > 0 {
+        // === RECORD FILLS (mirrors process logic: actual legs, not matched) ===
+        let fills = [
+            ("yes", result.yes_filled, result.yes_cost, &result.yes_order_id),
+            ("no", result.no_filled, result.no_cost, &result.no_order_id),
+        ];
+        {
             let mut tracker_guard = tracker.write().await;
-
-            // Record Poly YES fill
-            if result.yes_filled > 0 {
-                tracker_guard.record_fill(&FillRecord::new(
-                    &pair.pair_id,
-                    &pair.description,
-                    "polymarket",
-                    "yes",
-                    matched as f64,
-                    result.yes_cost as f64 / 100.0 / result.yes_filled.max(1) as f64,
-                    0.0,
-                    &result.yes_order_id,
-                ));
-            }
-
-            // Record Poly NO fill
-            if result.no_filled > 0 {
-                tracker_guard.record_fill(&FillRecord::new(
-                    &pair.pair_id,
-                    &pair.description,
-                    "polymarket",
-                    "no",
-                    matched as f64,
-                    result.no_cost as f64 / 100.0 / result.no_filled.max(1) as f64,
-                    0.0,
-                    &result.no_order_id,
-                ));
+            for (side, filled, cost, order_id) in fills {
+                if filled > 0 {
+                    let fill = FillRecord::new(
+                        &pair.pair_id,
+                        &pair.description,
+                        "polymarket",
+                        side,
+                        filled as f64,
+                        cost as f64 / 100.0 / filled as f64,
+                        0.0,
+                        order_id,
+                    );
+                    circuit_breaker.record_fill(&fill).await;
+                    tracker_guard.record_fill(&fill);
+                }
             }
+        }
 
-            tracker_guard.save_async();
+        // Record success to circuit breaker
+        if matched > 0 {
+            circuit_breaker.record_success(actual_profit as f64 / 100.0);
         }
 
         (matched, actual_profit)
@@ -1205,25 +2251,16 @@ mod process_mock_tests {
             poly_no_token: "pf_no_token".into(),
             line_value: None,
             team_suffix: None,
-        }
-    }
-
-    fn test_circuit_breaker_config() -> CircuitBreakerConfig {
-        CircuitBreakerConfig {
-            max_position_per_market: 100,
-            max_total_position: 500,
-            max_daily_loss: 50.0,
-            max_consecutive_errors: 5,
-            cooldown_secs: 60,
-            enabled: true,
+            event_id: None,
+            game_date: None,
         }
     }
 
     /// Test: process records both fills to position tracker with correct order IDs
     #[tokio::test]
     async fn test_process_records_fills_with_order_ids() {
-        let tracker = Arc::new(RwLock::new(PositionTracker::new()));
-        let cb = CircuitBreaker::new(test_circuit_breaker_config());
+        let tracker = Arc::new(RwLock::new(position_tracker()));
+        let cb = CircuitBreaker::new(breaker_config());
         let pair = test_market_pair();
 
         let req = FastExecutionRequest {
@@ -1232,6 +2269,8 @@ mod process_mock_tests {
             no_price: 50,
             yes_size: 1000,
             no_size: 1000,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
             arb_type: ArbType::PolyOnly,
             detected_ns: 0,
         };
@@ -1271,8 +2310,8 @@ mod process_mock_tests {
     /// Test: process handles PolyOnly arb correctly (both sides on Polymarket)
     #[tokio::test]
     async fn test_process_poly_only_sides() {
-        let tracker = Arc::new(RwLock::new(PositionTracker::new()));
-        let cb = CircuitBreaker::new(test_circuit_breaker_config());
+        let tracker = Arc::new(RwLock::new(position_tracker()));
+        let cb = CircuitBreaker::new(breaker_config());
         let pair = test_market_pair();
 
         // PolyOnly: Buy YES and NO both on Polymarket
@@ -1282,6 +2321,8 @@ mod process_mock_tests {
             no_price: 50,
             yes_size: 1000,
             no_size: 1000,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
             arb_type: ArbType::PolyOnly,
             detected_ns: 0,
         };
@@ -1309,8 +2350,8 @@ mod process_mock_tests {
     /// Test: process updates circuit breaker on success
     #[tokio::test]
     async fn test_process_updates_circuit_breaker() {
-        let tracker = Arc::new(RwLock::new(PositionTracker::new()));
-        let cb = CircuitBreaker::new(test_circuit_breaker_config());
+        let tracker = Arc::new(RwLock::new(position_tracker()));
+        let cb = CircuitBreaker::new(breaker_config());
         let pair = test_market_pair();
 
         let req = FastExecutionRequest {
@@ -1319,6 +2360,8 @@ mod process_mock_tests {
             no_price: 50,
             yes_size: 1000,
             no_size: 1000,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
             arb_type: ArbType::PolyOnly,
             detected_ns: 0,
         };
@@ -1343,8 +2386,8 @@ mod process_mock_tests {
     /// Test: process handles partial YES fill correctly
     #[tokio::test]
     async fn test_process_partial_yes_fill() {
-        let tracker = Arc::new(RwLock::new(PositionTracker::new()));
-        let cb = CircuitBreaker::new(test_circuit_breaker_config());
+        let tracker = Arc::new(RwLock::new(position_tracker()));
+        let cb = CircuitBreaker::new(breaker_config());
         let pair = test_market_pair();
 
         let req = FastExecutionRequest {
@@ -1353,6 +2396,8 @@ mod process_mock_tests {
             no_price: 50,
             yes_size: 1000,
             no_size: 1000,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
             arb_type: ArbType::PolyOnly,
             detected_ns: 0,
         };
@@ -1377,16 +2422,17 @@ mod process_mock_tests {
         let tracker_guard = tracker.read().await;
         let pos = tracker_guard.get(&pair.pair_id).expect("Should have position");
 
-        // Position tracker records matched amounts (7), not total fills
-        assert!((pos.poly_yes.contracts - 7.0).abs() < 0.01, "Should record matched Poly YES contracts");
-        assert!((pos.poly_no.contracts - 7.0).abs() < 0.01, "Should record matched Poly NO contracts");
+        // Position tracker records the actual fills; the 3-contract NO excess is left for auto-close
+        assert!((pos.poly_yes.contracts - 7.0).abs() < 0.01, "Should record filled Poly YES contracts");
+        assert!((pos.poly_no.contracts - 10.0).abs() < 0.01, "Should record filled Poly NO contracts");
+        assert_eq!(cb.position(&pair.pair_id).await.unmatched(), 3, "Breaker should see the unhedged excess");
     }
 
     /// Test: process handles partial NO fill correctly
     #[tokio::test]
     async fn test_process_partial_no_fill() {
-        let tracker = Arc::new(RwLock::new(PositionTracker::new()));
-        let cb = CircuitBreaker::new(test_circuit_breaker_config());
+        let tracker = Arc::new(RwLock::new(position_tracker()));
+        let cb = CircuitBreaker::new(breaker_config());
         let pair = test_market_pair();
 
         let req = FastExecutionRequest {
@@ -1395,6 +2441,8 @@ mod process_mock_tests {
             no_price: 50,
             yes_size: 1000,
             no_size: 1000,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
             arb_type: ArbType::PolyOnly,
             detected_ns: 0,
         };
@@ -1420,8 +2468,8 @@ mod process_mock_tests {
     /// Test: process handles zero YES fill
     #[tokio::test]
     async fn test_process_zero_yes_fill() {
-        let tracker = Arc::new(RwLock::new(PositionTracker::new()));
-        let cb = CircuitBreaker::new(test_circuit_breaker_config());
+        let tracker = Arc::new(RwLock::new(position_tracker()));
+        let cb = CircuitBreaker::new(breaker_config());
         let pair = test_market_pair();
 
         let req = FastExecutionRequest {
@@ -1430,6 +2478,8 @@ mod process_mock_tests {
             no_price: 50,
             yes_size: 1000,
             no_size: 1000,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
             arb_type: ArbType::PolyOnly,
             detected_ns: 0,
         };
@@ -1455,8 +2505,8 @@ mod process_mock_tests {
     /// Test: process handles zero NO fill
     #[tokio::test]
     async fn test_process_zero_no_fill() {
-        let tracker = Arc::new(RwLock::new(PositionTracker::new()));
-        let cb = CircuitBreaker::new(test_circuit_breaker_config());
+        let tracker = Arc::new(RwLock::new(position_tracker()));
+        let cb = CircuitBreaker::new(breaker_config());
         let pair = test_market_pair();
 
         let req = FastExecutionRequest {
@@ -1465,6 +2515,8 @@ mod process_mock_tests {
             no_price: 50,
             yes_size: 1000,
             no_size: 1000,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
             arb_type: ArbType::PolyOnly,
             detected_ns: 0,
         };
@@ -1489,8 +2541,8 @@ mod process_mock_tests {
     /// Test: process correctly calculates profit with full fills
     #[tokio::test]
     async fn test_process_profit_calculation_full_fill() {
-        let tracker = Arc::new(RwLock::new(PositionTracker::new()));
-        let cb = CircuitBreaker::new(test_circuit_breaker_config());
+        let tracker = Arc::new(RwLock::new(position_tracker()));
+        let cb = CircuitBreaker::new(breaker_config());
         let pair = test_market_pair();
 
         let req = FastExecutionRequest {
@@ -1499,6 +2551,8 @@ mod process_mock_tests {
             no_price: 50,   // Poly NO at 50¢
             yes_size: 1000,
             no_size: 1000,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
             arb_type: ArbType::PolyOnly,
             detected_ns: 0,
         };
@@ -1527,8 +2581,8 @@ mod process_mock_tests {
     /// Test: process correctly calculates profit with partial fill
     #[tokio::test]
     async fn test_process_profit_calculation_partial_fill() {
-        let tracker = Arc::new(RwLock::new(PositionTracker::new()));
-        let cb = CircuitBreaker::new(test_circuit_breaker_config());
+        let tracker = Arc::new(RwLock::new(position_tracker()));
+        let cb = CircuitBreaker::new(breaker_config());
         let pair = test_market_pair();
 
         let req = FastExecutionRequest {
@@ -1537,6 +2591,8 @@ mod process_mock_tests {
             no_price: 50,
             yes_size: 1000,
             no_size: 1000,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
             arb_type: ArbType::PolyOnly,
             detected_ns: 0,
         };
@@ -1566,8 +2622,8 @@ mod process_mock_tests {
     /// Test: Multiple executions accumulate in position tracker
     #[tokio::test]
     async fn test_process_multiple_executions_accumulate() {
-        let tracker = Arc::new(RwLock::new(PositionTracker::new()));
-        let cb = CircuitBreaker::new(test_circuit_breaker_config());
+        let tracker = Arc::new(RwLock::new(position_tracker()));
+        let cb = CircuitBreaker::new(breaker_config());
         let pair = test_market_pair();
 
         let req = FastExecutionRequest {
@@ -1576,6 +2632,8 @@ mod process_mock_tests {
             no_price: 50,
             yes_size: 1000,
             no_size: 1000,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
             arb_type: ArbType::PolyOnly,
             detected_ns: 0,
         };
@@ -1624,8 +2682,8 @@ mod process_mock_tests {
     /// Test: Circuit breaker tracks accumulated position per market
     #[tokio::test]
     async fn test_circuit_breaker_accumulates_position() {
-        let tracker = Arc::new(RwLock::new(PositionTracker::new()));
-        let cb = CircuitBreaker::new(test_circuit_breaker_config());
+        let tracker = Arc::new(RwLock::new(position_tracker()));
+        let cb = CircuitBreaker::new(breaker_config());
         let pair = test_market_pair();
 
         let req = FastExecutionRequest {
@@ -1634,6 +2692,8 @@ mod process_mock_tests {
             no_price: 50,
             yes_size: 1000,
             no_size: 1000,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
             arb_type: ArbType::PolyOnly,
             detected_ns: 0,
         };
@@ -1665,8 +2725,8 @@ mod process_mock_tests {
     /// Test: PolyOnly arb (Poly YES + Poly NO on same platform - zero fees)
     #[tokio::test]
     async fn test_process_poly_only_arb() {
-        let tracker = Arc::new(RwLock::new(PositionTracker::new()));
-        let cb = CircuitBreaker::new(test_circuit_breaker_config());
+        let tracker = Arc::new(RwLock::new(position_tracker()));
+        let cb = CircuitBreaker::new(breaker_config());
         let pair = test_market_pair();
 
         // PolyOnly: Buy YES and NO both on Polymarket
@@ -1677,6 +2737,8 @@ mod process_mock_tests {
             no_price: 50,   // Poly NO at 50¢ (total = 98¢, 2¢ profit with NO fees!)
             yes_size: 1000,
             no_size: 1000,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
             arb_type: ArbType::PolyOnly,
             detected_ns: 0,
         };
@@ -1697,6 +2759,8 @@ mod process_mock_tests {
                     no_price,
                     yes_size: 1000,
                     no_size: 1000,
+                    yes_fee_bps: 0,
+                    no_fee_bps: 0,
                     arb_type: ArbType::PolyOnly,
                     detected_ns: 0,
                 };
@@ -1707,3 +2771,3032 @@ mod process_mock_tests {
         }
     }
 }
+
+// ============================================================================
+// CLOB RESPONSE PARSING TESTS - Order management endpoints
+// ============================================================================
+
+mod clob_response_tests {
+    use arb_bot::polymarket_clob::*;
+
+    /// Test: cancel response parses cancelled and rejected IDs
+    #[test]
+    fn test_parse_cancel_response() {
+        let body = r#"{"canceled":["0xabc","0xdef"],"not_canceled":{"0x123":"order already matched"}}"#;
+        let resp: CancelOrdersResponse = serde_json::from_str(body).unwrap();
+
+        assert_eq!(resp.canceled, vec!["0xabc".to_string(), "0xdef".to_string()]);
+        assert_eq!(resp.not_canceled.get("0x123").map(String::as_str), Some("order already matched"));
+    }
+
+    /// Test: cancel-all with nothing open returns empty lists
+    #[test]
+    fn test_parse_empty_cancel_response() {
+        let resp: CancelOrdersResponse = serde_json::from_str(r#"{"canceled":[]}"#).unwrap();
+        assert!(resp.canceled.is_empty());
+        assert!(resp.not_canceled.is_empty());
+    }
+
+    /// Test: open orders page parses into PolymarketOrderResponse
+    #[test]
+    fn test_parse_open_orders_page() {
+        let body = r#"{
+            "data": [{
+                "id": "0xorder1", "status": "LIVE", "market": "0xcond", "outcome": "Yes",
+                "price": "0.45", "side": "BUY", "size_matched": "0", "original_size": "10",
+                "maker_address": "0xmaker", "asset_id": "123", "associate_trades": [],
+                "created_at": 1700000000, "expiration": "0", "type": "GTC", "owner": "key"
+            }],
+            "next_cursor": "LTE=",
+            "limit": 100,
+            "count": 1
+        }"#;
+        let page: PaginatedResponse<PolymarketOrderResponse> = serde_json::from_str(body).unwrap();
+
+        assert_eq!(page.data.len(), 1);
+        assert_eq!(page.data[0].id, "0xorder1");
+        assert_eq!(page.data[0].order_type.as_deref(), Some("GTC"));
+        assert_eq!(page.next_cursor.as_deref(), Some("LTE="));
+    }
+
+    /// Test: trades page parses into PolymarketTrade
+    #[test]
+    fn test_parse_trades_page() {
+        let body = r#"{
+            "data": [{
+                "id": "trade1", "taker_order_id": "0xorder1", "market": "0xcond", "asset_id": "123",
+                "side": "BUY", "size": "10", "fee_rate_bps": "0", "price": "0.45",
+                "status": "CONFIRMED", "match_time": "1700000000", "outcome": "Yes",
+                "owner": "key", "maker_address": "0xmaker", "trader_side": "TAKER",
+                "transaction_hash": "0xtx", "maker_orders": []
+            }],
+            "next_cursor": "LTE="
+        }"#;
+        let page: PaginatedResponse<PolymarketTrade> = serde_json::from_str(body).unwrap();
+
+        assert_eq!(page.data.len(), 1);
+        let trade = &page.data[0];
+        assert_eq!(trade.taker_order_id.as_deref(), Some("0xorder1"));
+        assert_eq!(trade.size, "10");
+        assert_eq!(trade.status, "CONFIRMED");
+    }
+
+    fn test_signed_order(token_id: &str, side: i32) -> SignedOrder {
+        SignedOrder {
+            order: OrderStruct {
+                salt: 12345,
+                maker: "0xmaker".into(),
+                signer: "0xsigner".into(),
+                taker: "0x0000000000000000000000000000000000000000".into(),
+                token_id: token_id.into(),
+                maker_amount: "4500000".into(),
+                taker_amount: "10000000".into(),
+                expiration: "0".into(),
+                nonce: "0".into(),
+                fee_rate_bps: "0".into(),
+                side,
+                signature_type: 1,
+            },
+            signature: "0xsig".into(),
+        }
+    }
+
+    /// Test: batch body is a JSON array of the single-order bodies
+    #[test]
+    fn test_batch_post_body() {
+        let yes = test_signed_order("111", 0);
+        let no = test_signed_order("222", 0);
+
+        let body = SignedOrder::batch_post_body(&[yes.clone(), no.clone()], "api-key", "FAK");
+        let parsed: serde_json::Value = serde_json::from_str(&body).expect("batch body must be valid JSON");
+
+        let entries = parsed.as_array().expect("batch body must be an array");
+        assert_eq!(entries.len(), 2);
+
+        let yes_single: serde_json::Value = serde_json::from_str(&yes.post_body("api-key", "FAK")).unwrap();
+        let no_single: serde_json::Value = serde_json::from_str(&no.post_body("api-key", "FAK")).unwrap();
+        assert_eq!(entries[0], yes_single);
+        assert_eq!(entries[1], no_single);
+        assert_eq!(entries[0]["order"]["tokenId"], "111");
+        assert_eq!(entries[1]["orderType"], "FAK");
+    }
+
+    /// Test: batch response entries are parsed individually (partial rejection)
+    #[test]
+    fn test_parse_batch_response_partial_rejection() {
+        let body = r#"[
+            {"success": true, "errorMsg": "", "orderID": "0xyes", "status": "matched",
+             "makingAmount": "4.5", "takingAmount": "10", "transactionsHashes": []},
+            {"success": false, "errorMsg": "not enough balance / allowance", "orderID": ""}
+        ]"#;
+        let responses: Vec<PostOrderResponse> = serde_json::from_str(body).unwrap();
+
+        assert_eq!(responses.len(), 2);
+        assert!(responses[0].is_accepted());
+        assert_eq!(responses[0].order_id, "0xyes");
+        assert!(!responses[1].is_accepted());
+        assert_eq!(responses[1].error_msg, "not enough balance / allowance");
+    }
+}
+
+// ============================================================================
+// RATE LIMIT TESTS - Client-side token buckets
+// ============================================================================
+
+mod rate_limit_tests {
+    use arb_bot::rate_limit::*;
+    use std::num::NonZeroU32;
+    use std::time::Duration;
+
+    fn limited(endpoint_rate: u32, burst: u32) -> RateLimitConfig {
+        let bucket = BucketConfig::new(
+            NonZeroU32::new(endpoint_rate).unwrap(),
+            NonZeroU32::new(burst).unwrap(),
+        );
+        RateLimitConfig {
+            order_post: bucket,
+            cancel: bucket,
+            order_query: bucket,
+            neg_risk: bucket,
+            fee_rate: bucket,
+            gamma: bucket,
+            clob_global: BucketConfig::UNLIMITED,
+            enabled: true,
+        }
+    }
+
+    /// Test: unlimited limiter never throttles
+    #[tokio::test]
+    async fn test_unlimited_never_throttles() {
+        let limiter = RateLimiter::unlimited();
+        for _ in 0..1000 {
+            limiter.acquire(Endpoint::OrderPost).await;
+        }
+
+        let metrics = limiter.metrics();
+        assert_eq!(metrics.get(Endpoint::OrderPost).requests, 1000);
+        assert_eq!(metrics.total_throttled(), 0);
+    }
+
+    /// Test: requests beyond the burst are throttled and counted
+    #[tokio::test]
+    async fn test_burst_exceeded_is_throttled() {
+        let limiter = RateLimiter::new(limited(100, 3));
+        for _ in 0..5 {
+            limiter.acquire(Endpoint::OrderQuery).await;
+        }
+
+        let m = limiter.metrics().get(Endpoint::OrderQuery);
+        assert_eq!(m.requests, 5);
+        assert_eq!(m.throttled, 2, "two requests beyond burst of 3 should wait");
+        assert!(m.wait_us > 0);
+    }
+
+    /// Test: each endpoint has its own bucket
+    #[tokio::test]
+    async fn test_buckets_are_independent() {
+        let limiter = RateLimiter::new(limited(1, 2));
+        limiter.acquire(Endpoint::Gamma).await;
+        limiter.acquire(Endpoint::Gamma).await;
+
+        // Gamma bucket is drained, order posts must still pass immediately
+        tokio::time::timeout(Duration::from_millis(50), limiter.acquire(Endpoint::OrderPost))
+            .await
+            .expect("order post should not wait on the Gamma bucket");
+
+        let metrics = limiter.metrics();
+        assert_eq!(metrics.get(Endpoint::OrderPost).throttled, 0);
+        assert_eq!(metrics.get(Endpoint::Gamma).throttled, 0);
+    }
+
+    /// Test: fee-rate lookups and cancels never drain the neg-risk or order-post buckets
+    #[tokio::test]
+    async fn test_fee_rate_and_cancel_have_own_buckets() {
+        let limiter = RateLimiter::new(limited(1, 2));
+        for _ in 0..2 {
+            limiter.acquire(Endpoint::FeeRate).await;
+            limiter.acquire(Endpoint::Cancel).await;
+        }
+
+        for endpoint in [Endpoint::NegRisk, Endpoint::NegRisk, Endpoint::OrderPost, Endpoint::OrderPost] {
+            tokio::time::timeout(Duration::from_millis(50), limiter.acquire(endpoint))
+                .await
+                .unwrap_or_else(|_| panic!("{} should not wait on fee-rate or cancel tokens", endpoint.as_str()));
+        }
+        assert_eq!(limiter.metrics().total_throttled(), 0);
+    }
+
+    /// Test: a cancel sweep yields the shared CLOB budget to a queued order post
+    #[tokio::test]
+    async fn test_cancel_yields_to_order_post() {
+        let mut config = limited(1000, 1000);
+        config.clob_global = BucketConfig::new(NonZeroU32::new(20).unwrap(), NonZeroU32::new(1).unwrap());
+        let limiter = std::sync::Arc::new(RateLimiter::new(config));
+        limiter.acquire(Endpoint::Cancel).await;
+
+        // Both wait for the next shared token; the post is queued first and takes it
+        let post = tokio::spawn({
+            let limiter = limiter.clone();
+            async move { limiter.acquire(Endpoint::OrderPost).await; std::time::Instant::now() }
+        });
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        let cancel = tokio::spawn({
+            let limiter = limiter.clone();
+            async move { limiter.acquire(Endpoint::Cancel).await; std::time::Instant::now() }
+        });
+
+        let (post, cancel) = (post.await.unwrap(), cancel.await.unwrap());
+        assert!(post < cancel, "order post went first");
+        assert_eq!(limiter.metrics().get(Endpoint::Cancel).throttled, 1);
+    }
+
+    /// Test: an order post waiting only on its own bucket does not hold back queries
+    #[tokio::test]
+    async fn test_post_waiting_on_own_bucket_lets_queries_through() {
+        let mut config = limited(1000, 1000);
+        config.order_post = BucketConfig::new(NonZeroU32::new(1).unwrap(), NonZeroU32::new(1).unwrap());
+        let limiter = std::sync::Arc::new(RateLimiter::new(config));
+        limiter.acquire(Endpoint::OrderPost).await;
+
+        // The next post waits about a second for its own bucket
+        let post = tokio::spawn({
+            let limiter = limiter.clone();
+            async move { limiter.acquire(Endpoint::OrderPost).await }
+        });
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        tokio::time::timeout(Duration::from_millis(50), limiter.acquire(Endpoint::OrderQuery))
+            .await
+            .expect("query should not yield to a post that is not waiting on the shared budget");
+        post.abort();
+    }
+
+    /// Test: disabled config ignores bucket settings
+    #[tokio::test]
+    async fn test_disabled_config_is_unlimited() {
+        let mut config = limited(1, 1);
+        config.enabled = false;
+        let limiter = RateLimiter::new(config);
+
+        for _ in 0..10 {
+            limiter.acquire(Endpoint::NegRisk).await;
+        }
+        assert_eq!(limiter.metrics().total_throttled(), 0);
+    }
+
+    /// Test: an order post cancelled while waiting no longer holds back queries
+    #[tokio::test]
+    async fn test_cancelled_order_post_releases_priority() {
+        let mut config = limited(1000, 1000);
+        config.clob_global = BucketConfig::new(NonZeroU32::new(20).unwrap(), NonZeroU32::new(1).unwrap());
+        let limiter = RateLimiter::new(config);
+        limiter.acquire(Endpoint::OrderQuery).await;
+
+        // Shared bucket is drained, so the post waits and is dropped by the timeout
+        let post = tokio::time::timeout(Duration::from_millis(5), limiter.acquire(Endpoint::OrderPost)).await;
+        assert!(post.is_err());
+
+        tokio::time::timeout(Duration::from_millis(500), limiter.acquire(Endpoint::OrderQuery))
+            .await
+            .expect("query should get the next shared token once the post is gone");
+    }
+}
+
+// ============================================================================
+// CLOB ERROR TESTS - Error classification and policies
+// ============================================================================
+
+mod clob_error_tests {
+    use arb_bot::clob_error::*;
+
+    /// Test: HTTP error bodies are classified by message
+    #[test]
+    fn test_classify_http_errors() {
+        let cases = [
+            (400, r#"{"error":"not enough balance / allowance"}"#, ClobErrorKind::InsufficientBalance),
+            (400, r#"{"error":"not enough allowance"}"#, ClobErrorKind::InsufficientAllowance),
+            (400, r#"{"error":"order is invalid. Price (0.555), breaks minimum tick size rule: 0.01"}"#, ClobErrorKind::InvalidTickSize),
+            (400, r#"{"error":"order is invalid. Size (0.5), lower than the minimum: 5"}"#, ClobErrorKind::InvalidOrder),
+            (400, r#"{"error":"invalid signature"}"#, ClobErrorKind::InvalidSignature),
+            (400, r#"{"error":"the market is closed"}"#, ClobErrorKind::MarketClosed),
+            (429, "Too Many Requests", ClobErrorKind::RateLimited),
+            (401, r#"{"error":"Unauthorized/Invalid api key"}"#, ClobErrorKind::AuthExpired),
+            (503, "upstream connect error", ClobErrorKind::ServerError),
+            (400, r#"{"error":"something new"}"#, ClobErrorKind::Unknown),
+        ];
+
+        for (status, body, expected) in cases {
+            let err = ClobError::from_response(status, body);
+            assert_eq!(err.kind, expected, "status={} body={}", status, body);
+            assert_eq!(err.status, Some(status));
+        }
+    }
+
+    /// Test: JSON error message is extracted from the body
+    #[test]
+    fn test_from_response_extracts_message() {
+        let err = ClobError::from_response(400, r#"{"error":"the market is closed"}"#);
+        assert_eq!(err.message, "the market is closed");
+
+        let err = ClobError::from_response(502, "  Bad Gateway\n");
+        assert_eq!(err.message, "Bad Gateway");
+    }
+
+    /// Test: per-order rejections (200 response, success=false)
+    #[test]
+    fn test_classify_order_rejection() {
+        let err = ClobError::rejected("no orders found to match with FAK order. FAK orders are partially filled or killed if no match is found.");
+        assert_eq!(err.kind, ClobErrorKind::NoMatch);
+        assert_eq!(err.status, None);
+        assert_eq!(err.policy().breaker, BreakerAction::Ignore);
+    }
+
+    /// Test: only the exchange's closed-market phrases disable a market
+    #[test]
+    fn test_classify_market_closed_phrases() {
+        for msg in ["market is closed", "Trading on a closed market", "the orderbook does not exist"] {
+            assert_eq!(ClobErrorKind::classify(Some(400), msg), ClobErrorKind::MarketClosed, "{}", msg);
+        }
+        for msg in ["connection closed before message completed", "order closed"] {
+            assert_ne!(ClobErrorKind::classify(None, msg), ClobErrorKind::MarketClosed, "{}", msg);
+        }
+    }
+
+    /// Test: breaker policy per class
+    #[test]
+    fn test_breaker_policies() {
+        assert_eq!(ClobErrorKind::MarketClosed.policy().breaker, BreakerAction::DisableMarket);
+        assert_eq!(ClobErrorKind::InsufficientBalance.policy().breaker, BreakerAction::Halt);
+        assert_eq!(ClobErrorKind::InsufficientAllowance.policy().breaker, BreakerAction::Halt);
+        assert_eq!(ClobErrorKind::AuthExpired.policy().breaker, BreakerAction::Halt);
+        assert_eq!(ClobErrorKind::RateLimited.policy().breaker, BreakerAction::Ignore);
+        assert_eq!(ClobErrorKind::Unknown.policy().breaker, BreakerAction::CountError);
+    }
+
+    /// Test: a failed order lookup is about the order, never the market
+    #[test]
+    fn test_query_errors_never_disable_market() {
+        let cases = [
+            (404, r#"{"error":"order does not exist"}"#, ClobErrorKind::OrderQuery),
+            (404, "order not found", ClobErrorKind::OrderQuery),
+            (400, r#"{"error":"market is closed"}"#, ClobErrorKind::OrderQuery),
+            (429, "Too Many Requests", ClobErrorKind::RateLimited),
+            (401, r#"{"error":"Unauthorized/Invalid api key"}"#, ClobErrorKind::AuthExpired),
+            (502, "bad gateway", ClobErrorKind::ServerError),
+        ];
+
+        for (status, body, expected) in cases {
+            let err = ClobError::from_query_response(status, body);
+            assert_eq!(err.kind, expected, "status={} body={}", status, body);
+            assert_ne!(err.policy().breaker, BreakerAction::DisableMarket);
+        }
+        assert!(ClobErrorKind::OrderQuery.policy().retries_for(false) > 0, "the order may not be indexed yet");
+
+        // The same text on an order post still means the market is gone
+        let post = ClobError::from_response(400, r#"{"error":"market does not exist"}"#);
+        assert_eq!(post.kind, ClobErrorKind::MarketClosed);
+    }
+
+    /// Test: order posts are never retried, idempotent queries are
+    #[test]
+    fn test_retry_policies() {
+        let rate_limited = ClobErrorKind::RateLimited.policy();
+        assert_eq!(rate_limited.retries_for(true), 0, "a FAK post retried later takes a stale book");
+        assert!(rate_limited.retries_for(false) > 0, "queries back off and retry on 429");
+
+        let server = ClobErrorKind::ServerError.policy();
+        assert_eq!(server.retries_for(true), 0, "5xx on a post may have been processed");
+        assert!(server.retries_for(false) > 0, "order queries are idempotent");
+
+        assert_eq!(ClobErrorKind::MarketClosed.policy().retries_for(false), 0);
+    }
+
+    /// Test: anyhow errors keep their class through downcasting
+    #[test]
+    fn test_from_anyhow() {
+        let err: anyhow::Error = ClobError::rejected("the market is closed").into();
+        assert_eq!(ClobError::kind_of(&err), ClobErrorKind::MarketClosed);
+        assert_eq!(ClobError::from_anyhow(&err).kind, ClobErrorKind::MarketClosed);
+
+        let other = anyhow::anyhow!("something else");
+        assert_eq!(ClobError::kind_of(&other), ClobErrorKind::Unknown);
+        assert_eq!(ClobError::from_anyhow(&other).message, "something else");
+    }
+}
+
+// ============================================================================
+// BALANCE TESTS - Collateral and allowance checks
+// ============================================================================
+
+mod balance_tests {
+    use super::fixtures::balance_config;
+    use arb_bot::balance::*;
+    use arb_bot::polymarket_clob::{BalanceAllowanceResponse, get_exchange_address};
+    use std::sync::Arc;
+
+    fn snapshot(balance_usdc: u64, allowance_usdc: u64) -> CollateralSnapshot {
+        CollateralSnapshot {
+            balance: balance_usdc * 1_000_000,
+            allowance: allowance_usdc * 1_000_000,
+            neg_risk_allowance: allowance_usdc * 1_000_000,
+        }
+    }
+
+    /// Test: trading is blocked until the first balance read
+    #[test]
+    fn test_blocks_until_known() {
+        let monitor = BalanceMonitor::new(balance_config());
+        assert_eq!(monitor.max_contracts(95, false), Err(BalanceBlock::Unknown));
+    }
+
+    /// Test: max contracts is capped by free collateral
+    #[test]
+    fn test_caps_by_collateral() {
+        let monitor = BalanceMonitor::new(balance_config());
+        monitor.update(snapshot(50, u64::MAX / 1_000_000));
+
+        // $50 at 95¢ per contract pair = 52 contracts
+        assert_eq!(monitor.max_contracts(95, false), Ok(52));
+    }
+
+    /// Test: allowance caps collateral and missing allowance blocks with a clear reason
+    #[test]
+    fn test_allowance_checks() {
+        let monitor = BalanceMonitor::new(balance_config());
+        monitor.update(snapshot(100, 10));
+        assert_eq!(monitor.max_contracts(100, false), Ok(10), "allowance below balance limits size");
+
+        monitor.update(CollateralSnapshot { balance: 100_000_000, allowance: 0, neg_risk_allowance: 100_000_000 });
+        match monitor.max_contracts(95, false) {
+            Err(BalanceBlock::AllowanceMissing { spender }) => {
+                assert_eq!(spender, get_exchange_address(137, false).unwrap());
+            }
+            other => panic!("expected AllowanceMissing, got {:?}", other),
+        }
+        assert!(monitor.max_contracts(95, true).is_ok(), "neg-risk exchange is approved");
+    }
+
+    /// Test: spends are debited until the next refresh, reserve is held back
+    #[test]
+    fn test_record_spend_and_reserve() {
+        let mut config = balance_config();
+        config.reserve_usdc = 10.0;
+        let monitor = BalanceMonitor::new(config);
+        monitor.update(snapshot(20, 1000));
+        assert!((monitor.available_usdc() - 10.0).abs() < 1e-9);
+
+        monitor.record_spend(950); // $9.50
+        assert!((monitor.available_usdc() - 0.5).abs() < 1e-9);
+        assert!(matches!(monitor.max_contracts(95, false), Err(BalanceBlock::InsufficientCollateral { .. })));
+
+        monitor.record_spend(10_000); // never underflows
+        assert_eq!(monitor.available_usdc(), 0.0);
+    }
+
+    /// Test: a snapshot requested before a spend does not undo the local debit
+    #[test]
+    fn test_stale_snapshot_keeps_in_flight_spends() {
+        let monitor = BalanceMonitor::new(balance_config());
+        monitor.update(snapshot(20, 1000));
+
+        let mark = monitor.spend_mark();
+        monitor.record_spend(500); // fill lands while the refresh is in flight
+        monitor.update_since(snapshot(20, 1000), mark);
+        assert!((monitor.available_usdc() - 15.0).abs() < 1e-9, "stale read must keep the $5 debit");
+
+        // The next refresh sees the spend on the exchange
+        let mark = monitor.spend_mark();
+        monitor.update_since(snapshot(15, 1000), mark);
+        assert!((monitor.available_usdc() - 15.0).abs() < 1e-9);
+    }
+
+    /// Test: concurrent reservations can't commit the same collateral
+    #[test]
+    fn test_reservations_share_collateral() {
+        let monitor = Arc::new(BalanceMonitor::new(balance_config()));
+        monitor.update(snapshot(50, 1000));
+
+        let first = monitor.reserve(95, 40, false).unwrap();
+        assert_eq!(first.contracts(), 40);
+        let second = monitor.reserve(95, 40, false).unwrap();
+        assert_eq!(second.contracts(), 12, "$12 left after the first $38");
+        assert!(matches!(monitor.reserve(95, 40, false), Err(BalanceBlock::InsufficientCollateral { .. })));
+
+        // Unused reservations are released, filled ones become spends
+        drop(first);
+        assert!((monitor.available_usdc() - 38.6).abs() < 1e-9);
+        second.settle(950);
+        assert!((monitor.available_usdc() - 40.5).abs() < 1e-9);
+        assert_eq!(monitor.spend_mark(), 9_500_000);
+    }
+
+    /// Test: a balance refresh while orders are in flight keeps their reservation
+    #[test]
+    fn test_snapshot_keeps_open_reservations() {
+        let monitor = Arc::new(BalanceMonitor::new(balance_config()));
+        monitor.update(snapshot(20, 1000));
+
+        let held = monitor.reserve(100, 10, false).unwrap();
+        monitor.update(snapshot(20, 1000));
+        assert!((monitor.available_usdc() - 10.0).abs() < 1e-9, "$10 still held");
+
+        drop(held);
+        assert!((monitor.available_usdc() - 20.0).abs() < 1e-9);
+    }
+
+    /// Test: disabled checks never block
+    #[test]
+    fn test_disabled_never_blocks() {
+        let mut config = balance_config();
+        config.enabled = false;
+        let monitor = BalanceMonitor::new(config);
+        assert!(monitor.max_contracts(95, false).is_ok());
+    }
+
+    /// Test: ABI helpers for eth_call
+    #[test]
+    fn test_abi_helpers() {
+        let word = abi_address("0x4bFb41d5B3570DeFd03C39a9A4D8dE6Bd8B8982E").unwrap();
+        assert_eq!(word.len(), 64);
+        assert!(word.starts_with("000000000000000000000000"));
+        assert!(word.ends_with("4bfb41d5b3570defd03c39a9a4d8de6bd8b8982e"));
+        assert!(abi_address("0x1234").is_err());
+
+        assert_eq!(parse_uint256_saturating("0x").unwrap(), 0);
+        assert_eq!(parse_uint256_saturating(&format!("0x{:064x}", 1_500_000u64)).unwrap(), 1_500_000);
+        assert_eq!(parse_uint256_saturating(&format!("0x{}", "f".repeat(64))).unwrap(), u64::MAX);
+    }
+
+    /// Test: CLOB balance-allowance response parsing
+    #[test]
+    fn test_parse_balance_allowance() {
+        let exchange = get_exchange_address(137, false).unwrap();
+        let body = format!(
+            r#"{{"balance":"25500000","allowances":{{"{}":"115792089237316195423570985008687907853269984665640564039457584007913129639935"}}}}"#,
+            exchange.to_lowercase()
+        );
+        let resp: BalanceAllowanceResponse = serde_json::from_str(&body).unwrap();
+
+        assert_eq!(resp.balance_micro(), 25_500_000);
+        assert_eq!(resp.allowance_micro(&exchange), u64::MAX, "address match is case-insensitive");
+        assert_eq!(resp.allowance_micro(&get_exchange_address(137, true).unwrap()), 0);
+    }
+}
+
+// ============================================================================
+// ORDER SIGNING TESTS - Signature types and EIP-712 vectors
+// ============================================================================
+
+mod order_signing_tests {
+    use arb_bot::polymarket_clob::*;
+
+    /// Well-known test key (never funded)
+    const TEST_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+    const TEST_SIGNER: &str = "0x2c7536E3605D9C16a7a3D7b1898e529396a65c23";
+    const TEST_PROXY: &str = "0x70997970C51812dc3A010C7d01b50e0d17dc79C8";
+    /// Wallets the Polygon proxy and Safe factories deploy for TEST_SIGNER (CREATE2)
+    const SIGNER_PROXY: &str = "0x96a9892De6A11FE0B18Cf63373B9763055EcA8a6";
+    const SIGNER_SAFE: &str = "0x907C14d6Cea8e8FC78dD3dB152F0a93f43276b4D";
+    const TEST_TOKEN: &str = "71321045679252212594626385532706912750332728571942532289631379312455583992563";
+
+    fn test_client(funder: &str, signature_type: SignatureType) -> anyhow::Result<SharedAsyncClient> {
+        let client = PolymarketAsyncClient::new("http://localhost", 137, TEST_KEY, funder)?
+            .with_signature_type(signature_type)?;
+        let creds = PreparedCreds::from_api_creds(&ApiCreds {
+            api_key: "test-key".into(),
+            api_secret: "c2VjcmV0".into(),
+            api_passphrase: "test-pass".into(),
+        })?;
+        Ok(SharedAsyncClient::new(client, creds, 137))
+    }
+
+    fn vector_order(signature_type: SignatureType, maker: &str) -> OrderStruct {
+        OrderStruct {
+            salt: 479249096354,
+            maker: maker.into(),
+            signer: TEST_SIGNER.into(),
+            taker: "0x0000000000000000000000000000000000000000".into(),
+            token_id: TEST_TOKEN.into(),
+            maker_amount: "4500000".into(),
+            taker_amount: "10000000".into(),
+            expiration: "0".into(),
+            nonce: "0".into(),
+            fee_rate_bps: "0".into(),
+            side: 0,
+            signature_type: signature_type.as_i32(),
+        }
+    }
+
+    fn assert_signed_by_wallet(signature_type: SignatureType, funder: &str) {
+        let client = test_client(funder, signature_type).unwrap();
+
+        for neg_risk in [false, true] {
+            let signed = client.build_signed_order(TEST_TOKEN, 0.45, 10.0, "BUY", neg_risk, 0).unwrap();
+
+            assert_eq!(signed.order.signature_type, signature_type.as_i32());
+            assert!(signed.order.maker.eq_ignore_ascii_case(funder), "maker must be the funder");
+            assert!(signed.order.signer.eq_ignore_ascii_case(TEST_SIGNER), "signer must be the EOA");
+
+            let recovered = signed.recover_signer(137, neg_risk).unwrap();
+            assert_eq!(format!("{:?}", recovered), TEST_SIGNER.to_lowercase());
+
+            // Signature is bound to the exchange it was built for
+            let other = signed.recover_signer(137, !neg_risk).unwrap();
+            assert_ne!(format!("{:?}", other), TEST_SIGNER.to_lowercase());
+        }
+    }
+
+    /// Test: EOA orders (maker == signer) recover to the wallet
+    #[test]
+    fn test_eoa_signature_recovers_signer() {
+        assert_signed_by_wallet(SignatureType::Eoa, TEST_SIGNER);
+    }
+
+    /// Test: proxy orders (maker = proxy wallet) recover to the wallet
+    #[test]
+    fn test_proxy_signature_recovers_signer() {
+        assert_signed_by_wallet(SignatureType::PolyProxy, SIGNER_PROXY);
+    }
+
+    /// Test: Safe orders (maker = Safe) recover to the wallet
+    #[test]
+    fn test_safe_signature_recovers_signer() {
+        assert_signed_by_wallet(SignatureType::PolyGnosisSafe, SIGNER_SAFE);
+    }
+
+    /// Test: fixed EIP-712 digests per signature type (regression vectors)
+    #[test]
+    fn test_order_digest_vectors() {
+        let cases = [
+            (SignatureType::Eoa, TEST_SIGNER, false, "0f2208e5249b81e4a42f5af04d56611e2042e88f9cf5c366bbde8ddcdc4328d2"),
+            (SignatureType::PolyProxy, TEST_PROXY, false, "0fc02d75ecfdadf95d06cf730669e426f800fbb898c545e35bd45bdfa4674835"),
+            (SignatureType::PolyGnosisSafe, TEST_PROXY, true, "094134620c7b0b71c0044186d57be27d7df7275e3fc0e72c13a4312fce234b9e"),
+        ];
+
+        for (signature_type, maker, neg_risk, expected) in cases {
+            let digest = order_digest(137, neg_risk, &vector_order(signature_type, maker)).unwrap();
+            assert_eq!(hex::encode(digest.as_bytes()), expected, "{:?}", signature_type);
+        }
+    }
+
+    /// Test: hand-rolled encoder is byte-identical to the TypedData path
+    #[test]
+    fn test_order_digest_matches_typed_data() {
+        let makers = [TEST_SIGNER, TEST_PROXY, "0x0000000000000000000000000000000000000001"];
+        let tokens = [TEST_TOKEN, "1", "115792089237316195423570985008687907853269984665640564039457584007913129639935"];
+
+        for (i, &maker) in makers.iter().enumerate() {
+            for (j, &token) in tokens.iter().enumerate() {
+                for signature_type in [SignatureType::Eoa, SignatureType::PolyProxy, SignatureType::PolyGnosisSafe] {
+                    let mut order = vector_order(signature_type, maker);
+                    order.token_id = token.into();
+                    order.salt = u128::MAX - (i * 7 + j) as u128;
+                    order.side = (i + j) as i32 % 2;
+                    order.maker_amount = (123_456_789u64 * (j as u64 + 1)).to_string();
+                    order.fee_rate_bps = (j * 100).to_string();
+                    order.expiration = if j == 2 { "1767225600".into() } else { "0".into() };
+
+                    for (chain_id, neg_risk) in [(137, false), (137, true), (80002, false), (80002, true)] {
+                        let fast = order_digest(chain_id, neg_risk, &order).unwrap();
+                        let reference = order_digest_typed_data(chain_id, neg_risk, &order).unwrap();
+                        assert_eq!(fast, reference, "chain={} neg_risk={} order={:?}", chain_id, neg_risk, order);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Test: malformed fields are rejected by the encoder
+    #[test]
+    fn test_order_digest_rejects_malformed() {
+        let mut order = vector_order(SignatureType::Eoa, TEST_SIGNER);
+        order.maker = "0x1234".into();
+        assert!(order_digest(137, false, &order).is_err());
+
+        let mut order = vector_order(SignatureType::Eoa, TEST_SIGNER);
+        order.token_id = "12abc".into();
+        assert!(order_digest(137, false, &order).is_err());
+
+        assert!(order_digest(1, false, &vector_order(SignatureType::Eoa, TEST_SIGNER)).is_err());
+    }
+
+    /// Test: the funder must be the wallet each type derives from the signer
+    #[test]
+    fn test_signature_type_validation() {
+        assert!(SignatureType::Eoa.validate(137, TEST_SIGNER, TEST_SIGNER).is_ok());
+        assert!(SignatureType::Eoa.validate(137, TEST_SIGNER, TEST_PROXY).is_err(), "EOA maker must be the signer");
+
+        assert!(SignatureType::PolyProxy.validate(137, TEST_SIGNER, SIGNER_PROXY).is_ok());
+        assert!(SignatureType::PolyProxy.validate(137, TEST_SIGNER, &SIGNER_PROXY.to_lowercase()).is_ok());
+        assert!(SignatureType::PolyProxy.validate(137, TEST_SIGNER, &TEST_SIGNER.to_lowercase()).is_err());
+        assert!(SignatureType::PolyProxy.validate(137, TEST_SIGNER, SIGNER_SAFE).is_err(), "Safe is not the proxy");
+        let err = SignatureType::PolyProxy.validate(137, TEST_SIGNER, TEST_PROXY).unwrap_err();
+        assert!(err.to_string().contains(&SIGNER_PROXY.to_lowercase()), "{}", err);
+
+        assert!(SignatureType::PolyGnosisSafe.validate(137, TEST_SIGNER, SIGNER_SAFE).is_ok());
+        assert!(SignatureType::PolyGnosisSafe.validate(137, TEST_SIGNER, SIGNER_PROXY).is_err(), "proxy is not the Safe");
+        assert!(SignatureType::PolyGnosisSafe.validate(137, TEST_SIGNER, TEST_SIGNER).is_err());
+
+        // No factory to derive from on other chains
+        assert!(SignatureType::PolyProxy.validate(80002, TEST_SIGNER, SIGNER_PROXY).is_err());
+        assert!(SignatureType::Eoa.validate(80002, TEST_SIGNER, TEST_SIGNER).is_ok());
+
+        assert!(SignatureType::PolyProxy.validate(137, TEST_SIGNER, "not-an-address").is_err());
+        assert!(test_client(TEST_PROXY, SignatureType::Eoa).is_err());
+        assert!(test_client(TEST_PROXY, SignatureType::PolyProxy).is_err(), "Startup fails on a foreign funder");
+    }
+
+    /// Test: signature type parsing
+    #[test]
+    fn test_signature_type_parse() {
+        assert_eq!(SignatureType::parse("0").unwrap(), SignatureType::Eoa);
+        assert_eq!(SignatureType::parse("proxy").unwrap(), SignatureType::PolyProxy);
+        assert_eq!(SignatureType::parse(" Safe ").unwrap(), SignatureType::PolyGnosisSafe);
+        assert_eq!(SignatureType::parse("2").unwrap().as_i32(), 2);
+        assert!(SignatureType::parse("3").is_err());
+    }
+}
+
+// ============================================================================
+// PRESIGN TESTS - Pre-signed order pool
+// ============================================================================
+
+mod presign_tests {
+    use super::fixtures::presign_config;
+    use arb_bot::polymarket_clob::{OrderStruct, SignedOrder};
+    use arb_bot::presign::*;
+    use std::sync::Arc;
+
+    fn presigned(token: &str, price: u16, size: u32, salt: u128) -> PresignedOrder {
+        PresignedOrder {
+            order: SignedOrder {
+                order: OrderStruct {
+                    salt,
+                    maker: "0xmaker".into(),
+                    signer: "0xsigner".into(),
+                    taker: "0x0000000000000000000000000000000000000000".into(),
+                    token_id: token.into(),
+                    maker_amount: (price as u64 * size as u64 * 10_000).to_string(),
+                    taker_amount: (size as u64 * 1_000_000).to_string(),
+                    expiration: "0".into(),
+                    nonce: "0".into(),
+                    fee_rate_bps: "0".into(),
+                    side: 0,
+                    signature_type: 1,
+                },
+                signature: format!("0xsig{}", salt),
+            },
+            fee_rate_bps: 0,
+            price,
+            size,
+        }
+    }
+
+    fn filled_pool(yes: &Arc<str>, no: &Arc<str>) -> PresignPool {
+        let pool = PresignPool::new(presign_config());
+        let mut salt = 0;
+        for size in [10, 25, 50] {
+            for price in [44, 45, 46] {
+                salt += 1;
+                pool.insert(yes, presigned(yes, price, size, salt));
+                pool.insert(no, presigned(no, 100 - price - 5, size, salt + 1000));
+            }
+        }
+        pool
+    }
+
+    /// Test: each pre-signed order is handed out at most once
+    #[test]
+    fn test_take_pair_used_once() {
+        let (yes, no): (Arc<str>, Arc<str>) = ("yes-token".into(), "no-token".into());
+        let pool = filled_pool(&yes, &no);
+        let before = pool.stats().held;
+
+        let (y, n) = pool.take_pair((&yes, 45, 0), (&no, 50, 0), 50).expect("pair available");
+        assert_eq!((y.price, y.size), (45, 50));
+        assert_eq!((n.price, n.size), (50, 50));
+        assert_eq!(y.order.order.token_id, "yes-token");
+        assert_eq!(pool.stats().held, before - 2);
+
+        // Same key again falls back to the next smaller size, never the same signature
+        let (y2, _) = pool.take_pair((&yes, 45, 0), (&no, 50, 0), 50).expect("smaller size available");
+        assert_eq!(y2.size, 25);
+        assert_ne!(y2.order.signature, y.order.signature);
+    }
+
+    /// Test: largest size not exceeding the requested contracts is chosen
+    #[test]
+    fn test_take_pair_largest_fitting_size() {
+        let (yes, no): (Arc<str>, Arc<str>) = ("yes-token".into(), "no-token".into());
+        let pool = filled_pool(&yes, &no);
+
+        let (y, n) = pool.take_pair((&yes, 44, 0), (&no, 51, 0), 30).unwrap();
+        assert_eq!(y.size, 25);
+        assert_eq!(n.size, 25);
+
+        assert!(pool.take_pair((&yes, 44, 0), (&no, 51, 0), 5).is_none(), "no size fits under 10");
+        assert_eq!((pool.stats().hits, pool.stats().misses), (1, 1));
+    }
+
+    /// Test: both legs must be available at their exact prices
+    #[test]
+    fn test_take_pair_requires_both_legs() {
+        let (yes, no): (Arc<str>, Arc<str>) = ("yes-token".into(), "no-token".into());
+        let pool = filled_pool(&yes, &no);
+
+        assert!(pool.take_pair((&yes, 45, 0), (&no, 40, 0), 50).is_none(), "NO price not pre-signed");
+        assert!(pool.take_pair((&yes, 60, 0), (&no, 50, 0), 50).is_none(), "YES price not pre-signed");
+        assert!(pool.take_pair(("other", 45, 0), (&no, 50, 0), 50).is_none());
+    }
+
+    /// Test: orders away from the book are dropped
+    #[test]
+    fn test_retain_prices_follows_book() {
+        let (yes, no): (Arc<str>, Arc<str>) = ("yes-token".into(), "no-token".into());
+        let pool = filled_pool(&yes, &no);
+
+        let prices = pool.target_prices(46);
+        assert_eq!(prices, vec![45, 46, 47]);
+        let dropped = pool.retain_current(&yes, &prices, 0);
+        assert_eq!(dropped, 3, "the 44¢ level for three sizes");
+        assert!(!pool.contains(&yes, 44, 10));
+        assert!(pool.contains(&yes, 45, 10));
+    }
+
+    /// Test: a fee rate change drops every order signed at the old rate
+    #[test]
+    fn test_retain_current_drops_old_fee_rate() {
+        let (yes, no): (Arc<str>, Arc<str>) = ("yes-token".into(), "no-token".into());
+        let pool = filled_pool(&yes, &no);
+        let held = pool.stats().held;
+
+        let dropped = pool.retain_current(&yes, &[44, 45, 46], 100);
+        assert_eq!(dropped, 9, "all YES orders were signed at 0 bps");
+        assert_eq!(pool.stats().held, held - 9);
+        assert_eq!(pool.retain_current(&no, &[49, 50, 51], 0), 0, "NO rate unchanged");
+    }
+
+    /// Test: an order signed at another fee rate is never handed out
+    #[test]
+    fn test_take_pair_requires_fee_rate() {
+        let (yes, no): (Arc<str>, Arc<str>) = ("yes-token".into(), "no-token".into());
+        let pool = filled_pool(&yes, &no);
+
+        assert!(pool.take_pair((&yes, 45, 100), (&no, 50, 0), 50).is_none());
+        assert!(pool.take_pair((&yes, 45, 0), (&no, 50, 100), 50).is_none());
+        assert_eq!(pool.stats().misses, 2);
+        assert!(pool.contains(&yes, 45, 50), "stale order left for refresh to evict");
+    }
+
+    /// Test: target prices stay within 1..=99
+    #[test]
+    fn test_target_prices_bounds() {
+        let pool = PresignPool::new(presign_config());
+        assert_eq!(pool.target_prices(1), vec![1, 2]);
+        assert_eq!(pool.target_prices(99), vec![98, 99]);
+    }
+}
+
+// ============================================================================
+// EXECUTION ENGINE TESTS - Real ExecutionEngine::process against a scripted exchange
+// ============================================================================
+
+mod engine_tests {
+    use super::fixtures::{balance_config, breaker_config, market_pairs};
+    use anyhow::{Result, anyhow};
+    use arb_bot::balance::{BalanceMonitor, CollateralSnapshot};
+    use arb_bot::circuit_breaker::*;
+    use arb_bot::clob_error::ClobError;
+    use arb_bot::exchange::{ExchangeClient, OrderStatus};
+    use arb_bot::execution::ExecutionEngine;
+    use arb_bot::exchange::{CancelResult, Fill, OrderLeg};
+    use arb_bot::position_tracker::*;
+    use arb_bot::types::*;
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::sync::mpsc::UnboundedReceiver;
+
+    type BatchResult = Result<Vec<Result<Fill>>>;
+
+    /// Order as seen by the fake exchange
+    #[derive(Debug, Clone, PartialEq)]
+    struct PlacedOrder {
+        token_id: String,
+        price: f64,
+        size: f64,
+        side: String,
+    }
+
+    /// Exchange that answers batch posts from a script and records every order
+    #[derive(Default)]
+    struct FakeExchange {
+        batches: Mutex<VecDeque<BatchResult>>,
+        placed: Mutex<Vec<PlacedOrder>>,
+        singles: tokio::sync::Notify,
+        /// Answers to order lookups by ID
+        orders: Mutex<std::collections::HashMap<String, OrderStatus>>,
+    }
+
+    impl FakeExchange {
+        fn scripted(batches: Vec<BatchResult>) -> Arc<Self> {
+            Arc::new(Self { batches: Mutex::new(batches.into()), ..Default::default() })
+        }
+
+        fn placed(&self) -> Vec<PlacedOrder> {
+            self.placed.lock().unwrap().clone()
+        }
+
+        fn record(&self, leg: &OrderLeg<'_>) {
+            self.placed.lock().unwrap().push(PlacedOrder {
+                token_id: leg.token_id.to_string(),
+                price: leg.price,
+                size: leg.size,
+                side: leg.side.to_string(),
+            });
+        }
+    }
+
+    impl ExchangeClient for FakeExchange {
+        async fn place_orders(&self, legs: &[OrderLeg<'_>]) -> BatchResult {
+            legs.iter().for_each(|leg| self.record(leg));
+            self.batches.lock().unwrap().pop_front().unwrap_or_else(|| Err(anyhow!("no scripted response")))
+        }
+
+        async fn place_order(&self, leg: OrderLeg<'_>) -> Result<Fill> {
+            self.record(&leg);
+            self.singles.notify_one();
+            Ok(fill("close-1", leg.size, leg.size * leg.price))
+        }
+
+        async fn query_order(&self, order_id: &str) -> Result<OrderStatus> {
+            self.orders.lock().unwrap().get(order_id).cloned()
+                .ok_or_else(|| anyhow!("unknown order {}", order_id))
+        }
+
+        async fn cancel_orders(&self, _order_ids: &[String]) -> Result<CancelResult> {
+            Ok(CancelResult::default())
+        }
+
+        fn neg_risk(&self, _token_id: &str) -> Option<bool> {
+            Some(false)
+        }
+
+        async fn collateral(&self) -> Result<CollateralSnapshot> {
+            Ok(CollateralSnapshot { balance: u64::MAX, allowance: u64::MAX, neg_risk_allowance: u64::MAX })
+        }
+    }
+
+    fn fill(order_id: &str, size: f64, cost: f64) -> Fill {
+        Fill { order_id: order_id.into(), filled_size: size, fill_cost: cost, fees: 0.0, fill_unknown: false }
+    }
+
+    /// Accepted order whose fill the venue could not report
+    #[cfg(feature = "replay")]
+    fn fill_unknown(order_id: &str) -> Fill {
+        Fill { fill_unknown: true, ..fill(order_id, 0.0, 0.0) }
+    }
+
+    fn rejected(msg: &str) -> anyhow::Error {
+        ClobError::rejected(msg).into()
+    }
+
+    fn test_market_pair() -> MarketPair {
+        MarketPair {
+            pair_id: "engine-test".into(),
+            league: "epl".into(),
+            market_type: MarketType::Moneyline,
+            description: "Engine Test Market".into(),
+            poly_slug: "engine-test".into(),
+            poly_yes_token: "eng_yes_token".into(),
+            poly_no_token: "eng_no_token".into(),
+            line_value: None,
+            team_suffix: None,
+            event_id: Some("engine-event".into()),
+            game_date: chrono::NaiveDate::from_ymd_opt(2025, 12, 8),
+        }
+    }
+
+    /// Breaker that knows the test pair and a second, unrelated market
+    fn test_breaker(config: CircuitBreakerConfig) -> Arc<CircuitBreaker> {
+        Arc::new(CircuitBreaker::new(config)
+            .with_markets(&[test_market_pair()])
+            .with_markets(&market_pairs(&["other-market"])))
+    }
+
+    fn test_circuit_breaker_config() -> CircuitBreakerConfig {
+        CircuitBreakerConfig {
+            max_consecutive_errors: 2,
+            ..breaker_config()
+        }
+    }
+
+    struct Harness {
+        engine: ExecutionEngine<FakeExchange>,
+        exchange: Arc<FakeExchange>,
+        cb: Arc<CircuitBreaker>,
+        fills: UnboundedReceiver<FillRecord>,
+    }
+
+    fn harness(batches: Vec<BatchResult>) -> Harness {
+        let mut state = GlobalState::new();
+        state.add_pair(test_market_pair()).expect("market slot");
+        let exchange = FakeExchange::scripted(batches);
+        let cb = test_breaker(test_circuit_breaker_config());
+        let (channel, fills) = create_position_channel();
+        let engine = ExecutionEngine::new(exchange.clone(), Arc::new(state), cb.clone(), channel, false);
+        Harness { engine, exchange, cb, fills }
+    }
+
+    fn arb_request(market_id: u16) -> FastExecutionRequest {
+        FastExecutionRequest {
+            market_id,
+            yes_price: 45,
+            no_price: 50,
+            yes_size: 1000,
+            no_size: 1000,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
+            arb_type: ArbType::PolyOnly,
+            detected_ns: 0,
+        }
+    }
+
+    /// Test: both legs fill - success, profit, breaker position and fills on the channel
+    #[tokio::test]
+    async fn test_full_fill_records_positions() {
+        let mut h = harness(vec![Ok(vec![
+            Ok(fill("yes-1", 10.0, 4.5)),
+            Ok(fill("no-1", 10.0, 5.0)),
+        ])]);
+
+        let result = h.engine.process(arb_request(0)).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.profit_cents, 50, "10 x (100 - 95)¢");
+        assert!(result.error.is_none());
+
+        let placed = h.exchange.placed();
+        assert_eq!(placed.len(), 2);
+        assert_eq!(placed[0], PlacedOrder { token_id: "eng_yes_token".into(), price: 0.45, size: 10.0, side: "BUY".into() });
+        assert_eq!(placed[1].token_id, "eng_no_token");
+
+        let yes = h.fills.try_recv().expect("YES fill");
+        let no = h.fills.try_recv().expect("NO fill");
+        assert_eq!((yes.side.as_str(), yes.contracts, yes.order_id.as_str()), ("yes", 10.0, "yes-1"));
+        assert_eq!((no.side.as_str(), no.contracts, no.order_id.as_str()), ("no", 10.0, "no-1"));
+        assert!((yes.price - 0.45).abs() < 1e-9);
+
+        let status = h.cb.status().await;
+        assert_eq!(status.total_position, 20);
+        assert!((status.daily_pnl - 0.50).abs() < 1e-9);
+    }
+
+    /// Test: profit of a fill above 327 contracts does not wrap
+    #[tokio::test]
+    async fn test_large_fill_profit_does_not_overflow() {
+        let h = harness(vec![Ok(vec![
+            Ok(fill("yes-1", 400.0, 180.0)),
+            Ok(fill("no-1", 400.0, 200.0)),
+        ])]);
+
+        let result = h.engine.process(arb_request(0)).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.profit_cents, 2_000, "400 x (100 - 95)¢");
+    }
+
+    /// Test: a quote older than the state's max age is rejected before any order is sent
+    #[cfg(feature = "replay")]
+    #[tokio::test(start_paused = true)]
+    async fn test_stale_quote_rejected() {
+        use arb_bot::execution::NanoClock;
+
+        let mut state = GlobalState::new();
+        state.add_pair(test_market_pair()).expect("market slot");
+        state.max_quote_age_ns = 1_000_000_000;
+        let state = Arc::new(state);
+        let clock = NanoClock::simulated();
+        state.markets[0].poly.touch_yes(clock.now_ns());
+        state.markets[0].poly.touch_no(clock.now_ns());
+
+        let exchange = FakeExchange::scripted(vec![Ok(vec![
+            Ok(fill("yes-1", 10.0, 4.5)),
+            Ok(fill("no-1", 10.0, 5.0)),
+        ])]);
+        let cb = test_breaker(test_circuit_breaker_config());
+        let (channel, _fills) = create_position_channel();
+        let engine = ExecutionEngine::new(exchange.clone(), state.clone(), cb, channel, false).with_clock(clock);
+
+        tokio::time::advance(Duration::from_secs(2)).await;
+        let result = engine.process(arb_request(0)).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.error, Some("Stale quote"));
+        assert!(exchange.placed().is_empty(), "No orders on a stale quote");
+
+        // Fresh again: the in-flight slot was released and the arb executes
+        state.markets[0].poly.touch_yes(clock.now_ns());
+        state.markets[0].poly.touch_no(clock.now_ns());
+        let result = engine.process(arb_request(0)).await.unwrap();
+        assert!(result.success);
+        assert_eq!(exchange.placed().len(), 2);
+    }
+
+    /// Test: a second request for the same market is deduplicated while in flight
+    #[tokio::test]
+    async fn test_duplicate_request_rejected_in_flight() {
+        let h = harness(vec![Ok(vec![Ok(fill("y", 10.0, 4.5)), Ok(fill("n", 10.0, 5.0))])]);
+
+        assert!(h.engine.process(arb_request(0)).await.unwrap().success);
+        let second = h.engine.process(arb_request(0)).await.unwrap();
+        assert_eq!(second.error, Some("Already in-flight"));
+        assert_eq!(h.exchange.placed().len(), 2, "no orders for the duplicate");
+    }
+
+    /// Test: fill mismatch records the actual legs and sells the excess
+    #[tokio::test]
+    async fn test_mismatch_auto_closes_excess() {
+        let mut h = harness(vec![Ok(vec![
+            Ok(fill("yes-1", 10.0, 4.5)),
+            Ok(fill("no-1", 6.0, 3.0)),
+        ])]);
+
+        let result = h.engine.process(arb_request(0)).await.unwrap();
+        assert!(result.success);
+
+        assert_eq!(h.fills.try_recv().unwrap().contracts, 10.0);
+        assert_eq!(h.fills.try_recv().unwrap().contracts, 6.0);
+        assert_eq!(h.cb.position("engine-test").await.unmatched(), 4);
+
+        tokio::time::timeout(Duration::from_secs(5), h.exchange.singles.notified())
+            .await
+            .expect("auto-close order");
+        let close = h.exchange.placed().pop().unwrap();
+        assert_eq!(close.token_id, "eng_yes_token");
+        assert_eq!(close.side, "SELL");
+        assert_eq!(close.size, 4.0);
+        assert!((close.price - 0.35).abs() < 1e-9, "10¢ below the buy price");
+
+        // The closing sell reaches both the position channel and the breaker
+        let sell = tokio::time::timeout(Duration::from_secs(1), h.fills.recv())
+            .await
+            .expect("auto-close fill")
+            .unwrap();
+        assert_eq!((sell.side.as_str(), sell.contracts, sell.order_id.as_str()), ("yes", -4.0, "close-1"));
+        assert!((sell.price - 0.35).abs() < 1e-9);
+
+        let pos = h.cb.position("engine-test").await;
+        assert_eq!((pos.poly_yes, pos.poly_no), (6.0, 6.0));
+        assert_eq!(pos.unmatched(), 0);
+        assert_eq!(h.cb.status().await.unmatched_position, 0);
+    }
+
+    /// Test: an accepted leg with an unknown fill is looked up, recorded and its mismatch auto-closed
+    #[cfg(feature = "replay")]
+    #[tokio::test(start_paused = true)]
+    async fn test_unknown_fill_looked_up_then_auto_closed() {
+        let mut h = harness(vec![Ok(vec![
+            Ok(fill("yes-1", 10.0, 4.5)),
+            Ok(fill_unknown("no-1")),
+        ])]);
+        h.exchange.orders.lock().unwrap().insert("no-1".into(), OrderStatus {
+            order_id: "no-1".into(),
+            status: "MATCHED".into(),
+            price: 0.50,
+            size_matched: 6.0,
+            original_size: 10.0,
+        });
+
+        let result = h.engine.process(arb_request(0)).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.error, Some("Fill unknown"));
+        assert_eq!(h.fills.try_recv().unwrap().contracts, 10.0, "Known leg recorded right away");
+
+        let no = tokio::time::timeout(Duration::from_secs(30), h.fills.recv()).await.unwrap().unwrap();
+        assert_eq!((no.side.as_str(), no.contracts, no.order_id.as_str()), ("no", 6.0, "no-1"));
+        assert!((no.price - 0.50).abs() < 1e-9);
+
+        let sell = tokio::time::timeout(Duration::from_secs(30), h.fills.recv()).await.unwrap().unwrap();
+        assert_eq!((sell.side.as_str(), sell.contracts), ("yes", -4.0));
+        let close = h.exchange.placed().pop().unwrap();
+        assert_eq!((close.token_id.as_str(), close.side.as_str(), close.size), ("eng_yes_token", "SELL", 4.0));
+
+        // A lookup failure is not a market problem
+        assert!(h.cb.can_execute("engine-test", 1, 45, 50).await.is_ok());
+        assert_eq!(h.cb.position("engine-test").await.unmatched(), 0);
+    }
+
+    /// Test: a fill that stays unknown is left to reconciliation, never closed against a guess
+    #[cfg(feature = "replay")]
+    #[tokio::test(start_paused = true)]
+    async fn test_unresolved_fill_not_auto_closed() {
+        use arb_bot::execution::ExecutionGate;
+
+        let mut h = harness(vec![Ok(vec![
+            Ok(fill("yes-1", 10.0, 4.5)),
+            Ok(fill_unknown("no-1")),
+        ])]);
+        let gate = ExecutionGate::new();
+        let engine = h.engine.with_gate(gate.clone());
+
+        assert_eq!(engine.process(arb_request(0)).await.unwrap().error, Some("Fill unknown"));
+        assert!(gate.drain(Duration::from_secs(60)).await, "Lookups give up");
+
+        let fills: Vec<f64> = std::iter::from_fn(|| h.fills.try_recv().ok()).map(|f| f.contracts).collect();
+        assert_eq!(fills, vec![10.0], "No guessed NO fill, no closing sell");
+        assert_eq!(h.exchange.placed().len(), 2, "Only the arb legs were sent");
+
+        let status = h.cb.status().await;
+        assert_eq!(status.consecutive_errors, 1);
+        assert!(h.cb.can_execute("engine-test", 1, 45, 50).await.is_ok(), "Market stays enabled");
+    }
+
+    /// Test: draining the gate waits for the auto-close; a closed gate refuses new executions
+    #[tokio::test]
+    async fn test_gate_drains_auto_close_then_refuses_work() {
+        use arb_bot::execution::ExecutionGate;
+
+        let mut h = harness(vec![Ok(vec![
+            Ok(fill("yes-1", 10.0, 4.5)),
+            Ok(fill("no-1", 6.0, 3.0)),
+        ])]);
+        let gate = ExecutionGate::new();
+        let engine = h.engine.with_gate(gate.clone());
+
+        assert!(engine.process(arb_request(0)).await.unwrap().success);
+        assert_eq!(gate.active(), 1, "Auto-close still running");
+
+        gate.close();
+        assert!(gate.drain(Duration::from_secs(10)).await, "Auto-close finishes");
+        assert_eq!(gate.active(), 0);
+        let fills: Vec<f64> = std::iter::from_fn(|| h.fills.try_recv().ok()).map(|f| f.contracts).collect();
+        assert_eq!(fills, vec![10.0, 6.0, -4.0], "Closing sell recorded before drain returns");
+
+        let placed = h.exchange.placed().len();
+        let result = engine.process(arb_request(0)).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.error, Some("Shutting down"));
+        assert_eq!(h.exchange.placed().len(), placed, "Nothing new sent after close");
+    }
+
+    /// Test: NoMatch rejections on both legs are not breaker faults
+    #[tokio::test]
+    async fn test_no_match_is_ignored_by_breaker() {
+        let mut h = harness(vec![Ok(vec![
+            Err(rejected("no orders found to match with FAK order")),
+            Err(rejected("no orders found to match with FAK order")),
+        ])]);
+
+        let result = h.engine.process(arb_request(0)).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.error, Some("Partial/no fill"));
+        assert!(h.fills.try_recv().is_err(), "nothing filled");
+
+        let status = h.cb.status().await;
+        assert!(!status.halted);
+        assert_eq!(status.consecutive_errors, 0);
+    }
+
+    /// Test: balance rejection halts trading
+    #[tokio::test]
+    async fn test_insufficient_balance_halts() {
+        let h = harness(vec![Ok(vec![
+            Err(rejected("not enough balance / allowance")),
+            Ok(fill("no-1", 0.0, 0.0)),
+        ])]);
+
+        h.engine.process(arb_request(0)).await.unwrap();
+
+        let status = h.cb.status().await;
+        assert!(status.halted);
+        assert!(matches!(status.trip_reason, Some(TripReason::ExchangeError { .. })));
+        assert!(h.cb.can_execute("other-market", 1, 45, 50).await.is_err());
+    }
+
+    /// Test: a closed market is disabled without halting other markets
+    #[tokio::test]
+    async fn test_market_closed_disables_market() {
+        let h = harness(vec![Err(ClobError::from_response(400, r#"{"error":"market is closed"}"#).into())]);
+
+        let result = h.engine.process(arb_request(0)).await.unwrap();
+        assert_eq!(result.error, Some("Market closed"));
+
+        assert!(matches!(
+            h.cb.can_execute("engine-test", 1, 45, 50).await,
+            Err(TripReason::MarketDisabled { .. })
+        ));
+        assert!(h.cb.can_execute("other-market", 1, 45, 50).await.is_ok());
+        assert!(!h.cb.status().await.halted);
+    }
+
+    /// Test: repeated server errors count toward the consecutive error limit
+    #[tokio::test]
+    async fn test_server_errors_trip_breaker() {
+        let h = harness(vec![
+            Err(ClobError::from_response(502, "bad gateway").into()),
+            Err(ClobError::from_response(503, "unavailable").into()),
+        ]);
+
+        let first = h.engine.process(arb_request(0)).await.unwrap();
+        assert_eq!(first.error, Some("Server error"));
+        assert!(!h.cb.status().await.halted);
+
+        // Same market is still in flight for 10s, so reuse the breaker through a new engine
+        let mut state = GlobalState::new();
+        state.add_pair(test_market_pair()).unwrap();
+        let (channel, _fills) = create_position_channel();
+        let engine = ExecutionEngine::new(h.exchange.clone(), Arc::new(state), h.cb.clone(), channel, false);
+        engine.process(arb_request(0)).await.unwrap();
+
+        let status = h.cb.status().await;
+        assert!(status.halted);
+        assert!(matches!(status.trip_reason, Some(TripReason::ConsecutiveErrors { .. })));
+    }
+
+    /// Test: a halted breaker stops the engine before any order is placed
+    #[tokio::test]
+    async fn test_halted_breaker_places_no_orders() {
+        let h = harness(vec![]);
+        h.cb.trip(TripReason::ManualHalt).await;
+
+        let result = h.engine.process(arb_request(0)).await.unwrap();
+        assert_eq!(result.error, Some("Circuit breaker"));
+        assert!(h.exchange.placed().is_empty());
+    }
+
+    /// Test: each leg counts toward the order-rate breaker
+    #[tokio::test]
+    async fn test_order_rate_trips_breaker() {
+        let mut state = GlobalState::new();
+        state.add_pair(test_market_pair()).unwrap();
+        let exchange = FakeExchange::scripted(vec![Ok(vec![Ok(fill("y", 10.0, 4.5)), Ok(fill("n", 10.0, 5.0))])]);
+        let mut config = test_circuit_breaker_config();
+        config.behaviour.max_orders_per_min = 1;
+        let cb = test_breaker(config);
+        let (channel, _fills) = create_position_channel();
+        let engine = ExecutionEngine::new(exchange, Arc::new(state), cb.clone(), channel, false);
+
+        assert!(engine.process(arb_request(0)).await.unwrap().success);
+        let status = cb.status().await;
+        assert!(status.halted);
+        assert!(matches!(status.trip_reason, Some(TripReason::OrderRate { orders: 2, limit: 1 })));
+    }
+
+    /// Test: collateral held for the order is settled at the filled cost
+    #[tokio::test]
+    async fn test_fill_settles_collateral_reservation() {
+        let mut state = GlobalState::new();
+        state.add_pair(test_market_pair()).unwrap();
+        let exchange = FakeExchange::scripted(vec![Ok(vec![Ok(fill("y", 5.0, 2.25)), Ok(fill("n", 5.0, 2.5))])]);
+        let cb = test_breaker(test_circuit_breaker_config());
+        let balance = Arc::new(BalanceMonitor::new(balance_config()));
+        balance.update(CollateralSnapshot { balance: 20_000_000, allowance: u64::MAX, neg_risk_allowance: u64::MAX });
+        let (channel, _fills) = create_position_channel();
+        let engine = ExecutionEngine::new(exchange, Arc::new(state), cb, channel, false)
+            .with_balance_monitor(balance.clone());
+
+        assert!(engine.process(arb_request(0)).await.unwrap().success);
+        assert!((balance.available_usdc() - 15.25).abs() < 1e-9, "$9.50 held, $4.75 spent");
+        assert_eq!(balance.spend_mark(), 4_750_000);
+    }
+
+    /// Test: dry run never reaches the exchange
+    #[tokio::test]
+    async fn test_dry_run_places_no_orders() {
+        let mut state = GlobalState::new();
+        state.add_pair(test_market_pair()).unwrap();
+        let exchange = FakeExchange::scripted(vec![]);
+        let cb = test_breaker(test_circuit_breaker_config());
+        let (channel, _fills) = create_position_channel();
+        let engine = ExecutionEngine::new(exchange.clone(), Arc::new(state), cb, channel, true);
+
+        let result = engine.process(arb_request(0)).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.error, Some("DRY_RUN"));
+        assert!(exchange.placed().is_empty());
+    }
+}
+
+// ============================================================================
+// PAPER TRADING TESTS - Depth book matching and the paper exchange
+// ============================================================================
+
+mod paper_tests {
+    use super::fixtures::{breaker_config, paper_config};
+    use arb_bot::circuit_breaker::*;
+    use arb_bot::clob_error::{ClobError, ClobErrorKind};
+    use arb_bot::exchange::ExchangeClient;
+    use arb_bot::execution::ExecutionEngine;
+    use arb_bot::paper::*;
+    use arb_bot::exchange::OrderLeg;
+    use arb_bot::polymarket_clob::PolyOrderType;
+    use arb_bot::position_tracker::create_position_channel;
+    use arb_bot::types::*;
+    use std::sync::Arc;
+
+    fn test_state() -> Arc<GlobalState> {
+        let mut state = GlobalState::new();
+        state.add_pair(MarketPair {
+            pair_id: "paper-test".into(),
+            league: "epl".into(),
+            market_type: MarketType::Moneyline,
+            description: "Paper Test Market".into(),
+            poly_slug: "paper-test".into(),
+            poly_yes_token: "paper_yes".into(),
+            poly_no_token: "paper_no".into(),
+            line_value: None,
+            team_suffix: None,
+            event_id: Some("paper-event".into()),
+            game_date: chrono::NaiveDate::from_ymd_opt(2025, 12, 8),
+        });
+        Arc::new(state)
+    }
+
+    /// YES asks 45¢ x5, 46¢ x10; NO asks 50¢ x20; YES bid 44¢ x30
+    fn test_depth() -> Arc<DepthBook> {
+        let depth = Arc::new(DepthBook::new());
+        depth.apply_snapshot("paper_yes", [("0.44", "30")], [("0.46", "10"), ("0.45", "5")]);
+        depth.apply_snapshot("paper_no", [], [("0.50", "20")]);
+        depth
+    }
+
+    /// Test: snapshots are sorted and changes insert / remove levels
+    #[test]
+    fn test_depth_book_snapshot_and_changes() {
+        let depth = test_depth();
+        let book = depth.book("paper_yes").unwrap();
+        assert_eq!(book.asks, vec![(45, 5.0), (46, 10.0)]);
+        assert_eq!(book.bids, vec![(44, 30.0)]);
+
+        depth.apply_change("paper_yes", BookSide::Ask, 44, 3.0);
+        depth.apply_change("paper_yes", BookSide::Ask, 46, 0.0);
+        depth.apply_change("paper_yes", BookSide::Bid, 43, 7.0);
+        let book = depth.book("paper_yes").unwrap();
+        assert_eq!(book.asks, vec![(44, 3.0), (45, 5.0)]);
+        assert_eq!(book.bids, vec![(44, 30.0), (43, 7.0)]);
+
+        assert_eq!(BookSide::parse("BUY"), Some(BookSide::Bid));
+        assert_eq!(BookSide::parse("ask"), Some(BookSide::Ask));
+        assert_eq!(BookSide::parse("other"), None);
+    }
+
+    /// Test: buys walk the asks up to the limit and consume liquidity
+    #[test]
+    fn test_take_walks_levels() {
+        let depth = test_depth();
+        let fills = depth.take("paper_yes", BookSide::Ask, 46, 8.0, 1.0, PolyOrderType::FAK);
+        assert_eq!(fills, vec![(45, 5.0), (46, 3.0)]);
+        assert_eq!(depth.book("paper_yes").unwrap().asks, vec![(46, 7.0)]);
+
+        // Limit below the book: nothing
+        assert!(depth.take("paper_yes", BookSide::Ask, 45, 1.0, 1.0, PolyOrderType::FAK).is_empty());
+    }
+
+    /// Test: FOK fills everything or nothing; queue share limits each level
+    #[test]
+    fn test_take_fok_and_queue_share() {
+        let depth = test_depth();
+        assert!(depth.take("paper_yes", BookSide::Ask, 46, 20.0, 1.0, PolyOrderType::FOK).is_empty());
+        assert_eq!(depth.book("paper_yes").unwrap().asks.len(), 2, "killed FOK leaves the book alone");
+
+        let fills = depth.take("paper_yes", BookSide::Ask, 46, 20.0, 0.5, PolyOrderType::FAK);
+        assert_eq!(fills, vec![(45, 2.0), (46, 5.0)]);
+    }
+
+    /// Test: paper fills move cash and holdings and land in the paper tracker
+    #[tokio::test]
+    async fn test_paper_buy_and_sell() {
+        let paper = PaperExchange::new(paper_config("buy_sell"), test_depth(), test_state());
+
+        let fill = paper.place_order(OrderLeg::buy("paper_yes", 0.46, 8.0)).await.unwrap();
+        assert_eq!(fill.filled_size, 8.0);
+        assert!((fill.fill_cost - (5.0 * 0.45 + 3.0 * 0.46)).abs() < 1e-9);
+        assert_eq!(paper.holdings("paper_yes"), 8.0);
+
+        let sell = paper.place_order(OrderLeg::sell("paper_yes", 0.40, 8.0)).await.unwrap();
+        assert!((sell.fill_cost - 8.0 * 0.44).abs() < 1e-9, "sold into the 44¢ bid");
+        assert_eq!(paper.holdings("paper_yes"), 0.0);
+
+        let stats = paper.stats();
+        assert_eq!(stats.orders, 2);
+        assert_eq!(stats.filled, 2);
+        assert!((stats.cash - (100.0 - 3.63 + 3.52)).abs() < 1e-9);
+
+        let status = paper.query_order(&fill.order_id).await.unwrap();
+        assert_eq!(status.size_matched, 8.0);
+
+        let tracker = paper.tracker();
+        let tracker = tracker.read().await;
+        let position = tracker.get("paper-test").expect("paper position");
+        assert_eq!(position.poly_yes.contracts, 0.0);
+    }
+
+    /// Test: selling more than held and buying beyond cash are balance rejections
+    #[tokio::test]
+    async fn test_paper_balance_rejections() {
+        let paper = PaperExchange::new(paper_config("rejects"), test_depth(), test_state());
+
+        let err = paper.place_order(OrderLeg::sell("paper_yes", 0.40, 1.0)).await.unwrap_err();
+        assert_eq!(ClobError::kind_of(&err), ClobErrorKind::InsufficientBalance);
+
+        let err = paper.place_order(OrderLeg::buy("paper_no", 0.50, 500.0)).await.unwrap_err();
+        assert_eq!(ClobError::kind_of(&err), ClobErrorKind::InsufficientBalance);
+
+        let err = paper.place_order(OrderLeg::buy("paper_no", 0.40, 5.0)).await.unwrap_err();
+        assert_eq!(ClobError::kind_of(&err), ClobErrorKind::NoMatch);
+        assert_eq!(paper.stats().rejected, 2);
+        assert_eq!(paper.stats().no_match, 1);
+    }
+
+    /// Test: the real engine against the paper book sees the thin YES side as a mismatch
+    #[tokio::test]
+    async fn test_engine_against_paper_book() {
+        let state = test_state();
+        let depth = test_depth();
+        let paper = Arc::new(PaperExchange::new(paper_config("engine"), depth.clone(), state.clone()));
+        let cb = Arc::new(CircuitBreaker::new(breaker_config())
+            .with_markets(state.markets.iter().filter_map(|m| m.pair.as_deref())));
+        let (channel, mut fills) = create_position_channel();
+        let engine = ExecutionEngine::new(paper.clone(), state, cb.clone(), channel, false);
+
+        // Detector saw 45¢ YES x10 (stale) but the book only has 5 at 45¢
+        let result = engine.process(FastExecutionRequest {
+            market_id: 0,
+            yes_price: 45,
+            no_price: 50,
+            yes_size: 1000,
+            no_size: 1000,
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
+            arb_type: ArbType::PolyOnly,
+            detected_ns: 0,
+        }).await.unwrap();
+
+        assert!(result.success);
+        assert_eq!(result.profit_cents, 5 * 100 - (225 + 500));
+        assert_eq!(fills.try_recv().unwrap().contracts, 5.0, "YES leg as filled");
+        assert_eq!(fills.try_recv().unwrap().contracts, 10.0, "NO leg as filled");
+        assert_eq!(paper.holdings("paper_yes"), 5.0);
+        assert_eq!(paper.holdings("paper_no"), 10.0);
+        assert_eq!(paper.stats().partial, 1);
+
+        let status = cb.status().await;
+        assert_eq!(status.total_position, 15);
+        assert_eq!(status.unmatched_position, 5, "NO excess until auto-close sells it");
+    }
+}
+
+// ============================================================================
+// CLOB EMULATOR TESTS - Real client, Gamma lookup and WS feed against the emulator
+// ============================================================================
+
+mod emulator_tests {
+    use super::fixtures::breaker_config;
+    use arb_bot::clob_error::{ClobError, ClobErrorKind};
+    use arb_bot::emulator::*;
+    use arb_bot::polymarket::GammaClient;
+    use arb_bot::exchange::{ExchangeClient, OrderLeg};
+    use arb_bot::polymarket_clob::{PolymarketAsyncClient, PreparedCreds, SharedAsyncClient, SignatureType};
+    use arb_bot::presign::{PresignConfig, PresignPool, PresignedOrder};
+    use futures_util::{SinkExt, StreamExt};
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio_tungstenite::tungstenite::Message;
+
+    /// Well-known test key (anvil/hardhat account #0)
+    const TEST_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    const TEST_ADDRESS: &str = "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266";
+
+    fn book(bids: &[(&str, &str)], asks: &[(&str, &str)]) -> ScriptBook {
+        let levels = |l: &[(&str, &str)]| l.iter().map(|(p, s)| (p.to_string(), s.to_string())).collect();
+        ScriptBook { bids: levels(bids), asks: levels(asks) }
+    }
+
+    fn script() -> EmulatorScript {
+        EmulatorScript {
+            markets: vec![
+                ScriptMarket {
+                    slug: "nba-lal-bos-2026-01-15".into(),
+                    question: "Lakers vs. Celtics".into(),
+                    yes_token: "111".into(),
+                    no_token: "222".into(),
+                    neg_risk: false,
+                    fee_rate_bps: 0,
+                    yes_book: book(&[("0.38", "100")], &[("0.40", "100")]),
+                    no_book: book(&[("0.53", "100")], &[("0.55", "50")]),
+                    outcome: None,
+                    event_id: Some("40001".into()),
+                    start_time: Some("2026-01-16T00:30:00Z".into()),
+                },
+                ScriptMarket {
+                    slug: "epl-che-ars-2026-01-15".into(),
+                    question: "Chelsea vs. Arsenal".into(),
+                    yes_token: "333".into(),
+                    no_token: "444".into(),
+                    neg_risk: true,
+                    fee_rate_bps: 100,
+                    yes_book: book(&[], &[("0.30", "20")]),
+                    no_book: ScriptBook::default(),
+                    outcome: None,
+                    event_id: None,
+                    start_time: None,
+                },
+            ],
+            updates: vec![ScriptUpdate {
+                at_ms: 200,
+                asset_id: "111".into(),
+                side: "SELL".into(),
+                price: "0.41".into(),
+                size: "75".into(),
+            }],
+            faults: ScriptFaults::default(),
+        }
+    }
+
+    async fn start(balance_usdc: f64) -> SocketAddr {
+        start_with_faults(balance_usdc, ScriptFaults::default()).await
+    }
+
+    async fn start_with_faults(balance_usdc: f64, faults: ScriptFaults) -> SocketAddr {
+        Emulator::spawn(EmulatorConfig {
+            addr: "127.0.0.1:0".parse().unwrap(),
+            chain_id: 137,
+            balance_usdc,
+            script: EmulatorScript { faults, ..script() },
+        })
+        .await
+        .expect("emulator starts")
+    }
+
+    async fn connect(addr: SocketAddr) -> SharedAsyncClient {
+        let client = PolymarketAsyncClient::new(&format!("http://{}", addr), 137, TEST_KEY, TEST_ADDRESS)
+            .unwrap()
+            .with_signature_type(SignatureType::Eoa)
+            .unwrap();
+        let creds = client.derive_api_key(0).await.expect("L1 auth accepted");
+        let prepared = PreparedCreds::from_api_creds(&creds).unwrap();
+        SharedAsyncClient::new(client, prepared, 137)
+    }
+
+    #[tokio::test]
+    async fn test_derive_api_key_is_deterministic() {
+        let addr = start(1000.0).await;
+        let client = PolymarketAsyncClient::new(&format!("http://{}", addr), 137, TEST_KEY, TEST_ADDRESS).unwrap();
+
+        let first = client.derive_api_key(0).await.unwrap();
+        let second = client.derive_api_key(1).await.unwrap();
+        assert_eq!(first.api_key, second.api_key);
+        assert_eq!(first.api_secret, second.api_secret);
+        assert!(!first.api_passphrase.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_bad_l1_and_l2_headers_rejected() {
+        let addr = start(1000.0).await;
+        let http = reqwest::Client::new();
+
+        let resp = http.get(format!("http://{}/auth/derive-api-key", addr))
+            .header("POLY_ADDRESS", TEST_ADDRESS)
+            .header("POLY_SIGNATURE", format!("0x{}", "11".repeat(65)))
+            .header("POLY_TIMESTAMP", chrono::Utc::now().timestamp().to_string())
+            .header("POLY_NONCE", "0")
+            .send().await.unwrap();
+        assert_eq!(resp.status(), 401);
+
+        let resp = http.get(format!("http://{}/balance-allowance", addr))
+            .header("POLY_ADDRESS", TEST_ADDRESS)
+            .header("POLY_API_KEY", "not-a-key")
+            .header("POLY_PASSPHRASE", "x")
+            .header("POLY_SIGNATURE", "x")
+            .header("POLY_TIMESTAMP", chrono::Utc::now().timestamp().to_string())
+            .send().await.unwrap();
+        assert_eq!(resp.status(), 401);
+    }
+
+    #[tokio::test]
+    async fn test_batch_fills_against_scripted_book() {
+        let addr = start(1000.0).await;
+        let client = connect(addr).await;
+
+        let legs = [OrderLeg::buy("111", 0.40, 10.0), OrderLeg::buy("222", 0.55, 60.0)];
+        let results = client.execute_batch(&legs).await.unwrap();
+
+        let yes = results[0].as_ref().expect("yes leg fills");
+        assert!((yes.filled_size - 10.0).abs() < 1e-9);
+        assert!((yes.fill_cost - 4.0).abs() < 1e-9);
+
+        // FAK takes what's there and cancels the rest
+        let no = results[1].as_ref().expect("no leg partially fills");
+        assert!((no.filled_size - 50.0).abs() < 1e-9);
+
+        let status = client.order(&yes.order_id).await.unwrap();
+        assert_eq!(status.status, "MATCHED");
+        assert_eq!(status.asset_id.as_deref(), Some("111"));
+
+        let balance = client.balance_allowance().await.unwrap();
+        assert_eq!(balance.balance_micro(), 1_000_000_000 - 4_000_000 - 27_500_000);
+    }
+
+    #[tokio::test]
+    async fn test_presigned_pair_posted_through_exchange_client() {
+        let addr = start(1000.0).await;
+        let client = connect(addr).await;
+
+        let pool = Arc::new(PresignPool::new(PresignConfig {
+            enabled: true,
+            sizes: vec![10],
+            price_levels: 1,
+            refresh_ms: 500,
+        }));
+        for (token, price) in [("111", 40), ("222", 55)] {
+            let (order, fee_rate_bps) = client.prepare_order(token, price as f64 / 100.0, 10.0, "BUY").await.unwrap();
+            pool.insert(&Arc::from(token), PresignedOrder { order, fee_rate_bps, price, size: 10 });
+        }
+        let client = client.with_presign_pool(pool.clone());
+
+        // Asked for 20, the pool only has 10 signed
+        let legs = [OrderLeg::buy("111", 0.40, 20.0), OrderLeg::buy("222", 0.55, 20.0)];
+        let results = client.place_orders(&legs).await.unwrap();
+
+        let yes = results[0].as_ref().expect("yes leg fills");
+        let no = results[1].as_ref().expect("no leg fills");
+        assert!((yes.filled_size - 10.0).abs() < 1e-9);
+        assert!((no.filled_size - 10.0).abs() < 1e-9);
+        assert_eq!(pool.stats().held, 0, "each pre-signed order is posted once");
+        assert_eq!(pool.stats().hits, 1);
+    }
+
+    /// Test: orders missing from a short batch response are matched by hash and looked up
+    #[tokio::test]
+    async fn test_short_batch_response_keeps_fills() {
+        let faults = ScriptFaults { batch_response_limit: Some(1), ..Default::default() };
+        let client = connect(start_with_faults(1000.0, faults).await).await;
+
+        let legs = [OrderLeg::buy("111", 0.40, 10.0), OrderLeg::buy("222", 0.55, 60.0)];
+        let results = client.execute_batch(&legs).await.expect("posted orders are not failed as a batch");
+
+        let yes = results[0].as_ref().expect("answered leg");
+        assert!((yes.filled_size - 10.0).abs() < 1e-9);
+        let no = results[1].as_ref().expect("unanswered leg is looked up, not failed");
+        assert!(!no.fill_unknown);
+        assert!((no.filled_size - 50.0).abs() < 1e-9);
+        assert!((no.fill_cost - 27.5).abs() < 1e-9);
+        assert_eq!(client.order(&no.order_id).await.unwrap().asset_id.as_deref(), Some("222"));
+    }
+
+    /// Test: with no response entry and no order lookup, the fill comes from trade history
+    #[tokio::test]
+    async fn test_empty_batch_response_falls_back_to_trades() {
+        let faults = ScriptFaults { batch_response_limit: Some(0), hide_orders: true, ..Default::default() };
+        let client = connect(start_with_faults(1000.0, faults).await).await;
+
+        let legs = [OrderLeg::buy("111", 0.40, 10.0), OrderLeg::buy("222", 0.50, 10.0)];
+        let results = client.execute_batch(&legs).await.unwrap();
+
+        let yes = results[0].as_ref().expect("yes leg from trades");
+        assert!(!yes.fill_unknown);
+        assert!((yes.filled_size - 10.0).abs() < 1e-9);
+        assert!((yes.fill_cost - 4.0).abs() < 1e-9);
+
+        // Nothing at 0.50 on the NO book: no trades, so known to be unfilled
+        let no = results[1].as_ref().expect("no leg resolved");
+        assert!(!no.fill_unknown);
+        assert_eq!(no.filled_size, 0.0);
+    }
+
+    /// Test: an accepted order whose lookup 404s is reported as fill unknown, not a closed market
+    #[tokio::test]
+    async fn test_order_lookup_failure_reports_fill_unknown() {
+        let faults = ScriptFaults { hide_orders: true, trades_unavailable: true, ..Default::default() };
+        let client = connect(start_with_faults(1000.0, faults).await).await;
+
+        let legs = [OrderLeg::buy("111", 0.40, 10.0), OrderLeg::buy("222", 0.55, 10.0)];
+        let results = client.place_orders(&legs).await.unwrap();
+        for fill in &results {
+            let fill = fill.as_ref().expect("accepted legs are not failed");
+            assert!(fill.fill_unknown);
+            assert!(fill.order_id.starts_with("0x"), "order ID kept for the lookup later");
+            assert_eq!(fill.filled_size, 0.0);
+        }
+
+        let err = client.order(&results[0].as_ref().unwrap().order_id).await.unwrap_err();
+        assert_eq!(ClobError::kind_of(&err), ClobErrorKind::OrderQuery);
+    }
+
+    /// Test: orders sent without an answer come back as unknown fills keyed by hash, not an error
+    #[tokio::test]
+    async fn test_unanswered_post_reports_fill_unknown() {
+        let faults = ScriptFaults { drop_post_response: true, ..Default::default() };
+        let client = connect(start_with_faults(1000.0, faults).await).await;
+
+        let legs = [OrderLeg::buy("111", 0.40, 10.0), OrderLeg::buy("222", 0.55, 10.0)];
+        let results = client.place_orders(&legs).await.expect("batch not failed as a whole");
+        for fill in &results {
+            let fill = fill.as_ref().expect("posted legs are not failed");
+            assert!(fill.fill_unknown);
+            assert!(fill.order_id.starts_with("0x"), "order hash kept for the lookup later");
+        }
+
+        // The exchange did take them; the settle step finds the fill by hash
+        let order = client.order(&results[0].as_ref().unwrap().order_id).await.unwrap();
+        assert_eq!(order.size_matched, "10");
+
+        let single = client.buy_fak("111", 0.40, 5.0).await.expect("single order not failed");
+        assert!(single.fill_unknown);
+    }
+
+    #[tokio::test]
+    async fn test_order_rejections_classified() {
+        let addr = start(5.0).await;
+        let client = connect(addr).await;
+
+        // Nothing left to take at this limit
+        let err = client.buy_fak("111", 0.35, 10.0).await.unwrap_err();
+        assert_eq!(ClobError::kind_of(&err), ClobErrorKind::NoMatch);
+
+        // $5 can't pay for 20 @ 0.40
+        let err = client.buy_fak("111", 0.40, 20.0).await.unwrap_err();
+        assert_eq!(ClobError::kind_of(&err), ClobErrorKind::InsufficientBalance);
+    }
+
+    /// Test: a single order rejected inside a 200 response is a classified error, not an unknown fill
+    #[tokio::test]
+    async fn test_single_order_rejection_in_ok_response() {
+        let faults = ScriptFaults { single_reject_ok: true, ..Default::default() };
+        let client = connect(start_with_faults(5.0, faults).await).await;
+
+        // An auto-close sell above the best bid
+        let err = client.sell_fak("111", 0.45, 10.0).await.unwrap_err();
+        assert_eq!(ClobError::kind_of(&err), ClobErrorKind::NoMatch);
+
+        let err = client.buy_fak("111", 0.40, 20.0).await.unwrap_err();
+        assert_eq!(ClobError::kind_of(&err), ClobErrorKind::InsufficientBalance);
+    }
+
+    #[tokio::test]
+    async fn test_wrong_exchange_domain_rejected_as_invalid_signature() {
+        let addr = start(1000.0).await;
+        let client = connect(addr).await;
+
+        // Stale cache claims a standard market is neg-risk, so the order is signed
+        // for the wrong exchange contract
+        let path = std::env::temp_dir().join(format!("emu_neg_risk_{}.json", std::process::id()));
+        std::fs::write(&path, r#"{"111": true}"#).unwrap();
+        client.load_cache(path.to_str().unwrap()).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let err = client.buy_fak("111", 0.40, 10.0).await.unwrap_err();
+        assert_eq!(ClobError::kind_of(&err), ClobErrorKind::InvalidSignature);
+    }
+
+    #[tokio::test]
+    async fn test_neg_risk_market_and_fee_rate() {
+        let addr = start(1000.0).await;
+        let client = connect(addr).await;
+
+        assert_eq!(client.fee_rate_bps("333").await.unwrap(), 100);
+        assert_eq!(client.fee_rate_bps("111").await.unwrap(), 0);
+
+        // Signed against the neg-risk exchange with the market fee rate
+        let fill = client.buy_fak("333", 0.30, 5.0).await.unwrap();
+        assert!((fill.filled_size - 5.0).abs() < 1e-9);
+        assert!(fill.fees > 0.0);
+        assert_eq!(client.cached_neg_risk("333"), Some(true));
+    }
+
+    #[tokio::test]
+    async fn test_gamma_lookup() {
+        let addr = start(1000.0).await;
+        let gamma = GammaClient::new().with_base_url(&format!("http://{}", addr));
+
+        let info = gamma.lookup_market("nba-lal-bos-2026-01-15").await.unwrap().unwrap();
+        assert_eq!((info.yes_token.as_str(), info.no_token.as_str()), ("111", "222"));
+        assert_eq!(info.description, "Lakers vs. Celtics");
+        assert_eq!(info.event_id.as_deref(), Some("40001"));
+        assert_eq!(info.start_date, chrono::NaiveDate::from_ymd_opt(2026, 1, 16), "Event start, not the slug date");
+
+        let no_event = gamma.lookup_market("epl-che-ars-2026-01-15").await.unwrap().unwrap();
+        assert_eq!((no_event.event_id, no_event.start_date), (None, None));
+
+        assert!(gamma.lookup_market("nba-mia-nyk-2026-01-15").await.unwrap().is_none());
+    }
+
+    /// Test: the breaker releases locked capital once Gamma reports the market resolved
+    #[tokio::test]
+    async fn test_resolution_releases_locked_capital() {
+        use arb_bot::circuit_breaker::CircuitBreaker;
+        use arb_bot::position_tracker::FillRecord;
+
+        let mut script = script();
+        script.markets[1].outcome = Some(false);
+        let addr = Emulator::spawn(EmulatorConfig {
+            addr: "127.0.0.1:0".parse().unwrap(),
+            chain_id: 137,
+            balance_usdc: 1000.0,
+            script,
+        })
+        .await
+        .unwrap();
+        let gamma = GammaClient::new().with_base_url(&format!("http://{}", addr));
+
+        assert_eq!(gamma.lookup_resolution("nba-lal-bos-2026-01-15").await.unwrap(), None, "Still trading");
+        assert_eq!(gamma.lookup_resolution("epl-che-ars-2026-01-15").await.unwrap(), Some(false));
+        assert_eq!(gamma.lookup_resolution("epl-che-ars-2026-01-14").await.unwrap(), Some(false), "Next-day slug");
+
+        let cb = CircuitBreaker::new(breaker_config());
+        for market in ["poly-nba-lal-bos-2026-01-15", "poly-epl-che-ars-2026-01-14", "replay-0"] {
+            cb.record_fill(&FillRecord::new(market, market, "polymarket", "yes", 10.0, 0.50, 0.0, "order")).await;
+        }
+        assert!((cb.status().await.locked - 15.0).abs() < 1e-9);
+
+        cb.check_resolutions(&gamma).await;
+        let status = cb.status().await;
+        assert!((status.locked - 10.0).abs() < 1e-9, "Resolved market no longer locked");
+        assert!((status.deployed - 15.0).abs() < 1e-9, "Still deployed until redeemed");
+        assert!(cb.position("poly-epl-che-ars-2026-01-14").await.resolved);
+    }
+
+    #[tokio::test]
+    async fn test_ws_snapshot_then_scripted_update() {
+        let addr = start(1000.0).await;
+        let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws/market", addr)).await.unwrap();
+
+        ws.send(Message::Text(r#"{"assets_ids":["111","222"],"type":"market"}"#.into())).await.unwrap();
+
+        let next_text = |msg: Option<Result<Message, _>>| match msg {
+            Some(Ok(Message::Text(t))) => serde_json::from_str::<serde_json::Value>(&t).unwrap(),
+            other => panic!("expected text frame, got {:?}", other),
+        };
+
+        let snapshot = next_text(ws.next().await);
+        let books = snapshot.as_array().unwrap();
+        assert_eq!(books.len(), 2);
+        assert_eq!(books[0]["asset_id"], "111");
+        assert_eq!(books[0]["asks"][0]["price"], "0.40");
+        assert_eq!(books[0]["asks"][0]["size"], "100");
+
+        let update = tokio::time::timeout(Duration::from_secs(5), ws.next()).await.expect("scripted update");
+        let update = next_text(update);
+        assert_eq!(update["event_type"], "price_change");
+        let change = &update["price_changes"][0];
+        assert_eq!(change["asset_id"], "111");
+        assert_eq!(change["price"], "0.41");
+        assert_eq!(change["size"], "75");
+        assert_eq!(change["side"], "SELL");
+    }
+
+    /// Test: unsubscribing and resubscribing a quiet token brings a fresh snapshot
+    #[tokio::test]
+    async fn test_ws_resubscribe_sends_fresh_snapshot() {
+        let addr = start(1000.0).await;
+        let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws/market", addr)).await.unwrap();
+
+        let next_books = |msg: Option<Result<Message, _>>| match msg {
+            Some(Ok(Message::Text(t))) => serde_json::from_str::<Vec<serde_json::Value>>(&t).unwrap(),
+            other => panic!("expected text frame, got {:?}", other),
+        };
+
+        // NO token only, so the scripted YES update never interleaves
+        ws.send(Message::Text(r#"{"assets_ids":["222"],"type":"market"}"#.into())).await.unwrap();
+        assert_eq!(next_books(ws.next().await).len(), 1);
+
+        // Subscribing again to a live token sends nothing new
+        ws.send(Message::Text(r#"{"assets_ids":["222"],"operation":"subscribe"}"#.into())).await.unwrap();
+        assert!(next_books(ws.next().await).is_empty());
+
+        ws.send(Message::Text(r#"{"assets_ids":["222"],"operation":"unsubscribe"}"#.into())).await.unwrap();
+        ws.send(Message::Text(r#"{"assets_ids":["222"],"operation":"subscribe"}"#.into())).await.unwrap();
+        let books = next_books(ws.next().await);
+        assert_eq!(books.len(), 1);
+        assert_eq!(books[0]["asset_id"], "222");
+        assert_eq!(books[0]["asks"][0]["price"], "0.55");
+    }
+}
+
+// ============================================================================
+// RECORDER TESTS - Segment rotation, index lookups and crash-truncated tails
+// ============================================================================
+
+mod recorder_tests {
+    use super::fixtures::recorder_config;
+    use arb_bot::recorder::*;
+    use std::path::PathBuf;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("arb_rec_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn frame(i: u64) -> String {
+        format!(r#"{{"event_type":"price_change","price_changes":[{{"asset_id":"{}","price":"0.45","size":"100","side":"SELL"}}]}}"#, i)
+    }
+
+    #[test]
+    fn test_frames_round_trip_in_order() {
+        let dir = test_dir("round_trip");
+        let recorder = WsRecorder::start(&recorder_config(&dir)).unwrap();
+
+        for i in 0..10 {
+            recorder.record_at(1_000 + i, frame(i).as_bytes());
+        }
+        recorder.flush().unwrap();
+
+        let segments = segments(dir.to_str().unwrap()).unwrap();
+        assert_eq!(segments.len(), 1);
+
+        let frames = read_segment(&segments[0]).unwrap();
+        assert_eq!(frames.len(), 10);
+        for (i, f) in frames.iter().enumerate() {
+            assert_eq!(f.recv_ns, 1_000 + i as u64);
+            assert_eq!(f.text().unwrap(), frame(i as u64));
+        }
+
+        // 4 + 4 + 2 (the flush writes the partial block)
+        let index = read_index(&segments[0]).unwrap();
+        assert_eq!(index.iter().map(|b| b.frames).collect::<Vec<_>>(), vec![4, 4, 2]);
+        assert_eq!((index[1].first_ns, index[1].last_ns), (1_004, 1_007));
+
+        let stats = recorder.stats();
+        assert_eq!((stats.frames, stats.dropped, stats.blocks, stats.segments), (10, 0, 3, 1));
+        assert!(stats.written_bytes > 0);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_rotates_by_size() {
+        let dir = test_dir("rotate");
+        let config = RecorderConfig { rotate_bytes: 1, block_frames: 2, ..recorder_config(&dir) };
+        let recorder = WsRecorder::start(&config).unwrap();
+
+        for i in 0..6 {
+            recorder.record_at(10_000 + i * 1_000, frame(i).as_bytes());
+        }
+        recorder.flush().unwrap();
+
+        // Every block overflows the 1-byte limit, so each one starts a segment
+        let segments = segments(dir.to_str().unwrap()).unwrap();
+        assert_eq!(segments.len(), 3);
+        assert!(segments[0].file_name().unwrap().to_str().unwrap().starts_with("ws-10000"));
+        let all: Vec<u64> = segments.iter()
+            .flat_map(|s| read_segment(s).unwrap())
+            .map(|f| f.recv_ns)
+            .collect();
+        assert_eq!(all, vec![10_000, 11_000, 12_000, 13_000, 14_000, 15_000]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_read_range_uses_index() {
+        let dir = test_dir("range");
+        let config = RecorderConfig { rotate_bytes: 200, ..recorder_config(&dir) };
+        let recorder = WsRecorder::start(&config).unwrap();
+
+        for i in 0..20 {
+            recorder.record_at(i * 100, frame(i).as_bytes());
+        }
+        recorder.flush().unwrap();
+
+        let frames = read_range(dir.to_str().unwrap(), 550, 1_200).unwrap();
+        let times: Vec<u64> = frames.iter().map(|f| f.recv_ns).collect();
+        assert_eq!(times, vec![600, 700, 800, 900, 1_000, 1_100, 1_200]);
+
+        let index = read_index(&segments(dir.to_str().unwrap()).unwrap()[0]).unwrap();
+        let block = read_block(&segments(dir.to_str().unwrap()).unwrap()[0], &index[0]).unwrap();
+        assert_eq!(block.len(), index[0].frames as usize);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_truncated_tail_keeps_complete_blocks() {
+        let dir = test_dir("truncated");
+        let recorder = WsRecorder::start(&recorder_config(&dir)).unwrap();
+
+        for i in 0..8 {
+            recorder.record_at(i, frame(i).as_bytes());
+        }
+        recorder.flush().unwrap();
+
+        // Simulate a crash mid-write: half of a third block lands on disk
+        let segment = segments(dir.to_str().unwrap()).unwrap().remove(0);
+        let bytes = std::fs::read(&segment).unwrap();
+        let first_block = read_index(&segment).unwrap()[0].clone();
+        let partial = &bytes[..first_block.len as usize / 2];
+        let mut file = std::fs::OpenOptions::new().append(true).open(&segment).unwrap();
+        std::io::Write::write_all(&mut file, partial).unwrap();
+
+        let frames = read_segment(&segment).unwrap();
+        assert_eq!(frames.len(), 8);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_full_queue_drops_instead_of_blocking() {
+        let dir = test_dir("backpressure");
+        let config = RecorderConfig { queue_size: 1, ..recorder_config(&dir) };
+        let recorder = WsRecorder::start(&config).unwrap();
+
+        let start = std::time::Instant::now();
+        for i in 0..10_000 {
+            recorder.record_at(i, frame(i).as_bytes());
+        }
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+        recorder.flush().unwrap();
+
+        let stats = recorder.stats();
+        assert_eq!(stats.frames + stats.dropped, 10_000);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// Test: recording range bounds accept dates, RFC 3339 and raw nanoseconds
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("2026-01-15", false).unwrap(), 1_768_435_200_000_000_000);
+        assert_eq!(parse_time("2026-01-15", true).unwrap(), 1_768_521_599_999_999_999);
+        assert_eq!(parse_time("2026-01-15T12:00:00Z", false).unwrap(), 1_768_478_400_000_000_000);
+        assert_eq!(parse_time("42", false).unwrap(), 42);
+        assert!(parse_time("yesterday", false).is_err());
+    }
+}
+
+// ============================================================================
+// REPLAY FIXTURES - Markets, configs and frames shared by replay and backtest tests
+// ============================================================================
+
+#[cfg(feature = "replay")]
+mod replay_fixtures {
+    use super::fixtures::breaker_config;
+    use arb_bot::circuit_breaker::CircuitBreakerConfig;
+    use arb_bot::paper::PaperConfig;
+    use arb_bot::recorder::*;
+    use arb_bot::replay::ReplayConfig;
+    use arb_bot::types::*;
+
+    pub const T0: u64 = 1_768_500_000_000_000_000;
+    pub const MS: u64 = 1_000_000;
+
+    pub fn market(i: usize) -> MarketSnapshot {
+        MarketSnapshot {
+            pair: MarketPair {
+                pair_id: format!("replay-{}", i).into(),
+                league: "nba".into(),
+                market_type: MarketType::Moneyline,
+                description: format!("Replay market {}", i).into(),
+                poly_slug: format!("replay-{}", i).into(),
+                poly_yes_token: format!("{}1", i).into(),
+                poly_no_token: format!("{}2", i).into(),
+                line_value: None,
+                team_suffix: None,
+                event_id: Some(format!("replay-event-{}", i).into()),
+                game_date: chrono::NaiveDate::from_ymd_opt(2026, 1, 15),
+            },
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
+        }
+    }
+
+    pub fn config(latency_ms: u64, jitter_ms: u64) -> ReplayConfig {
+        ReplayConfig {
+            dir: String::new(),
+            markets_file: String::new(),
+            from_ns: 0,
+            to_ns: u64::MAX,
+            threshold_cents: 99,
+            max_quote_age_ms: 60_000,
+            dry_run: false,
+            paper: PaperConfig {
+                enabled: true,
+                latency_ms,
+                latency_jitter_ms: jitter_ms,
+                queue_share: 1.0,
+                starting_usdc: 1_000.0,
+                positions_file: String::new(),
+                seed: Some(7),
+            },
+            breaker: CircuitBreakerConfig {
+                max_position_per_market: 10_000,
+                max_total_position: 100_000,
+                max_daily_loss: 1_000.0,
+                ..breaker_config()
+            },
+        }
+    }
+
+    pub fn frame(recv_ns: u64, text: &str) -> RecordedFrame {
+        RecordedFrame { recv_ns, payload: text.as_bytes().to_vec() }
+    }
+
+    /// Snapshot of both tokens of market i: YES ask / NO ask with 100 contracts, bids 5¢ lower
+    pub fn snapshot(recv_ns: u64, i: usize, yes_ask: &str, no_ask: &str) -> RecordedFrame {
+        let bid = |ask: &str| format!("{:.2}", ask.parse::<f64>().unwrap() - 0.05);
+        frame(recv_ns, &format!(
+            r#"[{{"asset_id":"{i}1","bids":[{{"price":"{}","size":"100"}}],"asks":[{{"price":"{yes_ask}","size":"100"}}]}},
+               {{"asset_id":"{i}2","bids":[{{"price":"{}","size":"100"}}],"asks":[{{"price":"{no_ask}","size":"100"}}]}}]"#,
+            bid(yes_ask), bid(no_ask)
+        ))
+    }
+
+    pub fn change(recv_ns: u64, asset: &str, side: &str, price: &str, size: &str) -> RecordedFrame {
+        frame(recv_ns, &format!(
+            r#"{{"event_type":"price_change","price_changes":[{{"asset_id":"{asset}","price":"{price}","size":"{size}","side":"{side}"}}]}}"#
+        ))
+    }
+}
+
+// ============================================================================
+// REPLAY TESTS - Recorded frames through detection and paper execution
+// ============================================================================
+
+#[cfg(feature = "replay")]
+mod replay_tests {
+    use super::replay_fixtures::*;
+    use arb_bot::recorder::*;
+    use arb_bot::replay::*;
+
+    fn session() -> Vec<RecordedFrame> {
+        vec![
+            snapshot(T0, 0, "0.40", "0.55"),
+            snapshot(T0 + 5 * MS, 1, "0.50", "0.52"),
+            change(T0 + 20 * MS, "02", "SELL", "0.55", "0"),
+            change(T0 + 40 * MS, "11", "ASK", "0.45", "30"),
+            snapshot(T0 + 12_000 * MS, 0, "0.41", "0.56"),
+            change(T0 + 12_030 * MS, "12", "ASK", "0.50", "60"),
+        ]
+    }
+
+    #[test]
+    fn test_same_input_same_decisions() {
+        let markets = vec![market(0), market(1)];
+        let config = config(25, 30);
+
+        let first = replay(&config, &session(), &markets).unwrap();
+        let second = replay(&config, &session(), &markets).unwrap();
+
+        assert!(!first.decisions.is_empty());
+        assert_eq!(first.decisions, second.decisions);
+        assert_eq!(first.digest(), second.digest());
+        assert_eq!(first.paper, second.paper);
+        assert_eq!(first.frames, 6);
+    }
+
+    #[test]
+    fn test_latency_runs_on_recorded_time() {
+        // NO liquidity is pulled 20ms after the arb appears
+        let frames = vec![
+            snapshot(T0, 0, "0.40", "0.55"),
+            change(T0 + 20 * MS, "02", "SELL", "0.55", "0"),
+        ];
+        let markets = vec![market(0)];
+
+        let fast = replay(&config(5, 0), &frames, &markets).unwrap();
+        assert_eq!(fast.decisions.len(), 1);
+        assert!(fast.decisions[0].executed);
+        assert_eq!(fast.decisions[0].profit_cents, 500);
+        assert_eq!(fast.decisions[0].recv_ns, T0);
+
+        // Orders land after the pull: YES fills, NO finds nothing, YES is closed out
+        let slow = replay(&config(50, 0), &frames, &markets).unwrap();
+        assert_eq!(slow.decisions.len(), 1);
+        assert!(!slow.decisions[0].executed);
+        assert_eq!(slow.decisions[0].error.as_deref(), Some("Partial/no fill"));
+        assert_eq!(slow.paper.no_match, 1);
+        // Buy YES + auto-close sell YES after the 2s settlement wait
+        assert_eq!(slow.paper.orders, 3);
+    }
+
+    #[test]
+    fn test_in_flight_hold_follows_simulated_clock() {
+        // Second arb on the same market 5s later is still in-flight (10s hold),
+        // the one 12s later is executed again
+        let frames = vec![
+            snapshot(T0, 0, "0.40", "0.55"),
+            snapshot(T0 + 5_000 * MS, 0, "0.41", "0.55"),
+            snapshot(T0 + 12_000 * MS, 0, "0.42", "0.55"),
+        ];
+        let report = replay(&config(5, 0), &frames, &[market(0)]).unwrap();
+
+        // The first snapshot completes the arb on its NO update; later snapshots
+        // re-price YES and NO with the arb still open, so each sends two requests
+        let outcomes: Vec<(u64, bool, Option<&str>)> = report.decisions.iter()
+            .map(|d| (d.detected_ns / (1_000 * MS), d.executed, d.error.as_deref()))
+            .collect();
+        assert_eq!(outcomes, vec![
+            (0, true, None),
+            (5, false, Some("Already in-flight")),
+            (5, false, Some("Already in-flight")),
+            (12, false, Some("Already in-flight")),
+            (12, true, None),
+        ]);
+    }
+
+    #[test]
+    fn test_dry_run_places_no_orders() {
+        let mut config = config(5, 0);
+        config.dry_run = true;
+        let report = replay(&config, &session(), &[market(0), market(1)]).unwrap();
+
+        assert!(!report.decisions.is_empty());
+        assert!(report.decisions.iter().all(|d| !d.executed));
+        assert!(report.decisions.iter().any(|d| d.error.as_deref() == Some("DRY_RUN")));
+        assert_eq!(report.paper.orders, 0);
+    }
+
+    #[test]
+    fn test_episodes_and_market_outcomes() {
+        let frames = vec![
+            snapshot(T0, 0, "0.40", "0.55"),
+            change(T0 + 20 * MS, "02", "SELL", "0.55", "0"),
+        ];
+        let markets = vec![market(0)];
+
+        // One episode from detection until the NO ask is pulled, 100 contracts at 5¢ edge
+        let fast = replay(&config(5, 0), &frames, &markets).unwrap();
+        assert_eq!(fast.episodes.len(), 1);
+        let episode = &fast.episodes[0];
+        assert_eq!(episode.pair_id, "replay-0");
+        assert_eq!(episode.peak_edge_cents, 5);
+        assert_eq!(episode.max_contracts, 100.0);
+        assert!((episode.max_capturable - 5.0).abs() < 1e-9);
+        assert!((episode.duration_ms() - 20.0).abs() < 1e-9);
+        assert!(!episode.open_at_end);
+
+        assert_eq!(fast.markets.len(), 1);
+        assert_eq!(fast.markets[0].matched_contracts, 100.0);
+        assert!((fast.pnl() - 5.0).abs() < 1e-9);
+        assert_eq!(fast.markets[0].mismatch_loss, 0.0);
+        assert_eq!(episode.outcome, arb_bot::opportunity::Outcome::Captured);
+        assert_eq!(episode.time_to_order_ms(), Some(0.0));
+
+        // Too slow: YES fills alone and is sold back into the 35¢ bid
+        let slow = replay(&config(50, 0), &frames, &markets).unwrap();
+        assert_eq!(slow.episodes.len(), 1);
+        assert_eq!(slow.markets[0].matched_contracts, 0.0);
+        assert!((slow.markets[0].mismatch_loss - 5.0).abs() < 1e-9);
+        assert!((slow.pnl() + 5.0).abs() < 1e-9);
+        assert_eq!(slow.episodes[0].outcome, arb_bot::opportunity::Outcome::Partial);
+    }
+
+    #[test]
+    fn test_replay_from_recording() {
+        let dir = std::env::temp_dir().join(format!("arb_replay_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let rec_config = RecorderConfig {
+            enabled: true,
+            dir: dir.to_str().unwrap().to_string(),
+            rotate_secs: 3600,
+            rotate_bytes: 1024 * 1024,
+            block_frames: 2,
+            flush_ms: 60_000,
+            queue_size: 1024,
+        };
+        let recorder = WsRecorder::start(&rec_config).unwrap();
+        let markets = vec![market(0), market(1)];
+        save_markets(&rec_config.dir, &build_state(&markets)).unwrap();
+        for f in session() {
+            recorder.record_at(f.recv_ns, &f.payload);
+        }
+        recorder.flush().unwrap();
+
+        let mut config = config(25, 30);
+        config.dir = rec_config.dir.clone();
+        config.markets_file = dir.join(MARKETS_FILE).to_string_lossy().into_owned();
+        let from_recording = replay_recording(&config).unwrap();
+        let direct = replay(&config, &session(), &markets).unwrap();
+        assert_eq!(from_recording.decisions, direct.decisions);
+
+        // A time window replays only its frames
+        config.to_ns = T0 + 100 * MS;
+        let window = replay_recording(&config).unwrap();
+        assert_eq!(window.frames, 4);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
+
+// ============================================================================
+// BACKTEST TESTS - Parameter sweeps over replayed data
+// ============================================================================
+
+#[cfg(feature = "replay")]
+mod backtest_tests {
+    use super::replay_fixtures::*;
+    use arb_bot::backtest::*;
+    use arb_bot::recorder::*;
+    use arb_bot::replay::replay;
+    use arb_bot::types::*;
+
+    /// Market 0: 5¢ arb for 20ms. Market 1: 2¢ arb (cost 98) for 200ms.
+    fn session() -> Vec<RecordedFrame> {
+        vec![
+            snapshot(T0, 0, "0.40", "0.55"),
+            snapshot(T0 + 10 * MS, 1, "0.48", "0.50"),
+            change(T0 + 20 * MS, "02", "SELL", "0.55", "0"),
+            change(T0 + 210 * MS, "12", "SELL", "0.50", "0"),
+        ]
+    }
+
+    fn grid(thresholds: Vec<PriceCents>, latencies: Vec<u64>) -> SweepGrid {
+        SweepGrid { thresholds_cents: thresholds, latencies_ms: latencies, ..SweepGrid::single(&config(5, 0)) }
+    }
+
+    #[test]
+    fn test_grid_is_cartesian_product() {
+        let combos = grid(vec![98, 99], vec![5, 50, 500]).combinations();
+        assert_eq!(combos.len(), 6);
+        assert_eq!((combos[0].threshold_cents, combos[0].latency_ms), (98, 5));
+        assert_eq!((combos[5].threshold_cents, combos[5].latency_ms), (99, 500));
+
+        let config = combos[4].apply(&config(5, 0));
+        assert_eq!(config.threshold_cents, 99);
+        assert_eq!(config.paper.latency_ms, 50);
+        assert_eq!(config.breaker.max_daily_loss, 1_000.0);
+    }
+
+    #[test]
+    fn test_summarize_per_market_and_total() {
+        let report = replay(&config(5, 0), &session(), &[market(0), market(1)]).unwrap();
+        let (markets, total) = summarize(&report);
+
+        assert_eq!(markets.len(), 2);
+        assert_eq!(markets[0].pair_id, "replay-0");
+        assert_eq!(markets[0].arbs, 1);
+        assert!((markets[0].max_duration_ms - 20.0).abs() < 1e-9);
+        assert!((markets[0].capturable_profit - 5.0).abs() < 1e-9);
+        assert!((markets[0].pnl - 5.0).abs() < 1e-9);
+        assert!((markets[1].avg_duration_ms - 200.0).abs() < 1e-9);
+        assert!((markets[1].pnl - 2.0).abs() < 1e-9);
+
+        assert_eq!(total.pair_id, "ALL");
+        assert_eq!(total.arbs, 2);
+        assert_eq!(total.executed, 2);
+        assert!((total.avg_duration_ms - 110.0).abs() < 1e-9);
+        assert!((total.pnl - 7.0).abs() < 1e-9);
+        assert_eq!(total.mismatch_loss, 0.0);
+    }
+
+    #[test]
+    fn test_sweep_is_parallel_and_ordered() {
+        let markets = vec![market(0), market(1)];
+        let config = BacktestConfig { base: config(5, 0), grid: grid(vec![97, 99], vec![5, 50]), threads: 3 };
+        let runs = sweep(&config, &session(), &markets).unwrap();
+
+        assert_eq!(runs.len(), 4);
+        assert_eq!(runs.iter().map(|r| r.params.clone()).collect::<Vec<_>>(), config.grid.combinations());
+
+        // Each run matches a sequential replay of the same point
+        for run in &runs {
+            let report = replay(&run.params.apply(&config.base), &session(), &markets).unwrap();
+            assert_eq!(run.digest, report.digest());
+        }
+
+        // 97¢ only sees the 5¢ arb; 99¢ sees both
+        assert_eq!(runs[0].total.arbs, 1);
+        assert_eq!(runs[2].total.arbs, 2);
+        // At 50ms both pulls beat the NO order on market 0: its YES leg is sold back at a loss
+        assert!(runs[1].total.mismatch_loss > 0.0);
+        assert!(runs[1].total.pnl < runs[0].total.pnl);
+    }
+}
+
+// ============================================================================
+// BEHAVIOUR TESTS - Sliding-window breakers on synthetic event streams
+// ============================================================================
+
+mod behaviour_tests {
+    use super::fixtures::breaker_config;
+    use arb_bot::behaviour::*;
+    use arb_bot::circuit_breaker::*;
+
+    fn config() -> BehaviourConfig {
+        BehaviourConfig {
+            window_secs: 60,
+            min_samples: 10,
+            max_reject_ratio: 0.5,
+            max_mismatch_ratio: 0.3,
+            latency_percentile: 0.9,
+            max_latency_ms: 500.0,
+            max_orders_per_min: 1000,
+        }
+    }
+
+    fn order(rejected: bool) -> BehaviourEvent {
+        BehaviourEvent::Order { rejected }
+    }
+
+    /// Test: Reject ratio needs enough samples and only counts the window
+    #[test]
+    fn test_reject_ratio() {
+        let mut monitor = BehaviourMonitor::new(config());
+
+        // 9 rejects in a row: not enough samples yet
+        for i in 0..9 {
+            assert_eq!(monitor.record(i * 100, order(true)), None);
+        }
+        let tripped = monitor.record(900, order(true));
+        assert!(matches!(tripped, Some(TripReason::RejectRatio { samples: 10, .. })), "{:?}", tripped);
+
+        // A minute later those rejects have aged out
+        let mut monitor = BehaviourMonitor::new(config());
+        for i in 0..9 {
+            monitor.record(i * 100, order(true));
+        }
+        for i in 0..10 {
+            assert_eq!(monitor.record(61_000 + i * 100, order(i % 2 == 0)), None, "5/10 is at, not over, the limit");
+        }
+        assert!(matches!(monitor.record(62_000, order(true)), Some(TripReason::RejectRatio { samples: 11, .. })));
+    }
+
+    /// Test: Mismatched fills as a share of executions
+    #[test]
+    fn test_mismatch_ratio() {
+        let mut monitor = BehaviourMonitor::new(config());
+        let mut trips = Vec::new();
+        for i in 0..20u64 {
+            // Every third execution fills the legs unevenly (7 of 20 = 35%)
+            let mismatched = i % 3 == 0;
+            if let Some(reason) = monitor.record(i * 1000, BehaviourEvent::Execution { mismatched }) {
+                trips.push((i, reason));
+            }
+        }
+        // 4 of the first 10 (0, 3, 6, 9) is already 40%
+        let (at, reason) = &trips[0];
+        assert_eq!(*at, 9);
+        assert!(matches!(reason, TripReason::MismatchRatio { samples: 10, ratio, .. } if (*ratio - 0.4).abs() < 1e-9));
+        assert_eq!(reason.class(), "mismatch_ratio");
+    }
+
+    /// Test: Latency percentile over the window
+    #[test]
+    fn test_latency_percentile() {
+        let mut monitor = BehaviourMonitor::new(config());
+        let latency = |ms| BehaviourEvent::Latency { ms };
+
+        // One slow outlier in 10 stays under p90
+        for i in 0..9 {
+            assert_eq!(monitor.record(i * 10, latency(50.0)), None);
+        }
+        assert_eq!(monitor.record(90, latency(5000.0)), None);
+
+        // A second one moves p90 onto it
+        let tripped = monitor.record(100, latency(800.0));
+        assert!(matches!(tripped, Some(TripReason::LatencySpike { latency_ms, .. }) if latency_ms == 800.0), "{:?}", tripped);
+    }
+
+    /// Test: Orders per minute, independent of the ratio window
+    #[test]
+    fn test_order_rate() {
+        let mut monitor = BehaviourMonitor::new(BehaviourConfig { max_orders_per_min: 5, ..config() });
+        for i in 0..5 {
+            assert_eq!(monitor.record(i * 10_000, order(false)), None);
+        }
+        assert!(matches!(monitor.record(50_000, order(false)), Some(TripReason::OrderRate { orders: 6, limit: 5 })));
+
+        // Spread over more than a minute the same count is fine
+        let mut monitor = BehaviourMonitor::new(BehaviourConfig { max_orders_per_min: 5, ..config() });
+        for i in 0..10 {
+            assert_eq!(monitor.record(i * 15_000, order(false)), None);
+        }
+    }
+
+    fn breaker(behaviour: BehaviourConfig) -> CircuitBreaker {
+        CircuitBreaker::new(CircuitBreakerConfig {
+            behaviour,
+            auto_reset: vec![],
+            ..breaker_config()
+        })
+    }
+
+    /// Test: The breaker halts on a window trip and starts a fresh window after reset
+    #[tokio::test]
+    async fn test_breaker_trips_and_resets_window() {
+        let cb = breaker(config());
+        for i in 0..10 {
+            cb.record_behaviour(i * 100, BehaviourEvent::Execution { mismatched: true }).await;
+        }
+        let status = cb.status().await;
+        assert!(status.halted);
+        assert!(matches!(status.trip_reason, Some(TripReason::MismatchRatio { .. })));
+
+        cb.reset().await;
+        cb.record_behaviour(1_100, BehaviourEvent::Execution { mismatched: true }).await;
+        assert!(cb.is_trading_allowed(), "One sample after reset is below min_samples");
+    }
+}
+
+// ============================================================================
+// OPPORTUNITY TESTS - Episode lifecycle, log and daily summaries
+// ============================================================================
+
+mod opportunity_tests {
+    use arb_bot::execution::NanoClock;
+    use arb_bot::opportunity::*;
+    use arb_bot::recorder::{MarketSnapshot, build_state};
+    use arb_bot::types::*;
+    use std::time::Duration;
+
+    const DAY1: u64 = 1_768_435_200_000_000_000; // 2026-01-15 00:00 UTC
+    const DAY: u64 = 86_400_000_000_000;
+
+    fn state() -> GlobalState {
+        let snapshot = MarketSnapshot {
+            pair: MarketPair {
+                pair_id: "opp-0".into(),
+                league: "nba".into(),
+                market_type: MarketType::Moneyline,
+                description: "Opportunity market".into(),
+                poly_slug: "opp-0".into(),
+                poly_yes_token: "01".into(),
+                poly_no_token: "02".into(),
+                line_value: None,
+                team_suffix: None,
+                event_id: None,
+                game_date: None,
+            },
+            yes_fee_bps: 0,
+            no_fee_bps: 0,
+        };
+        build_state(&[snapshot])
+    }
+
+    /// Price market 0 and report the check to the tracker like detection does
+    fn update(tracker: &OpportunityTracker, state: &GlobalState, yes: u16, no: u16, size: u16) {
+        let market = &state.markets[0];
+        market.poly.update_yes(yes, size);
+        market.poly.update_no(no, size);
+        tracker.observe(0, market, market.check_arbs(99));
+    }
+
+    #[cfg(feature = "replay")]
+    #[tokio::test(start_paused = true)]
+    async fn test_episode_lifecycle() {
+        let state = state();
+        let tracker = OpportunityTracker::new(NanoClock::simulated(), DAY1);
+
+        update(&tracker, &state, 45, 50, 2_000);
+        tokio::time::advance(Duration::from_millis(3)).await;
+        update(&tracker, &state, 44, 50, 5_000);
+        tracker.on_order(0);
+        tokio::time::advance(Duration::from_millis(7)).await;
+        tracker.on_result(0, 50, 50, 300);
+        update(&tracker, &state, 50, 50, 5_000);
+
+        // Closed and resulted: nothing open, one finished episode
+        assert!(tracker.open_markets().is_empty());
+        let episodes = tracker.drain();
+        assert_eq!(episodes.len(), 1);
+        let e = &episodes[0];
+        assert_eq!(e.pair_id, "opp-0");
+        assert_eq!(e.start_ns, DAY1);
+        assert_eq!(e.detections, 2);
+        assert_eq!(e.peak_edge_cents, 6);
+        assert_eq!(e.max_contracts, 50.0);
+        assert!((e.max_capturable - 3.0).abs() < 1e-9);
+        assert_eq!(e.time_to_order_ms(), Some(3.0));
+        assert_eq!(e.duration_ms(), 10.0);
+        assert_eq!((e.matched, e.profit_cents), (50, 300));
+        assert_eq!(e.outcome, Outcome::Captured);
+        assert!(!e.open_at_end);
+        assert_eq!(tracker.stats(), OpportunityStats { opened: 1, finalized: 1, captured: 1 });
+    }
+
+    /// Test: an ask change that moves no quote still re-checks an open episode
+    #[cfg(feature = "replay")]
+    #[tokio::test(start_paused = true)]
+    async fn test_feed_rechecks_open_episode_on_any_ask_change() {
+        use arb_bot::execution::create_execution_channel;
+        use arb_bot::polymarket::process_frame;
+
+        let book = |token: &str, asks: &str| format!(
+            r#"[{{"asset_id":"{}","bids":[],"asks":[{}]}}]"#, token, asks,
+        );
+        let (exec_tx, _exec_rx) = create_execution_channel();
+        let clock = NanoClock::simulated();
+        let mut state = state();
+        state.max_quote_age_ns = 1_000_000_000;
+        let tracker = OpportunityTracker::new(clock, DAY1);
+
+        process_frame(&state, &book("01", r#"{"price":"0.45","size":"100"}"#), &exec_tx, 99, &clock, None, Some(&tracker)).await;
+        process_frame(&state, &book("02", r#"{"price":"0.50","size":"100"}"#), &exec_tx, 99, &clock, None, Some(&tracker)).await;
+        assert!(tracker.is_open(0));
+
+        // NO quote goes stale; a worse YES ask leaves the quote alone but closes the episode
+        tokio::time::advance(Duration::from_secs(2)).await;
+        let change = r#"{"event_type":"price_change","price_changes":[{"asset_id":"01","price":"0.47","size":"10","side":"ASK"}]}"#;
+        process_frame(&state, change, &exec_tx, 99, &clock, None, Some(&tracker)).await;
+        assert_eq!(state.markets[0].poly.load().0, 45, "Not an improvement");
+        assert!(!tracker.is_open(0), "Stale NO side ends the arb");
+        assert_eq!(tracker.drain().len(), 1);
+    }
+
+    #[cfg(feature = "replay")]
+    #[tokio::test(start_paused = true)]
+    async fn test_close_waits_for_order_result() {
+        let state = state();
+        let tracker = OpportunityTracker::new(NanoClock::simulated(), DAY1);
+
+        update(&tracker, &state, 45, 50, 2_000);
+        tracker.on_order(0);
+        tokio::time::advance(Duration::from_millis(4)).await;
+        update(&tracker, &state, 50, 50, 2_000);
+        // Gone from the book but the order is still out
+        assert_eq!(tracker.stats().finalized, 0);
+
+        // A new crossing while the old episode waits opens a second one
+        update(&tracker, &state, 45, 50, 2_000);
+        tracker.on_result(0, 20, 0, -900);
+        assert_eq!(tracker.stats().finalized, 1);
+
+        tracker.stop();
+        let episodes = tracker.drain();
+        assert_eq!(episodes.len(), 2);
+        assert_eq!(episodes[0].outcome, Outcome::Partial);
+        assert_eq!(episodes[0].duration_ms(), 4.0);
+        assert_eq!(episodes[1].outcome, Outcome::NotAttempted);
+        assert!(episodes[1].open_at_end);
+    }
+
+    /// Test: at shutdown, open episodes are closed and on disk once flush_log returns
+    #[tokio::test]
+    async fn test_flush_log_writes_open_episodes() {
+        let path = std::env::temp_dir().join(format!("arb_opportunities_flush_{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let state = state();
+        let tracker = OpportunityTracker::new(NanoClock::new(), DAY1).with_log(path.to_str().unwrap());
+        update(&tracker, &state, 45, 50, 2_000);
+
+        tracker.stop();
+        assert!(tracker.drain().is_empty(), "Logged episodes are not returned");
+        tracker.flush_log().await;
+
+        let logged = load_log(&path).unwrap();
+        assert_eq!(logged.len(), 1);
+        assert!(logged[0].open_at_end);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_log_query_and_daily_summaries() {
+        let path = std::env::temp_dir().join(format!("arb_opportunities_{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let state = state();
+
+        // Day 1: captured, then missed; day 2: not attempted
+        for (epoch, fills) in [(DAY1, Some(10)), (DAY1 + 3_600_000_000_000, Some(0)), (DAY1 + DAY, None)] {
+            let tracker = OpportunityTracker::new(NanoClock::new(), epoch).with_log(path.to_str().unwrap());
+            update(&tracker, &state, 45, 50, 2_000);
+            if let Some(filled) = fills {
+                tracker.on_order(0);
+                tracker.on_result(0, filled, filled, filled * 5);
+            }
+            update(&tracker, &state, 50, 50, 2_000);
+        }
+
+        let mut logged = Vec::new();
+        for _ in 0..200 {
+            logged = load_log(&path).unwrap_or_default();
+            if logged.len() == 3 { break; }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(logged.len(), 3);
+
+        let captured = query(&path, &EpisodeQuery { outcome: Some(Outcome::Captured), ..Default::default() }).unwrap();
+        assert_eq!(captured.len(), 1);
+        let day2 = query(&path, &EpisodeQuery { from_ns: Some(DAY1 + DAY), ..Default::default() }).unwrap();
+        assert_eq!(day2.len(), 1);
+        let other = query(&path, &EpisodeQuery { market: Some("nfl".into()), ..Default::default() }).unwrap();
+        assert!(other.is_empty());
+
+        let days = daily_summaries(&logged);
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].date, "2026-01-15");
+        assert_eq!((days[0].episodes, days[0].attempted, days[0].captured, days[0].missed), (2, 2, 1, 1));
+        assert!((days[0].capture_rate - 0.5).abs() < 1e-9);
+        assert!((days[0].profit - 0.5).abs() < 1e-9);
+        assert_eq!(days[1].date, "2026-01-16");
+        assert_eq!((days[1].episodes, days[1].attempted), (1, 0));
+        let _ = std::fs::remove_file(&path);
+    }
+}
+
+// ============================================================================
+// KILL SWITCH TESTS - Kill-file edges, control endpoint auth and cancel-on-halt
+// ============================================================================
+
+mod kill_switch_tests {
+    use super::fixtures::breaker_config;
+    use anyhow::{Result, anyhow};
+    use arb_bot::balance::CollateralSnapshot;
+    use arb_bot::circuit_breaker::*;
+    use arb_bot::exchange::{ExchangeClient, OrderStatus};
+    use arb_bot::kill_switch::{KillSwitch, KillSwitchConfig};
+    use arb_bot::rate_limit::{Endpoint, RateLimiter};
+    use arb_bot::exchange::{CancelResult, Fill, OrderLeg};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Venue that only counts cancel-all requests
+    #[derive(Default)]
+    struct CancelCounter {
+        cancel_all_calls: AtomicUsize,
+    }
+
+    impl ExchangeClient for CancelCounter {
+        async fn place_orders(&self, _legs: &[OrderLeg<'_>]) -> Result<Vec<Result<Fill>>> {
+            Err(anyhow!("no orders in kill switch tests"))
+        }
+
+        async fn query_order(&self, order_id: &str) -> Result<OrderStatus> {
+            Err(anyhow!("unknown order {}", order_id))
+        }
+
+        async fn cancel_orders(&self, _order_ids: &[String]) -> Result<CancelResult> {
+            Ok(CancelResult::default())
+        }
+
+        async fn cancel_all(&self) -> Result<CancelResult> {
+            self.cancel_all_calls.fetch_add(1, Ordering::SeqCst);
+            Ok(CancelResult { canceled: vec!["open-1".into()], ..Default::default() })
+        }
+
+        fn neg_risk(&self, _token_id: &str) -> Option<bool> {
+            Some(false)
+        }
+
+        async fn collateral(&self) -> Result<CollateralSnapshot> {
+            Ok(CollateralSnapshot { balance: 0, allowance: 0, neg_risk_allowance: 0 })
+        }
+    }
+
+    fn breaker() -> Arc<CircuitBreaker> {
+        Arc::new(CircuitBreaker::new(breaker_config()))
+    }
+
+    fn config(kill_file: &str, cancel_on_halt: bool) -> KillSwitchConfig {
+        KillSwitchConfig {
+            kill_file: kill_file.to_string(),
+            poll_ms: 10,
+            control_addr: "127.0.0.1:0".to_string(),
+            control_token: "s3cret".to_string(),
+            cancel_on_halt,
+        }
+    }
+
+    /// Test: creating the kill-file halts, removing it resumes
+    #[tokio::test]
+    async fn test_kill_file_halts_and_resumes() {
+        let path = std::env::temp_dir().join(format!("arb_kill_{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let cb = breaker();
+        let kill_switch = KillSwitch::new(config(path.to_str().unwrap(), false), cb.clone());
+
+        kill_switch.check_kill_file().await;
+        assert!(!cb.status().await.manual_halt);
+
+        std::fs::write(&path, "").unwrap();
+        kill_switch.check_kill_file().await;
+        let status = cb.status().await;
+        assert!(status.manual_halt);
+        assert_eq!(status.trip_reason, None, "A manual halt is not a breaker trip");
+        assert_eq!(cb.can_execute("any", 1, 40, 50).await, Err(TripReason::ManualHalt));
+
+        // Manual halts are hard: the supervisor's cooldown never clears them
+        cb.check_cooldown().await;
+        cb.reset().await;
+        assert!(!cb.is_trading_allowed());
+
+        std::fs::remove_file(&path).unwrap();
+        kill_switch.check_kill_file().await;
+        assert!(!cb.status().await.manual_halt);
+        assert!(cb.is_trading_allowed());
+    }
+
+    /// Test: removing the kill-file leaves a halt that something else tripped since
+    #[tokio::test]
+    async fn test_kill_file_removal_keeps_other_halts() {
+        let path = std::env::temp_dir().join(format!("arb_kill_other_{}", std::process::id()));
+        std::fs::write(&path, "").unwrap();
+        let cb = breaker();
+        let kill_switch = KillSwitch::new(config(path.to_str().unwrap(), false), cb.clone());
+        kill_switch.check_kill_file().await;
+        assert!(cb.status().await.manual_halt);
+
+        cb.trip(TripReason::MaxDailyLoss { loss: 60.0, limit: 50.0 }).await;
+        std::fs::remove_file(&path).unwrap();
+        kill_switch.check_kill_file().await;
+        let status = cb.status().await;
+        assert!(status.halted && !status.manual_halt, "Loss halt outlives the kill-file");
+        assert!(matches!(status.trip_reason, Some(TripReason::MaxDailyLoss { .. })));
+    }
+
+    /// Test: a manual halt keeps an existing trip reason, and resuming leaves that trip in force
+    #[tokio::test]
+    async fn test_manual_halt_keeps_trip_reason() {
+        let cb = breaker();
+        let kill_switch = KillSwitch::new(config("", false), cb.clone());
+
+        cb.trip(TripReason::ConsecutiveErrors { count: 5, limit: 5 }).await;
+        kill_switch.halt("operator", false).await;
+        let status = cb.status().await;
+        assert!(status.halted && status.manual_halt);
+        assert!(matches!(status.trip_reason, Some(TripReason::ConsecutiveErrors { .. })));
+
+        kill_switch.resume("operator").await;
+        let status = cb.status().await;
+        assert!(status.halted && !status.manual_halt);
+        assert!(matches!(status.trip_reason, Some(TripReason::ConsecutiveErrors { .. })));
+        assert!(!cb.is_trading_allowed());
+    }
+
+    /// Test: the kill switch halts trading even with the circuit breaker disabled
+    #[tokio::test]
+    async fn test_halt_with_breaker_disabled() {
+        let cb = Arc::new(CircuitBreaker::new(CircuitBreakerConfig {
+            enabled: false,
+            ..breaker_config()
+        }));
+        let kill_switch = KillSwitch::new(config("", false), cb.clone());
+
+        kill_switch.halt("SIGUSR1", false).await;
+        assert_eq!(cb.can_execute("any", 1, 40, 50).await, Err(TripReason::ManualHalt));
+        assert!(!cb.is_trading_allowed());
+
+        kill_switch.resume("SIGUSR2").await;
+        assert!(cb.can_execute("any", 1, 40, 50).await.is_ok());
+    }
+
+    /// Test: the control endpoint needs the bearer token and halts/resumes on request
+    #[tokio::test]
+    async fn test_control_endpoint() {
+        let cb = breaker();
+        let venue = Arc::new(CancelCounter::default());
+        let kill_switch = Arc::new(KillSwitch::new(config("", false), cb.clone()).with_exchange(venue.clone()));
+        let addr = kill_switch.spawn_control("127.0.0.1:0".parse().unwrap()).await.unwrap();
+        let http = reqwest::Client::new();
+        let url = |path: &str| format!("http://{}{}", addr, path);
+
+        let resp = http.post(url("/halt")).send().await.unwrap();
+        assert_eq!(resp.status(), 401);
+        let resp = http.post(url("/halt")).bearer_auth("wrong!").send().await.unwrap();
+        assert_eq!(resp.status(), 401);
+        assert!(!cb.status().await.halted);
+
+        let resp = http.post(url("/halt?cancel=1"))
+            .bearer_auth("s3cret")
+            .header("X-Operator", "alice")
+            .send().await.unwrap();
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = resp.json().await.unwrap();
+        assert_eq!(body["halted"], true);
+        assert_eq!(body["manual_halt"], true);
+        assert!(body["reason"].is_null(), "No breaker trip");
+        assert_eq!(venue.cancel_all_calls.load(Ordering::SeqCst), 1);
+
+        let resp = http.get(url("/status")).bearer_auth("s3cret").send().await.unwrap();
+        let body: serde_json::Value = resp.json().await.unwrap();
+        assert_eq!(body["halted"], true);
+        assert!(body["rate_limits"].is_null(), "No limiter attached");
+
+        let resp = http.post(url("/resume")).bearer_auth("s3cret").send().await.unwrap();
+        assert_eq!(resp.json::<serde_json::Value>().await.unwrap()["halted"], false);
+        assert!(!cb.status().await.manual_halt);
+
+        let resp = http.post(url("/nope")).bearer_auth("s3cret").send().await.unwrap();
+        assert_eq!(resp.status(), 404);
+    }
+
+    /// Test: /status reports the rate limiter's throttling counters per endpoint
+    #[tokio::test]
+    async fn test_status_reports_rate_limits() {
+        let limiter = Arc::new(RateLimiter::unlimited());
+        limiter.acquire(Endpoint::OrderPost).await;
+        limiter.acquire(Endpoint::OrderQuery).await;
+        limiter.acquire(Endpoint::OrderQuery).await;
+
+        let kill_switch = Arc::new(KillSwitch::new(config("", false), breaker()).with_rate_limiter(limiter));
+        let addr = kill_switch.spawn_control("127.0.0.1:0".parse().unwrap()).await.unwrap();
+        let body: serde_json::Value = reqwest::Client::new()
+            .get(format!("http://{}/status", addr))
+            .bearer_auth("s3cret")
+            .send().await.unwrap()
+            .json().await.unwrap();
+
+        let limits = &body["rate_limits"];
+        assert_eq!(limits["order_post"]["requests"], 1);
+        assert_eq!(limits["order_query"]["requests"], 2);
+        assert_eq!(limits["order_query"]["throttled"], 0);
+        assert_eq!(limits["gamma"]["wait_us"], 0);
+    }
+
+    /// Test: KILL_CANCEL_ORDERS cancels on every halt; without it only an explicit request does
+    #[tokio::test]
+    async fn test_cancel_on_halt() {
+        let venue = Arc::new(CancelCounter::default());
+        let kill_switch = KillSwitch::new(config("", false), breaker()).with_exchange(venue.clone());
+        kill_switch.halt("test", false).await;
+        assert_eq!(venue.cancel_all_calls.load(Ordering::SeqCst), 0);
+        kill_switch.halt("test", true).await;
+        assert_eq!(venue.cancel_all_calls.load(Ordering::SeqCst), 1);
+
+        let kill_switch = KillSwitch::new(config("", true), breaker()).with_exchange(venue.clone());
+        kill_switch.halt("test", false).await;
+        assert_eq!(venue.cancel_all_calls.load(Ordering::SeqCst), 2);
+    }
+}